@@ -0,0 +1,3 @@
+mod reservation;
+
+pub use reservation::*;
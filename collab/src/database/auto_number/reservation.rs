@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use yrs::block::ClientID;
+
+/// A range of auto numbers issued by a client. The end is excluded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoNumberRange {
+  pub start: i64,
+  pub end: i64,
+}
+
+impl AutoNumberRange {
+  pub fn new(start: i64, end: i64) -> Self {
+    Self { start, end }
+  }
+}
+
+/// The numbers issued by each client for an auto number field.
+///
+/// Each client records the ranges of numbers it issued under its own key of the database
+/// [MetaMap](crate::database::meta::MetaMap), so the reservations of clients creating rows at
+/// the same time merge without overwriting each other. A new number follows the highest number
+/// issued by any client.
+///
+/// Clients that create rows while offline can still issue the same numbers. Once they have
+/// synced, the client with the lowest id keeps a number issued by several clients and the other
+/// ones give it up and renumber their rows, see [AutoNumberReservations::conflicts].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoNumberReservations {
+  ranges: BTreeMap<ClientID, Vec<AutoNumberRange>>,
+}
+
+impl AutoNumberReservations {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, client_id: ClientID, ranges: Vec<AutoNumberRange>) {
+    self.ranges.insert(client_id, ranges);
+  }
+
+  /// Return the clients and the ranges of numbers they issued, by client id.
+  pub fn iter(&self) -> impl Iterator<Item = (ClientID, &[AutoNumberRange])> {
+    self
+      .ranges
+      .iter()
+      .map(|(client_id, ranges)| (*client_id, ranges.as_slice()))
+  }
+
+  /// Return the ranges of numbers issued by the client.
+  pub fn ranges(&self, client_id: ClientID) -> &[AutoNumberRange] {
+    self
+      .ranges
+      .get(&client_id)
+      .map(|ranges| ranges.as_slice())
+      .unwrap_or_default()
+  }
+
+  /// Return the number the next row gets: the number following the highest issued number, or
+  /// `start` when no number greater or equal to it has been issued.
+  pub fn next_number(&self, start: i64) -> i64 {
+    self
+      .ranges
+      .values()
+      .flatten()
+      .map(|range| range.end)
+      .fold(start, i64::max)
+  }
+
+  /// Issue the next number to the client.
+  pub fn reserve(&mut self, client_id: ClientID, start: i64) -> i64 {
+    let number = self.next_number(start);
+    let ranges = self.ranges.entry(client_id).or_default();
+    match ranges.last_mut() {
+      Some(last) if last.end == number => last.end += 1,
+      _ => ranges.push(AutoNumberRange::new(number, number + 1)),
+    }
+    number
+  }
+
+  /// Return the numbers, in ascending order, issued by the client that a client with a lower id
+  /// issued too.
+  pub fn conflicts(&self, client_id: ClientID) -> Vec<i64> {
+    let own_ranges = self.ranges(client_id);
+    let mut numbers = self
+      .ranges
+      .range(..client_id)
+      .flat_map(|(_, ranges)| ranges)
+      .flat_map(move |other| {
+        own_ranges
+          .iter()
+          .flat_map(move |range| range.start.max(other.start)..range.end.min(other.end))
+      })
+      .collect::<Vec<_>>();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+  }
+
+  /// Remove the numbers from the ranges issued by the client.
+  pub fn release(&mut self, client_id: ClientID, numbers: &[i64]) {
+    let Some(ranges) = self.ranges.get_mut(&client_id) else {
+      return;
+    };
+    let mut numbers = numbers.to_vec();
+    numbers.sort_unstable();
+    *ranges = ranges
+      .iter()
+      .flat_map(|range| {
+        let mut remaining = vec![];
+        let mut start = range.start;
+        for number in numbers.iter().copied() {
+          if number < start || number >= range.end {
+            continue;
+          }
+          if number > start {
+            remaining.push(AutoNumberRange::new(start, number));
+          }
+          start = number + 1;
+        }
+        if start < range.end {
+          remaining.push(AutoNumberRange::new(start, range.end));
+        }
+        remaining
+      })
+      .collect();
+  }
+}
@@ -52,6 +52,10 @@ impl Block {
     }
   }
 
+  pub fn row_collab_service(&self) -> Arc<dyn DatabaseRowCollabService> {
+    self.collab_service.clone()
+  }
+
   pub fn subscribe_event(&self) -> broadcast::Receiver<BlockEvent> {
     self.notifier.subscribe()
   }
@@ -0,0 +1,153 @@
+use crate::database::entity::FieldType;
+use crate::database::util::get_i64_lenient;
+use crate::database::views::{CalculationMap, CalculationMapBuilder};
+use crate::error::CollabError;
+use crate::preclude::Any;
+use crate::util::AnyMapExt;
+
+pub const CALCULATION_ID: &str = "id";
+pub const CALCULATION_FIELD_ID: &str = "field_id";
+pub const CALCULATION_TYPE: &str = "ty";
+pub const CALCULATION_VALUE: &str = "calculation_value";
+
+/// A typed view calculation. Each calculation is shown in the footer of its field's column and
+/// `value` holds the last computed result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calculation {
+  pub id: String,
+  pub field_id: String,
+  pub calculation_type: CalculationType,
+  pub value: String,
+}
+
+impl Calculation {
+  pub fn new(id: String, field_id: String, calculation_type: CalculationType) -> Self {
+    Self {
+      id,
+      field_id,
+      calculation_type,
+      value: String::new(),
+    }
+  }
+}
+
+impl TryFrom<CalculationMap> for Calculation {
+  type Error = CollabError;
+
+  fn try_from(map: CalculationMap) -> Result<Self, Self::Error> {
+    let id: String = map
+      .get_as(CALCULATION_ID)
+      .ok_or_else(|| CollabError::NoRequiredData("calculation id".to_string()))?;
+    let field_id: String = map
+      .get_as(CALCULATION_FIELD_ID)
+      .ok_or_else(|| CollabError::NoRequiredData("calculation field id".to_string()))?;
+    let calculation_type = get_i64_lenient(&map, CALCULATION_TYPE)
+      .map(CalculationType::from)
+      .unwrap_or_default();
+    let value: String = map.get_as(CALCULATION_VALUE).unwrap_or_default();
+    Ok(Self {
+      id,
+      field_id,
+      calculation_type,
+      value,
+    })
+  }
+}
+
+impl From<Calculation> for CalculationMap {
+  fn from(calculation: Calculation) -> Self {
+    CalculationMapBuilder::from([
+      (CALCULATION_ID.into(), calculation.id.into()),
+      (CALCULATION_FIELD_ID.into(), calculation.field_id.into()),
+      (
+        CALCULATION_TYPE.into(),
+        Any::BigInt(calculation.calculation_type.value()),
+      ),
+      (CALCULATION_VALUE.into(), calculation.value.into()),
+    ])
+  }
+}
+
+impl From<&Calculation> for CalculationMap {
+  fn from(calculation: &Calculation) -> Self {
+    CalculationMap::from(calculation.clone())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CalculationType {
+  Average = 0,
+  Max = 1,
+  Median = 2,
+  Min = 3,
+  Sum = 4,
+  #[default]
+  Count = 5,
+  CountEmpty = 6,
+  CountNonEmpty = 7,
+  PercentEmpty = 8,
+  UniqueCount = 9,
+  Earliest = 10,
+  Latest = 11,
+  DateRange = 12,
+}
+
+impl From<i64> for CalculationType {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => CalculationType::Average,
+      1 => CalculationType::Max,
+      2 => CalculationType::Median,
+      3 => CalculationType::Min,
+      4 => CalculationType::Sum,
+      5 => CalculationType::Count,
+      6 => CalculationType::CountEmpty,
+      7 => CalculationType::CountNonEmpty,
+      8 => CalculationType::PercentEmpty,
+      9 => CalculationType::UniqueCount,
+      10 => CalculationType::Earliest,
+      11 => CalculationType::Latest,
+      12 => CalculationType::DateRange,
+      _ => {
+        tracing::error!("Unsupported calculation type, fallback to Count");
+        CalculationType::Count
+      },
+    }
+  }
+}
+
+impl CalculationType {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+
+  /// Whether the calculation can be computed for cells of the given field type. Counting works
+  /// for every field, arithmetic needs numbers and the date calculations need dates.
+  pub fn is_supported_by(&self, field_type: &FieldType) -> bool {
+    match self {
+      CalculationType::Count
+      | CalculationType::CountEmpty
+      | CalculationType::CountNonEmpty
+      | CalculationType::PercentEmpty
+      | CalculationType::UniqueCount => true,
+      CalculationType::Average
+      | CalculationType::Max
+      | CalculationType::Median
+      | CalculationType::Min
+      | CalculationType::Sum => matches!(
+        field_type,
+        FieldType::Number
+          | FieldType::Time
+          | FieldType::Checkbox
+          | FieldType::Rollup
+          | FieldType::Formula
+      ),
+      CalculationType::Earliest | CalculationType::Latest | CalculationType::DateRange => {
+        matches!(
+          field_type,
+          FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime
+        )
+      },
+    }
+  }
+}
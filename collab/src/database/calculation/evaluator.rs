@@ -0,0 +1,505 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::database::calculation::{Calculation, CalculationType};
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::media_type_option::MediaCellData;
+use crate::database::fields::select_type_option::SelectOptionIds;
+use crate::database::fields::{
+  Field, TypeOptionCellReader, TypeOptionData, type_option_cell_reader,
+};
+use crate::database::rows::{Cell, Row, RowChange};
+use crate::database::template::check_list_parse::ChecklistCellData;
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::entity::uuid_validation::RowId;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Computes a view's [Calculation]s over the rows the view shows.
+///
+/// The evaluator keeps the value of every row it was given, together with running aggregates,
+/// so a cell change only retracts the row's previous value and adds the new one instead of
+/// scanning every row again. Which rows are shown is up to the caller: rows that become visible
+/// or hidden are passed to [CalculationEvaluator::add_row] and [CalculationEvaluator::remove_row].
+///
+/// Calculations whose field was deleted, or whose type is not supported by the field type, are
+/// skipped.
+pub struct CalculationEvaluator {
+  calculations: Vec<CalculationField>,
+}
+
+struct CalculationField {
+  calculation: Calculation,
+  field_type: FieldType,
+  type_option: TypeOptionData,
+  state: CalculationState,
+}
+
+/// The value a row contributes to a calculation. A row is empty when it has no `text`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CellValue {
+  text: Option<String>,
+  number: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OrderedNumber(f64);
+
+impl PartialEq for OrderedNumber {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for OrderedNumber {}
+
+impl PartialOrd for OrderedNumber {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for OrderedNumber {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.total_cmp(&other.0)
+  }
+}
+
+#[derive(Debug, Default)]
+struct CalculationState {
+  rows: HashMap<RowId, CellValue>,
+  empty_count: usize,
+  /// Multiset of the rows' numbers, used for sum, average, median, min and max.
+  numbers: BTreeMap<OrderedNumber, usize>,
+  number_count: usize,
+  /// Multiset of the rows' non-empty texts, used for the unique count.
+  texts: HashMap<String, usize>,
+}
+
+impl CalculationEvaluator {
+  pub fn new(calculations: Vec<Calculation>, fields: Vec<Field>) -> Self {
+    let fields = fields
+      .into_iter()
+      .map(|field| (field.id.clone(), field))
+      .collect::<HashMap<_, _>>();
+    let calculations = calculations
+      .into_iter()
+      .flat_map(|calculation| {
+        let field = fields.get(&calculation.field_id)?;
+        let field_type = FieldType::from(field.field_type);
+        if !calculation.calculation_type.is_supported_by(&field_type) {
+          return None;
+        }
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        Some(CalculationField {
+          calculation,
+          field_type,
+          type_option,
+          state: CalculationState::default(),
+        })
+      })
+      .collect();
+    Self { calculations }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.calculations.is_empty()
+  }
+
+  /// Add the rows to the calculations. A row that was already added is replaced.
+  pub fn add_rows<'a>(&mut self, rows: impl IntoIterator<Item = &'a Row>) {
+    let rows = rows.into_iter().collect::<Vec<_>>();
+    for calculation in self.calculations.iter_mut() {
+      let reader = calculation.reader();
+      for row in &rows {
+        let value = calculation.row_value(reader.as_ref(), row);
+        calculation.state.insert(row.id, value);
+      }
+    }
+  }
+
+  /// Add the row to the calculations. A row that was already added is replaced.
+  pub fn add_row(&mut self, row: &Row) {
+    self.add_rows([row]);
+  }
+
+  /// Remove the row from the calculations, e.g. when it is deleted or hidden by a filter.
+  pub fn remove_row(&mut self, row_id: &RowId) {
+    for calculation in self.calculations.iter_mut() {
+      calculation.state.remove(row_id);
+    }
+  }
+
+  /// Update the value of a cell. Only the calculations of the cell's field are updated. Returns
+  /// true if any calculation took the change.
+  ///
+  /// A cell change doesn't carry the row's modified time, so the last edited time calculations
+  /// are left as is. Use [Self::update_row] with the updated row to update them.
+  ///
+  /// Changes of rows that were never added are ignored.
+  pub fn update_cell(&mut self, row_id: &RowId, field_id: &str, cell: &Cell) -> bool {
+    let mut is_changed = false;
+    for calculation in self.calculations.iter_mut() {
+      if calculation.calculation.field_id != field_id
+        || !calculation.state.rows.contains_key(row_id)
+      {
+        continue;
+      }
+      if matches!(
+        calculation.field_type,
+        FieldType::CreatedTime | FieldType::LastEditedTime
+      ) {
+        continue;
+      }
+      let reader = calculation.reader();
+      let value = cell_value(&calculation.field_type, reader.as_ref(), cell);
+      is_changed |= calculation.state.insert(*row_id, value);
+    }
+    is_changed
+  }
+
+  /// Update the values of a row that was already added, including its last edited time. Returns
+  /// true if any calculation took the change.
+  ///
+  /// Rows that were never added are ignored.
+  pub fn update_row(&mut self, row: &Row) -> bool {
+    let mut is_changed = false;
+    for calculation in self.calculations.iter_mut() {
+      if !calculation.state.rows.contains_key(&row.id) {
+        continue;
+      }
+      let reader = calculation.reader();
+      let value = calculation.row_value(reader.as_ref(), row);
+      is_changed |= calculation.state.insert(row.id, value);
+    }
+    is_changed
+  }
+
+  /// Apply a [RowChange] of a row. Returns true if any calculation took the change.
+  pub fn apply_row_change(&mut self, change: &RowChange) -> bool {
+    match change {
+      RowChange::DidUpdateCell {
+        row_id,
+        field_id,
+        value,
+        ..
+      } => self.update_cell(row_id, field_id, value),
+      _ => false,
+    }
+  }
+
+  /// Return the calculations with their computed value.
+  pub fn results(&self) -> Vec<Calculation> {
+    self
+      .calculations
+      .iter()
+      .map(|calculation| calculation.result())
+      .collect()
+  }
+
+  /// Return the calculation of the field with its computed value.
+  pub fn result(&self, field_id: &str) -> Option<Calculation> {
+    self
+      .calculations
+      .iter()
+      .find(|calculation| calculation.calculation.field_id == field_id)
+      .map(|calculation| calculation.result())
+  }
+}
+
+impl CalculationField {
+  fn reader(&self) -> Box<dyn TypeOptionCellReader> {
+    type_option_cell_reader(self.type_option.clone(), &self.field_type)
+  }
+
+  fn row_value(&self, reader: &dyn TypeOptionCellReader, row: &Row) -> CellValue {
+    match self.field_type {
+      FieldType::CreatedTime => timestamp_value(reader, row.created_at),
+      FieldType::LastEditedTime => timestamp_value(reader, row.modified_at),
+      _ => {
+        let empty_cell = Cell::new();
+        let cell = row
+          .cells
+          .get(&self.calculation.field_id)
+          .unwrap_or(&empty_cell);
+        cell_value(&self.field_type, reader, cell)
+      },
+    }
+  }
+
+  fn result(&self) -> Calculation {
+    let mut calculation = self.calculation.clone();
+    calculation.value = self.value();
+    calculation
+  }
+
+  fn value(&self) -> String {
+    let state = &self.state;
+    let reader = self.reader();
+    let format_value = |number: f64| {
+      let text = format_number(number);
+      if self.field_type == FieldType::Number {
+        reader.convert_raw_cell_data(&text)
+      } else {
+        text
+      }
+    };
+    let format_date =
+      |timestamp: f64| reader.convert_raw_cell_data(&(timestamp as i64).to_string());
+
+    match self.calculation.calculation_type {
+      CalculationType::Count => state.rows.len().to_string(),
+      CalculationType::CountEmpty => state.empty_count.to_string(),
+      CalculationType::CountNonEmpty => (state.rows.len() - state.empty_count).to_string(),
+      CalculationType::PercentEmpty => {
+        if state.rows.is_empty() {
+          String::new()
+        } else {
+          let percent = state.empty_count as f64 * 100.0 / state.rows.len() as f64;
+          format!("{}%", format_number(percent))
+        }
+      },
+      CalculationType::UniqueCount => state.texts.len().to_string(),
+      CalculationType::Sum => format_value(state.sum()),
+      CalculationType::Average => state.average().map(format_value).unwrap_or_default(),
+      CalculationType::Median => state.median().map(format_value).unwrap_or_default(),
+      CalculationType::Min => state.min().map(format_value).unwrap_or_default(),
+      CalculationType::Max => state.max().map(format_value).unwrap_or_default(),
+      CalculationType::Earliest => state.min().map(format_date).unwrap_or_default(),
+      CalculationType::Latest => state.max().map(format_date).unwrap_or_default(),
+      CalculationType::DateRange => match (state.min(), state.max()) {
+        (Some(min), Some(max)) => (((max - min) / SECONDS_PER_DAY).floor() as i64).to_string(),
+        _ => String::new(),
+      },
+    }
+  }
+}
+
+impl CalculationState {
+  /// Insert or replace the row's value. Returns true if the value changed.
+  fn insert(&mut self, row_id: RowId, value: CellValue) -> bool {
+    if self.rows.get(&row_id) == Some(&value) {
+      return false;
+    }
+    self.accumulate(&value);
+    if let Some(old_value) = self.rows.insert(row_id, value) {
+      self.retract(&old_value);
+    }
+    true
+  }
+
+  fn remove(&mut self, row_id: &RowId) {
+    if let Some(old_value) = self.rows.remove(row_id) {
+      self.retract(&old_value);
+    }
+  }
+
+  fn accumulate(&mut self, value: &CellValue) {
+    match &value.text {
+      None => self.empty_count += 1,
+      Some(text) => *self.texts.entry(text.clone()).or_default() += 1,
+    }
+    if let Some(number) = value.number {
+      *self.numbers.entry(OrderedNumber(number)).or_default() += 1;
+      self.number_count += 1;
+    }
+  }
+
+  fn retract(&mut self, value: &CellValue) {
+    match &value.text {
+      None => self.empty_count -= 1,
+      Some(text) => remove_one(&mut self.texts, text),
+    }
+    if let Some(number) = value.number {
+      if let Some(count) = self.numbers.get_mut(&OrderedNumber(number)) {
+        *count -= 1;
+        if *count == 0 {
+          self.numbers.remove(&OrderedNumber(number));
+        }
+      }
+      self.number_count -= 1;
+    }
+  }
+
+  /// The sum is recomputed from the multiset rather than kept as a running total, so that
+  /// adding and retracting values doesn't accumulate floating point errors.
+  fn sum(&self) -> f64 {
+    self
+      .numbers
+      .iter()
+      .map(|(number, count)| number.0 * *count as f64)
+      .sum()
+  }
+
+  fn average(&self) -> Option<f64> {
+    if self.number_count == 0 {
+      None
+    } else {
+      Some(self.sum() / self.number_count as f64)
+    }
+  }
+
+  fn median(&self) -> Option<f64> {
+    if self.number_count == 0 {
+      return None;
+    }
+    let lower = self.nth_number((self.number_count - 1) / 2)?;
+    let upper = self.nth_number(self.number_count / 2)?;
+    Some((lower + upper) / 2.0)
+  }
+
+  fn nth_number(&self, index: usize) -> Option<f64> {
+    let mut position = 0;
+    for (number, count) in self.numbers.iter() {
+      position += count;
+      if index < position {
+        return Some(number.0);
+      }
+    }
+    None
+  }
+
+  fn min(&self) -> Option<f64> {
+    self.numbers.keys().next().map(|number| number.0)
+  }
+
+  fn max(&self) -> Option<f64> {
+    self.numbers.keys().next_back().map(|number| number.0)
+  }
+}
+
+fn remove_one(texts: &mut HashMap<String, usize>, text: &str) {
+  if let Some(count) = texts.get_mut(text) {
+    *count -= 1;
+    if *count == 0 {
+      texts.remove(text);
+    }
+  }
+}
+
+fn cell_value(field_type: &FieldType, reader: &dyn TypeOptionCellReader, cell: &Cell) -> CellValue {
+  match field_type {
+    FieldType::DateTime => {
+      let timestamp = DateCellData::from(cell).timestamp;
+      CellValue {
+        text: timestamp.map(|_| reader.stringify_cell(cell)),
+        number: timestamp.map(|timestamp| timestamp as f64),
+      }
+    },
+    FieldType::Checkbox => {
+      // An unchecked checkbox counts as empty, but still adds 0 to the sum and average.
+      let is_checked = reader.numeric_cell(cell) == Some(1.0);
+      CellValue {
+        text: is_checked.then(|| "Yes".to_string()),
+        number: Some(if is_checked { 1.0 } else { 0.0 }),
+      }
+    },
+    FieldType::Checklist => {
+      let cell_data = ChecklistCellData::from(cell);
+      if cell_data.options.is_empty() {
+        CellValue::default()
+      } else {
+        CellValue {
+          text: Some(reader.stringify_cell(cell)),
+          number: Some(cell_data.percentage_complete()),
+        }
+      }
+    },
+    FieldType::SingleSelect | FieldType::MultiSelect => {
+      text_value(!SelectOptionIds::from(cell).is_empty(), reader, cell)
+    },
+    FieldType::Media => text_value(!MediaCellData::from(cell).files.is_empty(), reader, cell),
+    FieldType::Relation => text_value(
+      !RelationCellData::from(cell).row_ids.is_empty(),
+      reader,
+      cell,
+    ),
+    _ => {
+      let text = if cell.contains_key(CELL_DATA) {
+        reader.stringify_cell(cell)
+      } else {
+        String::new()
+      };
+      CellValue {
+        text: Some(text).filter(|text| !text.is_empty()),
+        number: reader.numeric_cell(cell),
+      }
+    },
+  }
+}
+
+fn text_value(is_not_empty: bool, reader: &dyn TypeOptionCellReader, cell: &Cell) -> CellValue {
+  CellValue {
+    text: is_not_empty.then(|| reader.stringify_cell(cell)),
+    number: None,
+  }
+}
+
+fn timestamp_value(reader: &dyn TypeOptionCellReader, timestamp: i64) -> CellValue {
+  CellValue {
+    text: Some(reader.convert_raw_cell_data(&timestamp.to_string())),
+    number: Some(timestamp as f64),
+  }
+}
+
+/// Format a computed number with at most two decimals and without trailing zeros.
+fn format_number(number: f64) -> String {
+  let rounded = (number * 100.0).round() / 100.0;
+  format!("{}", rounded)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn number_value(number: f64) -> CellValue {
+    CellValue {
+      text: Some(number.to_string()),
+      number: Some(number),
+    }
+  }
+
+  #[test]
+  fn median_of_odd_and_even_counts_test() {
+    let mut state = CalculationState::default();
+    let row_ids = (0..4).map(|_| RowId::new_v4()).collect::<Vec<_>>();
+    for (row_id, number) in row_ids.iter().zip([5.0, 1.0, 3.0]) {
+      state.insert(*row_id, number_value(number));
+    }
+    assert_eq!(state.median(), Some(3.0));
+
+    state.insert(row_ids[3], number_value(8.0));
+    assert_eq!(state.median(), Some(4.0));
+  }
+
+  #[test]
+  fn replacing_a_value_retracts_the_previous_one_test() {
+    let mut state = CalculationState::default();
+    let row_id = RowId::new_v4();
+    state.insert(RowId::new_v4(), number_value(2.0));
+    state.insert(row_id, number_value(10.0));
+    assert_eq!(state.max(), Some(10.0));
+
+    assert!(state.insert(row_id, CellValue::default()));
+    assert!(!state.insert(row_id, CellValue::default()));
+    assert_eq!(state.max(), Some(2.0));
+    assert_eq!(state.sum(), 2.0);
+    assert_eq!(state.empty_count, 1);
+    assert_eq!(state.texts.len(), 1);
+
+    state.remove(&row_id);
+    assert_eq!(state.rows.len(), 1);
+    assert_eq!(state.empty_count, 0);
+  }
+
+  #[test]
+  fn format_number_test() {
+    assert_eq!(format_number(3.0), "3");
+    assert_eq!(format_number(10.0 / 3.0), "3.33");
+    assert_eq!(format_number(-0.125), "-0.13");
+  }
+}
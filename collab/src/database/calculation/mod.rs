@@ -0,0 +1,5 @@
+mod calculation;
+mod evaluator;
+
+pub use calculation::*;
+pub use evaluator::*;
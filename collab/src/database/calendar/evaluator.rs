@@ -0,0 +1,96 @@
+use chrono::Weekday;
+use chrono_tz::Tz;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::Field;
+use crate::database::fields::date_type_option::{DateCellData, DateTypeOption};
+use crate::database::rows::Row;
+use crate::database::views::CalendarLayoutSetting;
+use crate::entity::uuid_validation::RowId;
+
+/// An occurrence of a row's event in a calendar view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarEvent {
+  pub row_id: RowId,
+  pub timestamp: i64,
+  /// Set for date ranges. Every occurrence of a recurring range has the length of the first one.
+  pub end_timestamp: Option<i64>,
+  pub include_time: bool,
+  pub is_recurring: bool,
+}
+
+/// Expands the rows of a calendar view into the events of a date range, from the date field of
+/// the view's [CalendarLayoutSetting].
+///
+/// Recurring events are expanded with their [RecurrenceRule](crate::database::calendar::RecurrenceRule)
+/// in the date field's time zone. Weeks start on the setting's first day of the week, counted
+/// from Sunday, or on Sunday when it's not set.
+pub struct CalendarEvaluator {
+  field_id: String,
+  timezone: Tz,
+  week_start: Weekday,
+}
+
+impl CalendarEvaluator {
+  /// Return None if the field isn't a date field, or isn't the setting's field.
+  pub fn new(setting: &CalendarLayoutSetting, field: &Field) -> Option<Self> {
+    if setting.field_id != field.id || FieldType::from(field.field_type) != FieldType::DateTime {
+      return None;
+    }
+    let type_option = field
+      .get_any_type_option(FieldType::DateTime.type_id())
+      .map(DateTypeOption::from)
+      .unwrap_or_else(DateTypeOption::default_utc);
+    let timezone = type_option.timezone_id.parse::<Tz>().unwrap_or(Tz::UTC);
+    let week_start = (0..setting.first_day_of_week.unwrap_or_default().rem_euclid(7))
+      .fold(Weekday::Sun, |weekday, _| weekday.succ());
+    Some(Self {
+      field_id: field.id.clone(),
+      timezone,
+      week_start,
+    })
+  }
+
+  /// Return the events of the rows that overlap `[from, to)`, ordered by start. Events starting
+  /// at the same time keep the order of the rows.
+  pub fn events(&self, rows: &[Row], from: i64, to: i64) -> Vec<CalendarEvent> {
+    let mut events = vec![];
+    for row in rows {
+      let Some(cell_data) = row.cells.get(&self.field_id).map(DateCellData::from) else {
+        continue;
+      };
+      let Some(timestamp) = cell_data.timestamp else {
+        continue;
+      };
+      let duration = cell_data
+        .end_timestamp
+        .filter(|_| cell_data.is_range)
+        .map(|end_timestamp| (end_timestamp - timestamp).max(0));
+      // An event overlaps the range if it starts in it, or if it started before and ends in it
+      let earliest_start = match duration {
+        Some(duration) if duration > 0 => from - duration + 1,
+        _ => from,
+      };
+      let timestamps = match &cell_data.recurrence {
+        Some(recurrence) => recurrence.occurrences(
+          timestamp,
+          &self.timezone,
+          self.week_start,
+          earliest_start,
+          to,
+        ),
+        None if timestamp >= earliest_start && timestamp < to => vec![timestamp],
+        None => vec![],
+      };
+      events.extend(timestamps.into_iter().map(|timestamp| CalendarEvent {
+        row_id: row.id,
+        timestamp,
+        end_timestamp: duration.map(|duration| timestamp + duration),
+        include_time: cell_data.include_time,
+        is_recurring: cell_data.recurrence.is_some(),
+      }));
+    }
+    events.sort_by_key(|event| event.timestamp);
+    events
+  }
+}
@@ -0,0 +1,5 @@
+mod evaluator;
+mod recurrence;
+
+pub use evaluator::*;
+pub use recurrence::*;
@@ -0,0 +1,430 @@
+use chrono::{
+  DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// The maximum number of occurrences returned by [RecurrenceRule::occurrences].
+pub const MAX_RECURRENCE_OCCURRENCES: usize = 1000;
+/// The maximum number of days, weeks, months or years walked by [RecurrenceRule::occurrences].
+const MAX_RECURRENCE_PERIODS: usize = 100_000;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RecurrenceFrequency {
+  #[default]
+  Daily = 0,
+  Weekly = 1,
+  Monthly = 2,
+  Yearly = 3,
+}
+
+impl From<i64> for RecurrenceFrequency {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => RecurrenceFrequency::Daily,
+      1 => RecurrenceFrequency::Weekly,
+      2 => RecurrenceFrequency::Monthly,
+      3 => RecurrenceFrequency::Yearly,
+      _ => {
+        tracing::error!("Unsupported recurrence frequency, fallback to Daily");
+        RecurrenceFrequency::Daily
+      },
+    }
+  }
+}
+
+impl RecurrenceFrequency {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RecurrenceWeekday {
+  Monday = 0,
+  Tuesday = 1,
+  Wednesday = 2,
+  Thursday = 3,
+  Friday = 4,
+  Saturday = 5,
+  Sunday = 6,
+}
+
+impl From<i64> for RecurrenceWeekday {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => RecurrenceWeekday::Monday,
+      1 => RecurrenceWeekday::Tuesday,
+      2 => RecurrenceWeekday::Wednesday,
+      3 => RecurrenceWeekday::Thursday,
+      4 => RecurrenceWeekday::Friday,
+      5 => RecurrenceWeekday::Saturday,
+      6 => RecurrenceWeekday::Sunday,
+      _ => {
+        tracing::error!("Unsupported weekday, fallback to Monday");
+        RecurrenceWeekday::Monday
+      },
+    }
+  }
+}
+
+impl RecurrenceWeekday {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+impl From<Weekday> for RecurrenceWeekday {
+  fn from(weekday: Weekday) -> Self {
+    Self::from(weekday.num_days_from_monday() as i64)
+  }
+}
+
+impl From<RecurrenceWeekday> for Weekday {
+  fn from(weekday: RecurrenceWeekday) -> Self {
+    match weekday {
+      RecurrenceWeekday::Monday => Weekday::Mon,
+      RecurrenceWeekday::Tuesday => Weekday::Tue,
+      RecurrenceWeekday::Wednesday => Weekday::Wed,
+      RecurrenceWeekday::Thursday => Weekday::Thu,
+      RecurrenceWeekday::Friday => Weekday::Fri,
+      RecurrenceWeekday::Saturday => Weekday::Sat,
+      RecurrenceWeekday::Sunday => Weekday::Sun,
+    }
+  }
+}
+
+/// The recurrence of a date cell, modeled after the RRULE of iCalendar (RFC 5545).
+///
+/// The occurrences are computed in the wall clock time of the date field's time zone, so an
+/// event at 9:00 stays at 9:00 across daylight saving time changes. Monthly and yearly events
+/// skip the months that don't have the first occurrence's day, e.g. the 31st or February 29th.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurrenceRule {
+  pub frequency: RecurrenceFrequency,
+  /// Repeat every `interval` days, weeks, months or years. 0 is read as 1.
+  #[serde(default = "default_interval")]
+  pub interval: u32,
+  /// The days a weekly event repeats on. Empty repeats on the first occurrence's day.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub by_weekday: Vec<RecurrenceWeekday>,
+  /// The number of occurrences, counting the first one and the exceptions.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub count: Option<u32>,
+  /// The timestamp after which no occurrence starts.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub until: Option<i64>,
+  /// The start timestamps of the occurrences that were cancelled.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub exceptions: Vec<i64>,
+}
+
+fn default_interval() -> u32 {
+  1
+}
+
+impl RecurrenceRule {
+  pub fn new(frequency: RecurrenceFrequency) -> Self {
+    Self {
+      frequency,
+      interval: 1,
+      by_weekday: vec![],
+      count: None,
+      until: None,
+      exceptions: vec![],
+    }
+  }
+
+  pub fn with_interval(mut self, interval: u32) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  pub fn with_weekdays(mut self, weekdays: Vec<RecurrenceWeekday>) -> Self {
+    self.by_weekday = weekdays;
+    self
+  }
+
+  pub fn with_count(mut self, count: u32) -> Self {
+    self.count = Some(count);
+    self
+  }
+
+  pub fn with_until(mut self, until: i64) -> Self {
+    self.until = Some(until);
+    self
+  }
+
+  pub fn with_exception(mut self, timestamp: i64) -> Self {
+    self.exceptions.push(timestamp);
+    self
+  }
+
+  /// Return the start timestamps of the occurrences of an event first starting at `start` that
+  /// start in `[from, to)`, in order. Weeks start on `week_start`, which decides the weeks
+  /// skipped by a weekly event repeating every other week or more.
+  ///
+  /// Without a [RecurrenceRule::count], the occurrences before `from` are skipped without being
+  /// computed. With one, they are counted from the first occurrence. At most
+  /// [MAX_RECURRENCE_OCCURRENCES] occurrences are returned, and the periods after the first
+  /// 100,000 walked are ignored.
+  pub fn occurrences(
+    &self,
+    start: i64,
+    timezone: &Tz,
+    week_start: Weekday,
+    from: i64,
+    to: i64,
+  ) -> Vec<i64> {
+    let Some(first) = DateTime::from_timestamp(start, 0) else {
+      return vec![];
+    };
+    let first = first.with_timezone(timezone).naive_local();
+    let interval = self.interval.max(1);
+    let mut occurrences = vec![];
+    let mut count = 0;
+    let first_period = match self.count {
+      Some(_) => 0,
+      None => self.first_period(first, timezone, week_start, from),
+    };
+    for period in (first_period..).take(MAX_RECURRENCE_PERIODS) {
+      let Some((period_start, candidates)) = period
+        .checked_mul(interval)
+        .and_then(|step| self.period_candidates(first, step, week_start))
+      else {
+        break;
+      };
+      let period_start = local_timestamp(timezone, period_start.and_time(NaiveTime::MIN));
+      if period_start >= to || self.until.is_some_and(|until| period_start > until) {
+        break;
+      }
+
+      for candidate in candidates
+        .into_iter()
+        .filter(|candidate| candidate >= &first)
+      {
+        let timestamp = local_timestamp(timezone, candidate);
+        if timestamp >= to
+          || self.until.is_some_and(|until| timestamp > until)
+          || self.count.is_some_and(|max| count >= max)
+        {
+          return occurrences;
+        }
+        count += 1;
+        if timestamp >= from && !self.exceptions.contains(&timestamp) {
+          occurrences.push(timestamp);
+          if occurrences.len() >= MAX_RECURRENCE_OCCURRENCES {
+            return occurrences;
+          }
+        }
+      }
+    }
+    occurrences
+  }
+
+  /// Return the index of the last period starting on or before the day of `from`, the first one
+  /// that can hold an occurrence starting at or after `from`.
+  fn first_period(
+    &self,
+    first: NaiveDateTime,
+    timezone: &Tz,
+    week_start: Weekday,
+    from: i64,
+  ) -> u32 {
+    let Some(from) = DateTime::from_timestamp(from, 0) else {
+      return 0;
+    };
+    let from = from.with_timezone(timezone).date_naive();
+    let date = first.date();
+    let units = match self.frequency {
+      RecurrenceFrequency::Daily => (from - date).num_days(),
+      RecurrenceFrequency::Weekly => {
+        let days_from_week_start =
+          (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+        ((from - date).num_days() + days_from_week_start as i64).div_euclid(7)
+      },
+      RecurrenceFrequency::Monthly => {
+        (from.year() - date.year()) as i64 * 12 + from.month() as i64 - date.month() as i64
+      },
+      RecurrenceFrequency::Yearly => (from.year() - date.year()) as i64,
+    };
+    if units <= 0 {
+      return 0;
+    }
+    u32::try_from(units / self.interval.max(1) as i64).unwrap_or(u32::MAX)
+  }
+
+  /// Return the first day of the period `step` days, weeks, months or years after the first
+  /// occurrence, with the occurrences of the period in order.
+  fn period_candidates(
+    &self,
+    first: NaiveDateTime,
+    step: u32,
+    week_start: Weekday,
+  ) -> Option<(NaiveDate, Vec<NaiveDateTime>)> {
+    let date = first.date();
+    let time = first.time();
+    match self.frequency {
+      RecurrenceFrequency::Daily => {
+        let day = date.checked_add_days(Days::new(step as u64))?;
+        Some((day, vec![day.and_time(time)]))
+      },
+      RecurrenceFrequency::Weekly => {
+        let days_from_week_start =
+          (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+        let week = date
+          .checked_sub_days(Days::new(days_from_week_start as u64))?
+          .checked_add_days(Days::new(step as u64 * 7))?;
+        let weekdays = if self.by_weekday.is_empty() {
+          vec![date.weekday()]
+        } else {
+          self
+            .by_weekday
+            .iter()
+            .map(|weekday| Weekday::from(*weekday))
+            .collect()
+        };
+        let candidates = week
+          .iter_days()
+          .take(7)
+          .filter(|day| weekdays.contains(&day.weekday()))
+          .map(|day| day.and_time(time))
+          .collect();
+        Some((week, candidates))
+      },
+      RecurrenceFrequency::Monthly => {
+        let month = date.with_day(1)?.checked_add_months(Months::new(step))?;
+        let candidates = month
+          .with_day(date.day())
+          .map(|day| day.and_time(time))
+          .into_iter()
+          .collect();
+        Some((month, candidates))
+      },
+      RecurrenceFrequency::Yearly => {
+        let year = date.year().checked_add(i32::try_from(step).ok()?)?;
+        let candidates = NaiveDate::from_ymd_opt(year, date.month(), date.day())
+          .map(|day| day.and_time(time))
+          .into_iter()
+          .collect();
+        Some((NaiveDate::from_ymd_opt(year, 1, 1)?, candidates))
+      },
+    }
+  }
+}
+
+/// The timestamp of a wall clock time in the time zone. A time skipped by a daylight saving time
+/// change is read with the offset before the change.
+fn local_timestamp(timezone: &Tz, local: NaiveDateTime) -> i64 {
+  match timezone.from_local_datetime(&local).earliest() {
+    Some(date_time) => date_time.timestamp(),
+    None => {
+      let offset = timezone.offset_from_utc_datetime(&local).fix();
+      local.and_utc().timestamp() - offset.local_minus_utc() as i64
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn timestamp(year: i32, month: u32, day: u32, hour: u32) -> i64 {
+    NaiveDate::from_ymd_opt(year, month, day)
+      .unwrap()
+      .and_hms_opt(hour, 0, 0)
+      .unwrap()
+      .and_utc()
+      .timestamp()
+  }
+
+  #[test]
+  fn monthly_skips_short_months_test() {
+    let rule = RecurrenceRule::new(RecurrenceFrequency::Monthly).with_count(3);
+    let start = timestamp(2024, 1, 31, 9);
+    let occurrences = rule.occurrences(start, &Tz::UTC, Weekday::Mon, 0, i64::MAX);
+    assert_eq!(
+      occurrences,
+      vec![
+        timestamp(2024, 1, 31, 9),
+        timestamp(2024, 3, 31, 9),
+        timestamp(2024, 5, 31, 9)
+      ]
+    );
+  }
+
+  #[test]
+  fn every_other_week_depends_on_week_start_test() {
+    // Sunday 2024-03-03, repeating on Sundays and Mondays every other week
+    let rule = RecurrenceRule::new(RecurrenceFrequency::Weekly)
+      .with_interval(2)
+      .with_weekdays(vec![RecurrenceWeekday::Sunday, RecurrenceWeekday::Monday])
+      .with_count(4);
+    let start = timestamp(2024, 3, 3, 9);
+    let occurrences = rule.occurrences(start, &Tz::UTC, Weekday::Sun, 0, i64::MAX);
+    assert_eq!(
+      occurrences,
+      vec![
+        timestamp(2024, 3, 3, 9),
+        timestamp(2024, 3, 4, 9),
+        timestamp(2024, 3, 17, 9),
+        timestamp(2024, 3, 18, 9)
+      ]
+    );
+    let occurrences = rule.occurrences(start, &Tz::UTC, Weekday::Mon, 0, i64::MAX);
+    assert_eq!(
+      occurrences,
+      vec![
+        timestamp(2024, 3, 3, 9),
+        timestamp(2024, 3, 11, 9),
+        timestamp(2024, 3, 17, 9),
+        timestamp(2024, 3, 25, 9)
+      ]
+    );
+  }
+
+  #[test]
+  fn occurrences_far_from_start_test() {
+    // Monday 2024-01-01, on Mondays and Fridays every other week
+    let rule = RecurrenceRule::new(RecurrenceFrequency::Weekly)
+      .with_interval(2)
+      .with_weekdays(vec![RecurrenceWeekday::Monday, RecurrenceWeekday::Friday]);
+    let start = timestamp(2024, 1, 1, 9);
+    let first_day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let expected = NaiveDate::from_ymd_opt(3024, 1, 1)
+      .unwrap()
+      .iter_days()
+      .take(31)
+      .filter(|day| matches!(day.weekday(), Weekday::Mon | Weekday::Fri))
+      .filter(|day| (*day - first_day).num_days() / 7 % 2 == 0)
+      .map(|day| day.and_hms_opt(9, 0, 0).unwrap().and_utc().timestamp())
+      .collect::<Vec<_>>();
+    let occurrences = rule.occurrences(
+      start,
+      &Tz::UTC,
+      Weekday::Mon,
+      timestamp(3024, 1, 1, 0),
+      timestamp(3024, 2, 1, 0),
+    );
+    assert_eq!(occurrences, expected);
+
+    let rule = RecurrenceRule::new(RecurrenceFrequency::Daily);
+    let occurrences = rule.occurrences(start, &Tz::UTC, Weekday::Mon, start, i64::MAX);
+    assert_eq!(occurrences.len(), MAX_RECURRENCE_OCCURRENCES);
+  }
+
+  #[test]
+  fn daily_keeps_wall_clock_time_across_dst_test() {
+    // 9:00 in New York, the day before and the day after the switch to daylight saving time
+    let rule = RecurrenceRule::new(RecurrenceFrequency::Daily).with_count(2);
+    let start = timestamp(2024, 3, 9, 14);
+    let occurrences = rule.occurrences(start, &Tz::America__New_York, Weekday::Sun, 0, i64::MAX);
+    assert_eq!(
+      occurrences,
+      vec![timestamp(2024, 3, 9, 14), timestamp(2024, 3, 10, 13)]
+    );
+  }
+}
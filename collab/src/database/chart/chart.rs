@@ -0,0 +1,25 @@
+use crate::database::fields::select_type_option::SelectOptionColor;
+use crate::database::views::{ChartAggregationType, ChartType};
+use crate::entity::uuid_validation::RowId;
+
+/// The data of a chart view: one bucket per value of the chart's X field, in the order the
+/// chart displays them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartData {
+  pub chart_type: ChartType,
+  pub aggregation_type: ChartAggregationType,
+  pub buckets: Vec<ChartBucket>,
+}
+
+/// The rows whose X value falls in the bucket, and the aggregate of their Y values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartBucket {
+  pub id: String,
+  pub label: String,
+  /// The color of the select option the bucket stands for.
+  pub color: Option<SelectOptionColor>,
+  /// True for the bucket holding the rows without an X value. Its id is the X field's id.
+  pub is_empty_value: bool,
+  pub value: f64,
+  pub row_ids: Vec<RowId>,
+}
@@ -0,0 +1,414 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate};
+use chrono_tz::Tz;
+
+use crate::database::chart::{ChartBucket, ChartData};
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::person_type_option::PersonTypeOption;
+use crate::database::fields::select_type_option::{
+  SelectOptionColor, SelectOptionIds, SelectTypeOption,
+};
+use crate::database::fields::{
+  Field, TypeOptionCellReader, TypeOptionData, type_option_cell_reader, type_option_timezone,
+};
+use crate::database::group::{CHECKBOX_CHECKED_GROUP_ID, CHECKBOX_UNCHECKED_GROUP_ID};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::views::{ChartAggregationType, ChartDateGranularity, ChartLayoutSetting};
+use crate::entity::uuid_validation::RowId;
+
+/// The number of ranges aimed for when the size of the number ranges is picked automatically.
+const NUMBER_RANGE_COUNT: f64 = 10.0;
+
+/// Computes the data of a chart view from its [ChartLayoutSetting].
+///
+/// The rows are bucketed by the X field: select options in the field's order, checkboxes in a
+/// checked and an unchecked bucket, dates by the setting's [ChartDateGranularity] in the timezone
+/// of the field, or UTC when it has none, numbers by
+/// ranges of the setting's size, and other fields by value in the order they were found. The Y
+/// field is aggregated in each bucket; a count doesn't need a Y field.
+///
+/// Empty buckets, and the bucket of the rows without an X value, are only kept when the setting
+/// shows empty values. Every client computes the same numbers from the same rows: the values are
+/// summed in the order of the rows.
+pub struct ChartEvaluator {
+  setting: ChartLayoutSetting,
+  x_field: ChartField,
+  y_field: Option<ChartField>,
+}
+
+struct ChartField {
+  id: String,
+  name: String,
+  field_type: FieldType,
+  type_option: TypeOptionData,
+  /// The timezone the dates of the field are bucketed in.
+  timezone: Tz,
+}
+
+impl ChartField {
+  fn new(field: &Field) -> Self {
+    let field_type = FieldType::from(field.field_type);
+    let type_option = field
+      .get_any_type_option(field_type.type_id())
+      .unwrap_or_default();
+    let timezone = type_option_timezone(&type_option, &field_type);
+    Self {
+      id: field.id.clone(),
+      name: field.name.clone(),
+      field_type,
+      type_option,
+      timezone,
+    }
+  }
+
+  fn reader(&self) -> Box<dyn TypeOptionCellReader> {
+    type_option_cell_reader(self.type_option.clone(), &self.field_type)
+  }
+}
+
+/// A bucket found for a row.
+#[derive(Debug, Clone, PartialEq)]
+struct BucketKey {
+  id: String,
+  label: String,
+  color: Option<SelectOptionColor>,
+  /// Dates and numbers are ordered by value; other buckets keep the order they were found in.
+  order: Option<f64>,
+}
+
+impl BucketKey {
+  fn new(id: String, label: String) -> Self {
+    Self {
+      id,
+      label,
+      color: None,
+      order: None,
+    }
+  }
+}
+
+impl ChartEvaluator {
+  /// Return None if the X field wasn't found, or if the aggregation needs a Y field that wasn't
+  /// found.
+  pub fn new(setting: ChartLayoutSetting, fields: &[Field]) -> Option<Self> {
+    let find_field = |field_id: &str| {
+      fields
+        .iter()
+        .find(|field| field.id == field_id)
+        .map(ChartField::new)
+    };
+    let x_field = find_field(&setting.x_field_id)?;
+    let y_field = match setting.aggregation_type {
+      ChartAggregationType::Count => None,
+      _ => Some(find_field(&setting.y_field_id)?),
+    };
+    Some(Self {
+      setting,
+      x_field,
+      y_field,
+    })
+  }
+
+  /// Bucket the rows and aggregate their Y values. The rows keep their order inside each bucket.
+  pub fn chart_data(&self, rows: &[Row]) -> ChartData {
+    let x_reader = self.x_field.reader();
+    let y_reader = self.y_field.as_ref().map(|field| field.reader());
+    let range_size = self.number_range_size(x_reader.as_ref(), rows);
+
+    let mut found = self.fixed_bucket_keys();
+    let mut row_ids_by_bucket = HashMap::<String, Vec<RowId>>::new();
+    let mut numbers_by_bucket = HashMap::<String, Vec<f64>>::new();
+    for row in rows {
+      let number = self
+        .y_field
+        .as_ref()
+        .zip(y_reader.as_ref())
+        .and_then(|(field, reader)| {
+          row
+            .cells
+            .get(&field.id)
+            .and_then(|cell| reader.numeric_cell(cell))
+        });
+      for key in self.bucket_keys(x_reader.as_ref(), row, range_size) {
+        row_ids_by_bucket
+          .entry(key.id.clone())
+          .or_default()
+          .push(row.id);
+        if let Some(number) = number {
+          numbers_by_bucket
+            .entry(key.id.clone())
+            .or_default()
+            .push(number);
+        }
+        if key.id != self.x_field.id && !found.iter().any(|found_key| found_key.id == key.id) {
+          found.push(key);
+        }
+      }
+    }
+    found.sort_by(|a, b| a.order.partial_cmp(&b.order).unwrap_or(Ordering::Equal));
+    if self.has_empty_bucket() {
+      found.push(BucketKey::new(
+        self.x_field.id.clone(),
+        format!("No {}", self.x_field.name),
+      ));
+    }
+
+    let buckets = found
+      .into_iter()
+      .map(|key| {
+        let row_ids = row_ids_by_bucket.remove(&key.id).unwrap_or_default();
+        let numbers = numbers_by_bucket.remove(&key.id).unwrap_or_default();
+        ChartBucket {
+          is_empty_value: key.id == self.x_field.id && self.has_empty_bucket(),
+          value: self.aggregate(row_ids.len(), &numbers),
+          id: key.id,
+          label: key.label,
+          color: key.color,
+          row_ids,
+        }
+      })
+      .filter(|bucket| {
+        self.setting.show_empty_values || (!bucket.row_ids.is_empty() && !bucket.is_empty_value)
+      })
+      .collect();
+    ChartData {
+      chart_type: self.setting.chart_type,
+      aggregation_type: self.setting.aggregation_type,
+      buckets,
+    }
+  }
+
+  fn aggregate(&self, row_count: usize, numbers: &[f64]) -> f64 {
+    let sum = || numbers.iter().sum::<f64>();
+    match self.setting.aggregation_type {
+      ChartAggregationType::Count => row_count as f64,
+      ChartAggregationType::Sum => sum(),
+      ChartAggregationType::Average if numbers.is_empty() => 0.0,
+      ChartAggregationType::Average => sum() / numbers.len() as f64,
+      ChartAggregationType::Min => numbers.iter().copied().reduce(f64::min).unwrap_or_default(),
+      ChartAggregationType::Max => numbers.iter().copied().reduce(f64::max).unwrap_or_default(),
+    }
+  }
+
+  fn has_empty_bucket(&self) -> bool {
+    self.x_field.field_type != FieldType::Checkbox
+  }
+
+  /// The buckets that exist whether or not a row falls in them.
+  fn fixed_bucket_keys(&self) -> Vec<BucketKey> {
+    match self.x_field.field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        SelectTypeOption::from(self.x_field.type_option.clone())
+          .options
+          .into_iter()
+          .map(|option| BucketKey {
+            id: option.id,
+            label: option.name,
+            color: Some(option.color),
+            order: None,
+          })
+          .collect()
+      },
+      FieldType::Checkbox => [CHECKBOX_CHECKED_GROUP_ID, CHECKBOX_UNCHECKED_GROUP_ID]
+        .into_iter()
+        .map(|id| BucketKey::new(id.to_string(), id.to_string()))
+        .collect(),
+      _ => vec![],
+    }
+  }
+
+  fn bucket_keys(
+    &self,
+    reader: &dyn TypeOptionCellReader,
+    row: &Row,
+    range_size: f64,
+  ) -> Vec<BucketKey> {
+    let empty_cell = Cell::new();
+    let cell = row.cells.get(&self.x_field.id).unwrap_or(&empty_cell);
+    let keys = match self.x_field.field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let options = self.fixed_bucket_keys();
+        SelectOptionIds::from(cell)
+          .iter()
+          .flat_map(|id| options.iter().find(|option| &option.id == id).cloned())
+          .collect()
+      },
+      FieldType::Checkbox => {
+        let id = if reader.numeric_cell(cell) == Some(1.0) {
+          CHECKBOX_CHECKED_GROUP_ID
+        } else {
+          CHECKBOX_UNCHECKED_GROUP_ID
+        };
+        vec![BucketKey::new(id.to_string(), id.to_string())]
+      },
+      FieldType::Number => reader
+        .numeric_cell(cell)
+        .map(|number| number_key(number, range_size))
+        .into_iter()
+        .collect(),
+      FieldType::DateTime => DateCellData::from(cell)
+        .timestamp
+        .and_then(|timestamp| self.date_key(timestamp))
+        .into_iter()
+        .collect(),
+      FieldType::CreatedTime => self.date_key(row.created_at).into_iter().collect(),
+      FieldType::LastEditedTime => self.date_key(row.modified_at).into_iter().collect(),
+      FieldType::Person => {
+        let type_option = PersonTypeOption::from(self.x_field.type_option.clone());
+        PersonCellData::from(cell)
+          .person_ids
+          .into_iter()
+          .map(|id| {
+            let name = type_option.person_name(&id);
+            BucketKey::new(id, name)
+          })
+          .collect()
+      },
+      _ => {
+        let text = if cell.contains_key(CELL_DATA) {
+          reader.stringify_cell(cell).trim().to_string()
+        } else {
+          String::new()
+        };
+        if text.is_empty() {
+          vec![]
+        } else {
+          vec![BucketKey::new(text.clone(), text)]
+        }
+      },
+    };
+
+    if keys.is_empty() && self.has_empty_bucket() {
+      vec![BucketKey::new(self.x_field.id.clone(), String::new())]
+    } else {
+      keys
+    }
+  }
+
+  fn date_key(&self, timestamp: i64) -> Option<BucketKey> {
+    let date = DateTime::from_timestamp(timestamp, 0)?
+      .with_timezone(&self.x_field.timezone)
+      .date_naive();
+    let (start, id, label) = match self.setting.date_granularity {
+      ChartDateGranularity::Day => (
+        date,
+        date.format("%Y/%m/%d").to_string(),
+        date.format("%b %-d, %Y").to_string(),
+      ),
+      ChartDateGranularity::Week => {
+        let start =
+          date.checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))?;
+        (
+          start,
+          start.format("%Y/%m/%d").to_string(),
+          format!("Week of {}", start.format("%b %-d, %Y")),
+        )
+      },
+      ChartDateGranularity::Month => {
+        let start = date.with_day(1)?;
+        (
+          start,
+          start.format("%Y/%m").to_string(),
+          start.format("%b %Y").to_string(),
+        )
+      },
+      ChartDateGranularity::Year => {
+        let start = NaiveDate::from_ymd_opt(date.year(), 1, 1)?;
+        let id = start.format("%Y").to_string();
+        (start, id.clone(), id)
+      },
+    };
+    Some(BucketKey {
+      id,
+      label,
+      color: None,
+      order: Some(start.num_days_from_ce() as f64),
+    })
+  }
+
+  /// The size of the number ranges: the setting's size, or a round size giving about
+  /// [NUMBER_RANGE_COUNT] ranges over the rows' values.
+  fn number_range_size(&self, reader: &dyn TypeOptionCellReader, rows: &[Row]) -> f64 {
+    if self.x_field.field_type != FieldType::Number {
+      return 0.0;
+    }
+    if self.setting.number_range_size > 0.0 {
+      return self.setting.number_range_size;
+    }
+    let (min, max) = rows
+      .iter()
+      .flat_map(|row| row.cells.get(&self.x_field.id))
+      .flat_map(|cell| reader.numeric_cell(cell))
+      .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), number| {
+        (min.min(number), max.max(number))
+      });
+    round_range_size((max - min) / NUMBER_RANGE_COUNT)
+  }
+}
+
+/// Round the size up to 1, 2 or 5 times a power of ten. Sizes that aren't positive give 1.
+fn round_range_size(size: f64) -> f64 {
+  if !size.is_finite() || size <= 0.0 {
+    return 1.0;
+  }
+  let magnitude = 10f64.powi(size.log10().floor() as i32);
+  let normalized = size / magnitude;
+  let step = if normalized <= 1.0 {
+    1.0
+  } else if normalized <= 2.0 {
+    2.0
+  } else if normalized <= 5.0 {
+    5.0
+  } else {
+    10.0
+  };
+  round_number(step * magnitude)
+}
+
+fn number_key(number: f64, range_size: f64) -> BucketKey {
+  let start = round_number((number / range_size).floor() * range_size);
+  let end = round_number(start + range_size);
+  BucketKey {
+    id: start.to_string(),
+    label: format!("{} - {}", start, end),
+    color: None,
+    order: Some(start),
+  }
+}
+
+/// Drop the floating point noise of the range bounds, e.g. 0.30000000000000004, and the sign
+/// of -0.
+fn round_number(number: f64) -> f64 {
+  (number * 1e9).round() / 1e9 + 0.0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_range_size_test() {
+    assert_eq!(round_range_size(0.0), 1.0);
+    assert_eq!(round_range_size(f64::NEG_INFINITY), 1.0);
+    assert_eq!(round_range_size(7.3), 10.0);
+    assert_eq!(round_range_size(13.0), 20.0);
+    assert_eq!(round_range_size(0.04), 0.05);
+    assert_eq!(round_range_size(100.0), 100.0);
+  }
+
+  #[test]
+  fn number_key_test() {
+    let key = number_key(27.5, 10.0);
+    assert_eq!(key.id, "20");
+    assert_eq!(key.label, "20 - 30");
+    let key = number_key(-3.0, 5.0);
+    assert_eq!(key.label, "-5 - 0");
+    let key = number_key(0.35, 0.1);
+    assert_eq!(key.label, "0.3 - 0.4");
+  }
+}
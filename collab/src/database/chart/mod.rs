@@ -0,0 +1,5 @@
+mod chart;
+mod evaluator;
+
+pub use chart::*;
+pub use evaluator::*;
@@ -0,0 +1,55 @@
+use crate::database::entity::FieldType;
+use crate::database::fields::TypeOptionData;
+use crate::database::rows::Cell;
+use crate::entity::uuid_validation::RowId;
+use serde::{Deserialize, Serialize};
+
+/// A value that couldn't be converted to the new field type. Its cell is cleared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversionFailure {
+  pub row_id: RowId,
+  /// The readable value of the cell before the conversion.
+  pub value: String,
+}
+
+/// The result of [Database::convert_field_type](crate::database::database::Database::convert_field_type).
+/// It keeps the field's type option and the cells as they were before the conversion, so that
+/// [Database::undo_field_conversion](crate::database::database::Database::undo_field_conversion)
+/// can restore them. It can be serialized to keep the undo across sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldConversion {
+  pub field_id: String,
+  pub from_type: FieldType,
+  pub to_type: FieldType,
+  pub updated_row_ids: Vec<RowId>,
+  pub failures: Vec<ConversionFailure>,
+  /// The type option the field had for `to_type`, if any.
+  pub(crate) previous_type_option: Option<TypeOptionData>,
+  pub(crate) converted_cells: Vec<ConvertedCell>,
+}
+
+/// A cell written by a conversion, with the cell it replaced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ConvertedCell {
+  pub(crate) row_id: RowId,
+  pub(crate) previous: Option<Cell>,
+  pub(crate) converted: Option<Cell>,
+}
+
+impl FieldConversion {
+  pub(crate) fn new(field_id: &str, from_type: FieldType, to_type: FieldType) -> Self {
+    Self {
+      field_id: field_id.to_string(),
+      from_type,
+      to_type,
+      updated_row_ids: vec![],
+      failures: vec![],
+      previous_type_option: None,
+      converted_cells: vec![],
+    }
+  }
+
+  pub fn is_lossless(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
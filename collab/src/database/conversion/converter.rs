@@ -0,0 +1,353 @@
+use crate::database::entity::{FieldType, default_type_option_data_from_type};
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::number_type_option::CURRENCY_SYMBOL;
+use crate::database::fields::select_type_option::{
+  SELECTION_IDS_SEPARATOR, SelectOption, SelectOptionColor, SelectOptionIds, SelectTypeOption,
+};
+use crate::database::fields::{
+  Field, TypeOptionCellReader, TypeOptionCellWriter, TypeOptionData, type_option_cell_reader,
+  type_option_cell_writer,
+};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::check_list_parse::ChecklistCellData;
+use crate::database::template::date_parse::cast_string_to_timestamp;
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::number_parse::NumberCellData;
+use crate::database::template::option_parse::SELECT_OPTION_COLOR_COUNT;
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::template::time_parse::TimeCellData;
+use crate::database::template::util::{ToCellString, TypeOptionCellData};
+use serde_json::{Value, json};
+
+/// The result of converting a cell to the new field type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellConversion {
+  Converted(Cell),
+  /// The cell is empty, or the new field type computes its value, e.g. a formula.
+  Cleared,
+  /// The value can't be represented by the new field type.
+  Failed,
+}
+
+/// Converts the cells of a field to another field type.
+///
+/// Most cells are converted through their readable text: the cell is read with the reader of
+/// the old type, and the text is parsed into the new type, e.g. a date is parsed with
+/// [cast_string_to_timestamp] and a multi-select splits the text on commas. The conversions
+/// between select types keep the option ids, and the created and last edited times are read
+/// from the row.
+///
+/// When converting to a select type, the options are created from the distinct values with
+/// [FieldConverter::add_options] before converting the cells.
+pub struct FieldConverter {
+  field_id: String,
+  from_type: FieldType,
+  to_type: FieldType,
+  reader: Box<dyn TypeOptionCellReader>,
+  writer: Box<dyn TypeOptionCellWriter>,
+  type_option: TypeOptionData,
+  /// The options of the field when it's converted to a select type.
+  select_type_option: SelectTypeOption,
+}
+
+impl FieldConverter {
+  pub fn new(field: &Field, to_type: FieldType) -> Self {
+    let from_type = FieldType::from(field.field_type);
+    let from_type_option = field
+      .get_any_type_option(from_type.type_id())
+      .unwrap_or_else(|| default_type_option_data_from_type(from_type));
+    let type_option = field
+      .get_any_type_option(to_type.type_id())
+      .unwrap_or_else(|| default_type_option_data_from_type(to_type));
+
+    let mut select_type_option = SelectTypeOption::from(type_option.clone());
+    if is_select(from_type) && is_select(to_type) {
+      for option in SelectTypeOption::from(from_type_option.clone()).options {
+        if !select_type_option
+          .options
+          .iter()
+          .any(|existing| existing.id == option.id)
+        {
+          select_type_option.options.push(option);
+        }
+      }
+    }
+
+    Self {
+      field_id: field.id.clone(),
+      from_type,
+      to_type,
+      reader: type_option_cell_reader(from_type_option, &from_type),
+      writer: type_option_cell_writer(type_option.clone(), &to_type),
+      type_option,
+      select_type_option,
+    }
+  }
+
+  /// Whether the row has a value to convert. The created and last edited times are read from
+  /// the row, the other types from the row's cell.
+  pub fn has_value(&self, row: &Row) -> bool {
+    matches!(
+      self.from_type,
+      FieldType::CreatedTime | FieldType::LastEditedTime
+    ) || row.cells.contains_key(&self.field_id)
+  }
+
+  /// Return the readable text of the row's value.
+  pub fn read_text(&self, row: &Row) -> String {
+    let timestamp = match self.from_type {
+      FieldType::CreatedTime => Some(row.created_at),
+      FieldType::LastEditedTime => Some(row.modified_at),
+      _ => None,
+    };
+    if let Some(timestamp) = timestamp {
+      return self.reader.convert_raw_cell_data(&timestamp.to_string());
+    }
+
+    let Some(cell) = row.cells.get(&self.field_id) else {
+      return String::new();
+    };
+    match self.from_type {
+      FieldType::Relation => RelationCellData::from(cell).to_cell_string(),
+      _ => self.reader.stringify_cell(cell),
+    }
+  }
+
+  /// Create a select option for each distinct value that doesn't match an option by name.
+  /// When converting to a multi-select, the values are split on commas.
+  pub fn add_options<'a>(&mut self, texts: impl IntoIterator<Item = &'a str>) {
+    if !is_select(self.to_type) || is_select(self.from_type) {
+      return;
+    }
+    for text in texts {
+      for name in self.option_names(text) {
+        let options = &mut self.select_type_option.options;
+        if !options.iter().any(|option| option.name == name) {
+          let color = SelectOptionColor::from(options.len() % SELECT_OPTION_COLOR_COUNT);
+          options.push(SelectOption::with_color(&name, color));
+        }
+      }
+    }
+  }
+
+  /// Return the type option of the field for the new type.
+  pub fn type_option_data(&self) -> TypeOptionData {
+    if is_select(self.to_type) {
+      self.select_type_option.clone().into()
+    } else {
+      self.type_option.clone()
+    }
+  }
+
+  /// Convert the row's value, whose readable text is `text`, to the new type.
+  pub fn convert(&self, row: &Row, text: &str) -> CellConversion {
+    match (self.from_type, self.to_type) {
+      (FieldType::CreatedTime, FieldType::DateTime) => {
+        return converted(Cell::from(&DateCellData::from_timestamp(row.created_at)));
+      },
+      (FieldType::LastEditedTime, FieldType::DateTime) => {
+        return converted(Cell::from(&DateCellData::from_timestamp(row.modified_at)));
+      },
+      (from_type, to_type) if is_select(from_type) && is_select(to_type) => {
+        let Some(cell) = row.cells.get(&self.field_id) else {
+          return CellConversion::Cleared;
+        };
+        let mut ids = SelectOptionIds::from(cell).into_inner();
+        if to_type == FieldType::SingleSelect {
+          ids.truncate(1);
+        }
+        if ids.is_empty() {
+          return CellConversion::Cleared;
+        }
+        return converted(SelectOptionIds::from(ids).to_cell(to_type));
+      },
+      _ => {},
+    }
+
+    let text = text.trim();
+    if text.is_empty() {
+      return CellConversion::Cleared;
+    }
+    match self.to_type {
+      FieldType::RichText | FieldType::URL | FieldType::Summary | FieldType::Translate => {
+        converted(self.writer.convert_json_to_cell(json!(text)))
+      },
+      FieldType::Number => match parse_number(text) {
+        Some(number) => converted(NumberCellData(number).into()),
+        None => CellConversion::Failed,
+      },
+      FieldType::Time => match parse_number(text).and_then(|number| number.parse::<i64>().ok()) {
+        Some(number) => converted(Cell::from(&TimeCellData(Some(number)))),
+        None => CellConversion::Failed,
+      },
+      FieldType::DateTime => match cast_string_to_timestamp(text) {
+        Some(timestamp) => converted(Cell::from(&DateCellData::from_timestamp(timestamp))),
+        None => CellConversion::Failed,
+      },
+      FieldType::Checkbox => match text.to_lowercase().as_str() {
+        "1" | "true" | "yes" => converted(self.writer.convert_json_to_cell(Value::Bool(true))),
+        "0" | "false" | "no" => converted(self.writer.convert_json_to_cell(Value::Bool(false))),
+        _ => CellConversion::Failed,
+      },
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let ids = self
+          .option_names(text)
+          .into_iter()
+          .filter_map(|name| {
+            self
+              .select_type_option
+              .options
+              .iter()
+              .find(|option| option.name == name)
+              .map(|option| option.id.clone())
+          })
+          .collect::<Vec<_>>();
+        if ids.is_empty() {
+          CellConversion::Failed
+        } else {
+          converted(SelectOptionIds::from(ids).to_cell(self.to_type))
+        }
+      },
+      FieldType::Checklist => {
+        let names = split_values(text);
+        converted(ChecklistCellData::from((names, vec![])).into())
+      },
+      FieldType::Relation => {
+        let row_ids = split_values(text)
+          .iter()
+          .map(|id| uuid::Uuid::parse_str(id))
+          .collect::<Result<Vec<_>, _>>();
+        match row_ids {
+          Ok(row_ids) => converted(Cell::from(RelationCellData { row_ids })),
+          Err(_) => CellConversion::Failed,
+        }
+      },
+      FieldType::AutoNumber => {
+        let cell = self.writer.convert_json_to_cell(json!(text));
+        if cell.contains_key(CELL_DATA) {
+          converted(cell)
+        } else {
+          CellConversion::Failed
+        }
+      },
+      // The values of these types are computed
+      FieldType::CreatedTime
+      | FieldType::LastEditedTime
+      | FieldType::Rollup
+      | FieldType::Formula => CellConversion::Cleared,
+      FieldType::Person => {
+        let cell = self.writer.convert_json_to_cell(json!(text));
+        if PersonCellData::from(&cell).is_cell_empty() {
+          CellConversion::Failed
+        } else {
+          converted(cell)
+        }
+      },
+      FieldType::Media => CellConversion::Failed,
+    }
+  }
+
+  fn option_names(&self, text: &str) -> Vec<String> {
+    if self.to_type == FieldType::MultiSelect {
+      split_values(text)
+    } else {
+      let name = text.trim();
+      if name.is_empty() {
+        vec![]
+      } else {
+        vec![name.to_string()]
+      }
+    }
+  }
+}
+
+fn converted(cell: Cell) -> CellConversion {
+  CellConversion::Converted(cell)
+}
+
+fn is_select(field_type: FieldType) -> bool {
+  field_type.is_select_option()
+}
+
+/// Split a text on commas, dropping the empty values.
+fn split_values(text: &str) -> Vec<String> {
+  text
+    .split(SELECTION_IDS_SEPARATOR)
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// Parse a number, ignoring the currency symbol, the thousands separators and the whitespace of
+/// formatted numbers such as "$1,234.50" or "-€ 12". A text holding any other character, such as
+/// "Room 101", "v2 release" or "1.234,50", isn't a number.
+fn parse_number(text: &str) -> Option<String> {
+  let (is_negative, text) = strip_minus(text.trim());
+  let text = strip_currency_symbol(text.trim()).trim();
+  let (is_negative_after_symbol, text) = strip_minus(text);
+  if is_negative && is_negative_after_symbol {
+    return None;
+  }
+
+  let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+  let integer = remove_thousands_separators(integer)?;
+  let is_number = !(integer.is_empty() && fraction.is_empty())
+    && integer
+      .chars()
+      .chain(fraction.chars())
+      .all(|c| c.is_ascii_digit());
+  if !is_number {
+    return None;
+  }
+  let sign = if is_negative || is_negative_after_symbol {
+    "-"
+  } else {
+    ""
+  };
+  let number = if fraction.is_empty() {
+    format!("{}{}", sign, integer)
+  } else {
+    format!("{}{}.{}", sign, integer, fraction)
+  };
+  number.parse::<f64>().ok().map(|_| number)
+}
+
+fn strip_minus(text: &str) -> (bool, &str) {
+  match text.strip_prefix('-') {
+    Some(text) => (true, text.trim_start()),
+    None => (false, text),
+  }
+}
+
+/// Remove the symbol of a [NumberFormat](crate::database::fields::number_type_option::NumberFormat)
+/// at the start or at the end of the text. The longest matching symbol is removed, so "CA$" is
+/// preferred over "$".
+fn strip_currency_symbol(text: &str) -> &str {
+  CURRENCY_SYMBOL
+    .iter()
+    .filter(|symbol| !symbol.is_empty())
+    .filter_map(|symbol| {
+      text
+        .strip_prefix(symbol.as_str())
+        .or_else(|| text.strip_suffix(symbol.as_str()))
+    })
+    .min_by_key(|text| text.len())
+    .unwrap_or(text)
+}
+
+/// Remove the thousands separators, commas or whitespace, of the integer part of a number. Return
+/// None when they don't separate groups of three digits, e.g. "1234,5".
+fn remove_thousands_separators(integer: &str) -> Option<String> {
+  let mut groups = integer.split(|c: char| c == ',' || c.is_whitespace());
+  let first = groups.next().unwrap_or_default();
+  let mut integer = first.to_string();
+  for group in groups {
+    if first.is_empty() || first.len() > 3 || group.len() != 3 {
+      return None;
+    }
+    integer.push_str(group);
+  }
+  Some(integer)
+}
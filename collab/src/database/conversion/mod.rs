@@ -0,0 +1,5 @@
+mod conversion;
+mod converter;
+
+pub use conversion::*;
+pub use converter::*;
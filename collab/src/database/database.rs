@@ -1,27 +1,52 @@
 use std::borrow::{Borrow, BorrowMut};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::io;
 use std::ops::{Deref, DerefMut};
 
 use anyhow::anyhow;
 
+use crate::database::auto_number::AutoNumberReservations;
 use crate::database::blocks::{Block, BlockEvent, InitRowChan};
+use crate::database::calculation::{Calculation, CalculationEvaluator};
+use crate::database::calendar::{CalendarEvaluator, CalendarEvent};
+use crate::database::chart::{ChartData, ChartEvaluator};
+use crate::database::conversion::{
+  CellConversion, ConversionFailure, ConvertedCell, FieldConversion, FieldConverter,
+};
 use crate::database::database_state::DatabaseNotify;
+use crate::database::fields::auto_number_type_option::{
+  AutoNumberCellData, auto_number_type_option,
+};
+use crate::database::fields::formula_type_option::{FormulaResultType, FormulaTypeOption};
+use crate::database::fields::person_type_option::PersonTypeOption;
+use crate::database::fields::relation_type_option::RelationTypeOption;
+use crate::database::fields::rollup_type_option::RollupTypeOption;
 use crate::database::fields::{
-  Field, FieldChangeReceiver, FieldMap, FieldUpdate, TypeOptionCellReader, TypeOptionCellWriter,
-  type_option_cell_reader, type_option_cell_writer,
+  Field, FieldChangeReceiver, FieldMap, FieldSettings, FieldUpdate, FieldVisibility,
+  TypeOptionCellReader, TypeOptionCellWriter, type_option_cell_reader, type_option_cell_writer,
 };
+use crate::database::filter::{Filter, FilterEvaluator};
+use crate::database::formula::{Formula, FormulaEvaluator, RelatedValues};
+use crate::database::group::{GroupData, GroupEvaluator};
 use crate::database::meta::MetaMap;
+use crate::database::query::{DatabaseQuery, QueryEvaluator, QueryPage};
+use crate::database::rollup::{RollupResolver, rollup_cell};
 use crate::database::rows::{
-  CreateRowParams, CreateRowParamsValidator, DatabaseRow, Row, RowCell, RowChangeReceiver,
-  RowDetail, RowMeta, RowMetaKey, RowMetaUpdate, RowUpdate, meta_id_from_row_id,
+  Cell, Cells, CreateRowParams, CreateRowParamsValidator, DEFAULT_ROW_BATCH_CHUNK_SIZE,
+  DatabaseRow, Row, RowBatchReport, RowCell, RowChange, RowChangeReceiver, RowDetail, RowMeta,
+  RowMetaKey, RowMetaUpdate, RowPatch, RowUpdate, cell_content, meta_id_from_row_id,
 };
+use crate::database::sort::{Sort, SortEvaluator};
+use crate::database::timeline::{TimelineBar, TimelineEvaluator};
 use crate::database::util::encoded_collab;
+use crate::database::validation::{ConstraintValidator, ConstraintViolation};
 use crate::database::views::define::DATABASE_VIEW_ROW_ORDERS;
 use crate::database::views::{
-  CalculationMap, DatabaseLayout, DatabaseViewUpdate, DatabaseViews, FieldOrder,
-  FieldSettingsByFieldIdMap, FieldSettingsMap, FilterMap, GroupSettingMap, LayoutSetting,
-  OrderArray, OrderObjectPosition, RowOrder, RowOrderArray, SortMap, ViewChangeReceiver,
+  CalculationMap, CalendarLayoutSetting, ChartLayoutSetting, DatabaseLayout, DatabaseViewUpdate,
+  DatabaseViews, FieldOrder, FieldSettingsByFieldIdMap, FieldSettingsMap, FilterMap, GroupSetting,
+  GroupSettingMap, LayoutSetting, OrderArray, OrderObjectPosition, RowOrder, RowOrderArray,
+  SortMap, TimelineLayoutSetting, ViewChangeReceiver,
 };
 use crate::database::workspace_database::DatabaseMeta;
 use crate::entity::uuid_validation::{RowId, try_parse_database_view_id};
@@ -31,7 +56,14 @@ use crate::database::entity::{
   CreateDatabaseParams, CreateViewParams, CreateViewParamsValidator, DatabaseView,
   DatabaseViewMeta, EncodedCollabInfo, EncodedDatabase, FieldType,
 };
-use crate::database::template::entity::DatabaseTemplate;
+use crate::database::template::csv_export::{CSVExportOptions, CSVExporter, RelationTitles};
+use crate::database::template::csv_import::{
+  CSVImportFailure, CSVImportMode, CSVImportOptions, CSVImportPlan, CSVImportReport,
+};
+use crate::database::template::entity::{CELL_DATA, DatabaseTemplate};
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::template::util::TypeOptionCellData;
 
 use crate::core::origin::CollabOrigin;
 use crate::entity::CollabType;
@@ -42,7 +74,7 @@ use crate::preclude::{
   Any, Array, Collab, FillRef, JsonValue, Map, MapExt, MapPrelim, MapRef, ReadTxn, ToJson,
   TransactionMut, YrsValue,
 };
-use crate::util::{AnyExt, ArrayExt};
+use crate::util::{AnyExt, AnyMapExt, ArrayExt};
 
 use futures::stream;
 use futures::stream::{BoxStream, StreamExt};
@@ -416,7 +448,9 @@ impl Database {
   /// created successfully. Otherwise, return None.
   pub async fn create_row(&mut self, params: CreateRowParams) -> Result<RowOrder, CollabError> {
     let client_id = self.collab_service.database_client_id().await;
-    let params = CreateRowParamsValidator::validate(params)?;
+    let mut params = CreateRowParamsValidator::validate(params)?;
+    self.fill_persons_with_creator(&mut params);
+    self.assign_auto_numbers(std::slice::from_mut(&mut params));
     let row_order = self.body.block.create_new_row(params, client_id).await?;
     let mut txn = self.collab.transact_mut();
     self
@@ -456,8 +490,10 @@ impl Database {
   pub async fn create_row_in_view(
     &mut self,
     view_id: &str,
-    params: CreateRowParams,
+    mut params: CreateRowParams,
   ) -> Result<(usize, RowOrder), CollabError> {
+    self.fill_persons_with_creator(&mut params);
+    self.assign_auto_numbers(std::slice::from_mut(&mut params));
     let client_id = self.collab_service.database_client_id().await;
     let row_position = params.row_position.clone();
     let row_order = self.body.create_row(params, client_id).await?;
@@ -477,7 +513,8 @@ impl Database {
   }
 
   /// Remove the row
-  /// The [RowOrder] of each view representing this row will be removed.
+  /// The [RowOrder] of each view representing this row will be removed, and the row is removed
+  /// from the relation cells that link to it, see [Database::remove_relation_links].
   pub async fn remove_row(&mut self, row_id: &RowId) {
     {
       let mut txn = self.collab.transact_mut();
@@ -485,6 +522,7 @@ impl Database {
         update.remove_row_order(&row_id.to_string());
       });
     };
+    self.remove_relation_links(&[*row_id]).await;
   }
 
   pub async fn move_row(&mut self, from_row_id: &str, to_row_id: &str) {
@@ -494,18 +532,568 @@ impl Database {
     });
   }
 
-  pub async fn remove_rows(&mut self, row_ids: &[RowId]) {
+  /// Remove the rows from the views in a single transaction. A row that isn't in the database is
+  /// reported as a failure. The removed rows are then removed from the relation cells that link
+  /// to them, see [Database::remove_relation_links].
+  pub async fn remove_rows(&mut self, row_ids: &[RowId]) -> RowBatchReport {
+    let mut existing_row_ids = self
+      .get_all_row_orders()
+      .await
+      .into_iter()
+      .map(|row_order| row_order.id)
+      .collect::<HashSet<_>>();
+    let mut report = RowBatchReport::default();
+    for row_id in row_ids {
+      if existing_row_ids.remove(row_id) {
+        report.row_ids.push(*row_id);
+      } else {
+        report.fail(
+          *row_id,
+          &CollabError::DatabaseRowNotFound {
+            row_id: *row_id,
+            reason: "the row isn't in the database".to_string(),
+          },
+        );
+      }
+    }
+    if report.row_ids.is_empty() {
+      return report;
+    }
+
     {
       let mut txn = self.collab.transact_mut();
       self.body.views.update_all_views(&mut txn, |_, mut update| {
-        for row_id in row_ids {
+        for row_id in &report.row_ids {
           update = update.remove_row_order(&row_id.to_string());
         }
       });
     };
+    self.remove_relation_links(&report.row_ids).await;
+    report
+  }
+
+  /// Create the rows, `chunk_size` rows at once, then insert them into every view at their
+  /// [CreateRowParams::row_position] in a single transaction. A row that can't be created is
+  /// reported as a failure without stopping the others.
+  pub async fn create_rows(
+    &mut self,
+    params: Vec<CreateRowParams>,
+    chunk_size: usize,
+  ) -> RowBatchReport {
+    let client_id = self.collab_service.database_client_id().await;
+    let mut report = RowBatchReport::default();
+    let mut valid_params = Vec::with_capacity(params.len());
+    for params in params {
+      let row_id = params.id;
+      let mut params = match CreateRowParamsValidator::validate(params) {
+        Ok(params) => params,
+        Err(err) => {
+          report.fail(row_id, &err);
+          continue;
+        },
+      };
+      self.fill_persons_with_creator(&mut params);
+      valid_params.push(params);
+    }
+    self.assign_auto_numbers(&mut valid_params);
+
+    let block = &self.body.block;
+    let mut row_orders = vec![];
+    let mut valid_params = valid_params.into_iter().peekable();
+    while valid_params.peek().is_some() {
+      let tasks = valid_params
+        .by_ref()
+        .take(chunk_size.max(1))
+        .map(|params| async move {
+          let row_id = params.id;
+          let row_position = params.row_position.clone();
+          (
+            row_id,
+            row_position,
+            block.create_new_row(params, client_id).await,
+          )
+        })
+        .collect::<Vec<_>>();
+      for (row_id, row_position, result) in join_all(tasks).await {
+        match result {
+          Ok(row_order) => row_orders.push((row_order, row_position)),
+          Err(err) => report.fail(row_id, &err),
+        }
+      }
+    }
+
+    if !row_orders.is_empty() {
+      let mut txn = self.collab.transact_mut();
+      self
+        .body
+        .views
+        .update_all_views(&mut txn, |_view_id, mut update| {
+          for (row_order, row_position) in &row_orders {
+            update = update.insert_row_order(row_order, row_position);
+          }
+        });
+    }
+    report.row_ids = row_orders
+      .into_iter()
+      .map(|(row_order, _)| row_order.id)
+      .collect();
+    report
+  }
+
+  /// Apply the patches to the rows, loading `chunk_size` rows at once. All the patches of a row
+  /// are applied in order in a single transaction of the row's collab. A row that can't be
+  /// loaded is reported as a failure without stopping the others.
+  ///
+  /// A single [RowChange::DidUpdateRows] is sent with the updated rows, after the
+  /// [RowChange::DidUpdateCell] of each changed cell, which have their `in_batch` set. Listen to
+  /// one or the other: a listener that refreshes whole rows can handle the batch event and skip
+  /// the cell events that are `in_batch`.
+  ///
+  /// Like [Database::update_row], the relation cells are written without updating the reverse
+  /// cells of their relation, see [Database::update_relation_cell].
+  pub async fn update_rows(&mut self, patches: Vec<RowPatch>, chunk_size: usize) -> RowBatchReport {
+    self.patch_rows(patches, chunk_size, None).await
+  }
+
+  /// Like [Database::update_rows], but the patches of a row are only applied if the changed
+  /// cells satisfy their fields' [FieldConstraints](crate::database::fields::FieldConstraints),
+  /// like [Database::update_row_strict]. A row whose changed cells violate a constraint is left
+  /// unchanged and reported as a failure with [CollabError::DatabaseConstraintViolation]. The
+  /// values of the unique fields are compared with the rows as the previous patches of the batch
+  /// left them.
+  ///
+  /// Return an error if the rows of the database can't be loaded to check the unique fields.
+  pub async fn update_rows_strict(
+    &mut self,
+    patches: Vec<RowPatch>,
+    chunk_size: usize,
+    auto_fetch: bool,
+  ) -> Result<RowBatchReport, CollabError> {
+    let fields = self.get_all_fields();
+    if !has_constraints(&fields) {
+      return Ok(self.patch_rows(patches, chunk_size, None).await);
+    }
+    let rows = if has_unique_field(&fields) {
+      self.get_all_database_rows(auto_fetch).await?
+    } else {
+      vec![]
+    };
+    Ok(
+      self
+        .patch_rows(patches, chunk_size, Some((fields, rows)))
+        .await,
+    )
+  }
+
+  /// Apply the patches of [Database::update_rows]. With the fields and the rows of the database,
+  /// the patches that break a constraint are skipped, see [Database::update_rows_strict].
+  async fn patch_rows(
+    &mut self,
+    patches: Vec<RowPatch>,
+    chunk_size: usize,
+    mut validation: Option<(Vec<Field>, Vec<Row>)>,
+  ) -> RowBatchReport {
+    let mut row_patches: Vec<RowPatch> = vec![];
+    let mut index_by_row_id = HashMap::new();
+    for patch in patches {
+      match index_by_row_id.get(&patch.row_id) {
+        Some(&index) => row_patches[index].cells.extend(patch.cells),
+        None => {
+          index_by_row_id.insert(patch.row_id, row_patches.len());
+          row_patches.push(patch);
+        },
+      }
+    }
+
+    let mut report = RowBatchReport::default();
+    let mut row_patches = row_patches.into_iter().peekable();
+    while row_patches.peek().is_some() {
+      let chunk = row_patches
+        .by_ref()
+        .take(chunk_size.max(1))
+        .collect::<Vec<_>>();
+      let row_ids = chunk.iter().map(|patch| patch.row_id).collect::<Vec<_>>();
+      let mut rows = HashMap::new();
+      match self.body.block.init_database_rows(row_ids, true).await {
+        Ok(database_rows) => {
+          for database_row in database_rows {
+            let row_id = database_row.read().await.row_id;
+            rows.insert(row_id, Ok(database_row));
+          }
+        },
+        // A single row that can't be loaded fails the whole chunk, so load the rows one by one
+        // to find it.
+        Err(_) => {
+          for patch in &chunk {
+            let result = self
+              .body
+              .block
+              .get_or_init_database_row(&patch.row_id)
+              .await;
+            rows.insert(patch.row_id, result);
+          }
+        },
+      }
+
+      let mut loaded = vec![];
+      for patch in chunk {
+        let row_id = patch.row_id;
+        match rows.remove(&row_id) {
+          Some(Ok(database_row)) => {
+            let row = match &validation {
+              Some(_) => database_row.read().await.get_row(),
+              None => None,
+            };
+            loaded.push((patch, database_row, row));
+          },
+          Some(Err(err)) => report.fail(row_id, &err),
+          None => report.fail(
+            row_id,
+            &CollabError::DatabaseRowNotFound {
+              row_id,
+              reason: "the row can't be loaded".to_string(),
+            },
+          ),
+        }
+      }
+
+      if let Some((fields, database_rows)) = validation.as_mut() {
+        let validator = ConstraintValidator::new(fields.clone());
+        loaded.retain(|(patch, _, row)| {
+          let mut row = row
+            .clone()
+            .unwrap_or_else(|| Row::empty(patch.row_id, Uuid::nil()));
+          patch.apply_to_cells(&mut row.cells);
+          let violations = validator
+            .validate_row(&row, database_rows)
+            .into_iter()
+            .filter(|violation| {
+              patch
+                .field_ids()
+                .any(|field_id| field_id == violation.field_id)
+            })
+            .collect::<Vec<_>>();
+          if !violations.is_empty() {
+            report.fail(
+              patch.row_id,
+              &CollabError::DatabaseConstraintViolation(violations),
+            );
+            return false;
+          }
+          match database_rows.iter_mut().find(|other| other.id == row.id) {
+            Some(other) => *other = row,
+            None => database_rows.push(row),
+          }
+          true
+        });
+      }
+
+      for (patch, database_row, _) in loaded {
+        let row_id = patch.row_id;
+        database_row.write().await.update_in_batch(|row_update| {
+          row_update.update_cells(|cells_update| {
+            patch.apply(cells_update);
+          });
+        });
+        report.row_ids.push(row_id);
+      }
+    }
+
+    if let Some(notifier) = &self.body.notifier {
+      if !report.row_ids.is_empty() {
+        let _ = notifier.row_change_tx.send(RowChange::DidUpdateRows {
+          row_ids: report.row_ids.clone(),
+          is_local_change: true,
+        });
+      }
+    }
+    report
+  }
+
+  /// Set the row ids of the row's relation cell.
+  ///
+  /// This is the only write that keeps the links of a relation in sync: a relation cell written
+  /// by [Database::update_row], [Database::update_rows] or [Database::apply_csv_import] is
+  /// stored as is, without updating the reverse cells or the [DatabaseRelation].
+  ///
+  /// When the relation field has a reverse field, the row is added to the reverse cells of the
+  /// rows it now links to, and removed from the reverse cells of the rows it no longer links
+  /// to. The links are also recorded in the [DatabaseRelation] of the collab service, if any.
+  ///
+  /// [DatabaseRelation]: crate::database::workspace_database::DatabaseRelation
+  pub async fn update_relation_cell(
+    &mut self,
+    row_id: &RowId,
+    field_id: &str,
+    row_ids: Vec<RowId>,
+  ) -> Result<(), CollabError> {
+    let field = self
+      .get_field(field_id)
+      .ok_or(CollabError::DatabaseRecordNotFound)?;
+    if FieldType::from(field.field_type) != FieldType::Relation {
+      return Err(CollabError::Internal(anyhow!(
+        "Field {} is not a relation field",
+        field_id
+      )));
+    }
+    let type_option = relation_type_option(&field);
+    let database_id = self.get_database_id()?.to_string();
+    let mut linked_row_ids: Vec<RowId> = vec![];
+    for id in row_ids {
+      if !linked_row_ids.contains(&id) {
+        linked_row_ids.push(id);
+      }
+    }
+
+    let previous_row_ids = relation_row_ids(self, row_id, field_id).await;
+    let cell = Cell::from(RelationCellData {
+      row_ids: linked_row_ids.clone(),
+    });
+    self
+      .replace_cells(vec![(*row_id, vec![(field_id.to_string(), Some(cell))])])
+      .await;
+    let database_relation = self.collab_service.database_relation();
+    if let Some(database_relation) = &database_relation {
+      database_relation
+        .set_linking_rows(
+          &database_id,
+          &type_option.database_id,
+          &row_id.to_string(),
+          field_id,
+          &row_id_strings(&linked_row_ids),
+        )
+        .await;
+    }
+
+    let Some(reverse_field_id) = type_option.reverse_field_id else {
+      return Ok(());
+    };
+    let added = linked_row_ids
+      .iter()
+      .filter(|id| !previous_row_ids.contains(id))
+      .copied()
+      .collect::<Vec<_>>();
+    let removed = previous_row_ids
+      .iter()
+      .filter(|id| !linked_row_ids.contains(id))
+      .copied()
+      .collect::<Vec<_>>();
+    if added.is_empty() && removed.is_empty() {
+      return Ok(());
+    }
+
+    let mut related = if type_option.database_id == database_id {
+      None
+    } else {
+      Some(Database::open(&type_option.database_id, self.context()).await?)
+    };
+    let related_database = related.as_mut().unwrap_or(self);
+    let reverse_cells = update_reverse_links(
+      related_database,
+      &reverse_field_id,
+      *row_id,
+      &added,
+      &removed,
+    )
+    .await;
+    if let Some(database_relation) = &database_relation {
+      for (related_row_id, reverse_row_ids) in reverse_cells {
+        database_relation
+          .set_linking_rows(
+            &type_option.database_id,
+            &database_id,
+            &related_row_id.to_string(),
+            &reverse_field_id,
+            &row_id_strings(&reverse_row_ids),
+          )
+          .await;
+      }
+    }
+    Ok(())
+  }
+
+  /// Return the rows that link to the row through a relation field, by the id of their
+  /// database. The rows of this database are found by reading its relation fields, and the rows
+  /// of the other databases are read from the [DatabaseRelation] of the collab service, if any.
+  ///
+  /// [DatabaseRelation]: crate::database::workspace_database::DatabaseRelation
+  pub async fn get_linked_by_rows(
+    &self,
+    row_id: &RowId,
+    auto_fetch: bool,
+  ) -> Result<HashMap<String, Vec<RowId>>, CollabError> {
+    let database_id = self.get_database_id()?.to_string();
+    let mut linked_by_rows = HashMap::new();
+    let field_ids = self.get_relation_field_ids(&database_id);
+    if !field_ids.is_empty() {
+      let row_orders = self.get_all_row_orders().await;
+      let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+      let row_ids = row_orders
+        .iter()
+        .filter_map(|row_order| rows.remove(&row_order.id))
+        .filter(|row| {
+          field_ids.iter().any(|field_id| {
+            row
+              .cells
+              .get(field_id)
+              .is_some_and(|cell| RelationCellData::from(cell).row_ids.contains(row_id))
+          })
+        })
+        .map(|row| row.id)
+        .collect::<Vec<_>>();
+      if !row_ids.is_empty() {
+        linked_by_rows.insert(database_id.clone(), row_ids);
+      }
+    }
+
+    if let Some(database_relation) = self.collab_service.database_relation() {
+      let recorded = database_relation
+        .get_linked_by_rows(&row_id.to_string())
+        .await;
+      for (linking_database_id, rows) in recorded {
+        if linking_database_id == database_id {
+          continue;
+        }
+        let row_ids = rows
+          .iter()
+          .flat_map(|row| Uuid::parse_str(&row.row_id).ok())
+          .collect::<Vec<_>>();
+        if !row_ids.is_empty() {
+          linked_by_rows.insert(linking_database_id, row_ids);
+        }
+      }
+    }
+    Ok(linked_by_rows)
+  }
+
+  /// Remove the removed rows from the relation cells that link to them: the cells of this
+  /// database's relation fields that point to this database, the reverse cells of the rows of
+  /// other databases they link to, and the cells of the rows of other databases recorded in the
+  /// [DatabaseRelation] of the collab service, if any.
+  ///
+  /// [Database::remove_row] and [Database::remove_rows] call it once the rows are removed. It
+  /// reads all the rows of this database when one of its relation fields relates to it, and opens
+  /// the other databases that link to the removed rows.
+  ///
+  /// [DatabaseRelation]: crate::database::workspace_database::DatabaseRelation
+  pub async fn remove_relation_links(&mut self, removed_row_ids: &[RowId]) {
+    let Ok(database_id) = self.get_database_id().map(|id| id.to_string()) else {
+      return;
+    };
+    let relation_fields = self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+      .map(|field| {
+        let type_option = relation_type_option(&field);
+        (field, type_option)
+      })
+      .collect::<Vec<_>>();
+
+    // The rows of other databases that may link to the removed rows, by database id
+    let mut linking_rows: HashMap<String, HashSet<RowId>> = HashMap::new();
+    let reverse_fields = relation_fields
+      .iter()
+      .filter(|(_, type_option)| {
+        type_option.database_id != database_id && type_option.reverse_field_id.is_some()
+      })
+      .collect::<Vec<_>>();
+    if !reverse_fields.is_empty() {
+      let row_orders = removed_row_ids
+        .iter()
+        .map(|row_id| RowOrder::new(*row_id, 0))
+        .collect();
+      let removed_rows = self
+        .get_rows_by_id(row_orders, true)
+        .await
+        .unwrap_or_default();
+      for (field, type_option) in reverse_fields {
+        let related_row_ids = linking_rows
+          .entry(type_option.database_id.clone())
+          .or_default();
+        for row in removed_rows.values() {
+          if let Some(cell) = row.cells.get(&field.id) {
+            related_row_ids.extend(RelationCellData::from(cell).row_ids);
+          }
+        }
+      }
+    }
+    if let Some(database_relation) = self.collab_service.database_relation() {
+      for row_id in removed_row_ids {
+        let row_id = row_id.to_string();
+        let recorded = database_relation.get_linked_by_rows(&row_id).await;
+        for (linking_database_id, rows) in recorded {
+          if linking_database_id != database_id {
+            linking_rows.entry(linking_database_id).or_default().extend(
+              rows
+                .iter()
+                .flat_map(|row| Uuid::parse_str(&row.row_id).ok()),
+            );
+          }
+        }
+        database_relation.remove_row(&row_id).await;
+      }
+    }
+
+    let field_ids = relation_fields
+      .iter()
+      .filter(|(_, type_option)| type_option.database_id == database_id)
+      .map(|(field, _)| field.id.clone())
+      .collect::<Vec<_>>();
+    if !field_ids.is_empty() {
+      let row_orders = self.get_all_row_orders().await;
+      let rows = self
+        .get_rows_by_id(row_orders, true)
+        .await
+        .unwrap_or_default();
+      let updates = remove_links_from_cells(rows.values(), &field_ids, removed_row_ids);
+      self.replace_cells(updates).await;
+    }
+
+    for (linking_database_id, row_ids) in linking_rows {
+      if row_ids.is_empty() {
+        continue;
+      }
+      let mut database = match Database::open(&linking_database_id, self.context()).await {
+        Ok(database) => database,
+        Err(err) => {
+          error!(
+            "Failed to open related database {}: {}",
+            linking_database_id, err
+          );
+          continue;
+        },
+      };
+      let field_ids = database.get_relation_field_ids(&database_id);
+      let row_orders = row_ids
+        .into_iter()
+        .map(|row_id| RowOrder::new(row_id, 0))
+        .collect();
+      let rows = database
+        .get_rows_by_id(row_orders, true)
+        .await
+        .unwrap_or_default();
+      let updates = remove_links_from_cells(rows.values(), &field_ids, removed_row_ids);
+      database.replace_cells(updates).await;
+    }
+  }
+
+  /// Return the ids of the relation fields that relate to the given database.
+  fn get_relation_field_ids(&self, related_database_id: &str) -> Vec<String> {
+    self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| {
+        FieldType::from(field.field_type) == FieldType::Relation
+          && relation_type_option(field).database_id == related_database_id
+      })
+      .map(|field| field.id)
+      .collect()
   }
 
   /// Update the row
+  /// The relation cells are written as they are, use [Database::update_relation_cell] to keep the
+  /// reverse cells of a relation in sync.
   pub async fn update_row<F>(&mut self, row_id: RowId, f: F)
   where
     F: FnOnce(RowUpdate),
@@ -513,6 +1101,232 @@ impl Database {
     self.body.block.update_row(row_id, f).await;
   }
 
+  /// Write the cells to the row, like [CellsUpdate::insert_cell](crate::database::rows::CellsUpdate::insert_cell),
+  /// only if the updated cells satisfy their fields' [FieldConstraints](crate::database::fields::FieldConstraints).
+  /// The violations of the cells that aren't written are ignored, so a row that already breaks a
+  /// constraint can still be edited.
+  ///
+  /// Return [CollabError::DatabaseConstraintViolation] without writing any cell if a written
+  /// cell violates a constraint.
+  pub async fn update_row_strict(
+    &mut self,
+    row_id: RowId,
+    cells: Cells,
+    auto_fetch: bool,
+  ) -> Result<(), CollabError> {
+    let fields = self.get_fields(Some(cells.keys().cloned().collect()));
+    if has_constraints(&fields) {
+      let mut row = self.get_row(&row_id).await?;
+      for (field_id, cell) in &cells {
+        row
+          .cells
+          .entry(field_id.clone())
+          .or_default()
+          .extend(cell.clone());
+      }
+      let rows = if has_unique_field(&fields) {
+        self.get_all_database_rows(auto_fetch).await?
+      } else {
+        vec![]
+      };
+      let violations = ConstraintValidator::new(fields).validate_row(&row, &rows);
+      if !violations.is_empty() {
+        return Err(CollabError::DatabaseConstraintViolation(violations));
+      }
+    }
+
+    self
+      .update_row(row_id, |row_update| {
+        row_update.update_cells(|cells_update| {
+          cells
+            .into_iter()
+            .fold(cells_update, |update, (field_id, cell)| {
+              update.insert_cell(&field_id, cell)
+            });
+        });
+      })
+      .await;
+    Ok(())
+  }
+
+  /// Return the violations of the row's cells of the fields' [FieldConstraints](crate::database::fields::FieldConstraints),
+  /// checked by [ConstraintValidator].
+  pub async fn validate_row(
+    &self,
+    row_id: &RowId,
+    auto_fetch: bool,
+  ) -> Result<Vec<ConstraintViolation>, CollabError> {
+    let fields = self.get_all_fields();
+    if !has_constraints(&fields) {
+      return Ok(vec![]);
+    }
+    let row = self.get_row(row_id).await?;
+    let rows = if has_unique_field(&fields) {
+      self.get_all_database_rows(auto_fetch).await?
+    } else {
+      vec![]
+    };
+    Ok(ConstraintValidator::new(fields).validate_row(&row, &rows))
+  }
+
+  /// Return the violations of the fields' [FieldConstraints](crate::database::fields::FieldConstraints)
+  /// in every row of the database, in the order of the inline view.
+  pub async fn validate_database(
+    &self,
+    auto_fetch: bool,
+  ) -> Result<Vec<ConstraintViolation>, CollabError> {
+    let fields = self.get_all_fields();
+    if !has_constraints(&fields) {
+      return Ok(vec![]);
+    }
+    let rows = self.get_all_database_rows(auto_fetch).await?;
+    Ok(ConstraintValidator::new(fields).validate_rows(&rows))
+  }
+
+  /// Return the rows of the database in the order of the inline view.
+  async fn get_all_database_rows(&self, auto_fetch: bool) -> Result<Vec<Row>, CollabError> {
+    let row_orders = self.get_all_row_orders().await;
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    Ok(
+      row_orders
+        .iter()
+        .flat_map(|row_order| rows.remove(&row_order.id))
+        .collect(),
+    )
+  }
+
+  /// Run the [DatabaseQuery] over the rows of its view, or of the inline view when the query has
+  /// no view, and return a page of the matching rows.
+  ///
+  /// The rows are loaded in chunks of `chunk_size`. Without an order, loading stops once the page
+  /// is full. With an order, every row of the view is loaded and filtered whatever the limit, so a
+  /// sorted query costs a full scan of the view. Only the first rows after the cursor are kept
+  /// while scanning, with the cells of the ordered fields, and the rows of the page are loaded
+  /// again to be projected.
+  ///
+  /// Return [CollabError::DatabaseInvalidQuery] if a field of the query or its cursor isn't in the
+  /// view, and [CollabError::DatabaseActionCancelled] if the `cancel_token` is cancelled.
+  pub async fn query_rows(
+    &self,
+    query: DatabaseQuery,
+    chunk_size: usize,
+    cancel_token: Option<CancellationToken>,
+    auto_fetch: bool,
+  ) -> Result<QueryPage, CollabError> {
+    let view_id = match &query.view_id {
+      Some(view_id) => view_id.clone(),
+      None => {
+        let txn = self.collab.transact();
+        self
+          .body
+          .try_get_inline_view_id(&txn)
+          .ok_or(CollabError::DatabaseViewNotExist)?
+      },
+    };
+    if self.get_view(&view_id).is_none() {
+      return Err(CollabError::DatabaseViewNotExist);
+    }
+    let evaluator = QueryEvaluator::new(&query, self.get_fields_in_view(&view_id, None))?;
+    let limit = query.limit.unwrap_or(usize::MAX);
+    if limit == 0 {
+      return Ok(QueryPage::default());
+    }
+    let cursor_not_found = |cursor: &RowId| {
+      CollabError::DatabaseInvalidQuery(format!("Cursor row {} is not in the view", cursor))
+    };
+
+    let mut row_orders = self.get_row_orders_for_view(&view_id);
+    let mut rows = if evaluator.has_sorts() {
+      let cursor = match &query.cursor {
+        Some(cursor) => {
+          let position = row_orders
+            .iter()
+            .position(|row_order| &row_order.id == cursor)
+            .ok_or_else(|| cursor_not_found(cursor))?;
+          let mut rows = self
+            .get_rows_by_id(vec![row_orders[position].clone()], auto_fetch)
+            .await?;
+          let row = rows
+            .remove(cursor)
+            .ok_or_else(|| cursor_not_found(cursor))?;
+          Some((position, row))
+        },
+        None => None,
+      };
+      let positions = row_orders
+        .iter()
+        .enumerate()
+        .map(|(position, row_order)| (row_order.id, position))
+        .collect::<HashMap<_, _>>();
+      let mut chunks = self
+        .get_rows_from_row_orders(row_orders, chunk_size, cancel_token.clone(), auto_fetch)
+        .await?
+        .ready_chunks(chunk_size);
+      let mut first_rows = vec![];
+      while let Some(chunk) = chunks.next().await {
+        let chunk = chunk
+          .into_iter()
+          .map(|row| {
+            let row = row?;
+            Ok((positions.get(&row.id).copied().unwrap_or(usize::MAX), row))
+          })
+          .collect::<Result<Vec<_>, CollabError>>()?;
+        // One more row than the limit tells whether another page follows
+        evaluator.keep_first_rows(
+          &mut first_rows,
+          chunk,
+          cursor.as_ref(),
+          limit.saturating_add(1),
+        );
+      }
+      let row_orders = first_rows
+        .iter()
+        .map(|(_, row)| RowOrder::new(row.id, 0))
+        .collect();
+      let mut rows = self.get_rows_by_id(row_orders, auto_fetch).await?;
+      first_rows
+        .into_iter()
+        .flat_map(|(_, row)| rows.remove(&row.id))
+        .collect::<Vec<_>>()
+    } else {
+      if let Some(cursor) = &query.cursor {
+        let position = row_orders
+          .iter()
+          .position(|row_order| &row_order.id == cursor)
+          .ok_or_else(|| cursor_not_found(cursor))?;
+        row_orders.drain(..=position);
+      }
+      // One more row than the limit tells whether another page follows
+      if !evaluator.has_filters() {
+        row_orders.truncate(limit.saturating_add(1));
+      }
+      let mut chunks = self
+        .get_rows_from_row_orders(row_orders, chunk_size, cancel_token.clone(), auto_fetch)
+        .await?
+        .ready_chunks(chunk_size);
+      let mut rows = vec![];
+      while rows.len() <= limit {
+        let Some(chunk) = chunks.next().await else {
+          break;
+        };
+        let chunk = chunk.into_iter().collect::<Result<Vec<_>, _>>()?;
+        rows.extend(evaluator.filter_rows(chunk));
+      }
+      rows
+    };
+    if cancel_token.is_some_and(|token| token.is_cancelled()) {
+      return Err(CollabError::DatabaseActionCancelled);
+    }
+
+    let has_more = rows.len() > limit;
+    rows.truncate(limit);
+    let next_cursor = rows.last().map(|row| row.id).filter(|_| has_more);
+    Ok(QueryPage {
+      rows: evaluator.project_rows(&rows),
+      next_cursor,
+    })
+  }
+
   /// Update the meta of the row
   pub async fn update_row_meta<F>(&mut self, row_id: &RowId, f: F)
   where
@@ -549,133 +1363,1271 @@ impl Database {
     self.body.block.get_row_meta(row_id).await
   }
 
-  /// Return [TypeOptionCellReader] for the given field id.
-  pub fn get_cell_reader(&self, field_id: &str) -> Option<Box<dyn TypeOptionCellReader>> {
-    let txn = self.collab.transact();
-    let field = self.body.fields.get_field(&txn, field_id)?;
-    drop(txn);
+  /// Return [TypeOptionCellReader] for the given field id.
+  pub fn get_cell_reader(&self, field_id: &str) -> Option<Box<dyn TypeOptionCellReader>> {
+    let txn = self.collab.transact();
+    let field = self.body.fields.get_field(&txn, field_id)?;
+    drop(txn);
+
+    let field_type = FieldType::from(field.field_type);
+    let type_option = field.get_any_type_option(field_type.type_id())?;
+    Some(type_option_cell_reader(type_option, &field_type))
+  }
+
+  /// Return [TypeOptionCellWriter] for the given field id.
+  pub fn get_cell_writer(&self, field_id: &str) -> Option<Box<dyn TypeOptionCellWriter>> {
+    let txn = self.collab.transact();
+    let field = self.body.fields.get_field(&txn, field_id)?;
+    drop(txn);
+
+    let field_type = FieldType::from(field.field_type);
+    let type_option = field.get_any_type_option(field_type.type_id())?;
+    Some(type_option_cell_writer(type_option, &field_type))
+  }
+
+  #[instrument(level = "debug", skip_all)]
+  pub fn init_database_row(&self, row_id: &RowId, ret: Option<InitRowChan>) {
+    self.body.block.init_database_row(row_id, ret)
+  }
+
+  pub fn init_database_rows<'a, T: Into<RowId> + Send + 'a>(
+    &'a self,
+    row_ids: Vec<T>,
+    chunk_size: usize,
+    cancel_token: Option<CancellationToken>,
+    auto_fetch: bool,
+  ) -> BoxStream<'a, Result<Arc<RwLock<DatabaseRow>>, CollabError>> {
+    let row_ids_chunk_stream = stream::iter(
+      row_ids
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<RowId>>()
+        .chunks(chunk_size)
+        .map(|chunk| chunk.to_vec())
+        .collect::<Vec<Vec<RowId>>>(),
+    );
+
+    // Stream that processes each chunk and yields rows, checking for cancellation if the token is provided
+    row_ids_chunk_stream
+      .then(move |chunk| {
+        let cancel_token = cancel_token.clone();
+        async move {
+          if let Some(ref token) = cancel_token {
+            if token.is_cancelled() {
+              return Err(CollabError::DatabaseActionCancelled);
+            }
+          }
+
+          self.body.block.init_database_rows(chunk, auto_fetch).await
+        }
+      })
+      .filter_map(|result| async {
+        match result {
+          Ok(rows) => Some(stream::iter(rows.into_iter().map(Ok))),
+          Err(err) => {
+            error!("Error initializing database rows: {:?}", err);
+            None
+          },
+        }
+      })
+      .flatten()
+      .boxed()
+  }
+
+  /// Return the cached [DatabaseRow] if it is already initialized.
+  pub fn get_cached_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    self.body.block.get_cached_database_row(row_id)
+  }
+
+  /// Return the [DatabaseRow], initializing it on demand if needed.
+  pub async fn get_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    self.body.block.get_database_row(row_id).await
+  }
+
+  #[instrument(level = "debug", skip_all)]
+  pub async fn get_row_detail(&self, row_id: &RowId) -> Option<RowDetail> {
+    let database_row = self
+      .body
+      .block
+      .get_or_init_database_row(row_id)
+      .await
+      .ok()?;
+    let read_guard = database_row.read().await;
+    read_guard.get_row_detail()
+  }
+
+  pub fn get_row_document_id(&self, row_id: &RowId) -> Option<String> {
+    self.body.block.get_row_document_id(row_id)
+  }
+
+  /// Return a list of [Row] for the given view.
+  /// The rows here are ordered by [RowOrder]s of the view.
+  pub async fn get_rows_for_view(
+    &self,
+    view_id: &str,
+    chunk_size: usize,
+    cancel_token: Option<CancellationToken>,
+    auto_fetch: bool,
+  ) -> Result<BoxStream<'_, Result<Row, CollabError>>, CollabError> {
+    let row_orders = self.get_row_orders_for_view(view_id);
+    self
+      .get_rows_from_row_orders(row_orders, chunk_size, cancel_token, auto_fetch)
+      .await
+  }
+
+  pub async fn get_row_order_at_index(&self, view_id: &str, index: u32) -> Option<RowOrder> {
+    let txn = self.collab.transact();
+    let view_id = self.body.parse_view_id(view_id).ok()?;
+    self
+      .body
+      .views
+      .get_row_order_at_index(&txn, &view_id, index)
+  }
+
+  pub fn get_row_orders_for_view(&self, view_id: &str) -> Vec<RowOrder> {
+    let txn = self.collab.transact();
+    match self.body.parse_view_id(view_id) {
+      Ok(view_id) => self.body.views.get_row_orders(&txn, &view_id),
+      Err(_) => vec![],
+    }
+  }
+
+  /// Return the [RowOrder]s of the view whose rows pass the view's filters, in the view's order.
+  /// The filters are evaluated with [FilterEvaluator].
+  ///
+  /// The filters can't be evaluated on the rows that can't be loaded, like the rows that aren't
+  /// cached when `auto_fetch` is false, so those rows are kept.
+  pub async fn get_filtered_row_orders_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowOrder>, CollabError> {
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    if filters.is_empty() {
+      return Ok(row_orders);
+    }
+
+    let rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let evaluator = FilterEvaluator::new(filters, self.get_all_fields());
+    Ok(filter_row_orders(row_orders, &rows, &evaluator))
+  }
+
+  /// Return the [RowOrder]s of the view as the view displays them: the rows that pass the view's
+  /// filters, sorted by the view's sorts. Rows that compare equal keep their manual order, and
+  /// the rows that can't be loaded are kept at the end.
+  pub async fn get_sorted_row_orders_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowOrder>, CollabError> {
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let sorts = self.get_all_sorts::<Sort>(view_id);
+    if filters.is_empty() && sorts.is_empty() {
+      return Ok(row_orders);
+    }
+
+    let rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let fields = self.get_all_fields();
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, fields.clone()),
+    );
+    Ok(SortEvaluator::new(sorts, fields).sort_row_orders(row_orders, &rows))
+  }
+
+  /// Export the rows of the view as CSV into `writer` with [CSVExporter], and return the writer.
+  /// The columns follow the view's field order, without the fields hidden in the view unless
+  /// [CSVExportOptions::include_hidden_fields] is set. The relation cells are written as the
+  /// titles of the related rows.
+  ///
+  /// The rows are loaded and written [CSVExportOptions::chunk_size] at a time, so the rows of the
+  /// database aren't held in memory, except to filter and sort them when
+  /// [CSVExportOptions::apply_filters_and_sorts] is set.
+  pub async fn export_view_to_csv<W: io::Write>(
+    &self,
+    view_id: &str,
+    options: &CSVExportOptions,
+    writer: W,
+    auto_fetch: bool,
+  ) -> Result<W, CollabError> {
+    let layout = self
+      .get_view(view_id)
+      .ok_or(CollabError::DatabaseViewNotExist)?
+      .layout;
+    let field_settings = self.get_field_settings::<FieldSettingsMap>(view_id, None);
+    let fields = self
+      .get_fields_in_view(view_id, None)
+      .into_iter()
+      .filter(|field| {
+        options.include_hidden_fields
+          || field.is_primary
+          || field_settings.get(&field.id).is_none_or(|settings| {
+            FieldSettings::from_any_map(&field.id, layout, settings).visibility
+              != FieldVisibility::AlwaysHidden
+          })
+      })
+      .collect::<Vec<_>>();
+    let relation_field_ids = fields
+      .iter()
+      .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+      .map(|field| field.id.clone())
+      .collect::<Vec<_>>();
+    let row_orders = if options.apply_filters_and_sorts {
+      self
+        .get_sorted_row_orders_for_view(view_id, auto_fetch)
+        .await?
+    } else {
+      self.get_row_orders_for_view(view_id)
+    };
+
+    let mut exporter = CSVExporter::new(fields, options, writer)?;
+    let mut resolver = RollupResolver::new(self, auto_fetch);
+    for row_orders in row_orders.chunks(options.chunk_size.max(1)) {
+      let mut rows = self.get_rows_by_id(row_orders.to_vec(), auto_fetch).await?;
+      let rows = row_orders
+        .iter()
+        .flat_map(|row_order| rows.remove(&row_order.id))
+        .collect::<Vec<_>>();
+
+      let mut relation_titles = RelationTitles::new();
+      if !options.raw_values {
+        for field_id in relation_field_ids.iter() {
+          for row in rows.iter() {
+            if let Some(titles) = resolver.related_titles(field_id, row).await {
+              relation_titles
+                .entry(field_id.clone())
+                .or_default()
+                .insert(row.id, titles.join(", "));
+            }
+          }
+        }
+      }
+      exporter.write_rows(&rows, &relation_titles)?;
+    }
+    exporter.finish()
+  }
+
+  /// Compute the changes of importing the CSV into the database, without applying them. See
+  /// [CSVImportPlan] for how the columns and the rows are matched. The report of the plan lists
+  /// the rows to insert, update and delete, and the values that can't be converted.
+  ///
+  /// With [CSVImportOptions::strict], the lines whose values violate their fields' constraints,
+  /// checked against the rows as the import would leave them, are skipped and listed in
+  /// [CSVImportReport::violations].
+  pub async fn plan_csv_import(
+    &self,
+    reader: impl io::Read,
+    options: &CSVImportOptions,
+    auto_fetch: bool,
+  ) -> Result<CSVImportPlan, CollabError> {
+    let rows = if options.mode == CSVImportMode::AppendOnly {
+      vec![]
+    } else {
+      let row_orders = self.get_all_row_orders().await;
+      let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+      row_orders
+        .iter()
+        .flat_map(|row_order| rows.remove(&row_order.id))
+        .collect()
+    };
+    CSVImportPlan::new(reader, options, &self.get_all_fields(), &rows)
+  }
+
+  /// Apply a [CSVImportPlan] computed by [Database::plan_csv_import]: add the new select options,
+  /// insert, update and delete the rows. Return the report of the plan with the ids of the
+  /// inserted rows.
+  ///
+  /// A row that can't be inserted, updated or deleted doesn't stop the others: it's listed in
+  /// [CSVImportReport::failures]. The updated rows are written by [Database::update_rows], so
+  /// they send its [RowChange::DidUpdateRows] besides the changes of each cell. The imported
+  /// relation cells don't update the reverse cells of their relation, see
+  /// [Database::update_relation_cell]. Return an error only if nothing was applied.
+  pub async fn apply_csv_import(
+    &mut self,
+    plan: CSVImportPlan,
+  ) -> Result<CSVImportReport, CollabError> {
+    let CSVImportPlan {
+      mut report,
+      type_options,
+      inserts,
+      updates,
+    } = plan;
+    let database_id = self.get_database_id()?;
+    for (field_id, field_type, type_option) in type_options {
+      self.update_field(&field_id, |update| {
+        update.set_type_option(field_type.into(), Some(type_option));
+      });
+    }
+
+    let params = inserts
+      .into_iter()
+      .map(|cells| CreateRowParams::new(gen_row_id(), database_id).with_cells(cells))
+      .collect::<Vec<_>>();
+    let line_by_row_id = params
+      .iter()
+      .zip(report.inserts.iter())
+      .map(|(params, line)| (params.id, *line))
+      .collect::<HashMap<_, _>>();
+    let inserted = self.create_rows(params, DEFAULT_ROW_BATCH_CHUNK_SIZE).await;
+    report.inserted_row_ids = inserted.row_ids;
+    let updated = self
+      .update_rows(
+        cell_replacement_patches(updates),
+        DEFAULT_ROW_BATCH_CHUNK_SIZE,
+      )
+      .await;
+    let deleted = self.remove_rows(&report.deletes).await;
+    report.failures = inserted
+      .failures
+      .into_iter()
+      .chain(updated.failures)
+      .chain(deleted.failures)
+      .map(|failure| CSVImportFailure {
+        line: line_by_row_id.get(&failure.row_id).copied(),
+        row_id: failure.row_id,
+        reason: failure.reason,
+      })
+      .collect();
+    Ok(report)
+  }
+
+  /// Return a [CalculationEvaluator] holding the view's calculations computed over the rows that
+  /// pass the view's filters. Keep the evaluator and apply the rows' changes to it with
+  /// [CalculationEvaluator::apply_row_change] to keep the results up to date without loading the
+  /// rows again.
+  pub async fn get_calculation_evaluator_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<CalculationEvaluator, CollabError> {
+    let calculations = self.get_all_calculations::<Calculation>(view_id);
+    if calculations.is_empty() {
+      return Ok(CalculationEvaluator::new(vec![], vec![]));
+    }
+
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let fields = self.get_all_fields();
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, fields.clone()),
+    );
+    let mut evaluator = CalculationEvaluator::new(calculations, fields);
+    evaluator.add_rows(
+      row_orders
+        .iter()
+        .flat_map(|row_order| rows.get(&row_order.id)),
+    );
+    Ok(evaluator)
+  }
+
+  /// Return the groups of the view with their rows, computed from the view's first
+  /// [GroupSetting] by [GroupEvaluator]. The rows are filtered and sorted the way the view shows
+  /// them. Return an empty list if the view isn't grouped.
+  pub async fn get_groups_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<Vec<GroupData>, CollabError> {
+    let evaluator = match self.get_group_evaluator(view_id) {
+      Some(evaluator) => evaluator,
+      None => return Ok(vec![]),
+    };
+
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let sorts = self.get_all_sorts::<Sort>(view_id);
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let fields = self.get_all_fields();
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, fields.clone()),
+    );
+    let row_orders = SortEvaluator::new(sorts, fields).sort_row_orders(row_orders, &rows);
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+    Ok(evaluator.group_rows(&rows))
+  }
+
+  /// Move the row's card between two groups of the view by writing the target group's value to
+  /// the grouping field, see [GroupEvaluator::cell_for_moved_row]. Return false if the view
+  /// isn't grouped or the row can't be moved to the group.
+  pub async fn move_row_to_group(
+    &mut self,
+    view_id: &str,
+    row_id: RowId,
+    from_group_id: Option<&str>,
+    to_group_id: &str,
+  ) -> Result<bool, CollabError> {
+    let evaluator = match self.get_group_evaluator(view_id) {
+      Some(evaluator) => evaluator,
+      None => return Ok(false),
+    };
+    let row = self.get_row(&row_id).await?;
+    let cell = match evaluator.cell_for_moved_row(&row, from_group_id, to_group_id) {
+      Some(cell) => cell,
+      None => return Ok(false),
+    };
+    let field_id = evaluator.field_id().to_string();
+    self
+      .update_row(row_id, |row_update| {
+        row_update.update_cells(|cells_update| {
+          cells_update.insert_cell(&field_id, cell);
+        });
+      })
+      .await;
+    Ok(true)
+  }
+
+  /// Return the data of the chart view computed from its [ChartLayoutSetting] by
+  /// [ChartEvaluator]: the rows that pass the view's filters bucketed by the X field, with the Y
+  /// field aggregated in each bucket. Return None if the view has no chart setting, or if the
+  /// setting's fields don't exist.
+  pub async fn get_chart_data_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<Option<ChartData>, CollabError> {
+    let setting =
+      match self.get_layout_setting::<ChartLayoutSetting>(view_id, &DatabaseLayout::Chart) {
+        Some(setting) => setting,
+        None => return Ok(None),
+      };
+    let fields = self.get_all_fields();
+    let evaluator = match ChartEvaluator::new(setting, &fields) {
+      Some(evaluator) => evaluator,
+      None => return Ok(None),
+    };
+
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let sorts = self.get_all_sorts::<Sort>(view_id);
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, fields.clone()),
+    );
+    let row_orders = SortEvaluator::new(sorts, fields).sort_row_orders(row_orders, &rows);
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+    Ok(Some(evaluator.chart_data(&rows)))
+  }
+
+  /// Return the events of the calendar view that overlap `[from, to)`, expanded from the rows
+  /// that pass the view's filters by [CalendarEvaluator]. A recurring row has one event per
+  /// occurrence. Return an empty list if the view has no calendar setting, or if its field isn't
+  /// a date field.
+  pub async fn get_calendar_events_for_view(
+    &self,
+    view_id: &str,
+    from: i64,
+    to: i64,
+    auto_fetch: bool,
+  ) -> Result<Vec<CalendarEvent>, CollabError> {
+    let evaluator = self
+      .get_layout_setting::<CalendarLayoutSetting>(view_id, &DatabaseLayout::Calendar)
+      .and_then(|setting| {
+        let field = self.get_field(&setting.field_id)?;
+        CalendarEvaluator::new(&setting, &field)
+      });
+    let evaluator = match evaluator {
+      Some(evaluator) => evaluator,
+      None => return Ok(vec![]),
+    };
+
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, self.get_all_fields()),
+    );
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+    Ok(evaluator.events(&rows, from, to))
+  }
+
+  /// Return the bars of the timeline view computed from its [TimelineLayoutSetting] by
+  /// [TimelineEvaluator], for the rows that pass the view's filters and have a start date, in the
+  /// view's sort order. Return an empty list if the view has no timeline setting, or if its start
+  /// field isn't a date field.
+  pub async fn get_timeline_bars_for_view(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<Vec<TimelineBar>, CollabError> {
+    let fields = self.get_all_fields();
+    let evaluator = match self.get_timeline_evaluator(view_id, &fields) {
+      Some(evaluator) => evaluator,
+      None => return Ok(vec![]),
+    };
+
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let filters = self.get_all_filters::<Filter>(view_id);
+    let sorts = self.get_all_sorts::<Sort>(view_id);
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let row_orders = filter_row_orders(
+      row_orders,
+      &rows,
+      &FilterEvaluator::new(filters, fields.clone()),
+    );
+    let row_orders = SortEvaluator::new(sorts, fields).sort_row_orders(row_orders, &rows);
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+    Ok(evaluator.bars(&rows))
+  }
+
+  /// Check that the dependencies between the rows of the timeline view have no cycle. The rows
+  /// hidden by the view's filters are checked too.
+  ///
+  /// Return [CollabError::DatabaseDependencyCycle] with the rows of a cycle if there's one.
+  pub async fn validate_timeline_dependencies(
+    &self,
+    view_id: &str,
+    auto_fetch: bool,
+  ) -> Result<(), CollabError> {
+    let fields = self.get_all_fields();
+    let evaluator = match self.get_timeline_evaluator(view_id, &fields) {
+      Some(evaluator) => evaluator,
+      None => return Ok(()),
+    };
+    let rows = self.get_view_rows(view_id, auto_fetch).await?;
+    match evaluator.find_cycle(&rows) {
+      Some(cycle) => Err(CollabError::DatabaseDependencyCycle(cycle)),
+      None => Ok(()),
+    }
+  }
+
+  /// Move the row's bar in the timeline view to `[start, end]` by writing its date cells, see
+  /// [TimelineEvaluator::cells_for_bar]. With `shift_dependents`, the rows that depend on it,
+  /// directly or not, are pushed so that each one starts once its predecessors end, see
+  /// [TimelineEvaluator::move_bar]. Return the ids of the updated rows, starting with the moved
+  /// one, or an empty list if the view has no timeline setting or the row has no start date.
+  ///
+  /// Return [CollabError::DatabaseDependencyCycle] without updating any row if the dependencies
+  /// have a cycle and `shift_dependents` is set.
+  pub async fn move_timeline_row(
+    &mut self,
+    view_id: &str,
+    row_id: RowId,
+    start: i64,
+    end: i64,
+    shift_dependents: bool,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let fields = self.get_all_fields();
+    let evaluator = match self.get_timeline_evaluator(view_id, &fields) {
+      Some(evaluator) => evaluator,
+      None => return Ok(vec![]),
+    };
+    let rows = self.get_view_rows(view_id, auto_fetch).await?;
+    let bars = evaluator.bars(&rows);
+    let bars = if shift_dependents {
+      TimelineEvaluator::move_bar(&bars, &row_id, start, end)
+        .map_err(CollabError::DatabaseDependencyCycle)?
+    } else {
+      bars
+        .into_iter()
+        .filter(|bar| bar.row_id == row_id)
+        .map(|bar| TimelineBar {
+          start,
+          end: end.max(start),
+          ..bar
+        })
+        .collect()
+    };
+
+    let mut updated_row_ids = vec![];
+    for bar in bars {
+      let Some(row) = rows.iter().find(|row| row.id == bar.row_id) else {
+        continue;
+      };
+      let cells = evaluator.cells_for_bar(row, bar.start, bar.end);
+      self
+        .update_row(bar.row_id, |row_update| {
+          row_update.update_cells(|cells_update| {
+            for (field_id, cell) in cells {
+              cells_update.insert_cell(&field_id, cell);
+            }
+          });
+        })
+        .await;
+      updated_row_ids.push(bar.row_id);
+    }
+    Ok(updated_row_ids)
+  }
+
+  fn get_timeline_evaluator(&self, view_id: &str, fields: &[Field]) -> Option<TimelineEvaluator> {
+    let setting =
+      self.get_layout_setting::<TimelineLayoutSetting>(view_id, &DatabaseLayout::Timeline)?;
+    TimelineEvaluator::new(&setting, fields)
+  }
+
+  /// Return the rows of the view in the view's row order, without applying its filters.
+  async fn get_view_rows(&self, view_id: &str, auto_fetch: bool) -> Result<Vec<Row>, CollabError> {
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    Ok(
+      row_orders
+        .iter()
+        .flat_map(|row_order| rows.remove(&row_order.id))
+        .collect(),
+    )
+  }
+
+  fn get_group_evaluator(&self, view_id: &str) -> Option<GroupEvaluator> {
+    let setting = self
+      .get_all_group_setting::<GroupSetting>(view_id)
+      .into_iter()
+      .next()?;
+    let field = self.get_field(&setting.field_id)?;
+    GroupEvaluator::new(&setting, &field)
+  }
+
+  /// Recompute the rollup cells of the rows, or of every row when `row_ids` is None, with
+  /// [RollupResolver], and write the values that changed. Every value is resolved before any
+  /// row is written, and the rollup cells of a row are written in a single transaction. Rollups
+  /// that can't be resolved keep their value. Return the ids of the updated rows.
+  pub async fn refresh_rollup_cells(
+    &mut self,
+    row_ids: Option<Vec<RowId>>,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let rollup_fields = self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| FieldType::from(field.field_type) == FieldType::Rollup)
+      .collect::<Vec<_>>();
+    if rollup_fields.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let row_orders = match row_ids {
+      Some(row_ids) => row_ids
+        .into_iter()
+        .map(|row_id| RowOrder::new(row_id, 0))
+        .collect(),
+      None => self.get_all_row_orders().await,
+    };
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect();
+    self
+      .write_rollup_cells(rollup_fields, rows, auto_fetch)
+      .await
+  }
+
+  /// Recompute the rollup cells that roll up rows of the related database, after these rows
+  /// changed. Only the rows whose relation cells point to one of the changed rows are updated.
+  /// Return the ids of the updated rows.
+  pub async fn refresh_rollups_for_related_rows(
+    &mut self,
+    related_database_id: &str,
+    related_row_ids: &[RowId],
+    auto_fetch: bool,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let relation_field_ids = self.get_relation_field_ids(related_database_id);
+    let rollup_fields = self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| {
+        FieldType::from(field.field_type) == FieldType::Rollup
+          && relation_field_ids.contains(&rollup_type_option(field).relation_field_id)
+      })
+      .collect::<Vec<_>>();
+    if rollup_fields.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let row_orders = self.get_all_row_orders().await;
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .filter(|row| {
+        relation_field_ids.iter().any(|field_id| {
+          row.cells.get(field_id).is_some_and(|cell| {
+            RelationCellData::from(cell)
+              .row_ids
+              .iter()
+              .any(|row_id| related_row_ids.contains(row_id))
+          })
+        })
+      })
+      .collect();
+    self
+      .write_rollup_cells(rollup_fields, rows, auto_fetch)
+      .await
+  }
+
+  async fn write_rollup_cells(
+    &mut self,
+    rollup_fields: Vec<Field>,
+    rows: Vec<Row>,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let mut updates = vec![];
+    {
+      let mut resolver = RollupResolver::new(self, auto_fetch);
+      for row in rows.iter() {
+        let mut cells = vec![];
+        for field in rollup_fields.iter() {
+          let value = match resolver.resolve(field, row).await {
+            Some(value) => value,
+            None => continue,
+          };
+          let current_value = row
+            .cells
+            .get(&field.id)
+            .and_then(|cell| cell.get_as::<String>(CELL_DATA));
+          if current_value.as_deref() != Some(value.as_str()) {
+            cells.push((
+              field.id.clone(),
+              rollup_cell(&rollup_type_option(field), value),
+            ));
+          }
+        }
+        if !cells.is_empty() {
+          updates.push((row.id, cells));
+        }
+      }
+    }
+
+    Ok(self.write_computed_cells(updates).await)
+  }
+
+  /// Write the computed cells of each row in a single transaction. Return the ids of the rows.
+  async fn write_computed_cells(
+    &mut self,
+    updates: Vec<(RowId, Vec<(String, Cell)>)>,
+  ) -> Vec<RowId> {
+    let mut updated_row_ids = vec![];
+    for (row_id, cells) in updates {
+      self
+        .update_row(row_id, |row_update| {
+          row_update.update_cells(|cells_update| {
+            cells
+              .into_iter()
+              .fold(cells_update, |update, (field_id, cell)| {
+                update.insert_cell(&field_id, cell)
+              });
+          });
+        })
+        .await;
+      updated_row_ids.push(row_id);
+    }
+    updated_row_ids
+  }
+
+  /// Parse and type check a formula expression against the fields of the database, and return
+  /// the type of its result, to be stored in the [FormulaTypeOption] of the field.
+  pub fn check_formula(&self, expression: &str) -> Result<FormulaResultType, CollabError> {
+    Formula::compile(expression, &self.get_all_fields()).map(|formula| formula.result_type())
+  }
+
+  /// Return a [FormulaEvaluator] for the formula fields of the database.
+  pub fn get_formula_evaluator(&self) -> FormulaEvaluator {
+    FormulaEvaluator::new(self.get_all_fields())
+  }
+
+  /// Recompute the formula cells of the rows, or of every row when `row_ids` is None, and write
+  /// the values that changed. The values of the relation aggregates are loaded from the related
+  /// databases with [RollupResolver] first. Formulas that can't be evaluated keep their value,
+  /// see [FormulaEvaluator::error]. Return the ids of the updated rows.
+  pub async fn refresh_formula_cells(
+    &mut self,
+    row_ids: Option<Vec<RowId>>,
+    auto_fetch: bool,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let evaluator = self.get_formula_evaluator();
+    if evaluator.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let row_orders = match row_ids {
+      Some(row_ids) => row_ids
+        .into_iter()
+        .map(|row_id| RowOrder::new(row_id, 0))
+        .collect(),
+      None => self.get_all_row_orders().await,
+    };
+    let mut rows = self.get_rows_by_id(row_orders.clone(), auto_fetch).await?;
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+
+    let aggregates = evaluator.aggregates();
+    let mut updates = vec![];
+    {
+      let mut resolver = RollupResolver::new(self, auto_fetch);
+      for row in rows.iter() {
+        let mut related = RelatedValues::new();
+        for aggregate in aggregates.iter() {
+          let Some(target) = aggregate.target.as_deref() else {
+            continue;
+          };
+          if let Some(values) = resolver
+            .related_values(&aggregate.relation_field_id, target, row)
+            .await
+          {
+            related.insert(aggregate.clone(), values);
+          }
+        }
+
+        let cells = evaluator
+          .evaluate_row(row, &related)
+          .into_iter()
+          .filter(|(field_id, value)| {
+            let current_value = row
+              .cells
+              .get(field_id)
+              .and_then(|cell| cell.get_as::<String>(CELL_DATA));
+            current_value != value.to_cell_data()
+          })
+          .map(|(field_id, value)| {
+            let json_value = value
+              .to_cell_data()
+              .map(serde_json::Value::String)
+              .unwrap_or_default();
+            let cell = FormulaTypeOption::default().convert_json_to_cell(json_value);
+            (field_id, cell)
+          })
+          .collect::<Vec<_>>();
+        if !cells.is_empty() {
+          updates.push((row.id, cells));
+        }
+      }
+    }
+    Ok(self.write_computed_cells(updates).await)
+  }
+
+  /// Fill the cells of the Person fields that are filled with the creator with the user who
+  /// creates the row, unless the row has a value for them.
+  fn fill_persons_with_creator(&self, params: &mut CreateRowParams) {
+    let Some(uid) = params.created_by else {
+      return;
+    };
+    for field in self.get_all_fields() {
+      if FieldType::from(field.field_type) != FieldType::Person {
+        continue;
+      }
+      let fill_with_creator = field
+        .get_type_option::<PersonTypeOption>(FieldType::Person.type_id())
+        .is_some_and(|type_option| type_option.fill_with_creator);
+      let is_empty = params
+        .cells
+        .get(&field.id)
+        .is_none_or(|cell| PersonCellData::from(cell).is_cell_empty());
+      if fill_with_creator && is_empty {
+        params.cells.insert(
+          field.id,
+          PersonCellData::new(vec![uid.to_string()]).to_cell(),
+        );
+      }
+    }
+  }
 
-    let field_type = FieldType::from(field.field_type);
-    let type_option = field.get_any_type_option(field_type.type_id())?;
-    Some(type_option_cell_reader(type_option, &field_type))
+  pub(crate) fn get_auto_number_fields(&self) -> Vec<Field> {
+    self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| FieldType::from(field.field_type).is_auto_number())
+      .collect()
   }
 
-  /// Return [TypeOptionCellWriter] for the given field id.
-  pub fn get_cell_writer(&self, field_id: &str) -> Option<Box<dyn TypeOptionCellWriter>> {
-    let txn = self.collab.transact();
-    let field = self.body.fields.get_field(&txn, field_id)?;
-    drop(txn);
+  /// Give each row the next number of each auto number field it has no value for. The numbers
+  /// follow the highest number issued by any client, so they don't depend on
+  /// [Database::resolve_auto_number_conflicts] having run. The reservations are read once per
+  /// field for all the rows, and only the ranges of this client that changed are written.
+  fn assign_auto_numbers(&mut self, params: &mut [CreateRowParams]) {
+    let fields = self.get_auto_number_fields();
+    if fields.is_empty() {
+      return;
+    }
 
-    let field_type = FieldType::from(field.field_type);
-    let type_option = field.get_any_type_option(field_type.type_id())?;
-    Some(type_option_cell_writer(type_option, &field_type))
-  }
+    let client_id = self.collab.client_id();
+    let mut txn = self.collab.transact_mut();
+    for field in fields {
+      let mut params = params
+        .iter_mut()
+        .filter(|params| {
+          params
+            .cells
+            .get(&field.id)
+            .is_none_or(|cell| AutoNumberCellData::from(cell).number.is_none())
+        })
+        .peekable();
+      if params.peek().is_none() {
+        continue;
+      }
 
-  #[instrument(level = "debug", skip_all)]
-  pub fn init_database_row(&self, row_id: &RowId, ret: Option<InitRowChan>) {
-    self.body.block.init_database_row(row_id, ret)
+      let start = auto_number_type_option(&field).start;
+      let mut reservations = self
+        .body
+        .metas
+        .get_auto_number_reservations(&txn, &field.id);
+      // Only the last range of the client is extended, the next ones are new
+      let unchanged = reservations.ranges(client_id).len().saturating_sub(1);
+      for params in params {
+        let number = reservations.reserve(client_id, start);
+        params.cells.insert(
+          field.id.clone(),
+          AutoNumberCellData::new(number, client_id).to_cell(),
+        );
+      }
+      for range in &reservations.ranges(client_id)[unchanged..] {
+        self
+          .body
+          .metas
+          .set_auto_number_range(&mut txn, &field.id, client_id, *range);
+      }
+    }
   }
 
-  pub fn init_database_rows<'a, T: Into<RowId> + Send + 'a>(
-    &'a self,
-    row_ids: Vec<T>,
-    chunk_size: usize,
-    cancel_token: Option<CancellationToken>,
-    auto_fetch: bool,
-  ) -> BoxStream<'a, Result<Arc<RwLock<DatabaseRow>>, CollabError>> {
-    let row_ids_chunk_stream = stream::iter(
-      row_ids
+  /// Renumber the rows holding an auto number that this client and a client with a lower id
+  /// both issued, which happens when they created rows while offline. Creating rows doesn't
+  /// resolve the conflicts, so call it once the database is synced, e.g. when
+  /// [Collab::subscribe_sync_state] reports
+  /// [SyncState::SyncFinished](crate::core::collab_state::SyncState::SyncFinished). Return the
+  /// ids of the updated rows.
+  pub async fn resolve_auto_number_conflicts(&mut self) -> Result<Vec<RowId>, CollabError> {
+    let fields = self.get_auto_number_fields();
+    let client_id = self.collab.client_id();
+    let conflicts = {
+      let txn = self.collab.transact();
+      fields
         .into_iter()
-        .map(Into::into)
-        .collect::<Vec<RowId>>()
-        .chunks(chunk_size)
-        .map(|chunk| chunk.to_vec())
-        .collect::<Vec<Vec<RowId>>>(),
-    );
+        .filter_map(|field| {
+          let numbers = self
+            .body
+            .metas
+            .get_auto_number_reservations(&txn, &field.id)
+            .conflicts(client_id);
+          (!numbers.is_empty()).then_some((field, numbers))
+        })
+        .collect::<Vec<_>>()
+    };
+    if conflicts.is_empty() {
+      return Ok(vec![]);
+    }
 
-    // Stream that processes each chunk and yields rows, checking for cancellation if the token is provided
-    row_ids_chunk_stream
-      .then(move |chunk| {
-        let cancel_token = cancel_token.clone();
-        async move {
-          if let Some(ref token) = cancel_token {
-            if token.is_cancelled() {
-              return Err(CollabError::DatabaseActionCancelled);
-            }
-          }
+    let row_orders = self.get_all_row_orders().await;
+    let mut rows = self.get_rows_by_id(row_orders.clone(), true).await?;
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
 
-          self.body.block.init_database_rows(chunk, auto_fetch).await
+    let mut updates = vec![];
+    {
+      let mut txn = self.collab.transact_mut();
+      let mut reservations = conflicts
+        .iter()
+        .map(|(field, numbers)| {
+          let mut reservations = self
+            .body
+            .metas
+            .get_auto_number_reservations(&txn, &field.id);
+          reservations.release(client_id, numbers);
+          reservations
+        })
+        .collect::<Vec<_>>();
+      for row in rows.iter() {
+        let mut cells = vec![];
+        for ((field, numbers), reservations) in conflicts.iter().zip(reservations.iter_mut()) {
+          let Some(cell) = row.cells.get(&field.id) else {
+            continue;
+          };
+          let cell_data = AutoNumberCellData::from(cell);
+          let is_conflict = cell_data
+            .number
+            .is_some_and(|number| numbers.binary_search(&number).is_ok());
+          if cell_data.client_id == Some(client_id) && is_conflict {
+            let number = reservations.reserve(client_id, auto_number_type_option(field).start);
+            let cell = AutoNumberCellData::new(number, client_id).to_cell();
+            cells.push((field.id.clone(), cell));
+          }
         }
-      })
-      .filter_map(|result| async {
-        match result {
-          Ok(rows) => Some(stream::iter(rows.into_iter().map(Ok))),
-          Err(err) => {
-            error!("Error initializing database rows: {:?}", err);
-            None
-          },
+        if !cells.is_empty() {
+          updates.push((row.id, cells));
         }
-      })
-      .flatten()
-      .boxed()
+      }
+      for ((field, _), reservations) in conflicts.iter().zip(reservations.iter()) {
+        self.body.metas.set_auto_number_ranges(
+          &mut txn,
+          &field.id,
+          client_id,
+          reservations.ranges(client_id),
+        );
+      }
+    }
+    Ok(self.write_computed_cells(updates).await)
   }
 
-  /// Return the cached [DatabaseRow] if it is already initialized.
-  pub fn get_cached_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
-    self.body.block.get_cached_database_row(row_id)
-  }
+  /// Renumber every row of the auto number field in the order of the rows of the database,
+  /// starting from the `start` of its type option, e.g. after importing rows that came with their
+  /// own numbers. The numbers issued before are forgotten, so the next row gets the number
+  /// following the last row. Return the ids of the updated rows.
+  pub async fn renumber_auto_number_field(
+    &mut self,
+    field_id: &str,
+  ) -> Result<Vec<RowId>, CollabError> {
+    let Some(field) = self
+      .get_field(field_id)
+      .filter(|field| FieldType::from(field.field_type).is_auto_number())
+    else {
+      return Ok(vec![]);
+    };
+    let start = auto_number_type_option(&field).start;
+    let client_id = self.collab.client_id();
 
-  /// Return the [DatabaseRow], initializing it on demand if needed.
-  pub async fn get_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
-    self.body.block.get_database_row(row_id).await
-  }
+    let row_orders = self.get_all_row_orders().await;
+    let mut rows = self.get_rows_by_id(row_orders.clone(), true).await?;
+    let mut reservations = AutoNumberReservations::new();
+    let mut updates = vec![];
+    for row in row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+    {
+      let cell_data = AutoNumberCellData::new(reservations.reserve(client_id, start), client_id);
+      let current = row.cells.get(&field.id).map(AutoNumberCellData::from);
+      if current.as_ref() != Some(&cell_data) {
+        updates.push((row.id, vec![(field.id.clone(), cell_data.to_cell())]));
+      }
+    }
 
-  #[instrument(level = "debug", skip_all)]
-  pub async fn get_row_detail(&self, row_id: &RowId) -> Option<RowDetail> {
-    let database_row = self
-      .body
-      .block
-      .get_or_init_database_row(row_id)
-      .await
-      .ok()?;
-    let read_guard = database_row.read().await;
-    read_guard.get_row_detail()
+    {
+      let mut txn = self.collab.transact_mut();
+      self
+        .body
+        .metas
+        .remove_auto_number_reservations(&mut txn, &field.id);
+      self.body.metas.set_auto_number_ranges(
+        &mut txn,
+        &field.id,
+        client_id,
+        reservations.ranges(client_id),
+      );
+    }
+    Ok(self.write_computed_cells(updates).await)
   }
 
-  pub fn get_row_document_id(&self, row_id: &RowId) -> Option<String> {
-    self.body.block.get_row_document_id(row_id)
+  /// Change the type of the field and convert its cells to the new type, see [FieldConverter].
+  /// Values that can't be converted are cleared and reported in [FieldConversion::failures].
+  /// The cells of a relation field are converted from the titles of the related rows.
+  ///
+  /// The returned [FieldConversion] can be given to [Database::undo_field_conversion] to restore
+  /// the field and its cells.
+  pub async fn convert_field_type(
+    &mut self,
+    field_id: &str,
+    to_type: FieldType,
+  ) -> Result<FieldConversion, CollabError> {
+    let field = self
+      .get_field(field_id)
+      .ok_or(CollabError::DatabaseRecordNotFound)?;
+    let from_type = FieldType::from(field.field_type);
+    let mut conversion = FieldConversion::new(field_id, from_type, to_type);
+    if from_type == to_type {
+      return Ok(conversion);
+    }
+
+    let row_orders = self.get_all_row_orders().await;
+    let mut rows = self.get_rows_by_id(row_orders.clone(), true).await?;
+    let rows = row_orders
+      .iter()
+      .flat_map(|row_order| rows.remove(&row_order.id))
+      .collect::<Vec<_>>();
+
+    let mut titles = HashMap::new();
+    if from_type == FieldType::Relation {
+      let mut resolver = RollupResolver::new(self, true);
+      for row in rows.iter() {
+        if let Some(values) = resolver.related_titles(field_id, row).await {
+          titles.insert(row.id, values.join(", "));
+        }
+      }
+    }
+
+    let (type_option, updates) = {
+      let mut converter = FieldConverter::new(&field, to_type);
+      let texts = rows
+        .iter()
+        .filter(|row| converter.has_value(row))
+        .map(|row| {
+          let text = titles
+            .remove(&row.id)
+            .unwrap_or_else(|| converter.read_text(row));
+          (row, text)
+        })
+        .collect::<Vec<_>>();
+      converter.add_options(texts.iter().map(|(_, text)| text.as_str()));
+
+      let mut updates = vec![];
+      for (row, text) in texts {
+        let cell = match converter.convert(row, &text) {
+          CellConversion::Converted(cell) => Some(cell),
+          CellConversion::Cleared => None,
+          CellConversion::Failed => {
+            conversion.failures.push(ConversionFailure {
+              row_id: row.id,
+              value: text,
+            });
+            None
+          },
+        };
+        let previous_cell = row.cells.get(field_id).cloned();
+        if cell.is_none() && previous_cell.is_none() {
+          continue;
+        }
+        conversion.converted_cells.push(ConvertedCell {
+          row_id: row.id,
+          previous: previous_cell,
+          converted: cell.clone(),
+        });
+        updates.push((row.id, vec![(field_id.to_string(), cell)]));
+      }
+      (converter.type_option_data(), updates)
+    };
+
+    conversion.previous_type_option = field.get_any_type_option(to_type.type_id());
+    self.update_field(field_id, |update| {
+      update
+        .set_field_type(to_type.into())
+        .set_type_option(to_type.into(), Some(type_option));
+    });
+    conversion.updated_row_ids = self.replace_cells(updates).await;
+    Ok(conversion)
+  }
+
+  /// Restore the type, the type option and the cells of a field converted by
+  /// [Database::convert_field_type]. A cell is only restored if it still holds the value the
+  /// conversion wrote, so cells edited since the conversion keep their edits. Return the ids of
+  /// the restored rows.
+  pub async fn undo_field_conversion(&mut self, conversion: FieldConversion) -> Vec<RowId> {
+    let FieldConversion {
+      field_id,
+      from_type,
+      to_type,
+      previous_type_option,
+      converted_cells,
+      ..
+    } = conversion;
+    self.update_field(&field_id, |update| {
+      update
+        .set_field_type(from_type.into())
+        .set_type_option(to_type.into(), previous_type_option);
+    });
+    let row_orders = converted_cells
+      .iter()
+      .map(|converted_cell| RowOrder::new(converted_cell.row_id, 0))
+      .collect();
+    let rows = self
+      .get_rows_by_id(row_orders, true)
+      .await
+      .unwrap_or_default();
+    let updates = converted_cells
+      .into_iter()
+      .filter(|converted_cell| {
+        rows.get(&converted_cell.row_id).is_some_and(|row| {
+          cell_content(row.cells.get(&field_id)) == cell_content(converted_cell.converted.as_ref())
+        })
+      })
+      .map(|converted_cell| {
+        (
+          converted_cell.row_id,
+          vec![(field_id.clone(), converted_cell.previous)],
+        )
+      })
+      .collect();
+    self.replace_cells(updates).await
   }
 
-  /// Return a list of [Row] for the given view.
-  /// The rows here are ordered by [RowOrder]s of the view.
-  pub async fn get_rows_for_view(
-    &self,
-    view_id: &str,
-    chunk_size: usize,
-    cancel_token: Option<CancellationToken>,
-    auto_fetch: bool,
-  ) -> Result<BoxStream<'_, Result<Row, CollabError>>, CollabError> {
-    let row_orders = self.get_row_orders_for_view(view_id);
+  /// Replace the cells of each row, removing the cells that are None. Unlike
+  /// [Database::write_computed_cells], the keys of the previous cells are not kept.
+  async fn replace_cells(
+    &mut self,
+    updates: Vec<(RowId, Vec<(String, Option<Cell>)>)>,
+  ) -> Vec<RowId> {
     self
-      .get_rows_from_row_orders(row_orders, chunk_size, cancel_token, auto_fetch)
+      .update_rows(
+        cell_replacement_patches(updates),
+        DEFAULT_ROW_BATCH_CHUNK_SIZE,
+      )
       .await
+      .row_ids
   }
 
-  pub async fn get_row_order_at_index(&self, view_id: &str, index: u32) -> Option<RowOrder> {
-    let txn = self.collab.transact();
-    let view_id = self.body.parse_view_id(view_id).ok()?;
-    self
-      .body
-      .views
-      .get_row_order_at_index(&txn, &view_id, index)
+  /// Return a [DatabaseContext] sharing the collab services of the database, used to open the
+  /// databases it relates to.
+  pub fn context(&self) -> DatabaseContext {
+    DatabaseContext::new(
+      self.collab_service.clone(),
+      self.body.block.row_collab_service(),
+    )
   }
 
-  pub fn get_row_orders_for_view(&self, view_id: &str) -> Vec<RowOrder> {
-    let txn = self.collab.transact();
-    match self.body.parse_view_id(view_id) {
-      Ok(view_id) => self.body.views.get_row_orders(&txn, &view_id),
-      Err(_) => vec![],
+  /// Load the rows of the row orders. The rows that can't be loaded are left out of the map.
+  pub(crate) async fn get_rows_by_id(
+    &self,
+    row_orders: Vec<RowOrder>,
+    auto_fetch: bool,
+  ) -> Result<HashMap<RowId, Row>, CollabError> {
+    let database_id = self.get_database_id()?;
+    let mut rows = HashMap::with_capacity(row_orders.len());
+    for chunk in row_orders.chunks(20) {
+      let row_ids = chunk.iter().map(|order| order.id).collect::<Vec<_>>();
+      let database_rows = match self
+        .body
+        .block
+        .init_database_rows(row_ids.clone(), auto_fetch)
+        .await
+      {
+        Ok(database_rows) => database_rows,
+        // A single row that can't be loaded fails the whole chunk, so load the rows one by one
+        Err(_) => {
+          let mut database_rows = vec![];
+          for row_id in row_ids {
+            if let Ok(loaded) = self
+              .body
+              .block
+              .init_database_rows(vec![row_id], auto_fetch)
+              .await
+            {
+              database_rows.extend(loaded);
+            }
+          }
+          database_rows
+        },
+      };
+      for database_row in database_rows {
+        let read_guard = database_row.read().await;
+        let row = read_guard
+          .get_row()
+          .unwrap_or_else(|| Row::empty(read_guard.row_id, database_id));
+        rows.insert(row.id, row);
+      }
     }
+    Ok(rows)
   }
 
   pub fn get_row_index(&self, view_id: &str, row_id: &RowId) -> Option<usize> {
@@ -1456,7 +3408,8 @@ impl Database {
     Some(duplicated_view)
   }
 
-  /// Duplicate the row, and insert it after the original row.
+  /// Duplicate the row, and insert it after the original row. The auto number cells are left
+  /// out, so the duplicated row gets new numbers when it's created.
   pub async fn duplicate_row(
     &self,
     row_id: &RowId,
@@ -1468,11 +3421,15 @@ impl Database {
     let Some(row) = database_row.read().await.get_row() else {
       return Ok(None);
     };
+    let mut cells = row.cells;
+    for field in self.get_auto_number_fields() {
+      cells.remove(&field.id);
+    }
     let timestamp = timestamp();
     Ok(Some(CreateRowParams {
       id: gen_row_id(),
       database_id,
-      cells: row.cells,
+      cells,
       height: row.height,
       visibility: row.visibility,
       row_position: OrderObjectPosition::after_row(&row.id),
@@ -1644,12 +3601,31 @@ impl Database {
     self.body.fields.insert_field(&mut txn, field);
   }
 
+  /// Update the field. When its type or type option changes, the groups of the views grouped by
+  /// the field are kept in sync with its select options, in the same transaction.
   pub fn update_field<F>(&mut self, field_id: &str, f: F)
   where
     F: FnOnce(FieldUpdate),
   {
     let mut txn = self.collab.transact_mut();
+    let old_field = self.body.fields.get_field(&txn, field_id);
     self.body.fields.update_field(&mut txn, field_id, f);
+    let (Some(old_field), Some(field)) = (old_field, self.body.fields.get_field(&txn, field_id))
+    else {
+      return;
+    };
+    if old_field.field_type != field.field_type || old_field.type_options != field.type_options {
+      self.body.sync_group_settings_with_field(&mut txn, &field);
+    }
+  }
+
+  /// Update the groups of every view grouped by the field, e.g. after select options were added
+  /// or deleted. See [GroupEvaluator::sync_group_setting].
+  pub fn sync_group_settings_with_field(&mut self, field_id: &str) {
+    let mut txn = self.collab.transact_mut();
+    if let Some(field) = self.body.fields.get_field(&txn, field_id) {
+      self.body.sync_group_settings_with_field(&mut txn, &field);
+    }
   }
 }
 
@@ -1725,6 +3701,150 @@ pub fn gen_option_id() -> String {
   nanoid!(4)
 }
 
+/// Keep the row orders whose rows pass the filters. The row orders whose rows weren't loaded are
+/// kept, since the filters can't be evaluated on them.
+fn filter_row_orders(
+  row_orders: Vec<RowOrder>,
+  rows: &HashMap<RowId, Row>,
+  evaluator: &FilterEvaluator,
+) -> Vec<RowOrder> {
+  if evaluator.is_empty() {
+    return row_orders;
+  }
+  row_orders
+    .into_iter()
+    .filter(|row_order| {
+      rows
+        .get(&row_order.id)
+        .is_none_or(|row| evaluator.is_row_visible(row))
+    })
+    .collect()
+}
+
+fn relation_type_option(field: &Field) -> RelationTypeOption {
+  RelationTypeOption::from(
+    field
+      .get_any_type_option(FieldType::Relation.type_id())
+      .unwrap_or_default(),
+  )
+}
+
+async fn relation_row_ids(database: &Database, row_id: &RowId, field_id: &str) -> Vec<RowId> {
+  database
+    .get_cell(field_id, row_id)
+    .await
+    .cell
+    .map(|cell| RelationCellData::from(&cell).row_ids)
+    .unwrap_or_default()
+}
+
+fn row_id_strings(row_ids: &[RowId]) -> Vec<String> {
+  row_ids.iter().map(|row_id| row_id.to_string()).collect()
+}
+
+/// Add the row to the reverse relation cells of the added rows, and remove it from the ones of
+/// the removed rows. Return the row ids of the updated cells.
+async fn update_reverse_links(
+  database: &mut Database,
+  reverse_field_id: &str,
+  row_id: RowId,
+  added: &[RowId],
+  removed: &[RowId],
+) -> Vec<(RowId, Vec<RowId>)> {
+  let mut reverse_cells = vec![];
+  for related_row_id in added.iter().chain(removed) {
+    let mut row_ids = relation_row_ids(database, related_row_id, reverse_field_id).await;
+    if added.contains(related_row_id) {
+      if !row_ids.contains(&row_id) {
+        row_ids.push(row_id);
+      }
+    } else {
+      row_ids.retain(|id| id != &row_id);
+    }
+    reverse_cells.push((*related_row_id, row_ids));
+  }
+
+  let updates = reverse_cells
+    .iter()
+    .map(|(related_row_id, row_ids)| {
+      (
+        *related_row_id,
+        vec![(reverse_field_id.to_string(), relation_cell(row_ids.clone()))],
+      )
+    })
+    .collect();
+  database.replace_cells(updates).await;
+  reverse_cells
+}
+
+/// Return the cell updates removing the removed rows from the rows' relation cells.
+fn remove_links_from_cells<'a>(
+  rows: impl IntoIterator<Item = &'a Row>,
+  field_ids: &[String],
+  removed_row_ids: &[RowId],
+) -> Vec<(RowId, Vec<(String, Option<Cell>)>)> {
+  rows
+    .into_iter()
+    .filter(|row| !removed_row_ids.contains(&row.id))
+    .filter_map(|row| {
+      let cells = field_ids
+        .iter()
+        .filter_map(|field_id| {
+          let row_ids = RelationCellData::from(row.cells.get(field_id)?).row_ids;
+          if !row_ids.iter().any(|id| removed_row_ids.contains(id)) {
+            return None;
+          }
+          let row_ids = row_ids
+            .into_iter()
+            .filter(|id| !removed_row_ids.contains(id))
+            .collect();
+          Some((field_id.clone(), relation_cell(row_ids)))
+        })
+        .collect::<Vec<_>>();
+      (!cells.is_empty()).then_some((row.id, cells))
+    })
+    .collect()
+}
+
+/// A relation cell holding the row ids, or None to remove the cell when there are none.
+fn relation_cell(row_ids: Vec<RowId>) -> Option<Cell> {
+  (!row_ids.is_empty()).then(|| Cell::from(RelationCellData { row_ids }))
+}
+
+fn rollup_type_option(field: &Field) -> RollupTypeOption {
+  RollupTypeOption::from(
+    field
+      .get_any_type_option(FieldType::Rollup.type_id())
+      .unwrap_or_default(),
+  )
+}
+
+/// Return the patches replacing the cells of each row, removing the cells that are None.
+fn cell_replacement_patches(updates: Vec<(RowId, Vec<(String, Option<Cell>)>)>) -> Vec<RowPatch> {
+  updates
+    .into_iter()
+    .map(|(row_id, cells)| {
+      cells.into_iter().fold(
+        RowPatch::new(row_id),
+        |patch, (field_id, cell)| match cell {
+          Some(cell) => patch.with_replaced_cell(field_id, cell),
+          None => patch.with_removed_cell(field_id),
+        },
+      )
+    })
+    .collect()
+}
+
+/// The [ConstraintValidator]'s cell readers aren't `Send`, so the validator is created after the
+/// rows are loaded, and these tell beforehand what the validation needs.
+fn has_constraints(fields: &[Field]) -> bool {
+  fields.iter().any(|field| !field.constraints.is_empty())
+}
+
+fn has_unique_field(fields: &[Field]) -> bool {
+  fields.iter().any(|field| field.constraints.unique)
+}
+
 pub fn timestamp() -> i64 {
   chrono::Utc::now().timestamp()
 }
@@ -2306,6 +4426,34 @@ impl DatabaseBody {
     Ok(())
   }
 
+  /// Update the groups of every view grouped by the field. Only the group settings of the views
+  /// are read, not their row orders.
+  pub(crate) fn sync_group_settings_with_field(&self, txn: &mut TransactionMut, field: &Field) {
+    for view_meta in self.views.get_all_views_meta(txn) {
+      for setting in self.views.get_view_group_setting(txn, &view_meta.id) {
+        let mut setting = match GroupSetting::try_from(setting) {
+          Ok(setting) if setting.field_id == field.id => setting,
+          _ => continue,
+        };
+        let is_changed = GroupEvaluator::new(&setting, field)
+          .map(|evaluator| evaluator.sync_group_setting(&mut setting))
+          .unwrap_or(false);
+        if is_changed {
+          let setting_id = setting.id.clone();
+          self
+            .views
+            .update_database_view(txn, &view_meta.id, |view_update| {
+              view_update.update_groups(|txn, group_update| {
+                group_update.update_map(txn, &setting_id, |map| {
+                  *map = GroupSettingMap::from(setting);
+                });
+              });
+            });
+        }
+      }
+    }
+  }
+
   /// Helper function to safely convert string view ID to DatabaseViewId
   fn parse_view_id(&self, view_id: &str) -> Result<DatabaseViewId, CollabError> {
     try_parse_database_view_id(view_id).ok_or_else(|| {
@@ -2346,7 +4494,7 @@ pub fn try_fixing_database(
   Ok(())
 }
 
-fn database_inline_view_id(database_id: &DatabaseId) -> DatabaseViewId {
+pub(crate) fn database_inline_view_id(database_id: &DatabaseId) -> DatabaseViewId {
   let key = "inline_view_id";
   Uuid::new_v5(database_id, key.as_bytes())
 }
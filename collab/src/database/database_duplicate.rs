@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tracing::error;
+use uuid::Uuid;
+
+use crate::database::database::{
+  Database, DatabaseContext, DatabaseData, database_inline_view_id, gen_database_id,
+  gen_database_view_id, gen_row_id, get_inline_view_id, get_row_document_id,
+};
+use crate::database::database_remapper::DatabaseCollabRemapper;
+use crate::database::database_trait::{DatabaseCollabService, NoPersistenceDatabaseCollabService};
+use crate::database::entity::{DatabaseView, EncodedCollabInfo, EncodedDatabase};
+use crate::database::views::{CalculationMap, DatabaseLayout};
+use crate::entity::CollabType;
+use crate::entity::uuid_validation::{DatabaseId, RowId};
+use crate::error::CollabError;
+use crate::folder::hierarchy_builder::{NestedChildViewBuilder, ParentChildViews};
+use crate::folder::{ViewId, ViewLayout};
+use crate::importer::workspace::id_remapper::JsonIdRemapper;
+
+/// The options of [Database::duplicate_database].
+#[derive(Debug, Clone)]
+pub struct DuplicateDatabaseParams {
+  pub uid: i64,
+  /// The folder view the duplicated views are placed under.
+  pub parent_view_id: ViewId,
+  pub include_comments: bool,
+  pub auto_fetch: bool,
+}
+
+impl DuplicateDatabaseParams {
+  pub fn new(uid: i64, parent_view_id: ViewId) -> Self {
+    Self {
+      uid,
+      parent_view_id,
+      include_comments: false,
+      auto_fetch: false,
+    }
+  }
+
+  pub fn with_comments(mut self, include_comments: bool) -> Self {
+    self.include_comments = include_comments;
+    self
+  }
+
+  pub fn with_auto_fetch(mut self, auto_fetch: bool) -> Self {
+    self.auto_fetch = auto_fetch;
+    self
+  }
+}
+
+/// The database created by [Database::duplicate_database].
+pub struct DuplicatedDatabase {
+  pub database_id: DatabaseId,
+  /// The folder views of the duplicated database, ready to be inserted with
+  /// [Folder::insert_nested_views](crate::folder::Folder::insert_nested_views): its first view,
+  /// with the other views as children. Each folder view has the id of the database view it shows.
+  pub views: ParentChildViews,
+  pub encoded_database: EncodedDatabase,
+  /// Maps the ids of the original database, its views, rows and row documents to the new ids.
+  pub id_map: HashMap<Uuid, Uuid>,
+  /// The rows whose comments couldn't be copied because the row couldn't be loaded.
+  pub skipped_comments: Vec<RowId>,
+  /// The rows whose document isn't empty but couldn't be copied because it isn't stored locally.
+  pub skipped_row_documents: Vec<RowId>,
+}
+
+impl Database {
+  /// Duplicates the database into a new database.
+  ///
+  /// The fields and their type options, the views with their settings and calculations, and the
+  /// rows with their cells, metas and documents are copied. The comments of the rows are copied
+  /// when [DuplicateDatabaseParams::include_comments] is set. Embedded views are left out. The
+  /// auto numbers issued for the database are copied too, so the duplicated database goes on
+  /// numbering its new rows after the copied ones.
+  ///
+  /// The row documents are read from the persistence of the database's collab service. The rows
+  /// whose comments or document can't be read are listed in [DuplicatedDatabase::skipped_comments]
+  /// and [DuplicatedDatabase::skipped_row_documents].
+  ///
+  /// The database, views, rows and row documents get new ids, and the references to them inside
+  /// the database are rewritten, so the relations of the database to itself link the duplicated
+  /// rows. The field and select option ids are kept, so the filters, sorts, groups and
+  /// calculations of the views apply to the duplicated fields as is.
+  ///
+  /// Nothing is persisted: the caller stores the encoded collabs and inserts the folder views.
+  pub async fn duplicate_database(
+    &self,
+    params: DuplicateDatabaseParams,
+  ) -> Result<DuplicatedDatabase, CollabError> {
+    let data = self.get_database_data(20, params.auto_fetch, false).await?;
+    let mut calculations = HashMap::new();
+    for view in &data.views {
+      calculations.insert(
+        view.id,
+        self.get_all_calculations::<CalculationMap>(&view.id.to_string()),
+      );
+    }
+    let mut comments = vec![];
+    let mut skipped_comments = vec![];
+    if params.include_comments {
+      for row in &data.rows {
+        match self.body.block.get_or_init_database_row(&row.id).await {
+          Ok(database_row) => comments.push((row.id, database_row.read().await.get_comments())),
+          Err(err) => {
+            error!("Failed to load the comments of row {}: {}", row.id, err);
+            skipped_comments.push(row.id);
+          },
+        }
+      }
+    }
+    let auto_numbers = {
+      let txn = self.collab.transact();
+      self
+        .get_auto_number_fields()
+        .into_iter()
+        .map(|field| {
+          let reservations = self
+            .body
+            .metas
+            .get_auto_number_reservations(&txn, &field.id);
+          (field.id, reservations)
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let new_database_id = gen_database_id();
+    let mut id_map = HashMap::from([(data.database_id, new_database_id)]);
+    if let Some(inline_view_id) =
+      get_inline_view_id(&self.collab).and_then(|view_id| Uuid::parse_str(&view_id).ok())
+    {
+      id_map.insert(inline_view_id, database_inline_view_id(&new_database_id));
+    }
+    for view in &data.views {
+      id_map.insert(view.id, gen_database_view_id());
+    }
+    for row in &data.rows {
+      let new_row_id = gen_row_id();
+      id_map.insert(row.id, new_row_id);
+      id_map.insert(row_document_id(&row.id)?, row_document_id(&new_row_id)?);
+    }
+
+    let old_view_ids = data.views.iter().map(|view| view.id).collect::<Vec<_>>();
+    let old_row_ids = data.rows.iter().map(|row| row.id).collect::<Vec<_>>();
+    let rows_with_document = data
+      .row_metas
+      .iter()
+      .filter(|(_, row_meta)| !row_meta.is_document_empty)
+      .map(|(row_id, _)| *row_id)
+      .collect::<HashSet<_>>();
+    let data = remap_database_data(data, &id_map)?;
+    let views = data.views.clone();
+    let (first_view, other_views) = views
+      .split_first()
+      .ok_or(CollabError::DatabaseViewNotExist)?;
+
+    let client_id = self.collab_service.database_client_id().await;
+    let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new(client_id));
+    let context = DatabaseContext::new(collab_service.clone(), collab_service);
+    let create_params = DatabaseCollabRemapper::create_database_params_with_mapped_ids(data);
+    let mut database = Database::create_with_view(create_params, context).await?;
+
+    // The views are created with the rows and fields in the order of the inline view
+    for (old_view_id, view) in old_view_ids.iter().zip(&views) {
+      let view_id = view.id.to_string();
+      database.update_database_view(&view_id, |update| {
+        update
+          .replace_row_orders(view.row_orders.clone())
+          .replace_field_orders(view.field_orders.clone());
+      });
+      for calculation in calculations.remove(old_view_id).unwrap_or_default() {
+        database.update_calculation(&view_id, calculation);
+      }
+    }
+    {
+      let mut txn = database.collab.transact_mut();
+      for (field_id, reservations) in &auto_numbers {
+        for (client_id, ranges) in reservations.iter() {
+          database
+            .body
+            .metas
+            .set_auto_number_ranges(&mut txn, field_id, client_id, ranges);
+        }
+      }
+    }
+    for (old_row_id, row_comments) in comments {
+      let Some(database_row) = database
+        .body
+        .block
+        .get_database_row(&id_map[&old_row_id])
+        .await
+      else {
+        skipped_comments.push(old_row_id);
+        continue;
+      };
+      let mut database_row = database_row.write().await;
+      for comment in row_comments {
+        database_row.add_comment(comment);
+      }
+    }
+
+    let mut encoded_database = database.encode_database_collabs().await?;
+    let persistence = self.collab_service.persistence();
+    let mut skipped_row_documents = vec![];
+    for old_row_id in old_row_ids {
+      let old_document_id = row_document_id(&old_row_id)?;
+      let encoded_collab = persistence.as_ref().and_then(|persistence| {
+        persistence.get_encoded_collab(&old_document_id, CollabType::Document)
+      });
+      match encoded_collab {
+        Some(encoded_collab) => {
+          encoded_database
+            .encoded_row_document_collabs
+            .push(EncodedCollabInfo {
+              object_id: id_map[&old_document_id],
+              collab_type: CollabType::Document,
+              encoded_collab,
+            });
+        },
+        None if rows_with_document.contains(&old_row_id) => skipped_row_documents.push(old_row_id),
+        None => {},
+      }
+    }
+
+    let children = other_views
+      .iter()
+      .map(|view| folder_view_builder(params.uid, first_view.id, view).build())
+      .collect();
+    let views = folder_view_builder(params.uid, params.parent_view_id, first_view)
+      .with_children(children)
+      .build();
+    Ok(DuplicatedDatabase {
+      database_id: new_database_id,
+      views,
+      encoded_database,
+      id_map,
+      skipped_comments,
+      skipped_row_documents,
+    })
+  }
+}
+
+/// Rewrite the ids of the data. Remapping the json representation also rewrites the row ids
+/// stored in relation cells and the database id stored in relation type options.
+fn remap_database_data(
+  data: DatabaseData,
+  id_map: &HashMap<Uuid, Uuid>,
+) -> Result<DatabaseData, CollabError> {
+  let id_map = id_map
+    .iter()
+    .map(|(old_id, new_id)| (old_id.to_string(), new_id.to_string()))
+    .collect();
+  let mut json_value = serde_json::to_value(&data)?;
+  JsonIdRemapper::new(&id_map).remap_json_value(&mut json_value);
+  Ok(serde_json::from_value(json_value)?)
+}
+
+fn row_document_id(row_id: &RowId) -> Result<Uuid, CollabError> {
+  Ok(Uuid::parse_str(&get_row_document_id(row_id)?)?)
+}
+
+fn folder_view_builder(
+  uid: i64,
+  parent_view_id: ViewId,
+  view: &DatabaseView,
+) -> NestedChildViewBuilder {
+  NestedChildViewBuilder::new(uid, parent_view_id)
+    .with_view_id(view.id)
+    .with_name(&view.name)
+    .with_layout(view_layout(view.layout))
+}
+
+fn view_layout(layout: DatabaseLayout) -> ViewLayout {
+  match layout {
+    DatabaseLayout::Grid => ViewLayout::Grid,
+    DatabaseLayout::Board => ViewLayout::Board,
+    DatabaseLayout::Calendar => ViewLayout::Calendar,
+    DatabaseLayout::Chart => ViewLayout::Chart,
+    DatabaseLayout::List => ViewLayout::List,
+    DatabaseLayout::Gallery => ViewLayout::Gallery,
+    DatabaseLayout::Feed => ViewLayout::Feed,
+    DatabaseLayout::Timeline => ViewLayout::Timeline,
+  }
+}
@@ -4,7 +4,10 @@ use crate::core::collab::{CollabOptions, DataSource};
 use crate::core::collab_plugin::CollabPersistence;
 use crate::core::origin::CollabOrigin;
 use crate::database::entity::CreateDatabaseParams;
-use crate::database::rows::{DatabaseRow, Row, RowChangeSender, default_database_row_from_row};
+use crate::database::rows::{
+  DatabaseRow, Row, RowChangeSender, RowHistoryStore, default_database_row_from_row,
+};
+use crate::database::workspace_database::DatabaseRelation;
 use crate::entity::CollabType;
 use crate::entity::EncodedCollab;
 use crate::entity::uuid_validation::{ObjectId, RowId};
@@ -80,6 +83,12 @@ pub trait DatabaseCollabService: Send + Sync + 'static {
   ) -> Result<Collab, CollabError>;
 
   fn persistence(&self) -> Option<Arc<dyn DatabaseCollabPersistenceService>>;
+
+  /// The [DatabaseRelation] recording the links between the rows of the workspace's databases.
+  /// Without it, only the relations within a database can be followed backwards.
+  fn database_relation(&self) -> Option<Arc<DatabaseRelation>> {
+    None
+  }
 }
 
 #[async_trait]
@@ -110,6 +119,12 @@ pub trait DatabaseRowCollabService: Send + Sync + 'static {
   fn database_row_cache(&self) -> Option<Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>> {
     None
   }
+
+  /// The store of the rows' history. The rows opened by the service record the changes of their
+  /// cells into it, see [DatabaseRow::enable_history]. Without it, no history is recorded.
+  fn row_history_store(&self) -> Option<Arc<dyn RowHistoryStore>> {
+    None
+  }
 }
 
 #[async_trait]
@@ -135,6 +150,14 @@ pub trait DatabaseCollabReader: Send + Sync + 'static {
   fn reader_row_cache(&self) -> Option<Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>> {
     None
   }
+
+  fn reader_database_relation(&self) -> Option<Arc<DatabaseRelation>> {
+    None
+  }
+
+  fn reader_row_history_store(&self) -> Option<Arc<dyn RowHistoryStore>> {
+    None
+  }
 }
 
 #[async_trait]
@@ -208,6 +231,10 @@ where
   fn persistence(&self) -> Option<Arc<dyn DatabaseCollabPersistenceService>> {
     self.reader_persistence()
   }
+
+  fn database_relation(&self) -> Option<Arc<DatabaseRelation>> {
+    self.reader_database_relation()
+  }
 }
 
 #[async_trait]
@@ -233,7 +260,10 @@ where
     }
 
     let collab = build_collab(client_id, row_id, collab_type, data).await?;
-    let database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    let mut database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    if let Some(store) = self.row_history_store() {
+      database_row.enable_history(store);
+    }
     let arc_row = Arc::new(RwLock::new(database_row));
     if let Some(cache) = self.database_row_cache() {
       cache.insert(*row_id, arc_row.clone());
@@ -260,7 +290,10 @@ where
       Some(data) => data.into_encode_collab(client_id),
     };
     let collab = build_collab(client_id, row_id, collab_type, data).await?;
-    let database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    let mut database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    if let Some(store) = self.row_history_store() {
+      database_row.enable_history(store);
+    }
     let arc_row = Arc::new(RwLock::new(database_row));
 
     if let Some(cache) = self.database_row_cache() {
@@ -333,6 +366,10 @@ where
   fn database_row_cache(&self) -> Option<Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>> {
     DatabaseCollabReader::reader_row_cache(self)
   }
+
+  fn row_history_store(&self) -> Option<Arc<dyn RowHistoryStore>> {
+    DatabaseCollabReader::reader_row_history_store(self)
+  }
 }
 
 /// Adapter to provide a dedicated row cache for a DatabaseCollabReader-backed service.
@@ -374,6 +411,10 @@ where
     self.row_cache.clone()
   }
 
+  fn row_history_store(&self) -> Option<Arc<dyn RowHistoryStore>> {
+    self.reader.reader_row_history_store()
+  }
+
   async fn create_arc_database_row(
     &self,
     row_id: &RowId,
@@ -388,7 +429,10 @@ where
     }
 
     let collab = build_collab(client_id, row_id, collab_type, data).await?;
-    let database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    let mut database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    if let Some(store) = self.row_history_store() {
+      database_row.enable_history(store);
+    }
     let arc_row = Arc::new(RwLock::new(database_row));
     if let Some(cache) = self.database_row_cache() {
       cache.insert(*row_id, arc_row.clone());
@@ -415,7 +459,10 @@ where
       Some(data) => data.into_encode_collab(client_id),
     };
     let collab = build_collab(client_id, row_id, collab_type, data).await?;
-    let database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    let mut database_row = DatabaseRow::open(*row_id, collab, sender)?;
+    if let Some(store) = self.row_history_store() {
+      database_row.enable_history(store);
+    }
     let arc_row = Arc::new(RwLock::new(database_row));
 
     if let Some(cache) = self.database_row_cache() {
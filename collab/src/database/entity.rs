@@ -2,9 +2,11 @@
 use crate::database::database::{
   DatabaseData, gen_database_id, gen_database_view_id, gen_row_id, timestamp,
 };
+use crate::database::fields::auto_number_type_option::AutoNumberTypeOption;
 use crate::database::fields::checkbox_type_option::CheckboxTypeOption;
 use crate::database::fields::checklist_type_option::ChecklistTypeOption;
 use crate::database::fields::date_type_option::{DateTypeOption, TimeTypeOption};
+use crate::database::fields::formula_type_option::FormulaTypeOption;
 use crate::database::fields::media_type_option::MediaTypeOption;
 use crate::database::fields::number_type_option::NumberTypeOption;
 use crate::database::fields::person_type_option::PersonTypeOption;
@@ -315,6 +317,8 @@ pub enum FieldType {
   Media = 14,
   Person = 15,
   Rollup = 16,
+  Formula = 17,
+  AutoNumber = 18,
 }
 
 impl FieldType {
@@ -389,6 +393,8 @@ impl FieldType {
       FieldType::Media => "Media",
       FieldType::Person => "Person",
       FieldType::Rollup => "Rollup",
+      FieldType::Formula => "Formula",
+      FieldType::AutoNumber => "ID",
     };
     s.to_string()
   }
@@ -449,6 +455,14 @@ impl FieldType {
     matches!(self, FieldType::Rollup)
   }
 
+  pub fn is_formula(&self) -> bool {
+    matches!(self, FieldType::Formula)
+  }
+
+  pub fn is_auto_number(&self) -> bool {
+    matches!(self, FieldType::AutoNumber)
+  }
+
   pub fn is_time(&self) -> bool {
     matches!(self, FieldType::Time)
   }
@@ -486,6 +500,8 @@ impl From<i64> for FieldType {
       14 => FieldType::Media,
       15 => FieldType::Person,
       16 => FieldType::Rollup,
+      17 => FieldType::Formula,
+      18 => FieldType::AutoNumber,
       _ => {
         error!("Unknown field type: {}, fallback to text", index);
         FieldType::RichText
@@ -516,6 +532,8 @@ pub fn default_type_option_data_from_type(field_type: FieldType) -> TypeOptionDa
     FieldType::Translate => TranslateTypeOption::default().into(),
     FieldType::Person => PersonTypeOption::default().into(),
     FieldType::Rollup => RollupTypeOption::default().into(),
+    FieldType::Formula => FormulaTypeOption::default().into(),
+    FieldType::AutoNumber => AutoNumberTypeOption::default().into(),
   }
 }
 
@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-use crate::preclude::{Any, Map, MapExt, MapRef, ReadTxn, TransactionMut, YrsValue};
+use crate::preclude::{
+  Any, FillRef, Map, MapExt, MapRef, ReadTxn, ToJson, TransactionMut, YrsValue,
+};
 
 use crate::database::database::gen_field_id;
 use crate::database::entity::{FieldType, default_type_option_data_from_type};
-use crate::database::fields::{TypeOptionData, TypeOptions, TypeOptionsUpdate};
+use crate::database::fields::{
+  FieldConstraints, FieldConstraintsData, TypeOptionData, TypeOptions, TypeOptionsUpdate,
+};
+use crate::util::AnyExt;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Field {
@@ -16,6 +21,8 @@ pub struct Field {
   pub type_options: TypeOptions,
   #[serde(default = "DEFAULT_IS_PRIMARY_VALUE")]
   pub is_primary: bool,
+  #[serde(default, skip_serializing_if = "FieldConstraints::is_empty")]
+  pub constraints: FieldConstraints,
 }
 
 impl Field {
@@ -38,6 +45,11 @@ impl Field {
     self
   }
 
+  pub fn with_constraints(mut self, constraints: FieldConstraints) -> Self {
+    self.constraints = constraints;
+    self
+  }
+
   pub fn from_field_type(name: &str, field_type: FieldType, is_primary: bool) -> Self {
     let new_field = Self {
       id: gen_field_id(),
@@ -219,6 +231,20 @@ impl<'a, 'b, 'c> FieldUpdate<'a, 'b, 'c> {
     self
   }
 
+  /// Set the constraints of the field. Empty constraints are removed.
+  pub fn set_constraints(self, constraints: FieldConstraints) -> Self {
+    if constraints.is_empty() {
+      self.map_ref.remove(self.txn, FIELD_CONSTRAINTS);
+    } else {
+      let map_ref: MapRef = self.map_ref.get_or_init(self.txn, FIELD_CONSTRAINTS);
+      map_ref.clear(self.txn);
+      Any::from(FieldConstraintsData::from(constraints))
+        .fill(self.txn, &map_ref)
+        .unwrap();
+    }
+    self
+  }
+
   pub fn done(self) -> Option<Field> {
     field_from_map_ref(self.map_ref, self.txn)
   }
@@ -230,6 +256,7 @@ const FIELD_ICON: &str = "icon";
 const FIELD_TYPE: &str = "ty";
 const FIELD_TYPE_OPTION: &str = "type_option";
 const FIELD_PRIMARY: &str = "is_primary";
+const FIELD_CONSTRAINTS: &str = "constraints";
 const CREATED_AT: &str = "created_at";
 const LAST_MODIFIED: &str = "last_modified";
 
@@ -271,6 +298,12 @@ pub fn field_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Field
 
   let is_primary: bool = map_ref.get_with_txn(txn, FIELD_PRIMARY).unwrap_or(false);
 
+  let constraints = map_ref
+    .get(txn, FIELD_CONSTRAINTS)
+    .and_then(|value| value.to_json(txn).into_map())
+    .map(FieldConstraints::from)
+    .unwrap_or_default();
+
   Some(Field {
     id,
     name,
@@ -278,5 +311,6 @@ pub fn field_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Field
     field_type,
     type_options,
     is_primary,
+    constraints,
   })
 }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use yrs::encoding::serde::from_any;
+
+use crate::preclude::Any;
+
+pub type FieldConstraintsData = HashMap<String, Any>;
+
+/// The constraints the cells of a field must satisfy, checked by
+/// [ConstraintValidator](crate::database::validation::ConstraintValidator).
+///
+/// Each constraint only applies to the field types it makes sense for: the number range to
+/// numbers, the pattern and maximum length to text and URLs, the date range to dates and the
+/// maximum number of selected options to multi-selects. The other constraints are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FieldConstraints {
+  /// The cell must not be empty.
+  #[serde(default)]
+  pub required: bool,
+  /// No two rows of the database may have the same non-empty value.
+  #[serde(default)]
+  pub unique: bool,
+  #[serde(default)]
+  pub min: Option<f64>,
+  #[serde(default)]
+  pub max: Option<f64>,
+  /// A regular expression the whole text must match.
+  #[serde(default)]
+  pub pattern: Option<String>,
+  /// The maximum number of characters of the text.
+  #[serde(default)]
+  pub max_length: Option<u32>,
+  /// The earliest timestamp of the date, or of both ends of a date range.
+  #[serde(default)]
+  pub min_date: Option<i64>,
+  /// The latest timestamp of the date, or of both ends of a date range.
+  #[serde(default)]
+  pub max_date: Option<i64>,
+  #[serde(default)]
+  pub max_selected_options: Option<u32>,
+}
+
+impl FieldConstraints {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_required(mut self) -> Self {
+    self.required = true;
+    self
+  }
+
+  pub fn with_unique(mut self) -> Self {
+    self.unique = true;
+    self
+  }
+
+  pub fn with_number_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+    self.min = min;
+    self.max = max;
+    self
+  }
+
+  pub fn with_pattern(mut self, pattern: impl ToString) -> Self {
+    self.pattern = Some(pattern.to_string());
+    self
+  }
+
+  pub fn with_max_length(mut self, max_length: u32) -> Self {
+    self.max_length = Some(max_length);
+    self
+  }
+
+  pub fn with_date_range(mut self, min_date: Option<i64>, max_date: Option<i64>) -> Self {
+    self.min_date = min_date;
+    self.max_date = max_date;
+    self
+  }
+
+  pub fn with_max_selected_options(mut self, max_selected_options: u32) -> Self {
+    self.max_selected_options = Some(max_selected_options);
+    self
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self == &Self::default()
+  }
+}
+
+impl From<FieldConstraintsData> for FieldConstraints {
+  fn from(data: FieldConstraintsData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<FieldConstraints> for FieldConstraintsData {
+  fn from(constraints: FieldConstraints) -> Self {
+    let mut data = FieldConstraintsData::from([
+      ("required".into(), constraints.required.into()),
+      ("unique".into(), constraints.unique.into()),
+    ]);
+    if let Some(min) = constraints.min {
+      data.insert("min".into(), Any::Number(min));
+    }
+    if let Some(max) = constraints.max {
+      data.insert("max".into(), Any::Number(max));
+    }
+    if let Some(pattern) = constraints.pattern {
+      data.insert("pattern".into(), pattern.into());
+    }
+    if let Some(max_length) = constraints.max_length {
+      data.insert("max_length".into(), Any::BigInt(max_length as i64));
+    }
+    if let Some(min_date) = constraints.min_date {
+      data.insert("min_date".into(), Any::BigInt(min_date));
+    }
+    if let Some(max_date) = constraints.max_date {
+      data.insert("max_date".into(), Any::BigInt(max_date));
+    }
+    if let Some(max_selected_options) = constraints.max_selected_options {
+      data.insert(
+        "max_selected_options".into(),
+        Any::BigInt(max_selected_options as i64),
+      );
+    }
+    data
+  }
+}
@@ -40,7 +40,8 @@ impl FieldMap {
           .set_last_modified(timestamp())
           .set_primary(field.is_primary)
           .set_field_type(field.field_type)
-          .set_type_options(field.type_options);
+          .set_type_options(field.type_options)
+          .set_constraints(field.constraints);
       })
       .done();
   }
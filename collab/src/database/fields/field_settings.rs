@@ -103,6 +103,7 @@ pub fn default_field_visibility(layout_type: DatabaseLayout) -> FieldVisibility
     DatabaseLayout::List => FieldVisibility::AlwaysShown,
     DatabaseLayout::Gallery => FieldVisibility::AlwaysShown,
     DatabaseLayout::Feed => FieldVisibility::AlwaysShown,
+    DatabaseLayout::Timeline => FieldVisibility::HideWhenEmpty,
   }
 }
 
@@ -1,4 +1,5 @@
 mod field;
+mod field_constraints;
 mod field_id;
 mod field_map;
 mod field_observer;
@@ -6,6 +7,7 @@ mod field_settings;
 mod type_option;
 
 pub use field::*;
+pub use field_constraints::*;
 pub use field_id::*;
 pub use field_map::*;
 pub use field_observer::*;
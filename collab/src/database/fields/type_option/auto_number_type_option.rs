@@ -0,0 +1,241 @@
+use super::{TypeOptionData, TypeOptionDataBuilder};
+use crate::database::entity::FieldType;
+use crate::database::fields::{Field, TypeOptionCellReader, TypeOptionCellWriter};
+use crate::database::rows::{Cell, new_cell_builder};
+use crate::database::template::entity::CELL_DATA;
+use crate::util::AnyMapExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use yrs::Any;
+use yrs::block::ClientID;
+
+/// The key of the cell storing the id of the client that issued the number.
+pub const AUTO_NUMBER_CLIENT_ID: &str = "client_id";
+
+/// The type option of an auto number field. Each row gets a unique number when it's created,
+/// displayed with the prefix and padded with zeros to `padding` digits, e.g. "TASK-0042".
+/// The numbers are issued as described in
+/// [AutoNumberReservations](crate::database::auto_number::AutoNumberReservations).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoNumberTypeOption {
+  #[serde(default)]
+  pub prefix: String,
+  #[serde(default)]
+  pub padding: u32,
+  /// The number of the first row.
+  #[serde(default = "default_start")]
+  pub start: i64,
+}
+
+fn default_start() -> i64 {
+  1
+}
+
+impl Default for AutoNumberTypeOption {
+  fn default() -> Self {
+    Self {
+      prefix: "".to_string(),
+      padding: 0,
+      start: default_start(),
+    }
+  }
+}
+
+impl AutoNumberTypeOption {
+  pub fn new(prefix: impl ToString, padding: u32) -> Self {
+    Self {
+      prefix: prefix.to_string(),
+      padding,
+      ..Default::default()
+    }
+  }
+
+  /// Return the readable value of the number, e.g. "TASK-0042".
+  pub fn format_number(&self, number: i64) -> String {
+    format!(
+      "{}{:0width$}",
+      self.prefix,
+      number,
+      width = self.padding as usize
+    )
+  }
+
+  /// Parse a number written with or without the prefix, e.g. "TASK-0042" or "42".
+  pub fn parse_number(&self, text: &str) -> Option<i64> {
+    let text = text.trim();
+    text
+      .strip_prefix(self.prefix.as_str())
+      .unwrap_or(text)
+      .parse()
+      .ok()
+  }
+}
+
+pub(crate) fn auto_number_type_option(field: &Field) -> AutoNumberTypeOption {
+  field
+    .get_any_type_option(FieldType::AutoNumber.type_id())
+    .map(AutoNumberTypeOption::from)
+    .unwrap_or_default()
+}
+
+impl From<TypeOptionData> for AutoNumberTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    let prefix: String = data.get_as("prefix").unwrap_or_default();
+    let padding = data
+      .get_as::<i64>("padding")
+      .map(|padding| padding.clamp(0, u32::MAX as i64) as u32)
+      .unwrap_or_default();
+    let start = data.get_as::<i64>("start").unwrap_or_else(default_start);
+    Self {
+      prefix,
+      padding,
+      start,
+    }
+  }
+}
+
+impl From<AutoNumberTypeOption> for TypeOptionData {
+  fn from(data: AutoNumberTypeOption) -> Self {
+    TypeOptionDataBuilder::from([
+      ("prefix".into(), Any::String(data.prefix.into())),
+      ("padding".into(), Any::BigInt(data.padding as i64)),
+      ("start".into(), Any::BigInt(data.start)),
+    ])
+  }
+}
+
+/// The value of an auto number cell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoNumberCellData {
+  pub number: Option<i64>,
+  /// The client that issued the number. It's None for the numbers written by
+  /// [TypeOptionCellWriter::convert_json_to_cell], e.g. when importing rows.
+  pub client_id: Option<ClientID>,
+}
+
+impl AutoNumberCellData {
+  pub fn new(number: i64, client_id: ClientID) -> Self {
+    Self {
+      number: Some(number),
+      client_id: Some(client_id),
+    }
+  }
+
+  pub fn to_cell(&self) -> Cell {
+    let mut cell = new_cell_builder(FieldType::AutoNumber);
+    if let Some(number) = self.number {
+      cell.insert(CELL_DATA.into(), number.to_string().into());
+    }
+    if let Some(client_id) = self.client_id {
+      cell.insert(AUTO_NUMBER_CLIENT_ID.into(), Any::BigInt(client_id as i64));
+    }
+    cell
+  }
+}
+
+impl From<&Cell> for AutoNumberCellData {
+  fn from(cell: &Cell) -> Self {
+    let number = cell
+      .get_as::<String>(CELL_DATA)
+      .and_then(|data| data.parse::<i64>().ok());
+    let client_id = cell
+      .get_as::<i64>(AUTO_NUMBER_CLIENT_ID)
+      .map(|client_id| client_id as ClientID);
+    Self { number, client_id }
+  }
+}
+
+impl TypeOptionCellReader for AutoNumberTypeOption {
+  fn json_cell(&self, cell: &Cell) -> Value {
+    match AutoNumberCellData::from(cell).number {
+      Some(number) => json!(self.format_number(number)),
+      None => Value::Null,
+    }
+  }
+
+  fn numeric_cell(&self, cell: &Cell) -> Option<f64> {
+    AutoNumberCellData::from(cell)
+      .number
+      .map(|number| number as f64)
+  }
+
+  fn convert_raw_cell_data(&self, cell_data: &str) -> String {
+    match cell_data.parse::<i64>() {
+      Ok(number) => self.format_number(number),
+      Err(_) => cell_data.to_string(),
+    }
+  }
+}
+
+impl TypeOptionCellWriter for AutoNumberTypeOption {
+  fn convert_json_to_cell(&self, json_value: Value) -> Cell {
+    let number = match json_value {
+      Value::Number(number) => number.as_i64(),
+      Value::String(text) => self.parse_number(&text),
+      _ => None,
+    };
+    AutoNumberCellData {
+      number,
+      client_id: None,
+    }
+    .to_cell()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn auto_number_type_option_round_trip() {
+    let option = AutoNumberTypeOption {
+      prefix: "TASK-".to_string(),
+      padding: 4,
+      start: 100,
+    };
+    let data: TypeOptionData = option.clone().into();
+    assert_eq!(AutoNumberTypeOption::from(data), option);
+    assert_eq!(
+      AutoNumberTypeOption::from(TypeOptionData::new()),
+      AutoNumberTypeOption::default()
+    );
+  }
+
+  #[test]
+  fn auto_number_format_and_parse() {
+    let option = AutoNumberTypeOption::new("TASK-", 4);
+    assert_eq!(option.format_number(42), "TASK-0042");
+    assert_eq!(option.format_number(123456), "TASK-123456");
+    assert_eq!(option.parse_number("TASK-0042"), Some(42));
+    assert_eq!(option.parse_number("42"), Some(42));
+    assert_eq!(option.parse_number("BUG-42"), None);
+    assert_eq!(AutoNumberTypeOption::default().format_number(7), "7");
+  }
+
+  #[test]
+  fn auto_number_cell_to_serde() {
+    let option = AutoNumberTypeOption::new("TASK-", 3);
+    let cell = AutoNumberCellData::new(7, 1).to_cell();
+    assert_eq!(option.json_cell(&cell), json!("TASK-007"));
+    assert_eq!(option.numeric_cell(&cell), Some(7.0));
+    assert_eq!(option.stringify_cell(&cell), "TASK-007");
+    assert_eq!(AutoNumberCellData::from(&cell).client_id, Some(1));
+  }
+
+  #[test]
+  fn auto_number_serde_to_cell() {
+    let option = AutoNumberTypeOption::new("TASK-", 3);
+    let cell = option.convert_json_to_cell(json!("TASK-012"));
+    assert_eq!(
+      AutoNumberCellData::from(&cell),
+      AutoNumberCellData {
+        number: Some(12),
+        client_id: None,
+      }
+    );
+    let cell = option.convert_json_to_cell(json!(5));
+    assert_eq!(AutoNumberCellData::from(&cell).number, Some(5));
+    let cell = option.convert_json_to_cell(json!(null));
+    assert_eq!(AutoNumberCellData::from(&cell).number, None);
+  }
+}
@@ -1,3 +1,4 @@
+use crate::database::calendar::RecurrenceRule;
 use crate::database::entity::FieldType;
 use crate::database::template::date_parse::cast_string_to_timestamp;
 use crate::error::CollabError;
@@ -428,6 +429,8 @@ impl DateFormat {
   }
 }
 
+const RECURRENCE: &str = "recurrence";
+
 #[derive(Default, Clone, Debug, Serialize)]
 pub struct DateCellData {
   pub timestamp: Option<i64>,
@@ -437,6 +440,9 @@ pub struct DateCellData {
   #[serde(default)]
   pub is_range: bool,
   pub reminder_id: String,
+  /// Set for recurring events. The timestamps are the first occurrence.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub recurrence: Option<RecurrenceRule>,
 }
 impl TypeOptionCellData for DateCellData {
   fn is_cell_empty(&self) -> bool {
@@ -452,6 +458,7 @@ impl DateCellData {
       include_time,
       is_range,
       reminder_id,
+      recurrence: None,
     }
   }
 
@@ -462,9 +469,15 @@ impl DateCellData {
       include_time: false,
       is_range: false,
       reminder_id: String::new(),
+      recurrence: None,
     }
   }
 
+  pub fn with_recurrence(mut self, recurrence: RecurrenceRule) -> Self {
+    self.recurrence = Some(recurrence);
+    self
+  }
+
   pub fn from_timestamp_include_time(timestamp: i64) -> Self {
     Self::new(timestamp, true, false, String::new())
   }
@@ -481,6 +494,9 @@ impl From<&Cell> for DateCellData {
     let include_time: bool = cell.get_as("include_time").unwrap_or_default();
     let is_range: bool = cell.get_as("is_range").unwrap_or_default();
     let reminder_id: String = cell.get_as("reminder_id").unwrap_or_default();
+    let recurrence = cell
+      .get_as::<String>(RECURRENCE)
+      .and_then(|data| serde_json::from_str::<RecurrenceRule>(&data).ok());
 
     Self {
       timestamp,
@@ -488,6 +504,7 @@ impl From<&Cell> for DateCellData {
       include_time,
       is_range,
       reminder_id,
+      recurrence,
     }
   }
 }
@@ -513,6 +530,13 @@ impl From<&DateCellData> for Cell {
       "reminder_id".into(),
       cell_data.reminder_id.to_owned().into(),
     );
+    // An empty string clears the recurrence of the updated cell
+    let recurrence = cell_data
+      .recurrence
+      .as_ref()
+      .and_then(|recurrence| serde_json::to_string(recurrence).ok())
+      .unwrap_or_default();
+    cell.insert(RECURRENCE.into(), recurrence.into());
     cell
   }
 }
@@ -540,6 +564,7 @@ impl<'de> serde::Deserialize<'de> for DateCellData {
           include_time: false,
           is_range: false,
           reminder_id: String::new(),
+          recurrence: None,
         })
       }
 
@@ -559,6 +584,7 @@ impl<'de> serde::Deserialize<'de> for DateCellData {
         let mut include_time: Option<bool> = None;
         let mut is_range: Option<bool> = None;
         let mut reminder_id: Option<String> = None;
+        let mut recurrence: Option<RecurrenceRule> = None;
 
         while let Some(key) = map.next_key::<String>()? {
           match key.as_str() {
@@ -577,6 +603,12 @@ impl<'de> serde::Deserialize<'de> for DateCellData {
             "reminder_id" => {
               reminder_id = map.next_value().ok();
             },
+            "recurrence" => {
+              recurrence = match map.next_value::<serde_json::Value>()? {
+                serde_json::Value::String(s) => serde_json::from_str(&s).ok(),
+                value => serde_json::from_value(value).ok(),
+              };
+            },
             _ => {
               let _: serde_json::Value = map.next_value()?; // Ignore unknown keys
             },
@@ -589,6 +621,7 @@ impl<'de> serde::Deserialize<'de> for DateCellData {
           include_time: include_time.unwrap_or_default(),
           is_range: is_range.unwrap_or_default(),
           reminder_id: reminder_id.unwrap_or_default(),
+          recurrence,
         })
       }
     }
@@ -655,6 +688,7 @@ mod tests {
       include_time: true,
       is_range: true,
       reminder_id: "reminder123".to_string(),
+      recurrence: None,
     };
 
     let cell = Cell::from(&date_cell_data);
@@ -0,0 +1,140 @@
+use super::{TypeOptionData, TypeOptionDataBuilder};
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateTypeOption;
+use crate::database::fields::{TypeOptionCellReader, TypeOptionCellWriter};
+use crate::database::rows::{Cell, new_cell_builder};
+use crate::database::template::entity::CELL_DATA;
+use crate::util::AnyMapExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use yrs::Any;
+
+/// The type of the value a formula evaluates to. It decides how the formula cells are read,
+/// sorted and filtered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum FormulaResultType {
+  #[default]
+  Text = 0,
+  Number = 1,
+  /// Stored as a timestamp in seconds.
+  Date = 2,
+  /// Stored as "true" or "false".
+  Checkbox = 3,
+}
+
+impl From<i64> for FormulaResultType {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => FormulaResultType::Text,
+      1 => FormulaResultType::Number,
+      2 => FormulaResultType::Date,
+      3 => FormulaResultType::Checkbox,
+      _ => {
+        tracing::error!("Unsupported formula result type, fallback to Text");
+        FormulaResultType::Text
+      },
+    }
+  }
+}
+
+impl FormulaResultType {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+/// The type option of a formula field. The cells hold the result of the expression, computed
+/// by the [FormulaEvaluator](crate::database::formula::FormulaEvaluator).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormulaTypeOption {
+  #[serde(default)]
+  pub expression: String,
+  #[serde(default)]
+  pub result_type: FormulaResultType,
+}
+
+impl FormulaTypeOption {
+  pub fn new(expression: impl ToString, result_type: FormulaResultType) -> Self {
+    Self {
+      expression: expression.to_string(),
+      result_type,
+    }
+  }
+}
+
+impl From<TypeOptionData> for FormulaTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    let expression: String = data.get_as("expression").unwrap_or_default();
+    let result_type = data
+      .get_as::<i64>("result_type")
+      .map(FormulaResultType::from)
+      .unwrap_or_default();
+    Self {
+      expression,
+      result_type,
+    }
+  }
+}
+
+impl From<FormulaTypeOption> for TypeOptionData {
+  fn from(data: FormulaTypeOption) -> Self {
+    TypeOptionDataBuilder::from([
+      ("expression".into(), Any::String(data.expression.into())),
+      ("result_type".into(), Any::BigInt(data.result_type.value())),
+    ])
+  }
+}
+
+impl TypeOptionCellReader for FormulaTypeOption {
+  fn json_cell(&self, cell: &Cell) -> Value {
+    let Some(cell_data) = cell.get_as::<String>(CELL_DATA) else {
+      return Value::Null;
+    };
+    match self.result_type {
+      FormulaResultType::Text => json!(cell_data),
+      FormulaResultType::Number => cell_data
+        .parse::<f64>()
+        .map(|number| json!(number))
+        .unwrap_or(Value::Null),
+      FormulaResultType::Date => cell_data
+        .parse::<i64>()
+        .map(|timestamp| json!(timestamp))
+        .unwrap_or(Value::Null),
+      FormulaResultType::Checkbox => json!(cell_data == "true"),
+    }
+  }
+
+  fn numeric_cell(&self, cell: &Cell) -> Option<f64> {
+    let cell_data = cell.get_as::<String>(CELL_DATA)?;
+    match self.result_type {
+      FormulaResultType::Text => None,
+      FormulaResultType::Number | FormulaResultType::Date => cell_data.parse().ok(),
+      FormulaResultType::Checkbox => Some(if cell_data == "true" { 1.0 } else { 0.0 }),
+    }
+  }
+
+  fn convert_raw_cell_data(&self, cell_data: &str) -> String {
+    match self.result_type {
+      FormulaResultType::Date => DateTypeOption::default_utc().convert_raw_cell_data(cell_data),
+      _ => cell_data.to_string(),
+    }
+  }
+}
+
+impl TypeOptionCellWriter for FormulaTypeOption {
+  fn convert_json_to_cell(&self, json_value: Value) -> Cell {
+    let mut cell = new_cell_builder(FieldType::Formula);
+    match json_value {
+      Value::Null => {},
+      Value::String(value_str) => {
+        cell.insert(CELL_DATA.into(), value_str.into());
+      },
+      _ => {
+        cell.insert(CELL_DATA.into(), json_value.to_string().into());
+      },
+    }
+    cell
+  }
+}
@@ -1,6 +1,8 @@
+pub mod auto_number_type_option;
 pub mod checkbox_type_option;
 pub mod checklist_type_option;
 pub mod date_type_option;
+pub mod formula_type_option;
 pub mod media_type_option;
 pub mod number_type_option;
 pub mod person_type_option;
@@ -17,8 +19,10 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
 use crate::database::entity::FieldType;
+use crate::database::fields::auto_number_type_option::AutoNumberTypeOption;
 use crate::database::fields::checklist_type_option::ChecklistTypeOption;
 use crate::database::fields::date_type_option::{DateTypeOption, TimeTypeOption};
+use crate::database::fields::formula_type_option::FormulaTypeOption;
 use crate::database::fields::media_type_option::MediaTypeOption;
 use crate::database::fields::number_type_option::NumberTypeOption;
 use crate::database::fields::person_type_option::PersonTypeOption;
@@ -35,6 +39,7 @@ use crate::database::rows::Cell;
 use crate::database::template::entity::CELL_DATA;
 use crate::preclude::{Any, FillRef, Map, MapRef, ReadTxn, ToJson, TransactionMut};
 use crate::util::{AnyExt, AnyMapExt};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 /// It's used to store lists of field's type option data
@@ -184,6 +189,8 @@ pub fn type_option_cell_writer(
     FieldType::Translate => Box::new(TranslateTypeOption::from(type_option_data)),
     FieldType::Person => Box::new(PersonTypeOption::from(type_option_data)),
     FieldType::Rollup => Box::new(RollupTypeOption::from(type_option_data)),
+    FieldType::Formula => Box::new(FormulaTypeOption::from(type_option_data)),
+    FieldType::AutoNumber => Box::new(AutoNumberTypeOption::from(type_option_data)),
   }
 }
 
@@ -209,5 +216,25 @@ pub fn type_option_cell_reader(
     FieldType::Translate => Box::new(TranslateTypeOption::from(type_option_data)),
     FieldType::Person => Box::new(PersonTypeOption::from(type_option_data)),
     FieldType::Rollup => Box::new(RollupTypeOption::from(type_option_data)),
+    FieldType::Formula => Box::new(FormulaTypeOption::from(type_option_data)),
+    FieldType::AutoNumber => Box::new(AutoNumberTypeOption::from(type_option_data)),
   }
 }
+
+/// Returns the timezone the dates of a date, created time or last edited time field are
+/// displayed in. Other field types, and unknown timezone ids, fall back to UTC.
+pub(crate) fn type_option_timezone(
+  type_option_data: &TypeOptionData,
+  field_type: &FieldType,
+) -> Tz {
+  let timezone = match field_type {
+    FieldType::DateTime => Some(DateTypeOption::from(type_option_data.clone()).timezone_id),
+    FieldType::CreatedTime | FieldType::LastEditedTime => {
+      TimestampTypeOption::from(type_option_data.clone()).timezone
+    },
+    _ => None,
+  };
+  timezone
+    .and_then(|timezone| timezone.parse::<Tz>().ok())
+    .unwrap_or(Tz::UTC)
+}
@@ -1,7 +1,9 @@
 use crate::util::AnyMapExt;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
+use uuid::Uuid;
 
+use crate::database::template::person_parse::PersonCellData;
 use crate::database::{
   fields::{TypeOptionCellReader, TypeOptionCellWriter, TypeOptionData, TypeOptionDataBuilder},
   rows::Cell,
@@ -10,18 +12,103 @@ use crate::database::{
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PersonTypeOption {
   pub is_single_select: bool,
+  /// Fill the cell of a new row with the user who created it.
   pub fill_with_creator: bool,
   pub disable_notification: bool,
+  /// The persons referenced by the cells, used to resolve their names and emails.
   pub persons: Vec<DatabasePerson>,
 }
 
+impl PersonTypeOption {
+  pub fn person(&self, id: &str) -> Option<&DatabasePerson> {
+    self.persons.iter().find(|person| person.id == id)
+  }
+
+  /// Return the name of the person, or its id when the person is unknown or has no name.
+  pub fn person_name(&self, id: &str) -> String {
+    self
+      .person(id)
+      .and_then(|person| person.name.clone())
+      .unwrap_or_else(|| id.to_string())
+  }
+
+  /// Resolve a value to a person id. The value is matched against the ids, the emails and then
+  /// the names of the known persons. A value that matches none of them is taken as an id when it
+  /// has the format of one, see [is_person_id], and is rejected otherwise.
+  pub fn resolve_person_id(&self, value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+      return None;
+    }
+    let person = self
+      .person(value)
+      .or_else(|| {
+        self.persons.iter().find(|person| {
+          person
+            .email
+            .as_ref()
+            .is_some_and(|email| email.eq_ignore_ascii_case(value))
+        })
+      })
+      .or_else(|| {
+        self
+          .persons
+          .iter()
+          .find(|person| person.name.as_deref() == Some(value))
+      });
+    match person {
+      Some(person) => Some(person.id.clone()),
+      None if is_person_id(value) => Some(value.to_string()),
+      None => None,
+    }
+  }
+
+  /// Resolve the values to person ids, without duplicates. Only the first person is kept when
+  /// the field is single select.
+  pub fn person_cell_data<'a>(&self, values: impl IntoIterator<Item = &'a str>) -> PersonCellData {
+    let mut person_ids: Vec<String> = vec![];
+    for id in values
+      .into_iter()
+      .flat_map(|value| self.resolve_person_id(value))
+    {
+      if !person_ids.contains(&id) {
+        person_ids.push(id);
+      }
+    }
+    if self.is_single_select {
+      person_ids.truncate(1);
+    }
+    PersonCellData::new(person_ids)
+  }
+
+  fn names(&self, cell_data: &PersonCellData) -> String {
+    cell_data
+      .person_ids
+      .iter()
+      .map(|id| self.person_name(id))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+}
+
 impl TypeOptionCellReader for PersonTypeOption {
+  /// Return the persons of the cell. The unknown persons only have an id.
   fn json_cell(&self, cell: &Cell) -> Value {
-    serde_json::to_value(self.stringify_cell(cell)).unwrap_or(Value::Null)
+    let persons = PersonCellData::from(cell)
+      .person_ids
+      .into_iter()
+      .map(|id| {
+        self.person(&id).cloned().unwrap_or(DatabasePerson {
+          id,
+          ..Default::default()
+        })
+      })
+      .collect::<Vec<_>>();
+    json!(persons)
   }
 
   fn stringify_cell(&self, cell_data: &Cell) -> String {
-    serde_json::to_string(cell_data).unwrap_or_default()
+    self.names(&PersonCellData::from(cell_data))
   }
 
   fn numeric_cell(&self, _cell: &Cell) -> Option<f64> {
@@ -29,13 +116,47 @@ impl TypeOptionCellReader for PersonTypeOption {
   }
 
   fn convert_raw_cell_data(&self, text: &str) -> String {
-    text.to_string()
+    self.names(&PersonCellData::from(text))
   }
 }
 
 impl TypeOptionCellWriter for PersonTypeOption {
-  fn convert_json_to_cell(&self, _json_value: Value) -> Cell {
-    Cell::new()
+  /// Accept a person id, email or name, a [DatabasePerson] object, an array of them, or a
+  /// string holding comma separated ones or a JSON array of them.
+  fn convert_json_to_cell(&self, json_value: Value) -> Cell {
+    let values = match json_value {
+      Value::String(s) => match serde_json::from_str::<Value>(&s) {
+        Ok(Value::Array(values)) => values.into_iter().flat_map(person_value).collect(),
+        _ => PersonCellData::from(s.as_str()).person_ids,
+      },
+      Value::Array(values) => values.into_iter().flat_map(person_value).collect(),
+      value => person_value(value).into_iter().collect(),
+    };
+    self
+      .person_cell_data(values.iter().map(|value| value.as_str()))
+      .to_cell()
+  }
+}
+
+/// Whether the value has the format of a person id: a user id or a UUID.
+pub fn is_person_id(value: &str) -> bool {
+  value.parse::<i64>().is_ok_and(|uid| uid > 0) || Uuid::parse_str(value).is_ok()
+}
+
+/// Return the id of a person value, or its email when an object has no id.
+fn person_value(value: Value) -> Option<String> {
+  match value {
+    Value::String(s) => Some(s),
+    Value::Number(n) => Some(n.to_string()),
+    Value::Object(_) => {
+      let person = serde_json::from_value::<DatabasePerson>(value).ok()?;
+      if person.id.is_empty() {
+        person.email
+      } else {
+        Some(person.id)
+      }
+    },
+    _ => None,
   }
 }
 
@@ -55,15 +176,99 @@ impl From<PersonTypeOption> for TypeOptionData {
   }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct DatabasePerson {
+  #[serde(default)]
   pub id: String,
 
   #[serde(default)]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub name: Option<String>,
 
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub email: Option<String>,
+
   #[serde(default)]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub avatar_url: Option<String>,
 }
+
+impl DatabasePerson {
+  pub fn new(id: impl ToString) -> Self {
+    Self {
+      id: id.to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_name(mut self, name: &str) -> Self {
+    self.name = Some(name.to_string());
+    self
+  }
+
+  pub fn with_email(mut self, email: &str) -> Self {
+    self.email = Some(email.to_string());
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::template::entity::CELL_DATA;
+
+  fn type_option() -> PersonTypeOption {
+    PersonTypeOption {
+      persons: vec![
+        DatabasePerson::new(1)
+          .with_name("Lucas")
+          .with_email("lucas@appflowy.io"),
+        DatabasePerson::new(2).with_name("Nathan"),
+      ],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn person_json_to_cell_test() {
+    let type_option = type_option();
+    let cell = type_option.convert_json_to_cell(json!("LUCAS@appflowy.io, Nathan, 3, x@y.z"));
+    assert_eq!(
+      cell.get_as::<String>(CELL_DATA).unwrap(),
+      r#"["1","2","3"]"#
+    );
+
+    let cell = type_option.convert_json_to_cell(json!([
+      2,
+      { "id": "1", "name": "Lucas" },
+      { "email": "lucas@appflowy.io" }
+    ]));
+    assert_eq!(PersonCellData::from(&cell).person_ids, vec!["2", "1"]);
+
+    let cell = type_option.convert_json_to_cell(json!(r#"["2",{"id":"1"}]"#));
+    assert_eq!(PersonCellData::from(&cell).person_ids, vec!["2", "1"]);
+  }
+
+  #[test]
+  fn single_select_person_json_to_cell_test() {
+    let type_option = PersonTypeOption {
+      is_single_select: true,
+      ..type_option()
+    };
+    let cell = type_option.convert_json_to_cell(json!(["2", "1"]));
+    assert_eq!(PersonCellData::from(&cell).person_ids, vec!["2"]);
+  }
+
+  #[test]
+  fn person_cell_to_string_test() {
+    let type_option = type_option();
+    let cell = PersonCellData::new(vec!["2".to_string(), "3".to_string()]).to_cell();
+    assert_eq!(type_option.stringify_cell(&cell), "Nathan, 3");
+    assert_eq!(
+      type_option.json_cell(&cell),
+      json!([{ "id": "2", "name": "Nathan" }, { "id": "3" }])
+    );
+    assert_eq!(type_option.convert_raw_cell_data(r#"["1"]"#), "Lucas");
+  }
+}
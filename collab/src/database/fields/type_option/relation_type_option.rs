@@ -11,18 +11,48 @@ use std::str::FromStr;
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RelationTypeOption {
   pub database_id: String,
+  /// The relation field of the related database that links back to the rows of this field's
+  /// database. When set, the two fields are kept in sync, see
+  /// [Database::update_relation_cell](crate::database::database::Database::update_relation_cell).
+  #[serde(default)]
+  pub reverse_field_id: Option<String>,
+}
+
+impl RelationTypeOption {
+  pub fn new(database_id: &str) -> Self {
+    Self {
+      database_id: database_id.to_string(),
+      reverse_field_id: None,
+    }
+  }
+
+  pub fn with_reverse_field(mut self, reverse_field_id: &str) -> Self {
+    self.reverse_field_id = Some(reverse_field_id.to_string());
+    self
+  }
 }
 
 impl From<TypeOptionData> for RelationTypeOption {
   fn from(data: TypeOptionData) -> Self {
     let database_id: String = data.get_as("database_id").unwrap_or_default();
-    Self { database_id }
+    let reverse_field_id: Option<String> = data
+      .get_as::<String>("reverse_field_id")
+      .filter(|field_id| !field_id.is_empty());
+    Self {
+      database_id,
+      reverse_field_id,
+    }
   }
 }
 
 impl From<RelationTypeOption> for TypeOptionData {
   fn from(data: RelationTypeOption) -> Self {
-    TypeOptionDataBuilder::from([("database_id".into(), data.database_id.into())])
+    let mut type_option =
+      TypeOptionDataBuilder::from([("database_id".into(), data.database_id.into())]);
+    if let Some(reverse_field_id) = data.reverse_field_id {
+      type_option.insert("reverse_field_id".into(), reverse_field_id.into());
+    }
+    type_option
   }
 }
 
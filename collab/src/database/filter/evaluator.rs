@@ -0,0 +1,609 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Days, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::media_type_option::MediaCellData;
+use crate::database::fields::select_type_option::SelectOptionIds;
+use crate::database::fields::{
+  Field, TypeOptionCellReader, type_option_cell_reader, type_option_timezone,
+};
+use crate::database::filter::{
+  CheckboxFilter, CheckboxFilterCondition, ChecklistFilter, ChecklistFilterCondition, DateFilter,
+  DateFilterCondition, FieldFilter, Filter, FilterInner, ListFilterCondition, MediaFilter,
+  MediaFilterCondition, NumberFilter, NumberFilterCondition, RelativeDate, SelectOptionFilter,
+  SelectOptionFilterCondition, TextFilter, TextFilterCondition,
+};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::check_list_parse::ChecklistCellData;
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::template::relation_parse::RelationCellData;
+
+/// Evaluates a view's [Filter]s against rows.
+///
+/// A filter that can't be applied, e.g. its field was deleted, its field type no longer matches
+/// the field, or its content is incomplete, doesn't hide any row. Dates, including the day of
+/// `now` that relative dates are resolved against, are compared in the timezone of the field.
+pub struct FilterEvaluator {
+  filters: Vec<Filter>,
+  fields: HashMap<String, FilterField>,
+  now: DateTime<Utc>,
+}
+
+struct FilterField {
+  field_type: FieldType,
+  reader: Box<dyn TypeOptionCellReader>,
+  timezone: Tz,
+}
+
+impl FilterEvaluator {
+  pub fn new(filters: Vec<Filter>, fields: Vec<Field>) -> Self {
+    Self::new_with_now(filters, fields, Utc::now())
+  }
+
+  pub fn new_with_now(filters: Vec<Filter>, fields: Vec<Field>, now: DateTime<Utc>) -> Self {
+    let fields = fields
+      .into_iter()
+      .map(|field| {
+        let field_type = FieldType::from(field.field_type);
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        let timezone = type_option_timezone(&type_option, &field_type);
+        let reader = type_option_cell_reader(type_option, &field_type);
+        let filter_field = FilterField {
+          field_type,
+          reader,
+          timezone,
+        };
+        (field.id, filter_field)
+      })
+      .collect();
+
+    Self {
+      filters,
+      fields,
+      now,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.filters.is_empty()
+  }
+
+  /// Return true if the row passes all the filters.
+  pub fn is_row_visible(&self, row: &Row) -> bool {
+    self
+      .filters
+      .iter()
+      .all(|filter| self.is_visible(filter, row))
+  }
+
+  fn is_visible(&self, filter: &Filter, row: &Row) -> bool {
+    match &filter.inner {
+      FilterInner::And { children } => children.iter().all(|child| self.is_visible(child, row)),
+      FilterInner::Or { children } => {
+        children.is_empty() || children.iter().any(|child| self.is_visible(child, row))
+      },
+      FilterInner::Data {
+        field_id,
+        field_type,
+        filter,
+      } => self
+        .is_data_visible(field_id, field_type, filter, row)
+        .unwrap_or(true),
+    }
+  }
+
+  /// Return None if the filter can't be applied to the row.
+  fn is_data_visible(
+    &self,
+    field_id: &str,
+    field_type: &FieldType,
+    filter: &FieldFilter,
+    row: &Row,
+  ) -> Option<bool> {
+    let field = self.fields.get(field_id)?;
+    if &field.field_type != field_type {
+      return None;
+    }
+
+    let empty_cell = Cell::new();
+    let cell = row.cells.get(field_id).unwrap_or(&empty_cell);
+    match filter {
+      FieldFilter::Text(filter) => Some(filter.is_visible(&field.reader.stringify_cell(cell))),
+      FieldFilter::Number(filter) => filter.is_visible(field.reader.numeric_cell(cell)),
+      FieldFilter::Date(filter) => {
+        let cell_data = match field_type {
+          FieldType::CreatedTime => DateCellData::from_timestamp(row.created_at),
+          FieldType::LastEditedTime => DateCellData::from_timestamp(row.modified_at),
+          _ => DateCellData::from(cell),
+        };
+        let today = self.now.with_timezone(&field.timezone).date_naive();
+        filter.is_visible(&cell_data, today, &field.timezone)
+      },
+      FieldFilter::SelectOption(filter) => {
+        filter.is_visible(field_type, &SelectOptionIds::from(cell))
+      },
+      FieldFilter::Checkbox(filter) => {
+        Some(filter.is_visible(field.reader.numeric_cell(cell) == Some(1.0)))
+      },
+      FieldFilter::Checklist(filter) => Some(filter.is_visible(&ChecklistCellData::from(cell))),
+      FieldFilter::Media(filter) => Some(filter.is_visible(&MediaCellData::from(cell))),
+      FieldFilter::Person(filter) => is_list_visible(
+        &filter.condition,
+        &filter.person_ids,
+        &PersonCellData::from(cell).person_ids,
+      ),
+      FieldFilter::Relation(filter) => {
+        let row_ids = RelationCellData::from(cell)
+          .row_ids
+          .iter()
+          .map(|row_id| row_id.to_string())
+          .collect::<Vec<_>>();
+        is_list_visible(&filter.condition, &filter.row_ids, &row_ids)
+      },
+    }
+  }
+}
+
+impl TextFilter {
+  /// Text is compared case-insensitively. An empty cell is treated as an empty string.
+  pub fn is_visible(&self, text: &str) -> bool {
+    let text = text.to_lowercase();
+    let content = self.content.to_lowercase();
+    match self.condition {
+      TextFilterCondition::Is => text == content,
+      TextFilterCondition::IsNot => text != content,
+      TextFilterCondition::Contains => text.contains(&content),
+      TextFilterCondition::DoesNotContain => !text.contains(&content),
+      TextFilterCondition::StartsWith => text.starts_with(&content),
+      TextFilterCondition::EndsWith => text.ends_with(&content),
+      TextFilterCondition::IsEmpty => text.is_empty(),
+      TextFilterCondition::IsNotEmpty => !text.is_empty(),
+    }
+  }
+}
+
+impl NumberFilter {
+  /// Return None if the content isn't a number. Comparisons never match an empty cell.
+  pub fn is_visible(&self, value: Option<f64>) -> Option<bool> {
+    let expected = || self.content.trim().parse::<f64>().ok();
+    let is_visible = match self.condition {
+      NumberFilterCondition::IsEmpty => return Some(value.is_none()),
+      NumberFilterCondition::IsNotEmpty => return Some(value.is_some()),
+      NumberFilterCondition::Equal => {
+        let expected = expected()?;
+        value.is_some_and(|value| value == expected)
+      },
+      NumberFilterCondition::NotEqual => {
+        let expected = expected()?;
+        value.is_some_and(|value| value != expected)
+      },
+      NumberFilterCondition::GreaterThan => {
+        let expected = expected()?;
+        value.is_some_and(|value| value > expected)
+      },
+      NumberFilterCondition::LessThan => {
+        let expected = expected()?;
+        value.is_some_and(|value| value < expected)
+      },
+      NumberFilterCondition::GreaterThanOrEqualTo => {
+        let expected = expected()?;
+        value.is_some_and(|value| value >= expected)
+      },
+      NumberFilterCondition::LessThanOrEqualTo => {
+        let expected = expected()?;
+        value.is_some_and(|value| value <= expected)
+      },
+    };
+    Some(is_visible)
+  }
+}
+
+impl DateFilter {
+  /// Dates are compared by their day in `timezone`, which `today` must be given in as well.
+  /// Return None if the content doesn't provide the dates the condition needs.
+  pub fn is_visible(
+    &self,
+    cell_data: &DateCellData,
+    today: NaiveDate,
+    timezone: &Tz,
+  ) -> Option<bool> {
+    let timestamp = if self.condition.is_filter_on_start() || !cell_data.is_range {
+      cell_data.timestamp
+    } else {
+      cell_data.end_timestamp.or(cell_data.timestamp)
+    };
+
+    let in_range = |is_visible: fn(NaiveDate, NaiveDate, NaiveDate) -> bool| {
+      let (start, end) = self.expected_range(today, timezone)?;
+      let date = timestamp.and_then(|timestamp| date_from_timestamp(timestamp, timezone));
+      Some(date.is_some_and(|date| is_visible(date, start, end)))
+    };
+    match self.condition {
+      DateFilterCondition::StartIsEmpty | DateFilterCondition::EndIsEmpty => {
+        Some(timestamp.is_none())
+      },
+      DateFilterCondition::StartIsNotEmpty | DateFilterCondition::EndIsNotEmpty => {
+        Some(timestamp.is_some())
+      },
+      DateFilterCondition::StartsOn
+      | DateFilterCondition::EndsOn
+      | DateFilterCondition::StartsBetween
+      | DateFilterCondition::EndsBetween => {
+        in_range(|date, start, end| start <= date && date <= end)
+      },
+      DateFilterCondition::StartsBefore | DateFilterCondition::EndsBefore => {
+        in_range(|date, start, _| date < start)
+      },
+      DateFilterCondition::StartsAfter | DateFilterCondition::EndsAfter => {
+        in_range(|date, _, end| date > end)
+      },
+      DateFilterCondition::StartsOnOrBefore | DateFilterCondition::EndsOnOrBefore => {
+        in_range(|date, _, end| date <= end)
+      },
+      DateFilterCondition::StartsOnOrAfter | DateFilterCondition::EndsOnOrAfter => {
+        in_range(|date, start, _| date >= start)
+      },
+    }
+  }
+
+  /// The inclusive range of days the condition compares against.
+  fn expected_range(&self, today: NaiveDate, timezone: &Tz) -> Option<(NaiveDate, NaiveDate)> {
+    if let Some(relative) = self.content.relative {
+      return relative.range(today);
+    }
+
+    match self.condition {
+      DateFilterCondition::StartsBetween | DateFilterCondition::EndsBetween => {
+        let start = date_from_timestamp(self.content.start?, timezone)?;
+        let end = date_from_timestamp(self.content.end?, timezone)?;
+        Some((start, end))
+      },
+      _ => {
+        let date = date_from_timestamp(self.content.timestamp?, timezone)?;
+        Some((date, date))
+      },
+    }
+  }
+}
+
+impl RelativeDate {
+  /// The inclusive range of days this relative date covers, seen from `today`.
+  pub fn range(&self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let range = match self {
+      RelativeDate::Today => (today, today),
+      RelativeDate::Yesterday => {
+        let yesterday = today.checked_sub_days(Days::new(1))?;
+        (yesterday, yesterday)
+      },
+      RelativeDate::Tomorrow => {
+        let tomorrow = today.checked_add_days(Days::new(1))?;
+        (tomorrow, tomorrow)
+      },
+      RelativeDate::PastWeek => (today.checked_sub_days(Days::new(7))?, today),
+      RelativeDate::PastMonth => (today.checked_sub_months(Months::new(1))?, today),
+      RelativeDate::PastYear => (today.checked_sub_months(Months::new(12))?, today),
+      RelativeDate::NextWeek => (today, today.checked_add_days(Days::new(7))?),
+      RelativeDate::NextMonth => (today, today.checked_add_months(Months::new(1))?),
+      RelativeDate::NextYear => (today, today.checked_add_months(Months::new(12))?),
+    };
+    Some(range)
+  }
+}
+
+fn date_from_timestamp(timestamp: i64, timezone: &Tz) -> Option<NaiveDate> {
+  DateTime::from_timestamp(timestamp, 0)
+    .map(|date_time| date_time.with_timezone(timezone).date_naive())
+}
+
+impl SelectOptionFilter {
+  /// Return None if the condition needs option ids and the filter has none.
+  ///
+  /// For a single select field, [SelectOptionFilterCondition::Is] matches when the selected
+  /// option is one of the filter's options. For a multi select field, the selected options must
+  /// be exactly the filter's options.
+  pub fn is_visible(&self, field_type: &FieldType, selected: &SelectOptionIds) -> Option<bool> {
+    let is_match = || match field_type {
+      FieldType::SingleSelect => selected
+        .first()
+        .is_some_and(|id| self.option_ids.contains(id)),
+      _ => {
+        let selected = selected.iter().collect::<HashSet<_>>();
+        let expected = self.option_ids.iter().collect::<HashSet<_>>();
+        selected == expected
+      },
+    };
+    let is_overlapping = || selected.iter().any(|id| self.option_ids.contains(id));
+    let has_options = !self.option_ids.is_empty();
+    match self.condition {
+      SelectOptionFilterCondition::IsEmpty => Some(selected.is_empty()),
+      SelectOptionFilterCondition::IsNotEmpty => Some(!selected.is_empty()),
+      SelectOptionFilterCondition::Is => has_options.then(|| !selected.is_empty() && is_match()),
+      SelectOptionFilterCondition::IsNot => has_options.then(|| selected.is_empty() || !is_match()),
+      SelectOptionFilterCondition::Contains => has_options.then(is_overlapping),
+      SelectOptionFilterCondition::DoesNotContain => has_options.then(|| !is_overlapping()),
+    }
+  }
+}
+
+impl CheckboxFilter {
+  pub fn is_visible(&self, is_checked: bool) -> bool {
+    match self.condition {
+      CheckboxFilterCondition::IsChecked => is_checked,
+      CheckboxFilterCondition::IsUnchecked => !is_checked,
+    }
+  }
+}
+
+impl ChecklistFilter {
+  /// A checklist is complete when it has tasks and all of them are selected.
+  pub fn is_visible(&self, cell_data: &ChecklistCellData) -> bool {
+    let is_complete = !cell_data.options.is_empty()
+      && cell_data
+        .options
+        .iter()
+        .all(|option| cell_data.selected_option_ids.contains(&option.id));
+    match self.condition {
+      ChecklistFilterCondition::IsComplete => is_complete,
+      ChecklistFilterCondition::IsIncomplete => !is_complete,
+    }
+  }
+}
+
+impl MediaFilter {
+  pub fn is_visible(&self, cell_data: &MediaCellData) -> bool {
+    match self.condition {
+      MediaFilterCondition::IsEmpty => cell_data.files.is_empty(),
+      MediaFilterCondition::IsNotEmpty => !cell_data.files.is_empty(),
+    }
+  }
+}
+
+/// Return None if the condition needs ids and the filter has none.
+fn is_list_visible(
+  condition: &ListFilterCondition,
+  expected: &[String],
+  values: &[String],
+) -> Option<bool> {
+  let is_overlapping = || values.iter().any(|value| expected.contains(value));
+  match condition {
+    ListFilterCondition::IsEmpty => Some(values.is_empty()),
+    ListFilterCondition::IsNotEmpty => Some(!values.is_empty()),
+    _ if expected.is_empty() => None,
+    ListFilterCondition::Contains => Some(is_overlapping()),
+    ListFilterCondition::DoesNotContain => Some(!is_overlapping()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::filter::DateFilterContent;
+
+  fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  fn timestamp(date: NaiveDate) -> i64 {
+    date.and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp()
+  }
+
+  #[test]
+  fn text_filter_is_case_insensitive_test() {
+    let filter = TextFilter {
+      condition: TextFilterCondition::Contains,
+      content: "HeLLo".to_string(),
+    };
+    assert!(filter.is_visible("say hello world"));
+    assert!(!filter.is_visible(""));
+
+    let filter = TextFilter {
+      condition: TextFilterCondition::IsEmpty,
+      content: "".to_string(),
+    };
+    assert!(filter.is_visible(""));
+  }
+
+  #[test]
+  fn number_filter_ignores_invalid_content_test() {
+    let filter = NumberFilter {
+      condition: NumberFilterCondition::GreaterThan,
+      content: "abc".to_string(),
+    };
+    assert_eq!(filter.is_visible(Some(1.0)), None);
+
+    let filter = NumberFilter {
+      condition: NumberFilterCondition::NotEqual,
+      content: "10".to_string(),
+    };
+    assert_eq!(filter.is_visible(Some(1.0)), Some(true));
+    assert_eq!(filter.is_visible(None), Some(false));
+  }
+
+  #[test]
+  fn date_filter_absolute_test() {
+    let today = date(2024, 5, 15);
+    let filter = DateFilter {
+      condition: DateFilterCondition::StartsBetween,
+      content: DateFilterContent {
+        start: Some(timestamp(date(2024, 5, 1))),
+        end: Some(timestamp(date(2024, 5, 10))),
+        ..Default::default()
+      },
+    };
+    let cell = DateCellData::from_timestamp(timestamp(date(2024, 5, 10)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(true));
+    let cell = DateCellData::from_timestamp(timestamp(date(2024, 5, 11)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(false));
+    assert_eq!(
+      filter.is_visible(&DateCellData::default(), today, &Tz::UTC),
+      Some(false)
+    );
+
+    // Between needs both ends
+    let filter = DateFilter {
+      condition: DateFilterCondition::StartsBetween,
+      content: DateFilterContent {
+        start: Some(timestamp(date(2024, 5, 1))),
+        ..Default::default()
+      },
+    };
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), None);
+  }
+
+  #[test]
+  fn date_filter_end_condition_test() {
+    let today = date(2024, 5, 15);
+    let filter = DateFilter {
+      condition: DateFilterCondition::EndsAfter,
+      content: DateFilterContent {
+        timestamp: Some(timestamp(date(2024, 5, 3))),
+        ..Default::default()
+      },
+    };
+    let mut cell = DateCellData::from_timestamp(timestamp(date(2024, 5, 1)));
+    cell.end_timestamp = Some(timestamp(date(2024, 5, 5)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(false));
+    cell.is_range = true;
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(true));
+  }
+
+  #[test]
+  fn date_filter_relative_test() {
+    let today = date(2024, 3, 1);
+    let filter = DateFilter {
+      condition: DateFilterCondition::StartsOn,
+      content: DateFilterContent {
+        relative: Some(RelativeDate::Yesterday),
+        ..Default::default()
+      },
+    };
+    let cell = DateCellData::from_timestamp(timestamp(date(2024, 2, 29)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(true));
+
+    let filter = DateFilter {
+      condition: DateFilterCondition::StartsOnOrAfter,
+      content: DateFilterContent {
+        relative: Some(RelativeDate::PastWeek),
+        ..Default::default()
+      },
+    };
+    let cell = DateCellData::from_timestamp(timestamp(date(2024, 2, 23)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(true));
+    let cell = DateCellData::from_timestamp(timestamp(date(2024, 2, 22)));
+    assert_eq!(filter.is_visible(&cell, today, &Tz::UTC), Some(false));
+  }
+
+  #[test]
+  fn date_filter_uses_field_timezone_test() {
+    use crate::database::fields::date_type_option::DateTypeOption;
+    use uuid::Uuid;
+
+    let type_option = DateTypeOption {
+      timezone_id: "Asia/Tokyo".to_string(),
+      ..DateTypeOption::new()
+    };
+    let field = Field::new(
+      "date".to_string(),
+      "Date".to_string(),
+      FieldType::DateTime.into(),
+      false,
+    )
+    .with_type_option_data(FieldType::DateTime.type_id(), type_option.into());
+    let filter = Filter::new_data(
+      "filter_1".to_string(),
+      "date".to_string(),
+      FieldType::DateTime,
+      FieldFilter::Date(DateFilter {
+        condition: DateFilterCondition::StartsOn,
+        content: DateFilterContent {
+          relative: Some(RelativeDate::Today),
+          ..Default::default()
+        },
+      }),
+    );
+    // 2024-05-15 16:00 UTC is already 2024-05-16 01:00 in Tokyo
+    let now = date(2024, 5, 15).and_hms_opt(16, 0, 0).unwrap().and_utc();
+    let evaluator = FilterEvaluator::new_with_now(vec![filter], vec![field], now);
+
+    let row_with_date = |hour: u32, minute: u32| {
+      let timestamp = date(2024, 5, 15)
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp();
+      let mut row = Row::new(Uuid::new_v4(), Uuid::new_v4());
+      row.cells.insert(
+        "date".to_string(),
+        Cell::from(&DateCellData::from_timestamp(timestamp)),
+      );
+      row
+    };
+    // 2024-05-16 00:30 in Tokyo
+    assert!(evaluator.is_row_visible(&row_with_date(15, 30)));
+    // 2024-05-15 23:00 in Tokyo, the same UTC day as now but yesterday in Tokyo
+    assert!(!evaluator.is_row_visible(&row_with_date(14, 0)));
+  }
+
+  #[test]
+  fn select_option_filter_test() {
+    let selected = SelectOptionIds::from(vec!["a".to_string(), "b".to_string()]);
+    let filter = SelectOptionFilter {
+      condition: SelectOptionFilterCondition::Is,
+      option_ids: vec!["b".to_string(), "a".to_string()],
+    };
+    assert_eq!(
+      filter.is_visible(&FieldType::MultiSelect, &selected),
+      Some(true)
+    );
+
+    let filter = SelectOptionFilter {
+      condition: SelectOptionFilterCondition::Is,
+      option_ids: vec!["a".to_string(), "c".to_string()],
+    };
+    assert_eq!(
+      filter.is_visible(&FieldType::MultiSelect, &selected),
+      Some(false)
+    );
+    let selected = SelectOptionIds::from(vec!["c".to_string()]);
+    assert_eq!(
+      filter.is_visible(&FieldType::SingleSelect, &selected),
+      Some(true)
+    );
+
+    let filter = SelectOptionFilter {
+      condition: SelectOptionFilterCondition::DoesNotContain,
+      option_ids: vec![],
+    };
+    assert_eq!(filter.is_visible(&FieldType::SingleSelect, &selected), None);
+  }
+
+  #[test]
+  fn list_filter_test() {
+    let values = vec!["1".to_string(), "2".to_string()];
+    assert_eq!(
+      is_list_visible(&ListFilterCondition::Contains, &["2".to_string()], &values),
+      Some(true)
+    );
+    assert_eq!(
+      is_list_visible(
+        &ListFilterCondition::DoesNotContain,
+        &["2".to_string()],
+        &values
+      ),
+      Some(false)
+    );
+    assert_eq!(
+      is_list_visible(&ListFilterCondition::Contains, &[], &values),
+      None
+    );
+    assert_eq!(
+      is_list_visible(&ListFilterCondition::IsEmpty, &[], &[]),
+      Some(true)
+    );
+  }
+}
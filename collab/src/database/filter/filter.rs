@@ -0,0 +1,704 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::entity::FieldType;
+use crate::database::fields::select_type_option::SELECTION_IDS_SEPARATOR;
+use crate::database::util::get_i64_lenient;
+use crate::database::views::{FilterMap, FilterMapBuilder};
+use crate::error::CollabError;
+use crate::preclude::Any;
+use crate::util::AnyMapExt;
+
+pub const FILTER_ID: &str = "id";
+pub const FILTER_TYPE: &str = "filter_type";
+pub const FILTER_FIELD_ID: &str = "field_id";
+pub const FILTER_FIELD_TYPE: &str = "ty";
+pub const FILTER_CONDITION: &str = "condition";
+pub const FILTER_CONTENT: &str = "content";
+pub const FILTER_CHILDREN: &str = "children";
+
+/// The kind of node stored in a [FilterMap]. Filters written before nested groups existed don't
+/// carry a [FILTER_TYPE], they are treated as [FilterType::Data].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterType {
+  And = 0,
+  Or = 1,
+  #[default]
+  Data = 2,
+}
+
+impl From<i64> for FilterType {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => FilterType::And,
+      1 => FilterType::Or,
+      2 => FilterType::Data,
+      _ => {
+        tracing::error!("Unsupported filter type, fallback to Data");
+        FilterType::Data
+      },
+    }
+  }
+}
+
+impl FilterType {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+/// A typed view filter.
+///
+/// A view's filters form a tree: [FilterInner::And] and [FilterInner::Or] group their children,
+/// and [FilterInner::Data] holds the condition applied to a single field. The top level filters
+/// of a view are combined with AND.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+  pub id: String,
+  pub inner: FilterInner,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterInner {
+  And {
+    children: Vec<Filter>,
+  },
+  Or {
+    children: Vec<Filter>,
+  },
+  Data {
+    field_id: String,
+    field_type: FieldType,
+    filter: FieldFilter,
+  },
+}
+
+impl Filter {
+  pub fn new_data(
+    id: String,
+    field_id: String,
+    field_type: FieldType,
+    filter: FieldFilter,
+  ) -> Self {
+    Self {
+      id,
+      inner: FilterInner::Data {
+        field_id,
+        field_type,
+        filter,
+      },
+    }
+  }
+
+  pub fn new_and(id: String, children: Vec<Filter>) -> Self {
+    Self {
+      id,
+      inner: FilterInner::And { children },
+    }
+  }
+
+  pub fn new_or(id: String, children: Vec<Filter>) -> Self {
+    Self {
+      id,
+      inner: FilterInner::Or { children },
+    }
+  }
+
+  pub fn filter_type(&self) -> FilterType {
+    match &self.inner {
+      FilterInner::And { .. } => FilterType::And,
+      FilterInner::Or { .. } => FilterType::Or,
+      FilterInner::Data { .. } => FilterType::Data,
+    }
+  }
+}
+
+impl TryFrom<FilterMap> for Filter {
+  type Error = CollabError;
+
+  fn try_from(map: FilterMap) -> Result<Self, Self::Error> {
+    let id: String = map
+      .get_as(FILTER_ID)
+      .ok_or_else(|| CollabError::NoRequiredData("filter id".to_string()))?;
+    let filter_type = get_i64_lenient(&map, FILTER_TYPE)
+      .map(FilterType::from)
+      .unwrap_or_default();
+
+    let inner = match filter_type {
+      FilterType::And | FilterType::Or => {
+        let children = match map.get(FILTER_CHILDREN) {
+          Some(Any::Array(children)) => children
+            .iter()
+            .flat_map(|child| match child {
+              Any::Map(child) => Filter::try_from(child.as_ref().clone()).ok(),
+              _ => None,
+            })
+            .collect(),
+          _ => vec![],
+        };
+        if filter_type == FilterType::And {
+          FilterInner::And { children }
+        } else {
+          FilterInner::Or { children }
+        }
+      },
+      FilterType::Data => {
+        let field_id: String = map
+          .get_as(FILTER_FIELD_ID)
+          .ok_or_else(|| CollabError::NoRequiredData("filter field id".to_string()))?;
+        let field_type = get_i64_lenient(&map, FILTER_FIELD_TYPE)
+          .map(FieldType::from)
+          .ok_or_else(|| CollabError::NoRequiredData("filter field type".to_string()))?;
+        let condition = get_i64_lenient(&map, FILTER_CONDITION).unwrap_or_default();
+        let content: String = map.get_as(FILTER_CONTENT).unwrap_or_default();
+        FilterInner::Data {
+          field_id,
+          field_type,
+          filter: FieldFilter::new(&field_type, condition, &content),
+        }
+      },
+    };
+
+    Ok(Self { id, inner })
+  }
+}
+
+impl From<Filter> for FilterMap {
+  fn from(filter: Filter) -> Self {
+    let filter_type = filter.filter_type();
+    let mut map = FilterMapBuilder::from([
+      (FILTER_ID.into(), filter.id.into()),
+      (FILTER_TYPE.into(), Any::BigInt(filter_type.value())),
+    ]);
+    match filter.inner {
+      FilterInner::And { children } | FilterInner::Or { children } => {
+        let children = children
+          .into_iter()
+          .map(|child| Any::from(FilterMap::from(child)))
+          .collect::<Vec<_>>();
+        map.insert(FILTER_CHILDREN.into(), Any::Array(Arc::from(children)));
+      },
+      FilterInner::Data {
+        field_id,
+        field_type,
+        filter,
+      } => {
+        map.insert(FILTER_FIELD_ID.into(), field_id.into());
+        map.insert(FILTER_FIELD_TYPE.into(), Any::BigInt(field_type.into()));
+        map.insert(FILTER_CONDITION.into(), Any::BigInt(filter.condition()));
+        map.insert(FILTER_CONTENT.into(), filter.content().into());
+      },
+    }
+    map
+  }
+}
+
+impl From<&Filter> for FilterMap {
+  fn from(filter: &Filter) -> Self {
+    FilterMap::from(filter.clone())
+  }
+}
+
+/// The condition of a [FilterInner::Data] filter, typed by the field type it applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldFilter {
+  /// RichText, URL, Summary, Translate, Rollup and Formula fields. Formulas are filtered by
+  /// their readable value.
+  Text(TextFilter),
+  /// Number, Time and AutoNumber fields.
+  Number(NumberFilter),
+  /// DateTime, CreatedTime and LastEditedTime fields.
+  Date(DateFilter),
+  /// SingleSelect and MultiSelect fields.
+  SelectOption(SelectOptionFilter),
+  Checkbox(CheckboxFilter),
+  Checklist(ChecklistFilter),
+  Media(MediaFilter),
+  Person(PersonFilter),
+  Relation(RelationFilter),
+}
+
+impl FieldFilter {
+  /// Build the typed filter from the raw `condition` and `content` stored in a [FilterMap].
+  pub fn new(field_type: &FieldType, condition: i64, content: &str) -> Self {
+    match field_type {
+      FieldType::RichText
+      | FieldType::URL
+      | FieldType::Summary
+      | FieldType::Translate
+      | FieldType::Rollup
+      | FieldType::Formula => FieldFilter::Text(TextFilter {
+        condition: TextFilterCondition::from(condition),
+        content: content.to_string(),
+      }),
+      FieldType::Number | FieldType::Time | FieldType::AutoNumber => {
+        FieldFilter::Number(NumberFilter {
+          condition: NumberFilterCondition::from(condition),
+          content: content.to_string(),
+        })
+      },
+      FieldType::DateTime | FieldType::LastEditedTime | FieldType::CreatedTime => {
+        FieldFilter::Date(DateFilter {
+          condition: DateFilterCondition::from(condition),
+          content: DateFilterContent::from_str(content).unwrap_or_default(),
+        })
+      },
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        FieldFilter::SelectOption(SelectOptionFilter {
+          condition: SelectOptionFilterCondition::from(condition),
+          option_ids: split_ids(content),
+        })
+      },
+      FieldType::Checkbox => FieldFilter::Checkbox(CheckboxFilter {
+        condition: CheckboxFilterCondition::from(condition),
+      }),
+      FieldType::Checklist => FieldFilter::Checklist(ChecklistFilter {
+        condition: ChecklistFilterCondition::from(condition),
+      }),
+      FieldType::Media => FieldFilter::Media(MediaFilter {
+        condition: MediaFilterCondition::from(condition),
+      }),
+      FieldType::Person => FieldFilter::Person(PersonFilter {
+        condition: ListFilterCondition::from(condition),
+        person_ids: split_ids(content),
+      }),
+      FieldType::Relation => FieldFilter::Relation(RelationFilter {
+        condition: ListFilterCondition::from(condition),
+        row_ids: split_ids(content),
+      }),
+    }
+  }
+
+  /// Return true if the filter is the kind [FieldFilter::new] builds for the field type.
+  pub fn is_for_field_type(&self, field_type: &FieldType) -> bool {
+    std::mem::discriminant(self)
+      == std::mem::discriminant(&FieldFilter::new(field_type, self.condition(), ""))
+  }
+
+  /// The raw condition value stored under [FILTER_CONDITION].
+  pub fn condition(&self) -> i64 {
+    match self {
+      FieldFilter::Text(filter) => filter.condition.value(),
+      FieldFilter::Number(filter) => filter.condition.value(),
+      FieldFilter::Date(filter) => filter.condition.value(),
+      FieldFilter::SelectOption(filter) => filter.condition.value(),
+      FieldFilter::Checkbox(filter) => filter.condition.value(),
+      FieldFilter::Checklist(filter) => filter.condition.value(),
+      FieldFilter::Media(filter) => filter.condition.value(),
+      FieldFilter::Person(filter) => filter.condition.value(),
+      FieldFilter::Relation(filter) => filter.condition.value(),
+    }
+  }
+
+  /// The raw content stored under [FILTER_CONTENT].
+  pub fn content(&self) -> String {
+    match self {
+      FieldFilter::Text(filter) => filter.content.clone(),
+      FieldFilter::Number(filter) => filter.content.clone(),
+      FieldFilter::Date(filter) => filter.content.to_string(),
+      FieldFilter::SelectOption(filter) => filter.option_ids.join(SELECTION_IDS_SEPARATOR),
+      FieldFilter::Person(filter) => filter.person_ids.join(SELECTION_IDS_SEPARATOR),
+      FieldFilter::Relation(filter) => filter.row_ids.join(SELECTION_IDS_SEPARATOR),
+      FieldFilter::Checkbox(_) | FieldFilter::Checklist(_) | FieldFilter::Media(_) => {
+        "".to_string()
+      },
+    }
+  }
+}
+
+pub(crate) fn split_ids(content: &str) -> Vec<String> {
+  content
+    .split(SELECTION_IDS_SEPARATOR)
+    .map(|id| id.trim())
+    .filter(|id| !id.is_empty())
+    .map(|id| id.to_string())
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextFilter {
+  pub condition: TextFilterCondition,
+  pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextFilterCondition {
+  #[default]
+  Is = 0,
+  IsNot = 1,
+  Contains = 2,
+  DoesNotContain = 3,
+  StartsWith = 4,
+  EndsWith = 5,
+  IsEmpty = 6,
+  IsNotEmpty = 7,
+}
+
+impl From<i64> for TextFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => TextFilterCondition::Is,
+      1 => TextFilterCondition::IsNot,
+      2 => TextFilterCondition::Contains,
+      3 => TextFilterCondition::DoesNotContain,
+      4 => TextFilterCondition::StartsWith,
+      5 => TextFilterCondition::EndsWith,
+      6 => TextFilterCondition::IsEmpty,
+      7 => TextFilterCondition::IsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported text filter condition, fallback to Is");
+        TextFilterCondition::Is
+      },
+    }
+  }
+}
+
+impl TextFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFilter {
+  pub condition: NumberFilterCondition,
+  pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFilterCondition {
+  #[default]
+  Equal = 0,
+  NotEqual = 1,
+  GreaterThan = 2,
+  LessThan = 3,
+  GreaterThanOrEqualTo = 4,
+  LessThanOrEqualTo = 5,
+  IsEmpty = 6,
+  IsNotEmpty = 7,
+}
+
+impl From<i64> for NumberFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => NumberFilterCondition::Equal,
+      1 => NumberFilterCondition::NotEqual,
+      2 => NumberFilterCondition::GreaterThan,
+      3 => NumberFilterCondition::LessThan,
+      4 => NumberFilterCondition::GreaterThanOrEqualTo,
+      5 => NumberFilterCondition::LessThanOrEqualTo,
+      6 => NumberFilterCondition::IsEmpty,
+      7 => NumberFilterCondition::IsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported number filter condition, fallback to Equal");
+        NumberFilterCondition::Equal
+      },
+    }
+  }
+}
+
+impl NumberFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateFilter {
+  pub condition: DateFilterCondition,
+  pub content: DateFilterContent,
+}
+
+/// Conditions 0..=7 apply to the start of the date, 8..=15 to its end. The end of a date that
+/// isn't a range is its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFilterCondition {
+  #[default]
+  StartsOn = 0,
+  StartsBefore = 1,
+  StartsAfter = 2,
+  StartsOnOrBefore = 3,
+  StartsOnOrAfter = 4,
+  StartsBetween = 5,
+  StartIsEmpty = 6,
+  StartIsNotEmpty = 7,
+  EndsOn = 8,
+  EndsBefore = 9,
+  EndsAfter = 10,
+  EndsOnOrBefore = 11,
+  EndsOnOrAfter = 12,
+  EndsBetween = 13,
+  EndIsEmpty = 14,
+  EndIsNotEmpty = 15,
+}
+
+impl From<i64> for DateFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => DateFilterCondition::StartsOn,
+      1 => DateFilterCondition::StartsBefore,
+      2 => DateFilterCondition::StartsAfter,
+      3 => DateFilterCondition::StartsOnOrBefore,
+      4 => DateFilterCondition::StartsOnOrAfter,
+      5 => DateFilterCondition::StartsBetween,
+      6 => DateFilterCondition::StartIsEmpty,
+      7 => DateFilterCondition::StartIsNotEmpty,
+      8 => DateFilterCondition::EndsOn,
+      9 => DateFilterCondition::EndsBefore,
+      10 => DateFilterCondition::EndsAfter,
+      11 => DateFilterCondition::EndsOnOrBefore,
+      12 => DateFilterCondition::EndsOnOrAfter,
+      13 => DateFilterCondition::EndsBetween,
+      14 => DateFilterCondition::EndIsEmpty,
+      15 => DateFilterCondition::EndIsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported date filter condition, fallback to StartsOn");
+        DateFilterCondition::StartsOn
+      },
+    }
+  }
+}
+
+impl DateFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+
+  pub fn is_filter_on_start(&self) -> bool {
+    self.value() < DateFilterCondition::EndsOn.value()
+  }
+}
+
+/// The content of a [DateFilter], stored as a JSON string.
+///
+/// `timestamp` is used by the single date conditions and `start`/`end` by the between
+/// conditions. When `relative` is set, it takes precedence over the absolute values and is
+/// resolved against the current date when the filter is evaluated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateFilterContent {
+  #[serde(default)]
+  pub start: Option<i64>,
+  #[serde(default)]
+  pub end: Option<i64>,
+  #[serde(default)]
+  pub timestamp: Option<i64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub relative: Option<RelativeDate>,
+}
+
+impl FromStr for DateFilterContent {
+  type Err = CollabError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if s.is_empty() {
+      return Ok(Self::default());
+    }
+    Ok(serde_json::from_str(s)?)
+  }
+}
+
+impl std::fmt::Display for DateFilterContent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", serde_json::to_string(self).unwrap_or_default())
+  }
+}
+
+/// A date range relative to the day the filter is evaluated. The ranges include both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelativeDate {
+  Today,
+  Yesterday,
+  Tomorrow,
+  /// The last seven days, including today.
+  PastWeek,
+  /// The last month, including today.
+  PastMonth,
+  /// The last year, including today.
+  PastYear,
+  /// The next seven days, including today.
+  NextWeek,
+  /// The next month, including today.
+  NextMonth,
+  /// The next year, including today.
+  NextYear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectOptionFilter {
+  pub condition: SelectOptionFilterCondition,
+  pub option_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectOptionFilterCondition {
+  #[default]
+  Is = 0,
+  IsNot = 1,
+  Contains = 2,
+  DoesNotContain = 3,
+  IsEmpty = 4,
+  IsNotEmpty = 5,
+}
+
+impl From<i64> for SelectOptionFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => SelectOptionFilterCondition::Is,
+      1 => SelectOptionFilterCondition::IsNot,
+      2 => SelectOptionFilterCondition::Contains,
+      3 => SelectOptionFilterCondition::DoesNotContain,
+      4 => SelectOptionFilterCondition::IsEmpty,
+      5 => SelectOptionFilterCondition::IsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported select option filter condition, fallback to Is");
+        SelectOptionFilterCondition::Is
+      },
+    }
+  }
+}
+
+impl SelectOptionFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckboxFilter {
+  pub condition: CheckboxFilterCondition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckboxFilterCondition {
+  #[default]
+  IsChecked = 0,
+  IsUnchecked = 1,
+}
+
+impl From<i64> for CheckboxFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => CheckboxFilterCondition::IsChecked,
+      1 => CheckboxFilterCondition::IsUnchecked,
+      _ => {
+        tracing::error!("Unsupported checkbox filter condition, fallback to IsChecked");
+        CheckboxFilterCondition::IsChecked
+      },
+    }
+  }
+}
+
+impl CheckboxFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecklistFilter {
+  pub condition: ChecklistFilterCondition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecklistFilterCondition {
+  #[default]
+  IsComplete = 0,
+  IsIncomplete = 1,
+}
+
+impl From<i64> for ChecklistFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => ChecklistFilterCondition::IsComplete,
+      1 => ChecklistFilterCondition::IsIncomplete,
+      _ => {
+        tracing::error!("Unsupported checklist filter condition, fallback to IsComplete");
+        ChecklistFilterCondition::IsComplete
+      },
+    }
+  }
+}
+
+impl ChecklistFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFilter {
+  pub condition: MediaFilterCondition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MediaFilterCondition {
+  #[default]
+  IsEmpty = 0,
+  IsNotEmpty = 1,
+}
+
+impl From<i64> for MediaFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => MediaFilterCondition::IsEmpty,
+      1 => MediaFilterCondition::IsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported media filter condition, fallback to IsEmpty");
+        MediaFilterCondition::IsEmpty
+      },
+    }
+  }
+}
+
+impl MediaFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonFilter {
+  pub condition: ListFilterCondition,
+  pub person_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelationFilter {
+  pub condition: ListFilterCondition,
+  pub row_ids: Vec<String>,
+}
+
+/// Condition of the filters whose cell holds a list of ids, i.e. [PersonFilter] and
+/// [RelationFilter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListFilterCondition {
+  #[default]
+  Contains = 0,
+  DoesNotContain = 1,
+  IsEmpty = 2,
+  IsNotEmpty = 3,
+}
+
+impl From<i64> for ListFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => ListFilterCondition::Contains,
+      1 => ListFilterCondition::DoesNotContain,
+      2 => ListFilterCondition::IsEmpty,
+      3 => ListFilterCondition::IsNotEmpty,
+      _ => {
+        tracing::error!("Unsupported list filter condition, fallback to Contains");
+        ListFilterCondition::Contains
+      },
+    }
+  }
+}
+
+impl ListFilterCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
@@ -0,0 +1,5 @@
+mod evaluator;
+mod filter;
+
+pub use evaluator::*;
+pub use filter::*;
@@ -0,0 +1,362 @@
+use crate::database::entity::FieldType;
+use crate::database::fields::Field;
+use crate::database::fields::formula_type_option::{FormulaResultType, FormulaTypeOption};
+use crate::database::formula::{BinaryOp, DateUnit, Expr, FormulaValue, UnaryOp};
+use crate::error::CollabError;
+
+/// The type of a sub-expression. Relation fields are only valid as the first argument of an
+/// aggregate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+  Text,
+  Number,
+  Date,
+  Checkbox,
+  Relation,
+}
+
+impl From<FormulaResultType> for ValueType {
+  fn from(result_type: FormulaResultType) -> Self {
+    match result_type {
+      FormulaResultType::Text => ValueType::Text,
+      FormulaResultType::Number => ValueType::Number,
+      FormulaResultType::Date => ValueType::Date,
+      FormulaResultType::Checkbox => ValueType::Checkbox,
+    }
+  }
+}
+
+/// The type a field reference evaluates to. Formula fields have the result type of their type
+/// option.
+fn field_value_type(field: &Field) -> ValueType {
+  match FieldType::from(field.field_type) {
+    FieldType::Number | FieldType::Time => ValueType::Number,
+    FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime => ValueType::Date,
+    FieldType::Checkbox => ValueType::Checkbox,
+    FieldType::Relation => ValueType::Relation,
+    FieldType::Formula => FormulaTypeOption::from(
+      field
+        .get_any_type_option(FieldType::Formula.type_id())
+        .unwrap_or_default(),
+    )
+    .result_type
+    .into(),
+    _ => ValueType::Text,
+  }
+}
+
+/// Find the field referenced by id or, failing that, by name.
+pub(crate) fn find_field<'a>(fields: &'a [Field], reference: &str) -> Option<&'a Field> {
+  fields
+    .iter()
+    .find(|field| field.id == reference)
+    .or_else(|| fields.iter().find(|field| field.name == reference))
+}
+
+/// Check the types of a parsed expression and return the type of its result. The field
+/// references are resolved to field ids.
+pub(crate) fn check_expr(
+  expr: &mut Expr,
+  fields: &[Field],
+) -> Result<FormulaResultType, CollabError> {
+  match check(expr, fields)? {
+    ValueType::Text => Ok(FormulaResultType::Text),
+    ValueType::Number => Ok(FormulaResultType::Number),
+    ValueType::Date => Ok(FormulaResultType::Date),
+    ValueType::Checkbox => Ok(FormulaResultType::Checkbox),
+    ValueType::Relation => Err(type_error(
+      "a relation field can only be used in count, sum, average, min or max",
+    )),
+  }
+}
+
+fn type_error(message: impl AsRef<str>) -> CollabError {
+  CollabError::DatabaseInvalidFormula(message.as_ref().to_string())
+}
+
+fn check(expr: &mut Expr, fields: &[Field]) -> Result<ValueType, CollabError> {
+  match expr {
+    Expr::Literal(value) => Ok(match value {
+      FormulaValue::Empty | FormulaValue::Text(_) => ValueType::Text,
+      FormulaValue::Number(_) => ValueType::Number,
+      FormulaValue::Date(_) => ValueType::Date,
+      FormulaValue::Checkbox(_) => ValueType::Checkbox,
+    }),
+    Expr::Field(reference) => {
+      let field = find_field(fields, reference)
+        .ok_or_else(|| type_error(format!("unknown field {{{}}}", reference)))?;
+      let value_type = field_value_type(field);
+      *reference = field.id.clone();
+      Ok(value_type)
+    },
+    Expr::Unary { op, expr } => {
+      let value_type = check(expr, fields)?;
+      let expected = match op {
+        UnaryOp::Negate => ValueType::Number,
+        UnaryOp::Not => ValueType::Checkbox,
+      };
+      expect_type(value_type, expected, "operand")?;
+      Ok(expected)
+    },
+    Expr::Binary { op, left, right } => {
+      let left = check(left, fields)?;
+      let right = check(right, fields)?;
+      check_binary(*op, left, right)
+    },
+    Expr::Call { function, args } => {
+      let types = args
+        .iter_mut()
+        .map(|arg| check(arg, fields))
+        .collect::<Result<Vec<_>, _>>()?;
+      check_call(function, args, &types)
+    },
+  }
+}
+
+fn expect_type(actual: ValueType, expected: ValueType, what: &str) -> Result<(), CollabError> {
+  if actual == expected {
+    Ok(())
+  } else {
+    Err(type_error(format!(
+      "expected {:?} {} but found {:?}",
+      expected, what, actual
+    )))
+  }
+}
+
+fn check_binary(op: BinaryOp, left: ValueType, right: ValueType) -> Result<ValueType, CollabError> {
+  if left == ValueType::Relation || right == ValueType::Relation {
+    return Err(type_error(
+      "a relation field can only be used in count, sum, average, min or max",
+    ));
+  }
+  match op {
+    // Adding a text to any value concatenates them
+    BinaryOp::Add if left == ValueType::Text || right == ValueType::Text => Ok(ValueType::Text),
+    BinaryOp::Add
+    | BinaryOp::Subtract
+    | BinaryOp::Multiply
+    | BinaryOp::Divide
+    | BinaryOp::Modulo => {
+      expect_type(left, ValueType::Number, "operand")?;
+      expect_type(right, ValueType::Number, "operand")?;
+      Ok(ValueType::Number)
+    },
+    BinaryOp::Equal | BinaryOp::NotEqual => {
+      expect_type(right, left, "operand")?;
+      Ok(ValueType::Checkbox)
+    },
+    BinaryOp::Less | BinaryOp::LessOrEqual | BinaryOp::Greater | BinaryOp::GreaterOrEqual => {
+      if left == ValueType::Checkbox {
+        return Err(type_error("checkboxes can't be ordered"));
+      }
+      expect_type(right, left, "operand")?;
+      Ok(ValueType::Checkbox)
+    },
+    BinaryOp::And | BinaryOp::Or => {
+      expect_type(left, ValueType::Checkbox, "operand")?;
+      expect_type(right, ValueType::Checkbox, "operand")?;
+      Ok(ValueType::Checkbox)
+    },
+  }
+}
+
+fn check_call(
+  function: &str,
+  args: &[Expr],
+  types: &[ValueType],
+) -> Result<ValueType, CollabError> {
+  let is_aggregate = match function {
+    "count" | "sum" | "average" => true,
+    "min" | "max" => types.first() == Some(&ValueType::Relation),
+    _ => false,
+  };
+  if is_aggregate {
+    return check_aggregate(function, args, types);
+  }
+  if types.contains(&ValueType::Relation) {
+    return Err(type_error(
+      "a relation field can only be used in count, sum, average, min or max",
+    ));
+  }
+
+  match function {
+    "if" => {
+      if types.len() != 3 || types[0] != ValueType::Checkbox || types[1] != types[2] {
+        return Err(type_error(
+          "if expects a checkbox condition and two values of the same type",
+        ));
+      }
+      Ok(types[1])
+    },
+    "empty" => {
+      expect_arity(function, types, 1)?;
+      Ok(ValueType::Checkbox)
+    },
+    "abs" | "floor" | "ceil" | "sqrt" => {
+      expect_args(function, types, &[ValueType::Number], 0)?;
+      Ok(ValueType::Number)
+    },
+    "round" => {
+      expect_args(function, types, &[ValueType::Number, ValueType::Number], 1)?;
+      Ok(ValueType::Number)
+    },
+    "pow" => {
+      expect_args(function, types, &[ValueType::Number, ValueType::Number], 0)?;
+      Ok(ValueType::Number)
+    },
+    "min" | "max" => {
+      if types.is_empty() {
+        return Err(type_error(format!(
+          "{} expects at least one argument",
+          function
+        )));
+      }
+      for (index, value_type) in types.iter().enumerate() {
+        expect_type(*value_type, ValueType::Number, &argument(function, index))?;
+      }
+      Ok(ValueType::Number)
+    },
+    "concat" => Ok(ValueType::Text),
+    "length" => {
+      expect_args(function, types, &[ValueType::Text], 0)?;
+      Ok(ValueType::Number)
+    },
+    "lower" | "upper" | "trim" => {
+      expect_args(function, types, &[ValueType::Text], 0)?;
+      Ok(ValueType::Text)
+    },
+    "contains" => {
+      expect_args(function, types, &[ValueType::Text, ValueType::Text], 0)?;
+      Ok(ValueType::Checkbox)
+    },
+    "replace" => {
+      expect_args(
+        function,
+        types,
+        &[ValueType::Text, ValueType::Text, ValueType::Text],
+        0,
+      )?;
+      Ok(ValueType::Text)
+    },
+    "substring" => {
+      expect_args(
+        function,
+        types,
+        &[ValueType::Text, ValueType::Number, ValueType::Number],
+        1,
+      )?;
+      Ok(ValueType::Text)
+    },
+    "to_text" => {
+      expect_arity(function, types, 1)?;
+      Ok(ValueType::Text)
+    },
+    "to_number" => {
+      expect_arity(function, types, 1)?;
+      Ok(ValueType::Number)
+    },
+    "now" => {
+      expect_arity(function, types, 0)?;
+      Ok(ValueType::Date)
+    },
+    "date_add" => {
+      expect_args(
+        function,
+        types,
+        &[ValueType::Date, ValueType::Number, ValueType::Text],
+        0,
+      )?;
+      check_date_unit(&args[2])?;
+      Ok(ValueType::Date)
+    },
+    "date_between" => {
+      expect_args(
+        function,
+        types,
+        &[ValueType::Date, ValueType::Date, ValueType::Text],
+        0,
+      )?;
+      check_date_unit(&args[2])?;
+      Ok(ValueType::Number)
+    },
+    "year" | "month" | "day" => {
+      expect_args(function, types, &[ValueType::Date], 0)?;
+      Ok(ValueType::Number)
+    },
+    "format_date" => {
+      expect_args(function, types, &[ValueType::Date, ValueType::Text], 0)?;
+      Ok(ValueType::Text)
+    },
+    _ => Err(type_error(format!("unknown function {}", function))),
+  }
+}
+
+fn argument(function: &str, index: usize) -> String {
+  format!("argument {} of {}", index + 1, function)
+}
+
+fn expect_arity(function: &str, types: &[ValueType], count: usize) -> Result<(), CollabError> {
+  if types.len() == count {
+    Ok(())
+  } else {
+    Err(type_error(format!(
+      "{} expects {} arguments but got {}",
+      function,
+      count,
+      types.len()
+    )))
+  }
+}
+
+/// Check the arguments of a function. The last `optional` arguments may be omitted.
+fn expect_args(
+  function: &str,
+  types: &[ValueType],
+  expected: &[ValueType],
+  optional: usize,
+) -> Result<(), CollabError> {
+  if types.len() > expected.len() || types.len() + optional < expected.len() {
+    return Err(type_error(format!(
+      "{} expects {} arguments but got {}",
+      function,
+      expected.len(),
+      types.len()
+    )));
+  }
+  for (index, (actual, expected)) in types.iter().zip(expected).enumerate() {
+    expect_type(*actual, *expected, &argument(function, index))?;
+  }
+  Ok(())
+}
+
+/// A date unit written as a literal must be a known unit.
+fn check_date_unit(arg: &Expr) -> Result<(), CollabError> {
+  match arg {
+    Expr::Literal(FormulaValue::Text(unit)) if DateUnit::from_name(unit).is_none() => {
+      Err(type_error(format!("unknown date unit \"{}\"", unit)))
+    },
+    _ => Ok(()),
+  }
+}
+
+fn check_aggregate(
+  function: &str,
+  args: &[Expr],
+  types: &[ValueType],
+) -> Result<ValueType, CollabError> {
+  if function == "count" {
+    if types != [ValueType::Relation] {
+      return Err(type_error("count expects a relation field"));
+    }
+    return Ok(ValueType::Number);
+  }
+  let has_target = matches!(args.get(1), Some(Expr::Literal(FormulaValue::Text(_))));
+  if types.len() != 2 || types[0] != ValueType::Relation || !has_target {
+    return Err(type_error(format!(
+      "{} expects a relation field and the name of a field of the related database",
+      function
+    )));
+  }
+  Ok(ValueType::Number)
+}
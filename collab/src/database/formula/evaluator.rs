@@ -0,0 +1,611 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Months, Timelike};
+use tracing::warn;
+
+use crate::database::database::timestamp;
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::formula_type_option::{FormulaResultType, FormulaTypeOption};
+use crate::database::fields::{Field, TypeOptionCellReader, type_option_cell_reader};
+use crate::database::formula::{
+  BinaryOp, Expr, Formula, FormulaAggregate, FormulaValue, UnaryOp, aggregate_of,
+};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::util::AnyMapExt;
+
+/// The values of the target fields of the related rows, loaded for each relation aggregate of
+/// a row.
+pub type RelatedValues = HashMap<FormulaAggregate, Vec<FormulaValue>>;
+
+/// A unit of `date_add` and `date_between`. Plural and singular names are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DateUnit {
+  Years,
+  Months,
+  Weeks,
+  Days,
+  Hours,
+  Minutes,
+}
+
+impl DateUnit {
+  pub(crate) fn from_name(name: &str) -> Option<Self> {
+    let name = name.trim().to_lowercase();
+    match name.strip_suffix('s').unwrap_or(&name) {
+      "year" => Some(DateUnit::Years),
+      "month" => Some(DateUnit::Months),
+      "week" => Some(DateUnit::Weeks),
+      "day" => Some(DateUnit::Days),
+      "hour" => Some(DateUnit::Hours),
+      "minute" => Some(DateUnit::Minutes),
+      _ => None,
+    }
+  }
+
+  /// The length of the unit, None for the calendar units.
+  fn seconds(&self) -> Option<i64> {
+    match self {
+      DateUnit::Years | DateUnit::Months => None,
+      DateUnit::Weeks => Some(7 * 24 * 3600),
+      DateUnit::Days => Some(24 * 3600),
+      DateUnit::Hours => Some(3600),
+      DateUnit::Minutes => Some(60),
+    }
+  }
+}
+
+/// Evaluates the formula fields of a database.
+///
+/// Formulas may read other formula fields: they are evaluated in dependency order, so a
+/// formula sees the values computed for the same row. A formula that can't be compiled, whose
+/// result doesn't match the result type of its type option, or that is part of a reference
+/// cycle isn't evaluated, and neither are the formulas that depend on it. [Self::error]
+/// returns the reason.
+pub struct FormulaEvaluator {
+  /// The formulas that can be evaluated, in dependency order.
+  formulas: Vec<(String, Formula)>,
+  fields: HashMap<String, Field>,
+  errors: HashMap<String, String>,
+  now: i64,
+}
+
+impl FormulaEvaluator {
+  pub fn new(fields: Vec<Field>) -> Self {
+    Self::new_with_now(fields, timestamp())
+  }
+
+  /// Create an evaluator where `now()` returns the given timestamp.
+  pub fn new_with_now(fields: Vec<Field>, now: i64) -> Self {
+    let mut errors = HashMap::new();
+    let mut compiled = HashMap::new();
+    for field in fields.iter() {
+      if FieldType::from(field.field_type) != FieldType::Formula {
+        continue;
+      }
+      let type_option = formula_type_option(field);
+      match Formula::compile(&type_option.expression, &fields) {
+        Ok(formula) if formula.result_type() != type_option.result_type => {
+          errors.insert(
+            field.id.clone(),
+            format!(
+              "the formula returns {:?} but the field expects {:?}",
+              formula.result_type(),
+              type_option.result_type
+            ),
+          );
+        },
+        Ok(formula) => {
+          compiled.insert(field.id.clone(), formula);
+        },
+        Err(err) => {
+          errors.insert(field.id.clone(), err.to_string());
+        },
+      }
+    }
+
+    let mut order = vec![];
+    let mut states = HashMap::new();
+    let mut field_ids = compiled.keys().cloned().collect::<Vec<_>>();
+    field_ids.sort();
+    for field_id in field_ids.iter() {
+      visit(field_id, &compiled, &mut states, &mut order, &mut errors);
+    }
+    for (field_id, error) in errors.iter() {
+      warn!("Formula field {} can't be evaluated: {}", field_id, error);
+    }
+
+    let formulas = order
+      .into_iter()
+      .flat_map(|field_id| {
+        let formula = compiled.remove(&field_id)?;
+        Some((field_id, formula))
+      })
+      .collect();
+    Self {
+      formulas,
+      fields: fields
+        .into_iter()
+        .map(|field| (field.id.clone(), field))
+        .collect(),
+      errors,
+      now,
+    }
+  }
+
+  /// True if there is no formula to evaluate.
+  pub fn is_empty(&self) -> bool {
+    self.formulas.is_empty()
+  }
+
+  /// Return why the formula field can't be evaluated, None if it can.
+  pub fn error(&self, field_id: &str) -> Option<&str> {
+    self.errors.get(field_id).map(|error| error.as_str())
+  }
+
+  /// The relation aggregates of the formulas that need values from the related databases.
+  pub fn aggregates(&self) -> Vec<FormulaAggregate> {
+    let mut aggregates = vec![];
+    for (_, formula) in self.formulas.iter() {
+      for aggregate in formula.aggregates() {
+        if aggregate.target.is_some() && !aggregates.contains(&aggregate) {
+          aggregates.push(aggregate);
+        }
+      }
+    }
+    aggregates
+  }
+
+  /// Return the formula fields whose value depends, directly or through other formulas, on
+  /// one of the given fields, in evaluation order.
+  pub fn dependent_fields(&self, field_ids: &[String]) -> Vec<String> {
+    let mut changed = field_ids.iter().cloned().collect::<HashSet<_>>();
+    let mut dependents = vec![];
+    for (field_id, formula) in self.formulas.iter() {
+      if formula.field_ids().iter().any(|id| changed.contains(id)) {
+        changed.insert(field_id.clone());
+        dependents.push(field_id.clone());
+      }
+    }
+    dependents
+  }
+
+  /// Evaluate the formulas of the row, in evaluation order.
+  pub fn evaluate_row(&self, row: &Row, related: &RelatedValues) -> Vec<(String, FormulaValue)> {
+    let mut context = RowContext {
+      row,
+      related,
+      readers: HashMap::new(),
+      values: HashMap::new(),
+      now: self.now,
+    };
+    for (field_id, formula) in self.formulas.iter() {
+      for id in formula.field_ids() {
+        if context.readers.contains_key(&id) {
+          continue;
+        }
+        if let Some(field) = self.fields.get(&id) {
+          let field_type = FieldType::from(field.field_type);
+          let reader = type_option_cell_reader(
+            field
+              .get_any_type_option(field_type.type_id())
+              .unwrap_or_default(),
+            &field_type,
+          );
+          context.readers.insert(id, (field, reader));
+        }
+      }
+      let value = context.eval(formula.expr());
+      context.values.insert(field_id.clone(), value);
+    }
+
+    self
+      .formulas
+      .iter()
+      .flat_map(|(field_id, _)| {
+        let value = context.values.remove(field_id)?;
+        Some((field_id.clone(), value))
+      })
+      .collect()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+  Visiting,
+  Done,
+}
+
+/// Depth-first visit of the formula references. The formula is pushed to `order` after the
+/// formulas it reads, and gets an error when one of them is invalid or when it is part of a
+/// cycle. Return true if the formula can be evaluated.
+fn visit(
+  field_id: &str,
+  compiled: &HashMap<String, Formula>,
+  states: &mut HashMap<String, VisitState>,
+  order: &mut Vec<String>,
+  errors: &mut HashMap<String, String>,
+) -> bool {
+  match states.get(field_id) {
+    Some(VisitState::Done) => return !errors.contains_key(field_id),
+    Some(VisitState::Visiting) => {
+      errors.insert(
+        field_id.to_string(),
+        "the formula references itself".to_string(),
+      );
+      return false;
+    },
+    None => {},
+  }
+  let Some(formula) = compiled.get(field_id) else {
+    return !errors.contains_key(field_id);
+  };
+
+  states.insert(field_id.to_string(), VisitState::Visiting);
+  let mut dependencies = formula
+    .field_ids()
+    .into_iter()
+    .filter(|id| compiled.contains_key(id) || errors.contains_key(id))
+    .collect::<Vec<_>>();
+  dependencies.sort();
+  let mut is_valid = true;
+  for dependency in dependencies.iter() {
+    if !visit(dependency, compiled, states, order, errors) {
+      is_valid = false;
+      errors
+        .entry(field_id.to_string())
+        .or_insert_with(|| format!("the formula reads the invalid formula field {}", dependency));
+    }
+  }
+  states.insert(field_id.to_string(), VisitState::Done);
+  if is_valid {
+    order.push(field_id.to_string());
+  }
+  is_valid
+}
+
+pub(crate) fn formula_type_option(field: &Field) -> FormulaTypeOption {
+  FormulaTypeOption::from(
+    field
+      .get_any_type_option(FieldType::Formula.type_id())
+      .unwrap_or_default(),
+  )
+}
+
+/// Read the value of a field of the row. Empty text cells read as an empty text.
+pub(crate) fn field_value(
+  field: &Field,
+  reader: &dyn TypeOptionCellReader,
+  row: &Row,
+) -> FormulaValue {
+  let cell = row.cells.get(&field.id);
+  let numeric = |cell: Option<&Cell>| cell.and_then(|cell| reader.numeric_cell(cell));
+  match FieldType::from(field.field_type) {
+    FieldType::Number | FieldType::Time => numeric(cell)
+      .map(FormulaValue::Number)
+      .unwrap_or(FormulaValue::Empty),
+    FieldType::DateTime => cell
+      .and_then(|cell| DateCellData::from(cell).timestamp)
+      .map(FormulaValue::Date)
+      .unwrap_or(FormulaValue::Empty),
+    FieldType::CreatedTime => FormulaValue::Date(row.created_at),
+    FieldType::LastEditedTime => FormulaValue::Date(row.modified_at),
+    FieldType::Checkbox => FormulaValue::Checkbox(numeric(cell) == Some(1.0)),
+    FieldType::Relation => FormulaValue::Empty,
+    FieldType::Formula => match formula_type_option(field).result_type {
+      FormulaResultType::Text => FormulaValue::Text(
+        cell
+          .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+          .unwrap_or_default(),
+      ),
+      FormulaResultType::Number => numeric(cell)
+        .map(FormulaValue::Number)
+        .unwrap_or(FormulaValue::Empty),
+      FormulaResultType::Date => numeric(cell)
+        .map(|timestamp| FormulaValue::Date(timestamp as i64))
+        .unwrap_or(FormulaValue::Empty),
+      FormulaResultType::Checkbox => FormulaValue::Checkbox(numeric(cell) == Some(1.0)),
+    },
+    _ => FormulaValue::Text(
+      cell
+        .filter(|cell| cell.contains_key(CELL_DATA))
+        .map(|cell| reader.stringify_cell(cell))
+        .unwrap_or_default(),
+    ),
+  }
+}
+
+struct RowContext<'a> {
+  row: &'a Row,
+  related: &'a RelatedValues,
+  readers: HashMap<String, (&'a Field, Box<dyn TypeOptionCellReader>)>,
+  /// The values of the formulas already evaluated for the row.
+  values: HashMap<String, FormulaValue>,
+  now: i64,
+}
+
+impl RowContext<'_> {
+  fn eval(&self, expr: &Expr) -> FormulaValue {
+    match expr {
+      Expr::Literal(value) => value.clone(),
+      Expr::Field(field_id) => {
+        if let Some(value) = self.values.get(field_id) {
+          return value.clone();
+        }
+        match self.readers.get(field_id) {
+          Some((field, reader)) => field_value(field, reader.as_ref(), self.row),
+          None => FormulaValue::Empty,
+        }
+      },
+      Expr::Unary { op, expr } => {
+        let value = self.eval(expr);
+        match op {
+          UnaryOp::Negate => number_value(value.to_number().map(|number| -number)),
+          UnaryOp::Not => FormulaValue::Checkbox(!is_true(&value)),
+        }
+      },
+      Expr::Binary { op, left, right } => self.eval_binary(*op, left, right),
+      Expr::Call { function, args } => self.eval_call(function, args, expr),
+    }
+  }
+
+  fn eval_binary(&self, op: BinaryOp, left: &Expr, right: &Expr) -> FormulaValue {
+    // And and Or don't evaluate the right side when the left side decides the result
+    match op {
+      BinaryOp::And => {
+        return FormulaValue::Checkbox(is_true(&self.eval(left)) && is_true(&self.eval(right)));
+      },
+      BinaryOp::Or => {
+        return FormulaValue::Checkbox(is_true(&self.eval(left)) || is_true(&self.eval(right)));
+      },
+      _ => {},
+    }
+
+    let left = self.eval(left);
+    let right = self.eval(right);
+    if op == BinaryOp::Add
+      && (matches!(left, FormulaValue::Text(_)) || matches!(right, FormulaValue::Text(_)))
+    {
+      return FormulaValue::Text(format!("{}{}", left.to_text(), right.to_text()));
+    }
+    let ordering = || compare_values(&left, &right);
+    let arithmetic = |f: fn(f64, f64) -> Option<f64>| {
+      let result = match (left.to_number(), right.to_number()) {
+        (Some(left), Some(right)) => f(left, right),
+        _ => None,
+      };
+      number_value(result)
+    };
+    match op {
+      BinaryOp::Add => arithmetic(|left, right| Some(left + right)),
+      BinaryOp::Subtract => arithmetic(|left, right| Some(left - right)),
+      BinaryOp::Multiply => arithmetic(|left, right| Some(left * right)),
+      BinaryOp::Divide => arithmetic(|left, right| (right != 0.0).then(|| left / right)),
+      BinaryOp::Modulo => arithmetic(|left, right| (right != 0.0).then(|| left % right)),
+      BinaryOp::Equal => FormulaValue::Checkbox(left == right),
+      BinaryOp::NotEqual => FormulaValue::Checkbox(left != right),
+      BinaryOp::Less => FormulaValue::Checkbox(ordering() == Some(Ordering::Less)),
+      BinaryOp::LessOrEqual => {
+        FormulaValue::Checkbox(matches!(ordering(), Some(Ordering::Less | Ordering::Equal)))
+      },
+      BinaryOp::Greater => FormulaValue::Checkbox(ordering() == Some(Ordering::Greater)),
+      BinaryOp::GreaterOrEqual => FormulaValue::Checkbox(matches!(
+        ordering(),
+        Some(Ordering::Greater | Ordering::Equal)
+      )),
+      BinaryOp::And | BinaryOp::Or => unreachable!(),
+    }
+  }
+
+  fn eval_call(&self, function: &str, args: &[Expr], expr: &Expr) -> FormulaValue {
+    if let Some(aggregate) = aggregate_of(expr) {
+      return self.eval_aggregate(function, &aggregate);
+    }
+    if function == "if" {
+      return match args {
+        [condition, then, otherwise] => {
+          if is_true(&self.eval(condition)) {
+            self.eval(then)
+          } else {
+            self.eval(otherwise)
+          }
+        },
+        _ => FormulaValue::Empty,
+      };
+    }
+
+    let values = args.iter().map(|arg| self.eval(arg)).collect::<Vec<_>>();
+    let number = |index: usize| values.get(index).and_then(|value| value.to_number());
+    let text = |index: usize| {
+      values
+        .get(index)
+        .map(|value| value.to_text())
+        .unwrap_or_default()
+    };
+    let date = |index: usize| match values.get(index) {
+      Some(FormulaValue::Date(timestamp)) => DateTime::from_timestamp(*timestamp, 0),
+      _ => None,
+    };
+    match function {
+      "empty" => FormulaValue::Checkbox(values.first().is_none_or(|value| value.is_empty())),
+      "abs" => number_value(number(0).map(f64::abs)),
+      "floor" => number_value(number(0).map(f64::floor)),
+      "ceil" => number_value(number(0).map(f64::ceil)),
+      "sqrt" => number_value(number(0).map(f64::sqrt)),
+      "round" => {
+        let factor = 10f64.powi(number(1).unwrap_or_default() as i32);
+        number_value(number(0).map(|number| (number * factor).round() / factor))
+      },
+      "pow" => number_value(number(0).zip(number(1)).map(|(base, exp)| base.powf(exp))),
+      "min" => number_value(
+        values
+          .iter()
+          .flat_map(|value| value.to_number())
+          .reduce(f64::min),
+      ),
+      "max" => number_value(
+        values
+          .iter()
+          .flat_map(|value| value.to_number())
+          .reduce(f64::max),
+      ),
+      "concat" => FormulaValue::Text(values.iter().map(|value| value.to_text()).collect()),
+      "length" => FormulaValue::Number(text(0).chars().count() as f64),
+      "lower" => FormulaValue::Text(text(0).to_lowercase()),
+      "upper" => FormulaValue::Text(text(0).to_uppercase()),
+      "trim" => FormulaValue::Text(text(0).trim().to_string()),
+      "contains" => FormulaValue::Checkbox(text(0).contains(&text(1))),
+      "replace" => {
+        let from = text(1);
+        if from.is_empty() {
+          FormulaValue::Text(text(0))
+        } else {
+          FormulaValue::Text(text(0).replace(&from, &text(2)))
+        }
+      },
+      "substring" => {
+        let chars = text(0).chars().collect::<Vec<_>>();
+        let clamp = |index: f64| (index.max(0.0) as usize).min(chars.len());
+        let start = clamp(number(1).unwrap_or_default());
+        let end = number(2).map(clamp).unwrap_or(chars.len());
+        FormulaValue::Text(chars[start..end.max(start)].iter().collect())
+      },
+      "to_text" => FormulaValue::Text(text(0)),
+      "to_number" => number_value(number(0)),
+      "now" => FormulaValue::Date(self.now),
+      "date_add" => {
+        let result = match (values.first(), number(1), DateUnit::from_name(&text(2))) {
+          (Some(FormulaValue::Date(timestamp)), Some(amount), Some(unit)) => {
+            add_to_date(*timestamp, amount as i64, unit)
+          },
+          _ => None,
+        };
+        result
+          .map(FormulaValue::Date)
+          .unwrap_or(FormulaValue::Empty)
+      },
+      "date_between" => {
+        let result = match (values.first(), values.get(1), DateUnit::from_name(&text(2))) {
+          (Some(FormulaValue::Date(left)), Some(FormulaValue::Date(right)), Some(unit)) => {
+            date_between(*left, *right, unit)
+          },
+          _ => None,
+        };
+        number_value(result.map(|count| count as f64))
+      },
+      "year" => number_value(date(0).map(|date| date.year() as f64)),
+      "month" => number_value(date(0).map(|date| date.month() as f64)),
+      "day" => number_value(date(0).map(|date| date.day() as f64)),
+      "format_date" => {
+        let pattern = text(1);
+        let items = StrftimeItems::new(&pattern).collect::<Vec<_>>();
+        match date(0) {
+          Some(date) if !items.contains(&Item::Error) => {
+            FormulaValue::Text(date.format_with_items(items.into_iter()).to_string())
+          },
+          _ => FormulaValue::Empty,
+        }
+      },
+      _ => FormulaValue::Empty,
+    }
+  }
+
+  fn eval_aggregate(&self, function: &str, aggregate: &FormulaAggregate) -> FormulaValue {
+    if function == "count" {
+      let count = self
+        .row
+        .cells
+        .get(&aggregate.relation_field_id)
+        .map(|cell| RelationCellData::from(cell).row_ids.len())
+        .unwrap_or_default();
+      return FormulaValue::Number(count as f64);
+    }
+
+    let numbers = self
+      .related
+      .get(aggregate)
+      .into_iter()
+      .flatten()
+      .flat_map(|value| value.to_number())
+      .collect::<Vec<_>>();
+    let result = match function {
+      "sum" => Some(numbers.iter().sum()),
+      "average" if !numbers.is_empty() => Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+      "min" => numbers.iter().copied().reduce(f64::min),
+      "max" => numbers.iter().copied().reduce(f64::max),
+      _ => None,
+    };
+    number_value(result)
+  }
+}
+
+/// A number result, Empty when there is none or when it isn't finite.
+fn number_value(number: Option<f64>) -> FormulaValue {
+  match number {
+    Some(number) if number.is_finite() => FormulaValue::Number(number),
+    _ => FormulaValue::Empty,
+  }
+}
+
+fn is_true(value: &FormulaValue) -> bool {
+  matches!(value, FormulaValue::Checkbox(true))
+}
+
+fn compare_values(left: &FormulaValue, right: &FormulaValue) -> Option<Ordering> {
+  match (left, right) {
+    (FormulaValue::Text(left), FormulaValue::Text(right)) => Some(left.cmp(right)),
+    (FormulaValue::Number(left), FormulaValue::Number(right)) => left.partial_cmp(right),
+    (FormulaValue::Date(left), FormulaValue::Date(right)) => Some(left.cmp(right)),
+    _ => None,
+  }
+}
+
+fn add_to_date(timestamp: i64, amount: i64, unit: DateUnit) -> Option<i64> {
+  if let Some(seconds) = unit.seconds() {
+    return timestamp.checked_add(amount.checked_mul(seconds)?);
+  }
+  let months = if unit == DateUnit::Years {
+    amount.checked_mul(12)?
+  } else {
+    amount
+  };
+  let date = DateTime::from_timestamp(timestamp, 0)?;
+  let months_to_add = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+  let date = if months >= 0 {
+    date.checked_add_months(months_to_add)?
+  } else {
+    date.checked_sub_months(months_to_add)?
+  };
+  Some(date.timestamp())
+}
+
+/// The number of whole units from `right` to `left`, negative when `left` is before `right`.
+fn date_between(left: i64, right: i64, unit: DateUnit) -> Option<i64> {
+  if let Some(seconds) = unit.seconds() {
+    return Some((left - right) / seconds);
+  }
+  let (start, end, sign) = if left >= right {
+    (right, left, 1)
+  } else {
+    (left, right, -1)
+  };
+  let start = DateTime::from_timestamp(start, 0)?;
+  let end = DateTime::from_timestamp(end, 0)?;
+  let mut months =
+    (end.year() - start.year()) as i64 * 12 + end.month() as i64 - start.month() as i64;
+  // The last month isn't complete when the end's day and time are before the start's
+  if (end.day(), end.num_seconds_from_midnight()) < (start.day(), start.num_seconds_from_midnight())
+  {
+    months -= 1;
+  }
+  let count = if unit == DateUnit::Years {
+    months / 12
+  } else {
+    months
+  };
+  Some(sign * count)
+}
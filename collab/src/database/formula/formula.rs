@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+
+use crate::database::fields::date_type_option::DateTypeOption;
+use crate::database::fields::formula_type_option::FormulaResultType;
+use crate::database::fields::{Field, TypeOptionCellReader};
+use crate::database::formula::{check_expr, parse_formula};
+use crate::error::CollabError;
+
+/// A value produced while evaluating a formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaValue {
+  /// A missing value, e.g. an empty cell or the result of a division by zero.
+  Empty,
+  Text(String),
+  Number(f64),
+  /// A timestamp in seconds.
+  Date(i64),
+  Checkbox(bool),
+}
+
+impl FormulaValue {
+  pub fn is_empty(&self) -> bool {
+    match self {
+      FormulaValue::Empty => true,
+      FormulaValue::Text(text) => text.is_empty(),
+      _ => false,
+    }
+  }
+
+  /// The numeric value: dates are timestamps, checkboxes are 1 or 0 and texts are parsed.
+  pub fn to_number(&self) -> Option<f64> {
+    match self {
+      FormulaValue::Empty => None,
+      FormulaValue::Text(text) => text.trim().parse().ok(),
+      FormulaValue::Number(number) => Some(*number),
+      FormulaValue::Date(timestamp) => Some(*timestamp as f64),
+      FormulaValue::Checkbox(checked) => Some(if *checked { 1.0 } else { 0.0 }),
+    }
+  }
+
+  /// The readable text of the value. Dates are formatted like the cells of a date field.
+  pub fn to_text(&self) -> String {
+    match self {
+      FormulaValue::Empty => String::new(),
+      FormulaValue::Text(text) => text.clone(),
+      FormulaValue::Number(number) => format_number(*number),
+      FormulaValue::Date(timestamp) => {
+        DateTypeOption::default_utc().convert_raw_cell_data(&timestamp.to_string())
+      },
+      FormulaValue::Checkbox(checked) => checked.to_string(),
+    }
+  }
+
+  /// The value stored in the [CELL_DATA](crate::database::template::entity::CELL_DATA) of a
+  /// formula cell, None for an empty value.
+  pub fn to_cell_data(&self) -> Option<String> {
+    match self {
+      FormulaValue::Empty => None,
+      FormulaValue::Date(timestamp) => Some(timestamp.to_string()),
+      _ => Some(self.to_text()),
+    }
+  }
+}
+
+pub(crate) fn format_number(number: f64) -> String {
+  if number.fract() == 0.0 && number.abs() < 1e15 {
+    format!("{}", number as i64)
+  } else {
+    number.to_string()
+  }
+}
+
+/// The expression of a formula, as parsed by [parse_formula].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+  Literal(FormulaValue),
+  /// A field reference, written `{Field name}` or `{field_id}`. After [Formula::compile] it
+  /// always holds the field id.
+  Field(String),
+  Unary {
+    op: UnaryOp,
+    expr: Box<Expr>,
+  },
+  Binary {
+    op: BinaryOp,
+    left: Box<Expr>,
+    right: Box<Expr>,
+  },
+  /// A function call. The name is lowercase.
+  Call {
+    function: String,
+    args: Vec<Expr>,
+  },
+}
+
+impl Expr {
+  /// Visit the expression and its sub-expressions, parents first.
+  pub fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Expr)) {
+    f(self);
+    match self {
+      Expr::Literal(_) | Expr::Field(_) => {},
+      Expr::Unary { expr, .. } => expr.visit(f),
+      Expr::Binary { left, right, .. } => {
+        left.visit(f);
+        right.visit(f);
+      },
+      Expr::Call { args, .. } => args.iter().for_each(|arg| arg.visit(f)),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+  Negate,
+  Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Modulo,
+  Equal,
+  NotEqual,
+  Less,
+  LessOrEqual,
+  Greater,
+  GreaterOrEqual,
+  And,
+  Or,
+}
+
+/// An aggregate over the rows of a relation field, e.g. `sum({Tasks}, "Estimate")`. The target
+/// is the id or name of a field of the related database. `count` has no target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormulaAggregate {
+  pub relation_field_id: String,
+  pub target: Option<String>,
+}
+
+/// A parsed and type checked formula.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+  expr: Expr,
+  result_type: FormulaResultType,
+}
+
+impl Formula {
+  /// Parse the expression and check its types against the fields of the database. Field
+  /// references by name are resolved to field ids.
+  pub fn compile(expression: &str, fields: &[Field]) -> Result<Self, CollabError> {
+    let mut expr = parse_formula(expression)?;
+    let result_type = check_expr(&mut expr, fields)?;
+    Ok(Self { expr, result_type })
+  }
+
+  pub fn expr(&self) -> &Expr {
+    &self.expr
+  }
+
+  pub fn result_type(&self) -> FormulaResultType {
+    self.result_type
+  }
+
+  /// The ids of the fields the formula reads.
+  pub fn field_ids(&self) -> HashSet<String> {
+    let mut field_ids = HashSet::new();
+    self.expr.visit(&mut |expr| {
+      if let Expr::Field(field_id) = expr {
+        field_ids.insert(field_id.clone());
+      }
+    });
+    field_ids
+  }
+
+  /// The relation aggregates the formula needs, whose values are loaded from the related
+  /// databases before the formula is evaluated.
+  pub fn aggregates(&self) -> Vec<FormulaAggregate> {
+    let mut aggregates = vec![];
+    self.expr.visit(&mut |expr| {
+      if let Some(aggregate) = aggregate_of(expr) {
+        if !aggregates.contains(&aggregate) {
+          aggregates.push(aggregate);
+        }
+      }
+    });
+    aggregates
+  }
+}
+
+/// Return the relation aggregate of a checked call expression. The checker guarantees that the
+/// first argument of an aggregate is a relation field and the second a text literal.
+pub(crate) fn aggregate_of(expr: &Expr) -> Option<FormulaAggregate> {
+  let Expr::Call { function, args } = expr else {
+    return None;
+  };
+  if !is_aggregate_function(function) {
+    return None;
+  }
+  let Some(Expr::Field(relation_field_id)) = args.first() else {
+    return None;
+  };
+  let target = match args.get(1) {
+    Some(Expr::Literal(FormulaValue::Text(target))) => Some(target.clone()),
+    _ => None,
+  };
+  if function != "count" && target.is_none() {
+    return None;
+  }
+  Some(FormulaAggregate {
+    relation_field_id: relation_field_id.clone(),
+    target,
+  })
+}
+
+/// `min` and `max` are aggregates only when their first argument is a relation field, which is
+/// decided by the checker.
+pub(crate) fn is_aggregate_function(function: &str) -> bool {
+  matches!(function, "count" | "sum" | "average" | "min" | "max")
+}
@@ -0,0 +1,9 @@
+mod checker;
+mod evaluator;
+mod formula;
+mod parser;
+
+pub(crate) use checker::*;
+pub use evaluator::*;
+pub use formula::*;
+pub use parser::*;
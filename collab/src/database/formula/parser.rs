@@ -0,0 +1,378 @@
+use std::iter::{Enumerate, Peekable};
+use std::str::Chars;
+
+use crate::database::formula::{BinaryOp, Expr, FormulaValue, UnaryOp};
+use crate::error::CollabError;
+
+/// Parse a formula expression.
+///
+/// The grammar, from the lowest to the highest precedence:
+/// - `a or b`, also written `a || b`
+/// - `a and b`, also written `a && b`
+/// - `not a`, also written `!a`
+/// - comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`
+/// - `+` and `-`
+/// - `*`, `/` and `%`
+/// - unary `-`
+/// - numbers, `"texts"`, `true`, `false`, field references such as `{Due date}`, function
+///   calls such as `round({Price} * 1.2, 2)` and parenthesized expressions.
+///
+/// Function names and keywords are case-insensitive.
+pub fn parse_formula(expression: &str) -> Result<Expr, CollabError> {
+  let tokens = tokenize(expression)?;
+  let mut parser = Parser { tokens, index: 0 };
+  let expr = parser.parse_or()?;
+  match parser.peek() {
+    None => Ok(expr),
+    Some(token) => Err(parse_error(token.position, "unexpected token")),
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+  Number(f64),
+  Text(String),
+  Field(String),
+  Ident(String),
+  LeftParen,
+  RightParen,
+  Comma,
+  Plus,
+  Minus,
+  Star,
+  Slash,
+  Percent,
+  Equal,
+  NotEqual,
+  Less,
+  LessOrEqual,
+  Greater,
+  GreaterOrEqual,
+  And,
+  Or,
+  Not,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+  kind: TokenKind,
+  /// The char offset of the token in the expression.
+  position: usize,
+}
+
+fn parse_error(position: usize, message: &str) -> CollabError {
+  CollabError::DatabaseInvalidFormula(format!("{} at position {}", message, position))
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, CollabError> {
+  let mut tokens = vec![];
+  let mut chars = expression.chars().enumerate().peekable();
+  while let Some((position, c)) = chars.next() {
+    let kind = match c {
+      c if c.is_whitespace() => continue,
+      '(' => TokenKind::LeftParen,
+      ')' => TokenKind::RightParen,
+      ',' => TokenKind::Comma,
+      '+' => TokenKind::Plus,
+      '-' => TokenKind::Minus,
+      '*' => TokenKind::Star,
+      '/' => TokenKind::Slash,
+      '%' => TokenKind::Percent,
+      '=' => {
+        expect_char(&mut chars, '=', position)?;
+        TokenKind::Equal
+      },
+      '!' => {
+        if next_is(&mut chars, '=') {
+          TokenKind::NotEqual
+        } else {
+          TokenKind::Not
+        }
+      },
+      '<' => {
+        if next_is(&mut chars, '=') {
+          TokenKind::LessOrEqual
+        } else {
+          TokenKind::Less
+        }
+      },
+      '>' => {
+        if next_is(&mut chars, '=') {
+          TokenKind::GreaterOrEqual
+        } else {
+          TokenKind::Greater
+        }
+      },
+      '&' => {
+        expect_char(&mut chars, '&', position)?;
+        TokenKind::And
+      },
+      '|' => {
+        expect_char(&mut chars, '|', position)?;
+        TokenKind::Or
+      },
+      '"' => {
+        let mut text = String::new();
+        loop {
+          match chars.next() {
+            None => return Err(parse_error(position, "unterminated text")),
+            Some((_, '"')) => break,
+            Some((escape_position, '\\')) => match chars.next() {
+              Some((_, c @ ('"' | '\\'))) => text.push(c),
+              Some((_, 'n')) => text.push('\n'),
+              _ => return Err(parse_error(escape_position, "invalid escape")),
+            },
+            Some((_, c)) => text.push(c),
+          }
+        }
+        TokenKind::Text(text)
+      },
+      '{' => {
+        let mut name = String::new();
+        loop {
+          match chars.next() {
+            None => return Err(parse_error(position, "unterminated field reference")),
+            Some((_, '}')) => break,
+            Some((_, c)) => name.push(c),
+          }
+        }
+        let name = name.trim().to_string();
+        if name.is_empty() {
+          return Err(parse_error(position, "empty field reference"));
+        }
+        TokenKind::Field(name)
+      },
+      c if c.is_ascii_digit() || c == '.' => {
+        let mut number = c.to_string();
+        while let Some((_, c)) = chars.peek() {
+          if c.is_ascii_digit() || *c == '.' {
+            number.push(*c);
+            chars.next();
+          } else {
+            break;
+          }
+        }
+        let number = number
+          .parse()
+          .map_err(|_| parse_error(position, "invalid number"))?;
+        TokenKind::Number(number)
+      },
+      c if c.is_alphabetic() || c == '_' => {
+        let mut ident = c.to_string();
+        while let Some((_, c)) = chars.peek() {
+          if c.is_alphanumeric() || *c == '_' {
+            ident.push(*c);
+            chars.next();
+          } else {
+            break;
+          }
+        }
+        let ident = ident.to_lowercase();
+        match ident.as_str() {
+          "and" => TokenKind::And,
+          "or" => TokenKind::Or,
+          "not" => TokenKind::Not,
+          _ => TokenKind::Ident(ident),
+        }
+      },
+      _ => return Err(parse_error(position, "unexpected character")),
+    };
+    tokens.push(Token { kind, position });
+  }
+  Ok(tokens)
+}
+
+type CharStream<'a> = Peekable<Enumerate<Chars<'a>>>;
+
+fn next_is(chars: &mut CharStream, expected: char) -> bool {
+  let is_expected = chars.peek().is_some_and(|(_, c)| *c == expected);
+  if is_expected {
+    chars.next();
+  }
+  is_expected
+}
+
+fn expect_char(chars: &mut CharStream, expected: char, position: usize) -> Result<(), CollabError> {
+  if next_is(chars, expected) {
+    Ok(())
+  } else {
+    Err(parse_error(position, "unexpected character"))
+  }
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  index: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.index)
+  }
+
+  fn advance(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.index).cloned();
+    self.index += 1;
+    token
+  }
+
+  /// Consume the next token if it is of the given kind.
+  fn eat(&mut self, kind: &TokenKind) -> bool {
+    let is_kind = self.peek().is_some_and(|token| &token.kind == kind);
+    if is_kind {
+      self.index += 1;
+    }
+    is_kind
+  }
+
+  fn end_position(&self) -> usize {
+    self
+      .tokens
+      .last()
+      .map(|token| token.position + 1)
+      .unwrap_or_default()
+  }
+
+  fn expect(&mut self, kind: &TokenKind, message: &str) -> Result<(), CollabError> {
+    if self.eat(kind) {
+      return Ok(());
+    }
+    let position = self
+      .peek()
+      .map(|token| token.position)
+      .unwrap_or_else(|| self.end_position());
+    Err(parse_error(position, message))
+  }
+
+  fn parse_or(&mut self) -> Result<Expr, CollabError> {
+    let mut left = self.parse_and()?;
+    while self.eat(&TokenKind::Or) {
+      let right = self.parse_and()?;
+      left = binary(BinaryOp::Or, left, right);
+    }
+    Ok(left)
+  }
+
+  fn parse_and(&mut self) -> Result<Expr, CollabError> {
+    let mut left = self.parse_not()?;
+    while self.eat(&TokenKind::And) {
+      let right = self.parse_not()?;
+      left = binary(BinaryOp::And, left, right);
+    }
+    Ok(left)
+  }
+
+  fn parse_not(&mut self) -> Result<Expr, CollabError> {
+    if self.eat(&TokenKind::Not) {
+      let expr = self.parse_not()?;
+      return Ok(Expr::Unary {
+        op: UnaryOp::Not,
+        expr: Box::new(expr),
+      });
+    }
+    self.parse_comparison()
+  }
+
+  fn parse_comparison(&mut self) -> Result<Expr, CollabError> {
+    let left = self.parse_additive()?;
+    let op = match self.peek().map(|token| &token.kind) {
+      Some(TokenKind::Equal) => BinaryOp::Equal,
+      Some(TokenKind::NotEqual) => BinaryOp::NotEqual,
+      Some(TokenKind::Less) => BinaryOp::Less,
+      Some(TokenKind::LessOrEqual) => BinaryOp::LessOrEqual,
+      Some(TokenKind::Greater) => BinaryOp::Greater,
+      Some(TokenKind::GreaterOrEqual) => BinaryOp::GreaterOrEqual,
+      _ => return Ok(left),
+    };
+    self.index += 1;
+    let right = self.parse_additive()?;
+    Ok(binary(op, left, right))
+  }
+
+  fn parse_additive(&mut self) -> Result<Expr, CollabError> {
+    let mut left = self.parse_multiplicative()?;
+    loop {
+      let op = match self.peek().map(|token| &token.kind) {
+        Some(TokenKind::Plus) => BinaryOp::Add,
+        Some(TokenKind::Minus) => BinaryOp::Subtract,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      let right = self.parse_multiplicative()?;
+      left = binary(op, left, right);
+    }
+  }
+
+  fn parse_multiplicative(&mut self) -> Result<Expr, CollabError> {
+    let mut left = self.parse_unary()?;
+    loop {
+      let op = match self.peek().map(|token| &token.kind) {
+        Some(TokenKind::Star) => BinaryOp::Multiply,
+        Some(TokenKind::Slash) => BinaryOp::Divide,
+        Some(TokenKind::Percent) => BinaryOp::Modulo,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      let right = self.parse_unary()?;
+      left = binary(op, left, right);
+    }
+  }
+
+  fn parse_unary(&mut self) -> Result<Expr, CollabError> {
+    if self.eat(&TokenKind::Minus) {
+      let expr = self.parse_unary()?;
+      return Ok(Expr::Unary {
+        op: UnaryOp::Negate,
+        expr: Box::new(expr),
+      });
+    }
+    self.parse_primary()
+  }
+
+  fn parse_primary(&mut self) -> Result<Expr, CollabError> {
+    let end_position = self.end_position();
+    let Some(token) = self.advance() else {
+      return Err(parse_error(end_position, "unexpected end of formula"));
+    };
+    match token.kind {
+      TokenKind::Number(number) => Ok(Expr::Literal(FormulaValue::Number(number))),
+      TokenKind::Text(text) => Ok(Expr::Literal(FormulaValue::Text(text))),
+      TokenKind::Field(name) => Ok(Expr::Field(name)),
+      TokenKind::Ident(ident) => match ident.as_str() {
+        "true" => Ok(Expr::Literal(FormulaValue::Checkbox(true))),
+        "false" => Ok(Expr::Literal(FormulaValue::Checkbox(false))),
+        _ => {
+          self.expect(&TokenKind::LeftParen, "expected '(' after function name")?;
+          let mut args = vec![];
+          if !self.eat(&TokenKind::RightParen) {
+            loop {
+              args.push(self.parse_or()?);
+              if self.eat(&TokenKind::RightParen) {
+                break;
+              }
+              self.expect(&TokenKind::Comma, "expected ',' or ')'")?;
+            }
+          }
+          Ok(Expr::Call {
+            function: ident,
+            args,
+          })
+        },
+      },
+      TokenKind::LeftParen => {
+        let expr = self.parse_or()?;
+        self.expect(&TokenKind::RightParen, "expected ')'")?;
+        Ok(expr)
+      },
+      _ => Err(parse_error(token.position, "unexpected token")),
+    }
+  }
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+  Expr::Binary {
+    op,
+    left: Box::new(left),
+    right: Box::new(right),
+  }
+}
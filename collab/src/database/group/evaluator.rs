@@ -0,0 +1,649 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::person_type_option::PersonTypeOption;
+use crate::database::fields::select_type_option::{SelectOptionIds, SelectTypeOption};
+use crate::database::fields::{
+  Field, TypeOptionCellReader, TypeOptionData, type_option_cell_reader, type_option_timezone,
+};
+use crate::database::group::{DateGroupCondition, GroupContent, GroupData};
+use crate::database::rows::{Cell, Row, new_cell_builder};
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::views::{Group, GroupSetting};
+use crate::entity::uuid_validation::RowId;
+
+pub const CHECKBOX_CHECKED_GROUP_ID: &str = "Yes";
+pub const CHECKBOX_UNCHECKED_GROUP_ID: &str = "No";
+
+const TODAY: &str = "today";
+const YESTERDAY: &str = "yesterday";
+const TOMORROW: &str = "tomorrow";
+const LAST_7_DAYS: &str = "last_7_days";
+const NEXT_7_DAYS: &str = "next_7_days";
+const LAST_30_DAYS: &str = "last_30_days";
+const NEXT_30_DAYS: &str = "next_30_days";
+
+/// Assigns rows to the groups of a view's [GroupSetting].
+///
+/// Select fields have one group per option and checkbox fields a checked and an unchecked
+/// group. Date fields are bucketed by the setting's [DateGroupCondition] in the timezone of the
+/// field, and URL, text and
+/// person fields have one group per value found in the rows. Except for checkboxes, rows without
+/// a value go to a default group whose id is the grouping field's id.
+///
+/// The stored [GroupSetting::groups] only keep the order and visibility of the groups. Group
+/// names are computed from the field, so renaming a select option renames its group.
+pub struct GroupEvaluator {
+  field_id: String,
+  field_name: String,
+  field_type: FieldType,
+  type_option: TypeOptionData,
+  groups: Vec<Group>,
+  content: GroupContent,
+  /// The timezone the dates of the field are bucketed in.
+  timezone: Tz,
+  /// Today in [GroupEvaluator::timezone].
+  today: NaiveDate,
+}
+
+/// A group found for a row, before it is merged with the stored groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupKey {
+  id: String,
+  name: String,
+  /// Dates are ordered by bucket; other groups keep the order they were found in.
+  order: Option<(u8, NaiveDate)>,
+}
+
+impl GroupEvaluator {
+  /// Return None if the field can't be grouped by, or if the setting groups by another field.
+  pub fn new(setting: &GroupSetting, field: &Field) -> Option<Self> {
+    Self::new_with_now(setting, field, Utc::now())
+  }
+
+  /// Same as [GroupEvaluator::new], with the time relative date groups are computed from.
+  pub fn new_with_now(setting: &GroupSetting, field: &Field, now: DateTime<Utc>) -> Option<Self> {
+    let field_type = FieldType::from(field.field_type);
+    if setting.field_id != field.id
+      || FieldType::from(setting.field_type) != field_type
+      || !is_groupable(&field_type)
+    {
+      return None;
+    }
+    let type_option = field
+      .get_any_type_option(field_type.type_id())
+      .unwrap_or_default();
+    let timezone = type_option_timezone(&type_option, &field_type);
+    Some(Self {
+      field_id: field.id.clone(),
+      field_name: field.name.clone(),
+      field_type,
+      type_option,
+      groups: setting.groups.clone(),
+      content: GroupContent::from_json(&setting.content),
+      timezone,
+      today: now.with_timezone(&timezone).date_naive(),
+    })
+  }
+
+  pub fn field_id(&self) -> &str {
+    &self.field_id
+  }
+
+  /// Return the ids of the groups the row belongs to. Multi-select and person cells can put a
+  /// row in several groups.
+  pub fn group_ids_for_row(&self, row: &Row) -> Vec<String> {
+    let reader = self.reader();
+    self
+      .group_keys(reader.as_ref(), row)
+      .into_iter()
+      .map(|key| key.id)
+      .collect()
+  }
+
+  /// Assign the rows to groups. The rows keep their order inside each group.
+  ///
+  /// The default group comes first, then the groups in the order of the setting, then the new
+  /// groups: select options in the field's order, dates chronologically and other values in the
+  /// order they were found.
+  pub fn group_rows(&self, rows: &[Row]) -> Vec<GroupData> {
+    let reader = self.reader();
+    let mut found = self.fixed_group_keys();
+    let mut row_ids_by_group = HashMap::<String, Vec<RowId>>::new();
+    for row in rows {
+      for key in self.group_keys(reader.as_ref(), row) {
+        row_ids_by_group
+          .entry(key.id.clone())
+          .or_default()
+          .push(row.id);
+        if key.id != self.field_id && !found.iter().any(|found_key| found_key.id == key.id) {
+          found.push(key);
+        }
+      }
+    }
+    found.sort_by_key(|key| key.order);
+
+    let mut ordered = self
+      .groups
+      .iter()
+      .flat_map(|group| found.iter().position(|key| key.id == group.id))
+      .collect::<Vec<_>>();
+    let known = ordered.iter().copied().collect::<HashSet<_>>();
+    ordered.extend((0..found.len()).filter(|index| !known.contains(index)));
+
+    let default_group = self.has_default_group().then(|| GroupKey {
+      id: self.field_id.clone(),
+      name: format!("No {}", self.field_name),
+      order: None,
+    });
+    default_group
+      .into_iter()
+      .chain(ordered.into_iter().map(|index| found[index].clone()))
+      .map(|key| {
+        let visible = self
+          .groups
+          .iter()
+          .find(|group| group.id == key.id)
+          .map(|group| group.visible)
+          .unwrap_or(true);
+        GroupData {
+          is_default: key.id == self.field_id && self.has_default_group(),
+          row_ids: row_ids_by_group.remove(&key.id).unwrap_or_default(),
+          id: key.id,
+          name: key.name,
+          visible,
+        }
+      })
+      .filter(|group| !self.content.hide_empty || !group.row_ids.is_empty())
+      .collect()
+  }
+
+  /// Return the cell to write to the row's grouping field when its card is moved from one group
+  /// to another. The cell holds the target group's value: moving to the default group clears
+  /// the cell, and moving a multi-select or person card replaces the value of the group it was
+  /// moved from.
+  ///
+  /// Return None if the row can't be moved to the group, e.g. for created and last edited time
+  /// fields.
+  pub fn cell_for_moved_row(
+    &self,
+    row: &Row,
+    from_group_id: Option<&str>,
+    to_group_id: &str,
+  ) -> Option<Cell> {
+    if to_group_id == self.field_id && self.has_default_group() {
+      return match self.field_type {
+        FieldType::CreatedTime | FieldType::LastEditedTime => None,
+        _ => Some(text_cell(self.field_type, "")),
+      };
+    }
+
+    let empty_cell = Cell::new();
+    let cell = row.cells.get(&self.field_id).unwrap_or(&empty_cell);
+    match self.field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let options = SelectTypeOption::from(self.type_option.clone()).options;
+        if !options.iter().any(|option| option.id == to_group_id) {
+          return None;
+        }
+        let ids = if self.field_type == FieldType::SingleSelect {
+          vec![to_group_id.to_string()]
+        } else {
+          replace_id(
+            SelectOptionIds::from(cell).to_vec(),
+            from_group_id,
+            to_group_id,
+          )
+        };
+        Some(SelectOptionIds::from(ids).to_cell(self.field_type))
+      },
+      FieldType::Checkbox => match to_group_id {
+        CHECKBOX_CHECKED_GROUP_ID | CHECKBOX_UNCHECKED_GROUP_ID => {
+          Some(text_cell(self.field_type, to_group_id))
+        },
+        _ => None,
+      },
+      FieldType::DateTime => {
+        let start = self.bucket_start(to_group_id)?;
+        let mut cell_data = DateCellData::from(cell);
+        // Keep the local time of day, and the length of a range
+        let time = cell_data
+          .timestamp
+          .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+          .map(|date_time| date_time.with_timezone(&self.timezone).time())
+          .unwrap_or(NaiveTime::MIN);
+        let timestamp = self
+          .timezone
+          .from_local_datetime(&start.and_time(time))
+          .earliest()?
+          .timestamp();
+        if let (Some(old_timestamp), Some(end_timestamp)) =
+          (cell_data.timestamp, cell_data.end_timestamp)
+        {
+          cell_data.end_timestamp = Some(end_timestamp + timestamp - old_timestamp);
+        }
+        cell_data.timestamp = Some(timestamp);
+        Some(Cell::from(&cell_data))
+      },
+      FieldType::URL | FieldType::RichText => Some(text_cell(self.field_type, to_group_id)),
+      FieldType::Person => {
+        let is_single_select = PersonTypeOption::from(self.type_option.clone()).is_single_select;
+        let ids = if is_single_select {
+          vec![to_group_id.to_string()]
+        } else {
+          replace_id(
+            PersonCellData::from(cell).person_ids,
+            from_group_id,
+            to_group_id,
+          )
+        };
+        Some(PersonCellData::new(ids).to_cell())
+      },
+      _ => None,
+    }
+  }
+
+  /// Bring the groups of a select or checkbox setting in line with the field: groups of deleted
+  /// options are removed and groups of new options are added at the end, visible. The order and
+  /// visibility of the other groups are kept. Return true if the groups changed.
+  ///
+  /// The groups of other field types follow the rows, so they are left untouched.
+  pub fn sync_group_setting(&self, setting: &mut GroupSetting) -> bool {
+    let fixed_keys = self.fixed_group_keys();
+    if fixed_keys.is_empty() && !self.is_select() {
+      return false;
+    }
+
+    let mut groups = setting
+      .groups
+      .iter()
+      .filter(|group| {
+        (group.id == self.field_id && self.has_default_group())
+          || fixed_keys.iter().any(|key| key.id == group.id)
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+    if self.has_default_group() && !groups.iter().any(|group| group.id == self.field_id) {
+      groups.insert(0, Group::new(self.field_id.clone()));
+    }
+    for key in fixed_keys {
+      if !groups.iter().any(|group| group.id == key.id) {
+        groups.push(Group::new(key.id));
+      }
+    }
+
+    let is_changed = groups.iter().map(|group| (&group.id, group.visible)).ne(
+      setting
+        .groups
+        .iter()
+        .map(|group| (&group.id, group.visible)),
+    );
+    setting.groups = groups;
+    is_changed
+  }
+
+  fn reader(&self) -> Box<dyn TypeOptionCellReader> {
+    type_option_cell_reader(self.type_option.clone(), &self.field_type)
+  }
+
+  fn is_select(&self) -> bool {
+    matches!(
+      self.field_type,
+      FieldType::SingleSelect | FieldType::MultiSelect
+    )
+  }
+
+  fn has_default_group(&self) -> bool {
+    self.field_type != FieldType::Checkbox
+  }
+
+  /// The groups that exist whether or not a row belongs to them.
+  fn fixed_group_keys(&self) -> Vec<GroupKey> {
+    match self.field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        SelectTypeOption::from(self.type_option.clone())
+          .options
+          .into_iter()
+          .map(|option| GroupKey {
+            id: option.id,
+            name: option.name,
+            order: None,
+          })
+          .collect()
+      },
+      FieldType::Checkbox => [CHECKBOX_CHECKED_GROUP_ID, CHECKBOX_UNCHECKED_GROUP_ID]
+        .into_iter()
+        .map(|id| GroupKey {
+          id: id.to_string(),
+          name: id.to_string(),
+          order: None,
+        })
+        .collect(),
+      _ => vec![],
+    }
+  }
+
+  fn group_keys(&self, reader: &dyn TypeOptionCellReader, row: &Row) -> Vec<GroupKey> {
+    let empty_cell = Cell::new();
+    let cell = row.cells.get(&self.field_id).unwrap_or(&empty_cell);
+    let keys = match self.field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let options = self.fixed_group_keys();
+        SelectOptionIds::from(cell)
+          .iter()
+          .flat_map(|id| options.iter().find(|option| &option.id == id).cloned())
+          .collect()
+      },
+      FieldType::Checkbox => {
+        let id = if reader.numeric_cell(cell) == Some(1.0) {
+          CHECKBOX_CHECKED_GROUP_ID
+        } else {
+          CHECKBOX_UNCHECKED_GROUP_ID
+        };
+        vec![GroupKey {
+          id: id.to_string(),
+          name: id.to_string(),
+          order: None,
+        }]
+      },
+      FieldType::DateTime => DateCellData::from(cell)
+        .timestamp
+        .and_then(|timestamp| self.date_key(timestamp))
+        .into_iter()
+        .collect(),
+      FieldType::CreatedTime => self.date_key(row.created_at).into_iter().collect(),
+      FieldType::LastEditedTime => self.date_key(row.modified_at).into_iter().collect(),
+      FieldType::Person => {
+        let type_option = PersonTypeOption::from(self.type_option.clone());
+        PersonCellData::from(cell)
+          .person_ids
+          .into_iter()
+          .map(|id| {
+            let name = type_option.person_name(&id);
+            GroupKey {
+              id,
+              name,
+              order: None,
+            }
+          })
+          .collect()
+      },
+      _ => {
+        let text = if cell.contains_key(CELL_DATA) {
+          reader.stringify_cell(cell).trim().to_string()
+        } else {
+          String::new()
+        };
+        if text.is_empty() {
+          vec![]
+        } else {
+          vec![GroupKey {
+            id: text.clone(),
+            name: text,
+            order: None,
+          }]
+        }
+      },
+    };
+
+    if keys.is_empty() && self.has_default_group() {
+      vec![GroupKey {
+        id: self.field_id.clone(),
+        name: String::new(),
+        order: None,
+      }]
+    } else {
+      keys
+    }
+  }
+
+  fn date_key(&self, timestamp: i64) -> Option<GroupKey> {
+    let date = DateTime::from_timestamp(timestamp, 0)?
+      .with_timezone(&self.timezone)
+      .date_naive();
+    let key = match self.content.condition {
+      DateGroupCondition::Relative => {
+        let days = (date - self.today).num_days();
+        let (id, name, rank) = match days {
+          0 => (TODAY, "Today", 4),
+          -1 => (YESTERDAY, "Yesterday", 3),
+          1 => (TOMORROW, "Tomorrow", 5),
+          -7..=-2 => (LAST_7_DAYS, "Last 7 days", 2),
+          2..=7 => (NEXT_7_DAYS, "Next 7 days", 6),
+          -30..=-8 => (LAST_30_DAYS, "Last 30 days", 1),
+          8..=30 => (NEXT_30_DAYS, "Next 30 days", 7),
+          _ => {
+            let start = date.with_day(1)?;
+            let rank = if days < 0 { 0 } else { 8 };
+            return Some(GroupKey {
+              id: start.format("%Y/%m").to_string(),
+              name: start.format("%b %Y").to_string(),
+              order: Some((rank, start)),
+            });
+          },
+        };
+        GroupKey {
+          id: id.to_string(),
+          name: name.to_string(),
+          order: Some((rank, self.bucket_start(id)?)),
+        }
+      },
+      DateGroupCondition::Day => GroupKey {
+        id: date.format("%Y/%m/%d").to_string(),
+        name: date.format("%b %-d, %Y").to_string(),
+        order: Some((0, date)),
+      },
+      DateGroupCondition::Week => {
+        let start =
+          date.checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))?;
+        GroupKey {
+          id: start.format("%Y/%m/%d").to_string(),
+          name: format!("Week of {}", start.format("%b %-d, %Y")),
+          order: Some((0, start)),
+        }
+      },
+      DateGroupCondition::Month => {
+        let start = date.with_day(1)?;
+        GroupKey {
+          id: start.format("%Y/%m").to_string(),
+          name: start.format("%b %Y").to_string(),
+          order: Some((0, start)),
+        }
+      },
+      DateGroupCondition::Year => {
+        let start = NaiveDate::from_ymd_opt(date.year(), 1, 1)?;
+        GroupKey {
+          id: start.format("%Y").to_string(),
+          name: start.format("%Y").to_string(),
+          order: Some((0, start)),
+        }
+      },
+    };
+    Some(key)
+  }
+
+  /// The first day of the date group with the given id.
+  fn bucket_start(&self, group_id: &str) -> Option<NaiveDate> {
+    let today = self.today;
+    match group_id {
+      TODAY => Some(today),
+      YESTERDAY => today.checked_sub_days(Days::new(1)),
+      TOMORROW => today.checked_add_days(Days::new(1)),
+      LAST_7_DAYS => today.checked_sub_days(Days::new(7)),
+      NEXT_7_DAYS => today.checked_add_days(Days::new(2)),
+      LAST_30_DAYS => today.checked_sub_days(Days::new(30)),
+      NEXT_30_DAYS => today.checked_add_days(Days::new(8)),
+      _ => {
+        let mut parts = group_id.split('/').map(|part| part.parse::<u32>().ok());
+        let year = parts.next()?? as i32;
+        let month = parts.next().unwrap_or(Some(1))?;
+        let day = parts.next().unwrap_or(Some(1))?;
+        NaiveDate::from_ymd_opt(year, month, day)
+      },
+    }
+  }
+}
+
+fn is_groupable(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::SingleSelect
+      | FieldType::MultiSelect
+      | FieldType::Checkbox
+      | FieldType::DateTime
+      | FieldType::CreatedTime
+      | FieldType::LastEditedTime
+      | FieldType::URL
+      | FieldType::RichText
+      | FieldType::Person
+  )
+}
+
+/// Replace `from_id` by `to_id` in the ids, or add `to_id` if the row wasn't in `from_id`.
+fn replace_id(mut ids: Vec<String>, from_id: Option<&str>, to_id: &str) -> Vec<String> {
+  if let Some(from_id) = from_id {
+    ids.retain(|id| id != from_id);
+  }
+  if !ids.iter().any(|id| id == to_id) {
+    ids.push(to_id.to_string());
+  }
+  ids
+}
+
+fn text_cell(field_type: FieldType, data: &str) -> Cell {
+  let mut cell = new_cell_builder(field_type);
+  cell.insert(CELL_DATA.into(), data.into());
+  cell
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  fn evaluator(condition: DateGroupCondition) -> GroupEvaluator {
+    GroupEvaluator {
+      field_id: "due".to_string(),
+      field_name: "Due".to_string(),
+      field_type: FieldType::DateTime,
+      type_option: Default::default(),
+      groups: vec![],
+      content: GroupContent {
+        hide_empty: false,
+        condition,
+      },
+      timezone: Tz::UTC,
+      today: date(2024, 3, 15),
+    }
+  }
+
+  fn timestamp(date: NaiveDate) -> i64 {
+    date.and_hms_opt(10, 30, 0).unwrap().and_utc().timestamp()
+  }
+
+  #[test]
+  fn relative_date_groups_test() {
+    let evaluator = evaluator(DateGroupCondition::Relative);
+    let group_id = |date: NaiveDate| evaluator.date_key(timestamp(date)).unwrap().id;
+    assert_eq!(group_id(date(2024, 3, 15)), TODAY);
+    assert_eq!(group_id(date(2024, 3, 14)), YESTERDAY);
+    assert_eq!(group_id(date(2024, 3, 10)), LAST_7_DAYS);
+    assert_eq!(group_id(date(2024, 3, 25)), NEXT_30_DAYS);
+    assert_eq!(group_id(date(2024, 1, 20)), "2024/01");
+
+    // Every relative group starts on a day that falls back into it
+    for id in [
+      TODAY,
+      YESTERDAY,
+      TOMORROW,
+      LAST_7_DAYS,
+      NEXT_7_DAYS,
+      LAST_30_DAYS,
+      NEXT_30_DAYS,
+    ] {
+      let start = evaluator.bucket_start(id).unwrap();
+      assert_eq!(group_id(start), id);
+    }
+  }
+
+  #[test]
+  fn week_groups_start_on_monday_test() {
+    let evaluator = evaluator(DateGroupCondition::Week);
+    let key = evaluator.date_key(timestamp(date(2024, 3, 17))).unwrap();
+    assert_eq!(key.id, "2024/03/11");
+    assert_eq!(key.name, "Week of Mar 11, 2024");
+    assert_eq!(evaluator.bucket_start(&key.id), Some(date(2024, 3, 11)));
+  }
+
+  #[test]
+  fn month_and_year_group_start_test() {
+    let evaluator = evaluator(DateGroupCondition::Month);
+    assert_eq!(evaluator.bucket_start("2024/02"), Some(date(2024, 2, 1)));
+    assert_eq!(evaluator.bucket_start("2023"), Some(date(2023, 1, 1)));
+    assert_eq!(evaluator.bucket_start("not a date"), None);
+  }
+
+  #[test]
+  fn date_groups_use_field_timezone_test() {
+    use crate::database::fields::date_type_option::DateTypeOption;
+    use uuid::Uuid;
+
+    let type_option = DateTypeOption {
+      timezone_id: "America/New_York".to_string(),
+      ..DateTypeOption::new()
+    };
+    let field = Field::new(
+      "due".to_string(),
+      "Due".to_string(),
+      FieldType::DateTime.into(),
+      false,
+    )
+    .with_type_option_data(FieldType::DateTime.type_id(), type_option.into());
+    let setting = GroupSetting::new(
+      "due".to_string(),
+      FieldType::DateTime.into(),
+      GroupContent {
+        hide_empty: false,
+        condition: DateGroupCondition::Relative,
+      }
+      .to_json(),
+    );
+    // 2024-03-15 02:00 UTC is still 2024-03-14 22:00 in New York
+    let now = date(2024, 3, 15).and_hms_opt(2, 0, 0).unwrap().and_utc();
+    let evaluator = GroupEvaluator::new_with_now(&setting, &field, now).unwrap();
+
+    // 2024-03-14 19:00 in New York, a day before now in UTC
+    let due = date(2024, 3, 14)
+      .and_hms_opt(23, 0, 0)
+      .unwrap()
+      .and_utc()
+      .timestamp();
+    assert_eq!(evaluator.date_key(due).unwrap().id, TODAY);
+
+    // Moving the row to tomorrow keeps its local time of day
+    let mut row = Row::new(Uuid::new_v4(), Uuid::new_v4());
+    row.cells.insert(
+      "due".to_string(),
+      Cell::from(&DateCellData::from_timestamp(due)),
+    );
+    let cell = evaluator
+      .cell_for_moved_row(&row, Some(TODAY), TOMORROW)
+      .unwrap();
+    assert_eq!(DateCellData::from(&cell).timestamp, Some(due + 86_400));
+  }
+
+  #[test]
+  fn replace_id_test() {
+    let ids = vec!["a".to_string(), "b".to_string()];
+    assert_eq!(replace_id(ids.clone(), Some("a"), "c"), vec!["b", "c"]);
+    assert_eq!(replace_id(ids.clone(), None, "b"), vec!["a", "b"]);
+  }
+}
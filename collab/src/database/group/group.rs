@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+use crate::entity::uuid_validation::RowId;
+
+/// The grouping options of a [GroupSetting](crate::database::views::GroupSetting), stored as
+/// JSON in its `content`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupContent {
+  /// Hide the groups that have no rows.
+  #[serde(default)]
+  pub hide_empty: bool,
+  /// How the rows of a date field are bucketed.
+  #[serde(default)]
+  pub condition: DateGroupCondition,
+}
+
+impl GroupContent {
+  /// Parse the content of a group setting. An empty or invalid content yields the default
+  /// options.
+  pub fn from_json(content: &str) -> Self {
+    if content.is_empty() {
+      return Self::default();
+    }
+    serde_json::from_str(content).unwrap_or_default()
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).unwrap_or_default()
+  }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum DateGroupCondition {
+  /// Today, yesterday, tomorrow, the last and next 7 and 30 days, then one group per month.
+  #[default]
+  Relative = 0,
+  Day = 1,
+  /// Weeks start on Monday.
+  Week = 2,
+  Month = 3,
+  Year = 4,
+}
+
+impl From<i64> for DateGroupCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => DateGroupCondition::Relative,
+      1 => DateGroupCondition::Day,
+      2 => DateGroupCondition::Week,
+      3 => DateGroupCondition::Month,
+      4 => DateGroupCondition::Year,
+      _ => {
+        tracing::error!("Unsupported date group condition, fallback to Relative");
+        DateGroupCondition::Relative
+      },
+    }
+  }
+}
+
+impl DateGroupCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
+/// A group of a grouped view with the rows that belong to it, in the view's order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupData {
+  pub id: String,
+  pub name: String,
+  /// True for the group holding the rows without a value. Its id is the grouping field's id.
+  pub is_default: bool,
+  pub visible: bool,
+  pub row_ids: Vec<RowId>,
+}
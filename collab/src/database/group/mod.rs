@@ -0,0 +1,5 @@
+mod evaluator;
+mod group;
+
+pub use evaluator::*;
+pub use group::*;
@@ -1,13 +1,22 @@
+use crate::database::auto_number::{AutoNumberRange, AutoNumberReservations};
 use crate::entity::define::DATABASE_INLINE_VIEW;
-use crate::preclude::{Any, Map, MapRef, ReadTxn, TransactionMut};
+use crate::preclude::{Any, Map, MapPrelim, MapRef, Out, ReadTxn, TransactionMut};
 use std::ops::Deref;
 use tracing::error;
+use yrs::block::ClientID;
 
 pub struct MetaMap {
   container: MapRef,
 }
 
 pub const DATABASE_ROW_TEMPLATES: &str = "row_templates";
+pub const DATABASE_AUTO_NUMBERS: &str = "auto_numbers";
+
+/// The auto numbers issued by a client are stored under a key of their own, made of the field id
+/// and the client id, so that clients never write the same key.
+fn auto_number_key_prefix(field_id: &str) -> String {
+  format!("{}:{}:", DATABASE_AUTO_NUMBERS, field_id)
+}
 
 impl MetaMap {
   pub fn new(container: MapRef) -> Self {
@@ -49,6 +58,128 @@ impl MetaMap {
       .cast::<String>()
       .ok()
   }
+
+  /// Return the auto numbers issued by each client for the field.
+  pub fn get_auto_number_reservations<T: ReadTxn>(
+    &self,
+    txn: &T,
+    field_id: &str,
+  ) -> AutoNumberReservations {
+    let prefix = auto_number_key_prefix(field_id);
+    let mut reservations = AutoNumberReservations::new();
+    for (key, value) in self.container.iter(txn) {
+      let Some(client_id) = key
+        .strip_prefix(&prefix)
+        .and_then(|client_id| client_id.parse::<ClientID>().ok())
+      else {
+        continue;
+      };
+      match auto_number_ranges_from_value(txn, value) {
+        Some(ranges) => reservations.insert(client_id, ranges),
+        None => error!("Failed to parse the auto numbers of {}", key),
+      }
+    }
+    reservations
+  }
+
+  /// Set the range of auto numbers issued by the client for the field, replacing the range
+  /// with the same start. The other ranges of the client are left untouched.
+  pub fn set_auto_number_range(
+    &self,
+    txn: &mut TransactionMut,
+    field_id: &str,
+    client_id: ClientID,
+    range: AutoNumberRange,
+  ) {
+    let map_ref = self.get_or_init_auto_number_map(txn, field_id, client_id);
+    map_ref.insert(txn, range.start.to_string(), Any::BigInt(range.end));
+  }
+
+  /// Set the ranges of auto numbers issued by the client for the field. Only the ranges that
+  /// changed are written.
+  pub fn set_auto_number_ranges(
+    &self,
+    txn: &mut TransactionMut,
+    field_id: &str,
+    client_id: ClientID,
+    ranges: &[AutoNumberRange],
+  ) {
+    let map_ref = self.get_or_init_auto_number_map(txn, field_id, client_id);
+    let removed_starts = map_ref
+      .keys(txn)
+      .filter(|start| ranges.iter().all(|range| range.start.to_string() != *start))
+      .map(|start| start.to_string())
+      .collect::<Vec<_>>();
+    for start in removed_starts {
+      map_ref.remove(txn, &start);
+    }
+    for range in ranges {
+      let start = range.start.to_string();
+      let end = map_ref
+        .get(txn, &start)
+        .and_then(|end| end.cast::<i64>().ok());
+      if end != Some(range.end) {
+        map_ref.insert(txn, start, Any::BigInt(range.end));
+      }
+    }
+  }
+
+  /// Return the map of the ranges issued by the client for the field, which maps the start of
+  /// each range to its end. The ranges stored as a JSON string by older versions are moved into
+  /// the map.
+  fn get_or_init_auto_number_map(
+    &self,
+    txn: &mut TransactionMut,
+    field_id: &str,
+    client_id: ClientID,
+  ) -> MapRef {
+    let key = format!("{}{}", auto_number_key_prefix(field_id), client_id);
+    let value = self.container.get(txn, &key);
+    if let Some(Out::YMap(map_ref)) = value {
+      return map_ref;
+    }
+    let ranges = value
+      .and_then(|value| auto_number_ranges_from_value(txn, value))
+      .unwrap_or_default();
+    let map_ref: MapRef = self.container.insert(txn, key, MapPrelim::default());
+    for range in ranges {
+      map_ref.insert(txn, range.start.to_string(), Any::BigInt(range.end));
+    }
+    map_ref
+  }
+
+  /// Remove the auto numbers issued by every client for the field.
+  pub fn remove_auto_number_reservations(&self, txn: &mut TransactionMut, field_id: &str) {
+    let prefix = auto_number_key_prefix(field_id);
+    let keys = self
+      .container
+      .keys(txn)
+      .filter(|key| key.starts_with(&prefix))
+      .map(|key| key.to_string())
+      .collect::<Vec<_>>();
+    for key in keys {
+      self.container.remove(txn, &key);
+    }
+  }
+}
+
+fn auto_number_ranges_from_value<T: ReadTxn>(txn: &T, value: Out) -> Option<Vec<AutoNumberRange>> {
+  match value {
+    Out::YMap(map_ref) => {
+      let mut ranges = map_ref
+        .iter(txn)
+        .map(|(start, end)| {
+          Some(AutoNumberRange::new(
+            start.parse().ok()?,
+            end.cast::<i64>().ok()?,
+          ))
+        })
+        .collect::<Option<Vec<_>>>()?;
+      ranges.sort_by_key(|range| range.start);
+      Some(ranges)
+    },
+    value => serde_json::from_str(&value.cast::<String>().ok()?).ok(),
+  }
 }
 
 impl Deref for MetaMap {
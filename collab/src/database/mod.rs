@@ -1,15 +1,29 @@
 #![allow(clippy::module_inception)]
 
+pub mod auto_number;
 pub mod blocks;
+pub mod calculation;
+pub mod calendar;
+pub mod chart;
+pub mod conversion;
 pub mod database;
+pub mod database_duplicate;
 pub mod database_remapper;
 pub mod database_state;
 pub mod database_trait;
 pub mod entity;
 pub mod fields;
+pub mod filter;
+pub mod formula;
+pub mod group;
 pub mod meta;
+pub mod query;
+pub mod rollup;
 pub mod rows;
+pub mod sort;
 pub mod template;
+pub mod timeline;
 pub mod util;
+pub mod validation;
 pub mod views;
 pub mod workspace_database;
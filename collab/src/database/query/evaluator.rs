@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::{Field, type_option_cell_reader};
+use crate::database::filter::{Filter, FilterEvaluator};
+use crate::database::query::{DatabaseQuery, FieldRef, QueryCell, QueryPredicate, QueryRow};
+use crate::database::rows::Row;
+use crate::database::sort::{Sort, SortEvaluator};
+use crate::database::template::timestamp_parse::TimestampCellData;
+use crate::error::CollabError;
+
+/// A [DatabaseQuery] resolved against the fields of a database: its field references are
+/// replaced by field ids, its predicates by [Filter]s and its orders by [Sort]s.
+///
+/// The filters and sorts are evaluated by [FilterEvaluator] and [SortEvaluator], created for each
+/// call, so the evaluator can be kept across awaits.
+pub struct QueryEvaluator {
+  fields: Vec<Field>,
+  select: Vec<Field>,
+  filters: Vec<Filter>,
+  sorts: Vec<Sort>,
+}
+
+impl QueryEvaluator {
+  /// `fields` are the fields of the queried view, in the view's order. Return
+  /// [CollabError::DatabaseInvalidQuery] if a field reference doesn't match exactly one field, or
+  /// if a predicate's filter doesn't match the type of its field.
+  pub fn new(query: &DatabaseQuery, fields: Vec<Field>) -> Result<Self, CollabError> {
+    let select = if query.select.is_empty() {
+      fields.clone()
+    } else {
+      query
+        .select
+        .iter()
+        .map(|field_ref| resolve_field(&fields, field_ref).cloned())
+        .collect::<Result<Vec<_>, _>>()?
+    };
+    let filters = query
+      .predicates
+      .iter()
+      .enumerate()
+      .map(|(index, predicate)| resolve_predicate(&fields, predicate, index.to_string()))
+      .collect::<Result<Vec<_>, _>>()?;
+    let sorts = query
+      .order_by
+      .iter()
+      .enumerate()
+      .map(|(index, order)| {
+        let field = resolve_field(&fields, &order.field)?;
+        Ok(Sort::new(
+          index.to_string(),
+          field.id.clone(),
+          FieldType::from(field.field_type),
+          order.condition,
+        ))
+      })
+      .collect::<Result<Vec<_>, CollabError>>()?;
+    Ok(Self {
+      fields,
+      select,
+      filters,
+      sorts,
+    })
+  }
+
+  pub fn has_filters(&self) -> bool {
+    !self.filters.is_empty()
+  }
+
+  pub fn has_sorts(&self) -> bool {
+    !self.sorts.is_empty()
+  }
+
+  /// Keep the rows that satisfy the query's predicates, in their order.
+  pub fn filter_rows(&self, rows: Vec<Row>) -> Vec<Row> {
+    if !self.has_filters() {
+      return rows;
+    }
+    let evaluator = FilterEvaluator::new(self.filters.clone(), self.fields.clone());
+    rows
+      .into_iter()
+      .filter(|row| evaluator.is_row_visible(row))
+      .collect()
+  }
+
+  /// Sort the rows in place by the query's orders. Equal rows keep their order.
+  pub fn sort_rows(&self, rows: &mut Vec<Row>) {
+    SortEvaluator::new(self.sorts.clone(), self.fields.clone()).sort_rows(rows);
+  }
+
+  /// Merge the rows that satisfy the query's predicates into `first_rows`, keeping the first
+  /// `count` rows by the query's orders, then by their index in the view, that come after
+  /// `cursor`. Only the cells of the ordered fields are kept, so the rows must be loaded again to
+  /// be projected.
+  pub fn keep_first_rows(
+    &self,
+    first_rows: &mut Vec<(usize, Row)>,
+    rows: Vec<(usize, Row)>,
+    cursor: Option<&(usize, Row)>,
+    count: usize,
+  ) {
+    let filter_evaluator = self
+      .has_filters()
+      .then(|| FilterEvaluator::new(self.filters.clone(), self.fields.clone()));
+    let field_ids = self
+      .sorts
+      .iter()
+      .map(|sort| sort.field_id.as_str())
+      .collect::<HashSet<_>>();
+    let rows = rows
+      .into_iter()
+      .filter(|(_, row)| {
+        filter_evaluator
+          .as_ref()
+          .is_none_or(|evaluator| evaluator.is_row_visible(row))
+      })
+      .map(|(index, mut row)| {
+        row
+          .cells
+          .retain(|field_id, _| field_ids.contains(field_id.as_str()));
+        (index, row)
+      })
+      .collect();
+    SortEvaluator::new(self.sorts.clone(), self.fields.clone())
+      .keep_first_rows(first_rows, rows, cursor, count);
+  }
+
+  /// Return the selected fields of the rows as JSON values.
+  pub fn project_rows(&self, rows: &[Row]) -> Vec<QueryRow> {
+    let readers = self
+      .select
+      .iter()
+      .map(|field| {
+        let field_type = FieldType::from(field.field_type);
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        (
+          field,
+          field_type,
+          type_option_cell_reader(type_option, &field_type),
+        )
+      })
+      .collect::<Vec<_>>();
+    rows
+      .iter()
+      .map(|row| QueryRow {
+        row_id: row.id,
+        cells: readers
+          .iter()
+          .map(|(field, field_type, reader)| {
+            let timestamp_cell = match field_type {
+              FieldType::CreatedTime => Some(row.created_at),
+              FieldType::LastEditedTime => Some(row.modified_at),
+              _ => None,
+            }
+            .map(|timestamp| TimestampCellData::new(timestamp).to_cell(*field_type));
+            let value = timestamp_cell
+              .as_ref()
+              .or_else(|| row.cells.get(&field.id))
+              .map(|cell| reader.json_cell(cell))
+              .unwrap_or(Value::Null);
+            QueryCell {
+              field_id: field.id.clone(),
+              value,
+            }
+          })
+          .collect(),
+      })
+      .collect()
+  }
+}
+
+fn resolve_field<'a>(fields: &'a [Field], field_ref: &FieldRef) -> Result<&'a Field, CollabError> {
+  let (mut matches, description) = match field_ref {
+    FieldRef::Id(field_id) => (
+      fields
+        .iter()
+        .filter(|field| &field.id == field_id)
+        .collect::<Vec<_>>(),
+      format!("id {}", field_id),
+    ),
+    FieldRef::Name(name) => (
+      fields
+        .iter()
+        .filter(|field| &field.name == name)
+        .collect::<Vec<_>>(),
+      format!("name {}", name),
+    ),
+  };
+  match matches.len() {
+    1 => Ok(matches.remove(0)),
+    0 => Err(CollabError::DatabaseInvalidQuery(format!(
+      "No field with {}",
+      description
+    ))),
+    _ => Err(CollabError::DatabaseInvalidQuery(format!(
+      "Several fields with {}",
+      description
+    ))),
+  }
+}
+
+fn resolve_predicate(
+  fields: &[Field],
+  predicate: &QueryPredicate,
+  id: String,
+) -> Result<Filter, CollabError> {
+  let resolve_children = |children: &[QueryPredicate]| {
+    children
+      .iter()
+      .enumerate()
+      .map(|(index, child)| resolve_predicate(fields, child, format!("{}-{}", id, index)))
+      .collect::<Result<Vec<_>, _>>()
+  };
+  match predicate {
+    QueryPredicate::Field { field, filter } => {
+      let field = resolve_field(fields, field)?;
+      let field_type = FieldType::from(field.field_type);
+      if !filter.is_for_field_type(&field_type) {
+        return Err(CollabError::DatabaseInvalidQuery(format!(
+          "The filter of field {} doesn't match its type {:?}",
+          field.id, field_type
+        )));
+      }
+      Ok(Filter::new_data(
+        id,
+        field.id.clone(),
+        field_type,
+        filter.clone(),
+      ))
+    },
+    QueryPredicate::And(children) => Ok(Filter::new_and(id.clone(), resolve_children(children)?)),
+    QueryPredicate::Or(children) => Ok(Filter::new_or(id.clone(), resolve_children(children)?)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::filter::{NumberFilterCondition, TextFilterCondition};
+
+  fn field(id: &str, name: &str) -> Field {
+    Field::new(
+      id.to_string(),
+      name.to_string(),
+      FieldType::RichText.into(),
+      false,
+    )
+  }
+
+  #[test]
+  fn resolve_field_test() {
+    let fields = vec![
+      field("a", "Title"),
+      field("b", "Title"),
+      field("c", "Notes"),
+    ];
+    assert_eq!(resolve_field(&fields, &FieldRef::id("b")).unwrap().id, "b");
+    assert_eq!(
+      resolve_field(&fields, &FieldRef::name("Notes")).unwrap().id,
+      "c"
+    );
+    // A name shared by several fields is ambiguous
+    assert!(matches!(
+      resolve_field(&fields, &FieldRef::name("Title")),
+      Err(CollabError::DatabaseInvalidQuery(_))
+    ));
+    assert!(matches!(
+      resolve_field(&fields, &FieldRef::id("d")),
+      Err(CollabError::DatabaseInvalidQuery(_))
+    ));
+  }
+
+  #[test]
+  fn select_every_field_by_default_test() {
+    let fields = vec![field("a", "Title"), field("c", "Notes")];
+    let evaluator = QueryEvaluator::new(&DatabaseQuery::new(), fields).unwrap();
+    let select = evaluator
+      .select
+      .iter()
+      .map(|field| field.id.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(select, vec!["a", "c"]);
+    assert!(!evaluator.has_filters());
+    assert!(!evaluator.has_sorts());
+  }
+
+  #[test]
+  fn filter_must_match_field_type_test() {
+    let fields = vec![
+      field("a", "Title"),
+      Field::new(
+        "b".to_string(),
+        "Amount".to_string(),
+        FieldType::Number.into(),
+        false,
+      ),
+    ];
+    let query = DatabaseQuery::new().with_predicate(QueryPredicate::or(vec![
+      QueryPredicate::text(FieldRef::name("Title"), TextFilterCondition::Contains, "a"),
+      QueryPredicate::number(
+        FieldRef::name("Amount"),
+        NumberFilterCondition::GreaterThan,
+        1.0,
+      ),
+    ]));
+    assert!(QueryEvaluator::new(&query, fields.clone()).is_ok());
+
+    // A text filter on a number field
+    let query = DatabaseQuery::new().with_predicate(QueryPredicate::and(vec![
+      QueryPredicate::text(FieldRef::name("Title"), TextFilterCondition::Contains, "a"),
+      QueryPredicate::text(FieldRef::name("Amount"), TextFilterCondition::Contains, "1"),
+    ]));
+    assert!(matches!(
+      QueryEvaluator::new(&query, fields),
+      Err(CollabError::DatabaseInvalidQuery(_))
+    ));
+  }
+}
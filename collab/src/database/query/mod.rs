@@ -0,0 +1,5 @@
+mod evaluator;
+mod query;
+
+pub use evaluator::*;
+pub use query::*;
@@ -0,0 +1,190 @@
+use serde_json::Value;
+
+use crate::database::filter::{
+  CheckboxFilter, CheckboxFilterCondition, FieldFilter, NumberFilter, NumberFilterCondition,
+  SelectOptionFilter, SelectOptionFilterCondition, TextFilter, TextFilterCondition,
+};
+use crate::database::sort::SortCondition;
+use crate::entity::uuid_validation::RowId;
+
+/// A field of a [DatabaseQuery], referred to by id or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldRef {
+  Id(String),
+  /// A name shared by several fields is ambiguous, and fails the query.
+  Name(String),
+}
+
+impl FieldRef {
+  pub fn id(field_id: impl ToString) -> Self {
+    Self::Id(field_id.to_string())
+  }
+
+  pub fn name(name: impl ToString) -> Self {
+    Self::Name(name.to_string())
+  }
+}
+
+/// A condition the rows of a [DatabaseQuery] must satisfy. It's resolved into a
+/// [Filter](crate::database::filter::Filter) on the field's current type, so the filter must
+/// match the field type: a text filter on a number field fails the query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryPredicate {
+  Field {
+    field: FieldRef,
+    filter: FieldFilter,
+  },
+  And(Vec<QueryPredicate>),
+  Or(Vec<QueryPredicate>),
+}
+
+impl QueryPredicate {
+  pub fn new(field: FieldRef, filter: FieldFilter) -> Self {
+    Self::Field { field, filter }
+  }
+
+  pub fn text(field: FieldRef, condition: TextFilterCondition, content: impl ToString) -> Self {
+    Self::new(
+      field,
+      FieldFilter::Text(TextFilter {
+        condition,
+        content: content.to_string(),
+      }),
+    )
+  }
+
+  pub fn number(field: FieldRef, condition: NumberFilterCondition, value: f64) -> Self {
+    Self::new(
+      field,
+      FieldFilter::Number(NumberFilter {
+        condition,
+        content: value.to_string(),
+      }),
+    )
+  }
+
+  pub fn select_option(
+    field: FieldRef,
+    condition: SelectOptionFilterCondition,
+    option_ids: Vec<String>,
+  ) -> Self {
+    Self::new(
+      field,
+      FieldFilter::SelectOption(SelectOptionFilter {
+        condition,
+        option_ids,
+      }),
+    )
+  }
+
+  pub fn checkbox(field: FieldRef, is_checked: bool) -> Self {
+    let condition = if is_checked {
+      CheckboxFilterCondition::IsChecked
+    } else {
+      CheckboxFilterCondition::IsUnchecked
+    };
+    Self::new(field, FieldFilter::Checkbox(CheckboxFilter { condition }))
+  }
+
+  pub fn and(children: Vec<QueryPredicate>) -> Self {
+    Self::And(children)
+  }
+
+  pub fn or(children: Vec<QueryPredicate>) -> Self {
+    Self::Or(children)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryOrder {
+  pub field: FieldRef,
+  pub condition: SortCondition,
+}
+
+/// A query over the rows of a database, run by
+/// [Database::query_rows](crate::database::database::Database::query_rows).
+///
+/// The query reads the rows of a view, or of the inline view when no view is set, in the view's
+/// row order. The view's own filters and sorts aren't applied. The rows must satisfy all the
+/// predicates, and are ordered by the first order, whose ties are broken by the next ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseQuery {
+  pub view_id: Option<String>,
+  /// The fields of the returned rows. Empty returns every field of the view, in its order.
+  pub select: Vec<FieldRef>,
+  pub predicates: Vec<QueryPredicate>,
+  pub order_by: Vec<QueryOrder>,
+  /// The maximum number of rows of a page. None returns every row in one page.
+  pub limit: Option<usize>,
+  /// The [QueryPage::next_cursor] of the previous page: the rows up to this one are skipped.
+  pub cursor: Option<RowId>,
+}
+
+impl DatabaseQuery {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_view_id(mut self, view_id: impl ToString) -> Self {
+    self.view_id = Some(view_id.to_string());
+    self
+  }
+
+  pub fn with_select(mut self, fields: Vec<FieldRef>) -> Self {
+    self.select = fields;
+    self
+  }
+
+  pub fn with_predicate(mut self, predicate: QueryPredicate) -> Self {
+    self.predicates.push(predicate);
+    self
+  }
+
+  pub fn with_order_by(mut self, field: FieldRef, condition: SortCondition) -> Self {
+    self.order_by.push(QueryOrder { field, condition });
+    self
+  }
+
+  pub fn with_limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+
+  pub fn with_cursor(mut self, cursor: RowId) -> Self {
+    self.cursor = Some(cursor);
+    self
+  }
+}
+
+/// The value of a selected field in a [QueryRow], as returned by
+/// [TypeOptionCellReader::json_cell](crate::database::fields::TypeOptionCellReader::json_cell).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryCell {
+  pub field_id: String,
+  /// Null when the row has no cell for the field.
+  pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryRow {
+  pub row_id: RowId,
+  /// The selected fields, in the order of the query's selection.
+  pub cells: Vec<QueryCell>,
+}
+
+impl QueryRow {
+  pub fn get(&self, field_id: &str) -> Option<&Value> {
+    self
+      .cells
+      .iter()
+      .find(|cell| cell.field_id == field_id)
+      .map(|cell| &cell.value)
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryPage {
+  pub rows: Vec<QueryRow>,
+  /// Set when more rows follow: pass it to [DatabaseQuery::with_cursor] to read the next page.
+  pub next_cursor: Option<RowId>,
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use crate::database::calculation::{Calculation, CalculationEvaluator, CalculationType};
+use crate::database::entity::FieldType;
+use crate::database::fields::rollup_type_option::{RollupDisplayMode, RollupTypeOption};
+use crate::database::fields::{Field, TypeOptionCellWriter, type_option_cell_reader};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::entity::CELL_DATA;
+
+/// Computes the value of a rollup cell from the related rows, read through the target field.
+///
+/// With [RollupDisplayMode::Calculated] the type option's calculation type is applied with
+/// [CalculationEvaluator], so it follows the same semantics as the view footers. The list modes
+/// join the non-empty target values with ", ".
+///
+/// When the type option has a `condition_value`, only the related rows whose target value is
+/// equal to it, ignoring case, are rolled up.
+pub struct RollupEvaluator {
+  type_option: RollupTypeOption,
+  target_field: Field,
+}
+
+impl RollupEvaluator {
+  pub fn new(type_option: RollupTypeOption, target_field: Field) -> Self {
+    Self {
+      type_option,
+      target_field,
+    }
+  }
+
+  /// Return the rollup value of the related rows.
+  pub fn evaluate(&self, related_rows: &[Row]) -> String {
+    let field_type = FieldType::from(self.target_field.field_type);
+    let reader = type_option_cell_reader(
+      self
+        .target_field
+        .get_any_type_option(field_type.type_id())
+        .unwrap_or_default(),
+      &field_type,
+    );
+    let empty_cell = Cell::new();
+    let condition = self.type_option.condition_value.trim().to_lowercase();
+    let rows = related_rows
+      .iter()
+      .map(|row| {
+        let cell = row.cells.get(&self.target_field.id).unwrap_or(&empty_cell);
+        let text = if cell.contains_key(CELL_DATA) {
+          reader.stringify_cell(cell)
+        } else {
+          String::new()
+        };
+        (row, text)
+      })
+      .filter(|(_, text)| condition.is_empty() || text.trim().to_lowercase() == condition)
+      .collect::<Vec<_>>();
+
+    match self.type_option.show_as {
+      RollupDisplayMode::Calculated => {
+        let calculation = Calculation::new(
+          String::new(),
+          self.target_field.id.clone(),
+          CalculationType::from(self.type_option.calculation_type),
+        );
+        let mut evaluator =
+          CalculationEvaluator::new(vec![calculation], vec![self.target_field.clone()]);
+        evaluator.add_rows(rows.iter().map(|(row, _)| *row));
+        evaluator
+          .result(&self.target_field.id)
+          .map(|calculation| calculation.value)
+          .unwrap_or_default()
+      },
+      RollupDisplayMode::OriginalList => rows
+        .into_iter()
+        .map(|(_, text)| text)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(", "),
+      RollupDisplayMode::UniqueList => {
+        let mut seen = HashSet::new();
+        rows
+          .into_iter()
+          .map(|(_, text)| text)
+          .filter(|text| !text.is_empty() && seen.insert(text.clone()))
+          .collect::<Vec<_>>()
+          .join(", ")
+      },
+    }
+  }
+
+  /// Return the rollup cell holding the value of the related rows.
+  pub fn evaluate_cell(&self, related_rows: &[Row]) -> Cell {
+    rollup_cell(&self.type_option, self.evaluate(related_rows))
+  }
+}
+
+pub(crate) fn rollup_cell(type_option: &RollupTypeOption, value: String) -> Cell {
+  type_option.convert_json_to_cell(serde_json::Value::String(value))
+}
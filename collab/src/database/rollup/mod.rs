@@ -0,0 +1,5 @@
+mod evaluator;
+mod resolver;
+
+pub use evaluator::*;
+pub(crate) use resolver::*;
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tracing::{error, warn};
+
+use crate::database::database::Database;
+use crate::database::entity::FieldType;
+use crate::database::fields::relation_type_option::RelationTypeOption;
+use crate::database::fields::rollup_type_option::RollupTypeOption;
+use crate::database::fields::{Field, type_option_cell_reader};
+use crate::database::formula::{FormulaValue, field_value, find_field};
+use crate::database::rollup::{RollupEvaluator, rollup_cell};
+use crate::database::rows::Row;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::views::RowOrder;
+use crate::entity::uuid_validation::RowId;
+
+type ResolveFuture<'b> = Pin<Box<dyn Future<Output = Option<String>> + Send + 'b>>;
+
+/// Resolves rollup cells across databases: it follows the relation cell's row ids into the
+/// related database, opened through the database's [DatabaseCollabService], and rolls up the
+/// target field with [RollupEvaluator].
+///
+/// When the target field is itself a rollup, its value is resolved again instead of trusting
+/// the stored cell. A rollup cell that depends on itself through a cycle of relations can't be
+/// resolved and yields None.
+///
+/// [DatabaseCollabService]: crate::database::database_trait::DatabaseCollabService
+pub(crate) struct RollupResolver<'a> {
+  database: &'a Database,
+  database_id: String,
+  /// Databases opened while resolving, None if the database couldn't be opened.
+  related_databases: HashMap<String, Option<Arc<Database>>>,
+  auto_fetch: bool,
+}
+
+impl<'a> RollupResolver<'a> {
+  pub(crate) fn new(database: &'a Database, auto_fetch: bool) -> Self {
+    let database_id = database
+      .get_database_id()
+      .map(|database_id| database_id.to_string())
+      .unwrap_or_default();
+    Self {
+      database,
+      database_id,
+      related_databases: HashMap::new(),
+      auto_fetch,
+    }
+  }
+
+  /// Return the value of the row's rollup field, or None if it can't be resolved, e.g. when
+  /// the related database can't be opened or the rollups form a cycle.
+  pub(crate) async fn resolve(&mut self, field: &Field, row: &Row) -> Option<String> {
+    let mut visiting = HashSet::new();
+    self.resolve_in(None, field, row, &mut visiting).await
+  }
+
+  /// Resolve the rollup field of a row of `current`, or of the root database when None.
+  fn resolve_in<'b>(
+    &'b mut self,
+    current: Option<Arc<Database>>,
+    field: &'b Field,
+    row: &'b Row,
+    visiting: &'b mut HashSet<(String, String, RowId)>,
+  ) -> ResolveFuture<'b> {
+    Box::pin(async move {
+      let root = self.database;
+      let database = current.as_deref().unwrap_or(root);
+      let database_id = database.get_database_id().ok()?.to_string();
+      let key = (database_id.clone(), field.id.clone(), row.id);
+      if !visiting.insert(key.clone()) {
+        warn!(
+          "Rollup field {} of row {} in database {} depends on itself",
+          field.id, row.id, database_id
+        );
+        return None;
+      }
+
+      let type_option = RollupTypeOption::from(
+        field
+          .get_any_type_option(FieldType::Rollup.type_id())
+          .unwrap_or_default(),
+      );
+      let relation_field = database.get_field(&type_option.relation_field_id)?;
+      let related_database_id = RelationTypeOption::from(
+        relation_field
+          .get_any_type_option(FieldType::Relation.type_id())
+          .unwrap_or_default(),
+      )
+      .database_id;
+      let related = if related_database_id == database_id {
+        current.clone()
+      } else {
+        self.open_database(&related_database_id).await?
+      };
+      let related_database = related.as_deref().unwrap_or(root);
+      let target_field = related_database.get_field(&type_option.target_field_id)?;
+
+      let mut related_rows = related_rows(
+        related_database,
+        row,
+        &type_option.relation_field_id,
+        self.auto_fetch,
+      )
+      .await?;
+
+      if FieldType::from(target_field.field_type) == FieldType::Rollup {
+        let target_type_option = RollupTypeOption::from(
+          target_field
+            .get_any_type_option(FieldType::Rollup.type_id())
+            .unwrap_or_default(),
+        );
+        for index in 0..related_rows.len() {
+          let value = self
+            .resolve_in(
+              related.clone(),
+              &target_field,
+              &related_rows[index],
+              visiting,
+            )
+            .await?;
+          related_rows[index].cells.insert(
+            target_field.id.clone(),
+            rollup_cell(&target_type_option, value),
+          );
+        }
+      }
+
+      visiting.remove(&key);
+      Some(RollupEvaluator::new(type_option, target_field).evaluate(&related_rows))
+    })
+  }
+
+  /// Return the values of the target field, given by id or name, of the rows related to the
+  /// row through a relation field of the root database. Used by the formula aggregates.
+  pub(crate) async fn related_values(
+    &mut self,
+    relation_field_id: &str,
+    target: &str,
+    row: &Row,
+  ) -> Option<Vec<FormulaValue>> {
+    let relation_field = self.database.get_field(relation_field_id)?;
+    let related_database_id = RelationTypeOption::from(
+      relation_field
+        .get_any_type_option(FieldType::Relation.type_id())
+        .unwrap_or_default(),
+    )
+    .database_id;
+    let related = self.open_database(&related_database_id).await?;
+    let related_database = related.as_deref().unwrap_or(self.database);
+    let target_field = find_field(&related_database.get_all_fields(), target)?.clone();
+    let related_rows =
+      related_rows(related_database, row, relation_field_id, self.auto_fetch).await?;
+
+    let field_type = FieldType::from(target_field.field_type);
+    let reader = type_option_cell_reader(
+      target_field
+        .get_any_type_option(field_type.type_id())
+        .unwrap_or_default(),
+      &field_type,
+    );
+    Some(
+      related_rows
+        .iter()
+        .map(|related_row| field_value(&target_field, reader.as_ref(), related_row))
+        .collect(),
+    )
+  }
+
+  /// Return the primary field values of the rows related to the row through a relation field of
+  /// the root database, e.g. the titles of the related rows. Used when converting a relation
+  /// field to another type.
+  pub(crate) async fn related_titles(
+    &mut self,
+    relation_field_id: &str,
+    row: &Row,
+  ) -> Option<Vec<String>> {
+    let relation_field = self.database.get_field(relation_field_id)?;
+    let related_database_id = RelationTypeOption::from(
+      relation_field
+        .get_any_type_option(FieldType::Relation.type_id())
+        .unwrap_or_default(),
+    )
+    .database_id;
+    let related = self.open_database(&related_database_id).await?;
+    let primary_field = related
+      .as_deref()
+      .unwrap_or(self.database)
+      .get_primary_field()?;
+    let values = self
+      .related_values(relation_field_id, &primary_field.id, row)
+      .await?;
+    Some(values.iter().map(FormulaValue::to_text).collect())
+  }
+
+  /// Return the related database, None for the root database. The outer None means the
+  /// database couldn't be opened.
+  async fn open_database(&mut self, database_id: &str) -> Option<Option<Arc<Database>>> {
+    if database_id == self.database_id {
+      return Some(None);
+    }
+    if let Some(database) = self.related_databases.get(database_id) {
+      return database.clone().map(Some);
+    }
+
+    let database = match Database::open(database_id, self.database.context()).await {
+      Ok(database) => Some(Arc::new(database)),
+      Err(err) => {
+        error!("Failed to open related database {}: {}", database_id, err);
+        None
+      },
+    };
+    self
+      .related_databases
+      .insert(database_id.to_string(), database.clone());
+    database.map(Some)
+  }
+}
+
+/// Load the rows of the database that the row's relation cell points to, in the cell's order.
+async fn related_rows(
+  database: &Database,
+  row: &Row,
+  relation_field_id: &str,
+  auto_fetch: bool,
+) -> Option<Vec<Row>> {
+  let row_ids = row
+    .cells
+    .get(relation_field_id)
+    .map(|cell| RelationCellData::from(cell).row_ids)
+    .unwrap_or_default();
+  let row_orders = row_ids
+    .iter()
+    .map(|row_id| RowOrder::new(*row_id, 0))
+    .collect::<Vec<_>>();
+  let mut rows_by_id = database.get_rows_by_id(row_orders, auto_fetch).await.ok()?;
+  Some(
+    row_ids
+      .iter()
+      .flat_map(|row_id| rows_by_id.remove(row_id))
+      .collect(),
+  )
+}
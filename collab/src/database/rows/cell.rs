@@ -45,6 +45,12 @@ impl<'a, 'b> CellsUpdate<'a, 'b> {
 
     self
   }
+
+  /// Remove the cell, as if it was never set
+  pub fn remove(self, key: &str) -> Self {
+    self.map_ref.remove(self.txn, key);
+    self
+  }
 }
 
 pub type Cell = HashMap<String, Any>;
@@ -8,6 +8,8 @@ use crate::preclude::{
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 #[cfg(feature = "verbose_log")]
@@ -21,10 +23,11 @@ use crate::util::AnyExt;
 
 use crate::database::database::timestamp;
 
+use super::row_history::{RowHistory, subscribe_row_history};
 use super::row_observer::subscribe_row_comment_change;
 use crate::database::rows::{
-  Cell, Cells, CellsUpdate, RowChangeSender, RowMeta, RowMetaUpdate, subscribe_row_data_change,
-  subscribe_row_meta_change,
+  Cell, CellChange, Cells, CellsUpdate, RowActivity, RowChangeSender, RowHistoryStore, RowMeta,
+  RowMetaUpdate, subscribe_row_data_change, subscribe_row_meta_change,
 };
 use crate::error::CollabError;
 
@@ -51,6 +54,10 @@ pub struct DatabaseRow {
   pub row_id: RowId,
   pub collab: Collab,
   pub body: DatabaseRowBody,
+  /// Set by [DatabaseRow::enable_history].
+  history: Option<RowHistory>,
+  /// Set while [DatabaseRow::update_in_batch] writes the row.
+  in_batch: Arc<AtomicBool>,
 }
 
 pub fn default_database_row_from_row(row: Row, client_id: ClientID) -> EncodedCollab {
@@ -94,11 +101,18 @@ impl DatabaseRow {
       row_id,
       change_tx.is_some()
     );
+    let in_batch = Arc::new(AtomicBool::new(false));
     if let Some(change_tx) = change_tx {
       let origin = collab.origin().clone();
       let meta_change_tx = change_tx.clone();
       let comment_change_tx = change_tx.clone();
-      subscribe_row_data_change(origin.clone(), row_id, &body.data, change_tx);
+      subscribe_row_data_change(
+        origin.clone(),
+        row_id,
+        &body.data,
+        change_tx,
+        in_batch.clone(),
+      );
       subscribe_row_meta_change(origin.clone(), row_id, &body.meta, meta_change_tx);
       subscribe_row_comment_change(origin, row_id, &body.comments, comment_change_tx);
     }
@@ -106,6 +120,8 @@ impl DatabaseRow {
       row_id,
       collab,
       body,
+      history: None,
+      in_batch,
     })
   }
 
@@ -116,11 +132,18 @@ impl DatabaseRow {
     row: Row,
   ) -> Self {
     let body = DatabaseRowBody::create(row_id, &mut collab, row);
+    let in_batch = Arc::new(AtomicBool::new(false));
     if let Some(change_tx) = change_tx {
       let origin = collab.origin().clone();
       let meta_change_tx = change_tx.clone();
       let comment_change_tx = change_tx.clone();
-      subscribe_row_data_change(origin.clone(), row_id, &body.data, change_tx);
+      subscribe_row_data_change(
+        origin.clone(),
+        row_id,
+        &body.data,
+        change_tx,
+        in_batch.clone(),
+      );
       subscribe_row_meta_change(origin.clone(), row_id, &body.meta, meta_change_tx);
       subscribe_row_comment_change(origin, row_id, &body.comments, comment_change_tx);
     }
@@ -128,6 +151,8 @@ impl DatabaseRow {
       row_id,
       collab,
       body,
+      history: None,
+      in_batch,
     }
   }
 
@@ -187,6 +212,20 @@ impl DatabaseRow {
     };
   }
 
+  /// Like [DatabaseRow::update], but the [RowChange::DidUpdateCell] of the changed cells have
+  /// their `in_batch` set, as the caller sends a [RowChange::DidUpdateRows] for the batch.
+  ///
+  /// [RowChange::DidUpdateCell]: crate::database::rows::RowChange::DidUpdateCell
+  /// [RowChange::DidUpdateRows]: crate::database::rows::RowChange::DidUpdateRows
+  pub fn update_in_batch<F>(&mut self, f: F)
+  where
+    F: FnOnce(RowUpdate),
+  {
+    self.in_batch.store(true, Ordering::Relaxed);
+    self.update(f);
+    self.in_batch.store(false, Ordering::Relaxed);
+  }
+
   pub fn update_meta<F>(&mut self, f: F)
   where
     F: FnOnce(RowMetaUpdate),
@@ -290,6 +329,57 @@ impl DatabaseRow {
     let txn = self.collab.transact();
     self.body.get_row_reactions(&txn)
   }
+
+  // ==================== History Methods ====================
+
+  /// Record the changes of the row's cells into the store from now on. The history is only
+  /// recorded for the rows it's enabled on, and is kept by the store rather than in the row's
+  /// document. Enabling it again replaces the store.
+  pub fn enable_history(&mut self, store: Arc<dyn RowHistoryStore>) {
+    let cells = self.body.cells(&self.collab.transact()).unwrap_or_default();
+    let history = RowHistory::new(self.row_id, cells, store);
+    subscribe_row_history(
+      self.collab.origin().clone(),
+      self.collab.client_id(),
+      &self.body.data,
+      history.clone(),
+    );
+    self.history = Some(history);
+  }
+
+  /// Get the recorded changes of the row's cells, oldest first. Empty if the history isn't
+  /// enabled, see [DatabaseRow::enable_history].
+  pub fn get_row_history(&self) -> Vec<CellChange> {
+    self.field_changes(|_| true)
+  }
+
+  /// Get the recorded changes of a field's cell, oldest first
+  pub fn get_field_history(&self, field_id: &str) -> Vec<CellChange> {
+    self.field_changes(|change_field_id| change_field_id == field_id)
+  }
+
+  fn field_changes<F>(&self, filter: F) -> Vec<CellChange>
+  where
+    F: Fn(&str) -> bool,
+  {
+    self
+      .history
+      .as_ref()
+      .map(|history| history.changes(self.collab.user_data(), filter))
+      .unwrap_or_default()
+  }
+
+  /// Get the comments and the cell changes of the row, ordered by time
+  pub fn get_row_activity(&self) -> Vec<RowActivity> {
+    let mut activity = self
+      .get_row_history()
+      .into_iter()
+      .map(RowActivity::CellChange)
+      .chain(self.get_comments().into_iter().map(RowActivity::Comment))
+      .collect::<Vec<_>>();
+    activity.sort_by_key(|activity| activity.timestamp());
+    activity
+  }
 }
 
 impl Deref for DatabaseRow {
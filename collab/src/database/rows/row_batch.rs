@@ -0,0 +1,117 @@
+use crate::database::rows::{Cell, Cells, CellsUpdate};
+use crate::entity::uuid_validation::RowId;
+use crate::error::CollabError;
+
+/// The number of rows loaded or created at once by the batch operations of a database.
+pub const DEFAULT_ROW_BATCH_CHUNK_SIZE: usize = 100;
+
+/// A change of one cell of a [RowPatch].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellPatch {
+  /// Write the keys of the cell, keeping the other keys of the existing cell, like
+  /// [CellsUpdate::insert_cell].
+  Insert(Cell),
+  /// Replace the existing cell.
+  Replace(Cell),
+  /// Remove the cell, as if it was never set.
+  Remove,
+}
+
+/// The changes of the cells of a row, applied by
+/// [Database::update_rows](crate::database::database::Database::update_rows).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowPatch {
+  pub row_id: RowId,
+  /// The changes, by field id, applied in order.
+  pub cells: Vec<(String, CellPatch)>,
+}
+
+impl RowPatch {
+  pub fn new(row_id: RowId) -> Self {
+    Self {
+      row_id,
+      cells: vec![],
+    }
+  }
+
+  pub fn with_cell(mut self, field_id: impl ToString, cell: Cell) -> Self {
+    self
+      .cells
+      .push((field_id.to_string(), CellPatch::Insert(cell)));
+    self
+  }
+
+  pub fn with_replaced_cell(mut self, field_id: impl ToString, cell: Cell) -> Self {
+    self
+      .cells
+      .push((field_id.to_string(), CellPatch::Replace(cell)));
+    self
+  }
+
+  pub fn with_removed_cell(mut self, field_id: impl ToString) -> Self {
+    self.cells.push((field_id.to_string(), CellPatch::Remove));
+    self
+  }
+
+  /// Return the ids of the fields whose cells are changed.
+  pub(crate) fn field_ids(&self) -> impl Iterator<Item = &str> {
+    self.cells.iter().map(|(field_id, _)| field_id.as_str())
+  }
+
+  /// Apply the changes to a copy of the row's cells, the way [RowPatch::apply] changes the row.
+  pub(crate) fn apply_to_cells(&self, cells: &mut Cells) {
+    for (field_id, patch) in &self.cells {
+      match patch {
+        CellPatch::Insert(cell) => cells
+          .entry(field_id.clone())
+          .or_default()
+          .extend(cell.clone()),
+        CellPatch::Replace(cell) => {
+          cells.insert(field_id.clone(), cell.clone());
+        },
+        CellPatch::Remove => {
+          cells.remove(field_id);
+        },
+      }
+    }
+  }
+
+  pub(crate) fn apply<'a, 'b>(self, update: CellsUpdate<'a, 'b>) -> CellsUpdate<'a, 'b> {
+    self
+      .cells
+      .into_iter()
+      .fold(update, |update, (field_id, patch)| match patch {
+        CellPatch::Insert(cell) => update.insert_cell(&field_id, cell),
+        CellPatch::Replace(cell) => update.clear(&field_id).insert_cell(&field_id, cell),
+        CellPatch::Remove => update.remove(&field_id),
+      })
+  }
+}
+
+/// A row a batch operation couldn't create, update or remove.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowBatchFailure {
+  pub row_id: RowId,
+  pub reason: String,
+}
+
+/// What a batch operation on rows did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowBatchReport {
+  /// The rows that were created, updated or removed, in the order they were given.
+  pub row_ids: Vec<RowId>,
+  pub failures: Vec<RowBatchFailure>,
+}
+
+impl RowBatchReport {
+  pub fn is_success(&self) -> bool {
+    self.failures.is_empty()
+  }
+
+  pub(crate) fn fail(&mut self, row_id: RowId, error: &CollabError) {
+    self.failures.push(RowBatchFailure {
+      row_id,
+      reason: error.to_string(),
+    });
+  }
+}
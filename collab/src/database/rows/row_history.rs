@@ -0,0 +1,447 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::origin::CollabOrigin;
+use crate::database::database::timestamp;
+use crate::database::rows::{
+  CREATED_AT, Cell, Cells, LAST_MODIFIED, ROW_CELLS, RowComment, cell_from_map_ref,
+};
+use crate::entity::uuid_validation::RowId;
+use crate::preclude::{
+  ClientID, DeepObservable, Event, Map, MapExt, MapRef, PathSegment, PermanentUserData,
+  TransactionMut,
+};
+use crate::util::AnyMapExt;
+
+/// The number of cell changes a [MemoryRowHistoryStore] keeps for a row. The oldest changes are
+/// dropped first.
+pub const DEFAULT_ROW_HISTORY_CAPACITY: usize = 256;
+
+/// A change of a row's cell, derived from an update of the row's collab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange {
+  pub row_id: RowId,
+  pub field_id: String,
+  /// None when the cell didn't exist before the change.
+  pub old_value: Option<Cell>,
+  /// None when the cell was removed.
+  pub new_value: Option<Cell>,
+  /// The user who made the change, as described by the row's [PermanentUserData], or the uid of
+  /// the change's origin when the collab doesn't remember its users.
+  pub author: Option<String>,
+  /// The cell's last modified time, or the time the change was received for a removed cell.
+  pub timestamp: i64,
+  pub is_local_change: bool,
+}
+
+/// An entry of a row's activity feed, which shows the changes of its cells next to its comments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowActivity {
+  Comment(RowComment),
+  CellChange(CellChange),
+}
+
+impl RowActivity {
+  pub fn timestamp(&self) -> i64 {
+    match self {
+      RowActivity::Comment(comment) => comment.created_at,
+      RowActivity::CellChange(change) => change.timestamp,
+    }
+  }
+}
+
+/// A change of a row's cell as kept by a [RowHistoryStore]. The author isn't stored, because the
+/// [PermanentUserData] may learn the user of a client after its changes: it's resolved from the
+/// client id or the uid when the history is read, see [CellChange::author].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RowHistoryEntry {
+  pub field_id: String,
+  pub old_value: Option<Cell>,
+  pub new_value: Option<Cell>,
+  pub timestamp: i64,
+  pub is_local_change: bool,
+  pub client_id: Option<ClientID>,
+  pub uid: Option<i64>,
+}
+
+/// Keeps the history of the rows outside of their documents, so the history doesn't grow the
+/// rows' documents and outlives an open row. Implement it on top of the application's storage to
+/// keep the history across restarts, and give it to the rows with
+/// [DatabaseRow::enable_history](crate::database::rows::DatabaseRow::enable_history).
+pub trait RowHistoryStore: Send + Sync + 'static {
+  /// Append the entry to the history of the row.
+  fn append(&self, row_id: &RowId, entry: RowHistoryEntry);
+
+  /// Return the history of the row, oldest first.
+  fn entries(&self, row_id: &RowId) -> Vec<RowHistoryEntry>;
+}
+
+/// A [RowHistoryStore] that keeps the last entries of each row in memory, until the store is
+/// dropped.
+pub struct MemoryRowHistoryStore {
+  entries: Mutex<HashMap<RowId, VecDeque<RowHistoryEntry>>>,
+  capacity: usize,
+}
+
+impl MemoryRowHistoryStore {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      entries: Mutex::new(HashMap::new()),
+      capacity,
+    }
+  }
+}
+
+impl Default for MemoryRowHistoryStore {
+  fn default() -> Self {
+    Self::new(DEFAULT_ROW_HISTORY_CAPACITY)
+  }
+}
+
+impl RowHistoryStore for MemoryRowHistoryStore {
+  fn append(&self, row_id: &RowId, entry: RowHistoryEntry) {
+    let mut entries = self.entries.lock();
+    let entries = entries.entry(*row_id).or_default();
+    if entries.len() >= self.capacity {
+      entries.pop_front();
+    }
+    entries.push_back(entry);
+  }
+
+  fn entries(&self, row_id: &RowId) -> Vec<RowHistoryEntry> {
+    self
+      .entries
+      .lock()
+      .get(row_id)
+      .map(|entries| entries.iter().cloned().collect())
+      .unwrap_or_default()
+  }
+}
+
+/// Records the [CellChange]s of an open row into its [RowHistoryStore].
+#[derive(Clone)]
+pub(crate) struct RowHistory {
+  row_id: RowId,
+  /// The last known cells of the row, which the old values of the changes come from.
+  cells: Arc<Mutex<Cells>>,
+  store: Arc<dyn RowHistoryStore>,
+}
+
+impl RowHistory {
+  pub(crate) fn new(row_id: RowId, cells: Cells, store: Arc<dyn RowHistoryStore>) -> Self {
+    Self {
+      row_id,
+      cells: Arc::new(Mutex::new(cells)),
+      store,
+    }
+  }
+
+  /// Return the changes accepted by the filter, oldest first.
+  pub(crate) fn changes<F>(
+    &self,
+    user_data: Option<&PermanentUserData>,
+    filter: F,
+  ) -> Vec<CellChange>
+  where
+    F: Fn(&str) -> bool,
+  {
+    self
+      .store
+      .entries(&self.row_id)
+      .into_iter()
+      .filter(|entry| filter(&entry.field_id))
+      .map(|entry| CellChange {
+        row_id: self.row_id,
+        author: entry
+          .client_id
+          .zip(user_data)
+          .and_then(|(client_id, user_data)| user_data.user_by_client_id(client_id))
+          .map(|user| user.to_string())
+          .or_else(|| entry.uid.map(|uid| uid.to_string())),
+        field_id: entry.field_id,
+        old_value: entry.old_value,
+        new_value: entry.new_value,
+        timestamp: entry.timestamp,
+        is_local_change: entry.is_local_change,
+      })
+      .collect()
+  }
+
+  fn known_field_ids(&self) -> Vec<String> {
+    self.cells.lock().keys().cloned().collect()
+  }
+
+  fn record(
+    &self,
+    txn: &TransactionMut,
+    row_data_map: &MapRef,
+    field_ids: BTreeSet<String>,
+    is_local_change: bool,
+    client_id: Option<ClientID>,
+  ) {
+    let uid = CollabOrigin::from(txn).client_user_id();
+    let mut cells = self.cells.lock();
+    for field_id in field_ids {
+      let new_value = cell_from_map_ref(row_data_map, txn, &field_id);
+      let old_value = match &new_value {
+        Some(cell) => cells.insert(field_id.clone(), cell.clone()),
+        None => cells.remove(&field_id),
+      };
+      // Writing a cell always touches its modified time, which alone isn't a change
+      if cell_content(old_value.as_ref()) == cell_content(new_value.as_ref()) {
+        continue;
+      }
+      let timestamp = new_value
+        .as_ref()
+        .and_then(|cell| cell.get_as::<i64>(LAST_MODIFIED))
+        .unwrap_or_else(timestamp);
+      self.store.append(
+        &self.row_id,
+        RowHistoryEntry {
+          field_id,
+          old_value,
+          new_value,
+          timestamp,
+          is_local_change,
+          client_id,
+          uid,
+        },
+      );
+    }
+  }
+}
+
+/// Record the changes of the row's cells into the history.
+pub(crate) fn subscribe_row_history(
+  origin: CollabOrigin,
+  local_client_id: ClientID,
+  row_data_map: &MapRef,
+  history: RowHistory,
+) {
+  let data_map = row_data_map.clone();
+  row_data_map.observe_deep_with("history", move |txn, events| {
+    let mut field_ids = BTreeSet::new();
+    for event in events.iter() {
+      let Event::Map(map_event) = event else {
+        continue;
+      };
+      let mut path = event.path();
+      let is_cells_path = matches!(
+        path.pop_front(),
+        Some(PathSegment::Key(key)) if key.deref() == ROW_CELLS
+      );
+      match path.pop_front() {
+        // The content of a cell changed
+        Some(PathSegment::Key(field_id)) if is_cells_path => {
+          field_ids.insert(field_id.to_string());
+        },
+        // Cells were inserted, replaced or removed
+        None if is_cells_path => {
+          field_ids.extend(map_event.keys(txn).keys().map(|key| key.to_string()));
+        },
+        // The whole cells map was replaced
+        None if map_event.keys(txn).contains_key(ROW_CELLS) => {
+          field_ids.extend(history.known_field_ids());
+          if let Some(cells_map) = data_map.get_with_txn::<_, MapRef>(txn, ROW_CELLS) {
+            field_ids.extend(cells_map.keys(txn).map(|key| key.to_string()));
+          }
+        },
+        _ => {},
+      }
+    }
+    if field_ids.is_empty() {
+      return;
+    }
+
+    let is_local_change = CollabOrigin::from(txn) == origin;
+    let client_id = change_client_id(txn, local_client_id, is_local_change);
+    history.record(txn, &data_map, field_ids, is_local_change, client_id);
+  });
+}
+
+/// Return the client that made the changes of the transaction: the only one whose clock moved
+/// forward, or the local client for a local transaction that only removed data.
+fn change_client_id(
+  txn: &TransactionMut,
+  local_client_id: ClientID,
+  is_local_change: bool,
+) -> Option<ClientID> {
+  let before_state = txn.before_state();
+  let mut client_ids = txn
+    .after_state()
+    .iter()
+    .filter(|(client_id, clock)| **clock > before_state.get(client_id))
+    .map(|(client_id, _)| *client_id);
+  match (client_ids.next(), client_ids.next()) {
+    (Some(client_id), None) => Some(client_id),
+    (None, _) if is_local_change => Some(local_client_id),
+    _ => None,
+  }
+}
+
+/// Return the cell without the times that are updated on every write.
+pub(crate) fn cell_content(cell: Option<&Cell>) -> Option<Cell> {
+  cell.map(|cell| {
+    cell
+      .iter()
+      .filter(|(key, _)| key.as_str() != CREATED_AT && key.as_str() != LAST_MODIFIED)
+      .map(|(key, value)| (key.clone(), value.clone()))
+      .collect()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::collab::CollabOptions;
+  use crate::core::origin::CollabClient;
+  use crate::database::entity::FieldType;
+  use crate::database::rows::{DatabaseRow, Row, new_cell_builder};
+  use crate::database::template::entity::CELL_DATA;
+  use crate::preclude::{Collab, ReadTxn, Transact, Update};
+  use uuid::Uuid;
+  use yrs::updates::decoder::Decode;
+
+  fn text_cell(text: &str) -> Cell {
+    let mut cell = new_cell_builder(FieldType::RichText);
+    cell.insert(CELL_DATA.into(), text.into());
+    cell
+  }
+
+  fn cell_text(cell: &Option<Cell>) -> Option<String> {
+    cell.as_ref()?.get_as::<String>(CELL_DATA)
+  }
+
+  fn new_collab(row_id: RowId, uid: i64, client_id: ClientID) -> Collab {
+    let origin = CollabOrigin::Client(CollabClient::new(uid, format!("device-{}", uid)));
+    let options = CollabOptions::new(row_id, client_id).with_remember_user(true);
+    Collab::new_with_options(origin, options).unwrap()
+  }
+
+  /// Apply the updates of `from` to `to`, without an origin like an update from the server.
+  fn sync(from: &Collab, to: &Collab) {
+    let state_vector = to.transact().state_vector();
+    let update = from.transact().encode_state_as_update_v1(&state_vector);
+    to.doc()
+      .transact_mut_with(CollabOrigin::Empty)
+      .apply_update(Update::decode_v1(&update).unwrap())
+      .unwrap();
+  }
+
+  fn set_name(row: &mut DatabaseRow, name: &str) {
+    row.update(|update| {
+      update.update_cells(|cells| {
+        cells.insert_cell("name", text_cell(name));
+      });
+    });
+  }
+
+  #[test]
+  fn row_history_records_cell_changes_test() {
+    let row_id = Uuid::new_v4();
+    let mut row = Row::new(row_id, Uuid::new_v4());
+    row.cells.insert("name".to_string(), text_cell("a"));
+    let mut row_a = DatabaseRow::create(row_id, new_collab(row_id, 1, 1), None, row);
+    row_a.enable_history(Arc::new(MemoryRowHistoryStore::default()));
+    let collab_b = new_collab(row_id, 2, 2);
+    sync(&row_a, &collab_b);
+    let mut row_b = DatabaseRow::open(row_id, collab_b, None).unwrap();
+    row_b.enable_history(Arc::new(MemoryRowHistoryStore::default()));
+
+    set_name(&mut row_a, "b");
+    // Writing the same value again isn't a change
+    set_name(&mut row_a, "b");
+    let changes = row_a.get_row_history();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(cell_text(&changes[0].old_value), Some("a".to_string()));
+    assert_eq!(cell_text(&changes[0].new_value), Some("b".to_string()));
+    assert_eq!(changes[0].author, Some("1".to_string()));
+    assert!(changes[0].is_local_change);
+
+    sync(&row_a, &row_b);
+    set_name(&mut row_b, "c");
+    row_b.update(|update| {
+      update.update_cells(|cells| {
+        cells.insert_cell("note", text_cell("x"));
+      });
+    });
+    sync(&row_b, &row_a);
+
+    // The author of a remote change comes from the users remembered by the row
+    let changes = row_a.get_field_history("name");
+    assert_eq!(changes.len(), 2);
+    assert_eq!(cell_text(&changes[1].old_value), Some("b".to_string()));
+    assert_eq!(cell_text(&changes[1].new_value), Some("c".to_string()));
+    assert_eq!(changes[1].author, Some("2".to_string()));
+    assert!(!changes[1].is_local_change);
+    let changes = row_a.get_field_history("note");
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].old_value, None);
+
+    let changes = row_b.get_row_history();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0].author, Some("1".to_string()));
+
+    row_a.update(|update| {
+      update.update_cells(|cells| {
+        cells.remove("note");
+      });
+    });
+    let changes = row_a.get_field_history("note");
+    assert_eq!(changes.len(), 2);
+    assert_eq!(cell_text(&changes[1].old_value), Some("x".to_string()));
+    assert_eq!(changes[1].new_value, None);
+
+    row_a.add_comment(RowComment::new("Looks good".to_string(), "1".to_string()));
+    let activity = row_a.get_row_activity();
+    assert_eq!(activity.len(), 5);
+    assert_eq!(
+      activity
+        .iter()
+        .filter(|activity| matches!(activity, RowActivity::Comment(_)))
+        .count(),
+      1
+    );
+  }
+
+  #[test]
+  fn row_history_outlives_the_open_row_test() {
+    let row_id = Uuid::new_v4();
+    let store = Arc::new(MemoryRowHistoryStore::default());
+    let mut row = DatabaseRow::create(
+      row_id,
+      new_collab(row_id, 1, 1),
+      None,
+      Row::new(row_id, Uuid::new_v4()),
+    );
+    // Nothing is recorded until the history is enabled
+    set_name(&mut row, "a");
+    assert!(row.get_row_history().is_empty());
+
+    row.enable_history(store.clone());
+    set_name(&mut row, "b");
+    let update = row
+      .transact()
+      .encode_state_as_update_v1(&Default::default());
+    drop(row);
+
+    let collab = new_collab(row_id, 1, 3);
+    collab
+      .doc()
+      .transact_mut_with(CollabOrigin::Empty)
+      .apply_update(Update::decode_v1(&update).unwrap())
+      .unwrap();
+    let mut row = DatabaseRow::open(row_id, collab, None).unwrap();
+    row.enable_history(store);
+    set_name(&mut row, "c");
+    let changes = row.get_field_history("name");
+    assert_eq!(changes.len(), 2);
+    assert_eq!(cell_text(&changes[0].old_value), Some("a".to_string()));
+    assert_eq!(cell_text(&changes[0].new_value), Some("b".to_string()));
+    assert_eq!(cell_text(&changes[1].new_value), Some("c".to_string()));
+  }
+}
@@ -8,6 +8,8 @@ use crate::entity::uuid_validation::RowId;
 use crate::preclude::{DeepObservable, EntryChange, Event, MapRef, TransactionMut};
 use crate::preclude::{PathSegment, ToJson};
 use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::preclude::map::MapEvent;
 use crate::util::AnyExt;
@@ -37,11 +39,24 @@ pub enum RowChange {
     field_id: String,
     value: Cell,
     is_local_change: bool,
+    /// The cell was written by
+    /// [Database::update_rows](crate::database::database::Database::update_rows), which also
+    /// sends a [RowChange::DidUpdateRows] for the whole batch. A listener that only wants the
+    /// batch event skips these.
+    in_batch: bool,
   },
   DidUpdateRowMeta {
     row_id: RowId,
     is_local_change: bool,
   },
+  /// The rows were updated by
+  /// [Database::update_rows](crate::database::database::Database::update_rows). It's sent once per
+  /// batch, after the [RowChange::DidUpdateCell] of each changed cell, which are still sent for the
+  /// listeners of single cells and have their `in_batch` set.
+  DidUpdateRows {
+    row_ids: Vec<RowId>,
+    is_local_change: bool,
+  },
   /// A comment was added to the row
   DidAddComment {
     row_id: RowId,
@@ -62,21 +77,33 @@ pub enum RowChange {
   },
 }
 
+/// `in_batch` is set while the row is written as part of a batch, see
+/// [RowChange::DidUpdateCell::in_batch].
 pub(crate) fn subscribe_row_data_change(
   origin: CollabOrigin,
   row_id: RowId,
   row_data_map: &MapRef,
   change_tx: RowChangeSender,
+  in_batch: Arc<AtomicBool>,
 ) {
   row_data_map.observe_deep_with("change", move |txn, events| {
     let txn_origin = CollabOrigin::from(txn);
     let is_local_change = txn_origin == origin;
+    let in_batch = in_batch.load(Ordering::Relaxed);
     for event in events.iter() {
       match event {
         Event::Text(_) => {},
         Event::Array(_) => {},
         Event::Map(map_event) => {
-          handle_map_event(&row_id, &change_tx, is_local_change, txn, event, map_event);
+          handle_map_event(
+            &row_id,
+            &change_tx,
+            is_local_change,
+            in_batch,
+            txn,
+            event,
+            map_event,
+          );
         },
         Event::XmlFragment(_) => {},
         Event::XmlText(_) => {},
@@ -186,6 +213,7 @@ fn handle_map_event(
   row_id: &RowId,
   change_tx: &RowChangeSender,
   is_local_change: bool,
+  in_batch: bool,
   txn: &TransactionMut,
   event: &Event,
   map_event: &MapEvent,
@@ -240,6 +268,7 @@ fn handle_map_event(
                 field_id,
                 value: cell,
                 is_local_change,
+                in_batch,
               });
             }
           },
@@ -259,6 +288,7 @@ fn handle_map_event(
                   field_id,
                   value: cell,
                   is_local_change,
+                  in_batch,
                 });
               }
             }
@@ -272,6 +302,7 @@ fn handle_map_event(
                 field_id,
                 value: Cell::default(),
                 is_local_change,
+                in_batch,
               });
             }
           },
@@ -0,0 +1,455 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::media_type_option::MediaCellData;
+use crate::database::fields::select_type_option::{SelectOptionIds, SelectTypeOption};
+use crate::database::fields::{Field, TypeOptionCellReader, type_option_cell_reader};
+use crate::database::rows::{Cell, Row};
+use crate::database::sort::{Sort, SortCondition};
+use crate::database::template::check_list_parse::ChecklistCellData;
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::views::RowOrder;
+use crate::entity::uuid_validation::RowId;
+
+/// Sorts rows by a view's [Sort]s.
+///
+/// The sort is stable, so rows that compare equal on every sort keep their manual order. Empty
+/// cells always sort last, whatever the direction. Sorts whose field was deleted or whose field
+/// type no longer matches the field are skipped. Text is compared with the collation rules of
+/// the evaluator's locale, the language-neutral root collation unless one is given.
+pub struct SortEvaluator {
+  sorts: Vec<SortField>,
+  locale: Locale,
+}
+
+struct SortField {
+  field_id: String,
+  field_type: FieldType,
+  condition: SortCondition,
+  reader: Box<dyn TypeOptionCellReader>,
+  /// Position of each select option in the field's type option.
+  option_positions: HashMap<String, usize>,
+}
+
+/// The value a cell is compared by. A sort usually compares values of one variant, but a formula
+/// field mixes numbers and texts, e.g. when a cell holds an error, so values of different
+/// variants are ordered by [SortValue::rank].
+#[derive(Debug, Clone, PartialEq)]
+enum SortValue {
+  Empty,
+  Number(f64),
+  Text(String),
+  Positions(Vec<usize>),
+  Bool(bool),
+}
+
+impl SortValue {
+  /// Numbers sort before texts, texts before booleans and booleans before select options.
+  fn rank(&self) -> u8 {
+    match self {
+      SortValue::Number(_) => 0,
+      SortValue::Text(_) => 1,
+      SortValue::Bool(_) => 2,
+      SortValue::Positions(_) => 3,
+      SortValue::Empty => 4,
+    }
+  }
+}
+
+impl SortEvaluator {
+  pub fn new(sorts: Vec<Sort>, fields: Vec<Field>) -> Self {
+    Self::new_with_locale(sorts, fields, Locale::UND)
+  }
+
+  /// Text is compared with the collation rules of `locale`, e.g. "ä" sorts with "a" in German
+  /// but after "z" in Swedish.
+  pub fn new_with_locale(sorts: Vec<Sort>, fields: Vec<Field>, locale: Locale) -> Self {
+    let fields = fields
+      .into_iter()
+      .map(|field| (field.id.clone(), field))
+      .collect::<HashMap<_, _>>();
+    let sorts = sorts
+      .into_iter()
+      .flat_map(|sort| {
+        let field = fields.get(&sort.field_id)?;
+        let field_type = FieldType::from(field.field_type);
+        if field_type != sort.field_type {
+          return None;
+        }
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        let option_positions = match field_type {
+          FieldType::SingleSelect | FieldType::MultiSelect => {
+            SelectTypeOption::from(type_option.clone())
+              .options
+              .into_iter()
+              .enumerate()
+              .map(|(index, option)| (option.id, index))
+              .collect()
+          },
+          _ => HashMap::new(),
+        };
+        Some(SortField {
+          field_id: sort.field_id,
+          field_type,
+          condition: sort.condition,
+          reader: type_option_cell_reader(type_option, &field_type),
+          option_positions,
+        })
+      })
+      .collect();
+    Self { sorts, locale }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.sorts.is_empty()
+  }
+
+  /// Sort the rows in place.
+  pub fn sort_rows(&self, rows: &mut Vec<Row>) {
+    if self.is_empty() {
+      return;
+    }
+    let collator = self.collator();
+    let mut keyed_rows = rows
+      .drain(..)
+      .map(|row| (self.sort_values(&row), row))
+      .collect::<Vec<_>>();
+    keyed_rows.sort_by(|(left, _), (right, _)| self.compare(collator.as_ref(), left, right));
+    rows.extend(keyed_rows.into_iter().map(|(_, row)| row));
+  }
+
+  /// Merge the rows into `first_rows`, keeping the first `count` rows by the sorts, then by their
+  /// index, that come after `after` in the same order. `first_rows` must be the result of a
+  /// previous call, so only `count` rows are sorted along with the merged ones.
+  pub fn keep_first_rows(
+    &self,
+    first_rows: &mut Vec<(usize, Row)>,
+    rows: Vec<(usize, Row)>,
+    after: Option<&(usize, Row)>,
+    count: usize,
+  ) {
+    let collator = self.collator();
+    let after = after.map(|(index, row)| (self.sort_values(row), *index));
+    let mut keyed_rows = first_rows
+      .drain(..)
+      .chain(rows)
+      .map(|(index, row)| (self.sort_values(&row), index, row))
+      .filter(|(values, index, _)| {
+        after.as_ref().is_none_or(|(after_values, after_index)| {
+          self
+            .compare(collator.as_ref(), values, after_values)
+            .then(index.cmp(after_index))
+            == Ordering::Greater
+        })
+      })
+      .collect::<Vec<_>>();
+    keyed_rows.sort_by(|(left, left_index, _), (right, right_index, _)| {
+      self
+        .compare(collator.as_ref(), left, right)
+        .then(left_index.cmp(right_index))
+    });
+    keyed_rows.truncate(count);
+    first_rows.extend(keyed_rows.into_iter().map(|(_, index, row)| (index, row)));
+  }
+
+  /// Sort the row orders by the rows they refer to. Row orders without a row keep their
+  /// relative position after the rows that were found.
+  pub fn sort_row_orders(
+    &self,
+    row_orders: Vec<RowOrder>,
+    rows: &HashMap<RowId, Row>,
+  ) -> Vec<RowOrder> {
+    if self.is_empty() {
+      return row_orders;
+    }
+    let (mut keyed_orders, missing): (Vec<_>, Vec<_>) = row_orders
+      .into_iter()
+      .map(|row_order| {
+        let values = rows.get(&row_order.id).map(|row| self.sort_values(row));
+        (values, row_order)
+      })
+      .partition(|(values, _)| values.is_some());
+    let collator = self.collator();
+    keyed_orders.sort_by(|(left, _), (right, _)| {
+      self.compare(
+        collator.as_ref(),
+        left.as_deref().unwrap_or(&[]),
+        right.as_deref().unwrap_or(&[]),
+      )
+    });
+    keyed_orders
+      .into_iter()
+      .chain(missing)
+      .map(|(_, row_order)| row_order)
+      .collect()
+  }
+
+  /// The collator isn't kept in the evaluator, as it isn't `Send`.
+  fn collator(&self) -> Option<Collator> {
+    Collator::try_new(&(&self.locale).into(), CollatorOptions::new())
+      .or_else(|_| Collator::try_new(&Default::default(), CollatorOptions::new()))
+      .ok()
+  }
+
+  fn compare(
+    &self,
+    collator: Option<&Collator>,
+    left: &[SortValue],
+    right: &[SortValue],
+  ) -> Ordering {
+    for ((sort, left), right) in self.sorts.iter().zip(left).zip(right) {
+      let ordering = match (left, right) {
+        (SortValue::Empty, SortValue::Empty) => Ordering::Equal,
+        (SortValue::Empty, _) => Ordering::Greater,
+        (_, SortValue::Empty) => Ordering::Less,
+        (left, right) => sort.condition.apply(compare_values(collator, left, right)),
+      };
+      if ordering != Ordering::Equal {
+        return ordering;
+      }
+    }
+    Ordering::Equal
+  }
+
+  fn sort_values(&self, row: &Row) -> Vec<SortValue> {
+    let empty_cell = Cell::new();
+    self
+      .sorts
+      .iter()
+      .map(|sort| {
+        let cell = row.cells.get(&sort.field_id).unwrap_or(&empty_cell);
+        sort.sort_value(cell, row)
+      })
+      .collect()
+  }
+}
+
+impl SortField {
+  fn sort_value(&self, cell: &Cell, row: &Row) -> SortValue {
+    match self.field_type {
+      FieldType::RichText
+      | FieldType::URL
+      | FieldType::Summary
+      | FieldType::Translate
+      | FieldType::Rollup
+      | FieldType::Person => self.text_value(cell),
+      // Number, date and checkbox formulas have a numeric value
+      FieldType::Formula => match self.reader.numeric_cell(cell) {
+        Some(number) => SortValue::Number(number),
+        None => self.text_value(cell),
+      },
+      FieldType::Number | FieldType::Time | FieldType::AutoNumber => self
+        .reader
+        .numeric_cell(cell)
+        .map(SortValue::Number)
+        .unwrap_or(SortValue::Empty),
+      FieldType::DateTime => DateCellData::from(cell)
+        .timestamp
+        .map(|timestamp| SortValue::Number(timestamp as f64))
+        .unwrap_or(SortValue::Empty),
+      FieldType::CreatedTime => SortValue::Number(row.created_at as f64),
+      FieldType::LastEditedTime => SortValue::Number(row.modified_at as f64),
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let positions = SelectOptionIds::from(cell)
+          .iter()
+          .flat_map(|id| self.option_positions.get(id).copied())
+          .collect::<Vec<_>>();
+        if positions.is_empty() {
+          SortValue::Empty
+        } else {
+          SortValue::Positions(positions)
+        }
+      },
+      FieldType::Checkbox => SortValue::Bool(self.reader.numeric_cell(cell) == Some(1.0)),
+      FieldType::Checklist => {
+        let cell_data = ChecklistCellData::from(cell);
+        if cell_data.options.is_empty() {
+          SortValue::Empty
+        } else {
+          SortValue::Number(cell_data.percentage_complete())
+        }
+      },
+      FieldType::Media => count_value(MediaCellData::from(cell).files.len()),
+      FieldType::Relation => count_value(RelationCellData::from(cell).row_ids.len()),
+    }
+  }
+
+  fn text_value(&self, cell: &Cell) -> SortValue {
+    let text = if cell.contains_key(CELL_DATA) {
+      self.reader.stringify_cell(cell)
+    } else {
+      String::new()
+    };
+    if text.is_empty() {
+      SortValue::Empty
+    } else {
+      SortValue::Text(text)
+    }
+  }
+}
+
+fn count_value(count: usize) -> SortValue {
+  if count == 0 {
+    SortValue::Empty
+  } else {
+    SortValue::Number(count as f64)
+  }
+}
+
+/// Texts that the collator considers equal, e.g. because they only differ in characters the
+/// collation ignores, are ordered by code point so the order doesn't depend on the input.
+fn compare_values(collator: Option<&Collator>, left: &SortValue, right: &SortValue) -> Ordering {
+  match (left, right) {
+    (SortValue::Number(left), SortValue::Number(right)) => left.total_cmp(right),
+    (SortValue::Text(left), SortValue::Text(right)) => collator
+      .map(|collator| collator.compare(left, right))
+      .unwrap_or(Ordering::Equal)
+      .then_with(|| left.cmp(right)),
+    (SortValue::Positions(left), SortValue::Positions(right)) => left.cmp(right),
+    (SortValue::Bool(left), SortValue::Bool(right)) => left.cmp(right),
+    (left, right) => left.rank().cmp(&right.rank()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn text_is_sorted_by_locale_collation_test() {
+    let field = Field::new(
+      "f1".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    );
+    let sorts = vec![Sort::new(
+      "s1".to_string(),
+      "f1".to_string(),
+      FieldType::RichText,
+      SortCondition::Ascending,
+    )];
+    let words = ["banana", "Zebra", "Äpfel", "apple", "éclair"];
+    let sorted_words = |locale: Locale| {
+      let mut values = words
+        .iter()
+        .map(|word| vec![SortValue::Text(word.to_string())])
+        .collect::<Vec<_>>();
+      let evaluator = SortEvaluator::new_with_locale(sorts.clone(), vec![field.clone()], locale);
+      let collator = evaluator.collator();
+      values.sort_by(|left, right| evaluator.compare(collator.as_ref(), left, right));
+      values
+        .into_iter()
+        .map(|mut values| match values.remove(0) {
+          SortValue::Text(text) => text,
+          value => panic!("unexpected value {:?}", value),
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // Case and accents only matter when the letters are the same
+    assert_eq!(
+      sorted_words(Locale::UND),
+      vec!["Äpfel", "apple", "banana", "éclair", "Zebra"]
+    );
+    // Swedish sorts "ä" after "z"
+    assert_eq!(
+      sorted_words("sv".parse().unwrap()),
+      vec!["apple", "banana", "éclair", "Zebra", "Äpfel"]
+    );
+  }
+
+  #[test]
+  fn mixed_formula_values_sort_test() {
+    use crate::database::fields::formula_type_option::{FormulaResultType, FormulaTypeOption};
+    use crate::util::AnyMapExt;
+    use uuid::Uuid;
+
+    let field = Field::new(
+      "f1".to_string(),
+      "Formula".to_string(),
+      FieldType::Formula.into(),
+      false,
+    )
+    .with_type_option_data(
+      FieldType::Formula.type_id(),
+      FormulaTypeOption::new("{a}", FormulaResultType::Number).into(),
+    );
+    let rows = ["10", "#ERROR", "2", "abc"]
+      .into_iter()
+      .map(|data| {
+        let mut row = Row::new(Uuid::new_v4(), Uuid::new_v4());
+        let mut cell = Cell::new();
+        cell.insert(CELL_DATA.into(), data.into());
+        row.cells.insert("f1".to_string(), cell);
+        row
+      })
+      .collect::<Vec<_>>();
+    let cell_data = |rows: &[Row]| {
+      rows
+        .iter()
+        .map(|row| row.cells["f1"].get_as::<String>(CELL_DATA).unwrap())
+        .collect::<Vec<_>>()
+    };
+
+    for condition in [SortCondition::Ascending, SortCondition::Descending] {
+      let evaluator = SortEvaluator::new(
+        vec![Sort::new(
+          "s1".to_string(),
+          "f1".to_string(),
+          FieldType::Formula,
+          condition,
+        )],
+        vec![field.clone()],
+      );
+      // The order doesn't depend on the order of the input
+      let mut sorted = rows.clone();
+      evaluator.sort_rows(&mut sorted);
+      let mut reversed = rows.iter().rev().cloned().collect::<Vec<_>>();
+      evaluator.sort_rows(&mut reversed);
+      assert_eq!(cell_data(&sorted), cell_data(&reversed));
+
+      let mut expected = vec!["2", "10", "#ERROR", "abc"];
+      if condition == SortCondition::Descending {
+        expected.reverse();
+      }
+      assert_eq!(cell_data(&sorted), expected);
+    }
+  }
+
+  #[test]
+  fn empty_values_sort_last_test() {
+    let evaluator = SortEvaluator {
+      sorts: vec![SortField {
+        field_id: "f1".to_string(),
+        field_type: FieldType::Number,
+        condition: SortCondition::Descending,
+        reader: type_option_cell_reader(Default::default(), &FieldType::Number),
+        option_positions: HashMap::new(),
+      }],
+      locale: Locale::UND,
+    };
+    let mut values = vec![
+      vec![SortValue::Empty],
+      vec![SortValue::Number(1.0)],
+      vec![SortValue::Number(3.0)],
+    ];
+    values.sort_by(|left, right| evaluator.compare(None, left, right));
+    assert_eq!(
+      values,
+      vec![
+        vec![SortValue::Number(3.0)],
+        vec![SortValue::Number(1.0)],
+        vec![SortValue::Empty],
+      ]
+    );
+  }
+}
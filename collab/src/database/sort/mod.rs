@@ -0,0 +1,5 @@
+mod evaluator;
+mod sort;
+
+pub use evaluator::*;
+pub use sort::*;
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+
+use crate::database::entity::FieldType;
+use crate::database::util::get_i64_lenient;
+use crate::database::views::{SortMap, SortMapBuilder};
+use crate::error::CollabError;
+use crate::preclude::Any;
+use crate::util::AnyMapExt;
+
+pub const SORT_ID: &str = "id";
+pub const SORT_FIELD_ID: &str = "field_id";
+pub const SORT_FIELD_TYPE: &str = "ty";
+pub const SORT_CONDITION: &str = "condition";
+
+/// A typed view sort. A view's sorts are applied in order: the first sort is the primary key,
+/// the following ones break its ties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+  pub id: String,
+  pub field_id: String,
+  pub field_type: FieldType,
+  pub condition: SortCondition,
+}
+
+impl Sort {
+  pub fn new(
+    id: String,
+    field_id: String,
+    field_type: FieldType,
+    condition: SortCondition,
+  ) -> Self {
+    Self {
+      id,
+      field_id,
+      field_type,
+      condition,
+    }
+  }
+}
+
+impl TryFrom<SortMap> for Sort {
+  type Error = CollabError;
+
+  fn try_from(map: SortMap) -> Result<Self, Self::Error> {
+    let id: String = map
+      .get_as(SORT_ID)
+      .ok_or_else(|| CollabError::NoRequiredData("sort id".to_string()))?;
+    let field_id: String = map
+      .get_as(SORT_FIELD_ID)
+      .ok_or_else(|| CollabError::NoRequiredData("sort field id".to_string()))?;
+    let field_type = get_i64_lenient(&map, SORT_FIELD_TYPE)
+      .map(FieldType::from)
+      .ok_or_else(|| CollabError::NoRequiredData("sort field type".to_string()))?;
+    let condition = get_i64_lenient(&map, SORT_CONDITION)
+      .map(SortCondition::from)
+      .unwrap_or_default();
+    Ok(Self {
+      id,
+      field_id,
+      field_type,
+      condition,
+    })
+  }
+}
+
+impl From<Sort> for SortMap {
+  fn from(sort: Sort) -> Self {
+    SortMapBuilder::from([
+      (SORT_ID.into(), sort.id.into()),
+      (SORT_FIELD_ID.into(), sort.field_id.into()),
+      (SORT_FIELD_TYPE.into(), Any::BigInt(sort.field_type.into())),
+      (SORT_CONDITION.into(), Any::BigInt(sort.condition.value())),
+    ])
+  }
+}
+
+impl From<&Sort> for SortMap {
+  fn from(sort: &Sort) -> Self {
+    SortMap::from(sort.clone())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortCondition {
+  #[default]
+  Ascending = 0,
+  Descending = 1,
+}
+
+impl From<i64> for SortCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => SortCondition::Ascending,
+      1 => SortCondition::Descending,
+      _ => {
+        tracing::error!("Unsupported sort condition, fallback to Ascending");
+        SortCondition::Ascending
+      },
+    }
+  }
+}
+
+impl SortCondition {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+
+  /// Apply the direction to an ascending ordering.
+  pub fn apply(&self, ordering: Ordering) -> Ordering {
+    match self {
+      SortCondition::Ascending => ordering,
+      SortCondition::Descending => ordering.reverse(),
+    }
+  }
+}
@@ -5,10 +5,12 @@ use crate::database::template::entity::{
 use crate::entity::uuid_validation::{DatabaseId, DatabaseViewId};
 
 use crate::database::entity::FieldType;
+use crate::database::fields::TypeOptionCellWriter;
 use crate::database::fields::checkbox_type_option::CheckboxTypeOption;
 use crate::database::fields::date_type_option::{DateFormat, DateTypeOption};
 use crate::database::fields::media_type_option::MediaTypeOption;
 use crate::database::fields::number_type_option::NumberTypeOption;
+use crate::database::fields::person_type_option::PersonTypeOption;
 use crate::database::fields::select_type_option::SelectTypeOption;
 use crate::database::fields::text_type_option::RichTextTypeOption;
 use crate::database::fields::timestamp_type_option::TimestampTypeOption;
@@ -20,10 +22,12 @@ use crate::database::template::media_parse::replace_cells_with_files;
 use crate::database::template::option_parse::{
   SELECT_OPTION_SEPARATOR, build_options_from_cells, replace_cells_with_options_id,
 };
+use crate::database::template::person_parse::build_persons_from_cells;
 use crate::database::views::DatabaseLayout;
 
 use crate::preclude::Any;
 
+use serde_json::Value;
 use std::collections::HashMap;
 
 use std::path::Path;
@@ -283,6 +287,21 @@ impl FieldTemplateBuilder {
 
         cell_template
       },
+      FieldType::Person => {
+        let type_option = PersonTypeOption {
+          persons: build_persons_from_cells(&self.cells),
+          ..Default::default()
+        };
+        let cell_template = self
+          .cells
+          .into_iter()
+          .map(|cell| type_option.convert_json_to_cell(Value::String(cell)))
+          .collect();
+        field_template
+          .type_options
+          .insert(field_type, type_option.into());
+        cell_template
+      },
       _ => string_cell_template(&field_type, self.cells),
     };
 
@@ -0,0 +1,201 @@
+use crate::database::entity::FieldType;
+use crate::database::fields::{Field, TypeOptionCellReader, type_option_cell_reader};
+use crate::database::rows::Row;
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::template::util::ToCellString;
+use crate::entity::uuid_validation::RowId;
+use crate::error::CollabError;
+use crate::util::AnyMapExt;
+use std::collections::HashMap;
+use std::io;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The options of [Database::export_view_to_csv](crate::database::database::Database::export_view_to_csv).
+#[derive(Debug, Clone)]
+pub struct CSVExportOptions {
+  /// Write the stored values instead of the readable ones, e.g. the option ids of a select
+  /// field and the timestamps of a date field.
+  pub raw_values: bool,
+  pub delimiter: u8,
+  /// Start the output with a UTF-8 byte order mark, so that Excel reads it as UTF-8.
+  pub with_bom: bool,
+  /// Only export the rows that pass the view's filters, sorted by the view's sorts.
+  pub apply_filters_and_sorts: bool,
+  /// Export the fields that are hidden in the view.
+  pub include_hidden_fields: bool,
+  /// The number of rows that are loaded and written at a time.
+  pub chunk_size: usize,
+}
+
+impl Default for CSVExportOptions {
+  fn default() -> Self {
+    Self {
+      raw_values: false,
+      delimiter: b',',
+      with_bom: false,
+      apply_filters_and_sorts: false,
+      include_hidden_fields: false,
+      chunk_size: 100,
+    }
+  }
+}
+
+impl CSVExportOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_raw_values(mut self, raw_values: bool) -> Self {
+    self.raw_values = raw_values;
+    self
+  }
+
+  pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+
+  pub fn with_bom(mut self, with_bom: bool) -> Self {
+    self.with_bom = with_bom;
+    self
+  }
+
+  pub fn with_filters_and_sorts(mut self, apply_filters_and_sorts: bool) -> Self {
+    self.apply_filters_and_sorts = apply_filters_and_sorts;
+    self
+  }
+
+  pub fn with_hidden_fields(mut self, include_hidden_fields: bool) -> Self {
+    self.include_hidden_fields = include_hidden_fields;
+    self
+  }
+
+  pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+    self.chunk_size = chunk_size.max(1);
+    self
+  }
+}
+
+/// The titles of the related rows, by relation field id and row id.
+pub type RelationTitles = HashMap<String, HashMap<RowId, String>>;
+
+/// Writes rows as CSV, one column per field. The header holds the field names.
+///
+/// The cells are written with [TypeOptionCellReader::stringify_cell], e.g. the names of the
+/// selected options and the formatted dates and numbers, or as stored in raw value mode. The
+/// created and last edited times are read from the row, and the relation cells are written as
+/// the titles of the related rows when they are given, or as the related row ids otherwise.
+///
+/// The rows are written as they are given, so that the caller can load them in chunks.
+pub struct CSVExporter<W: io::Write> {
+  fields: Vec<Field>,
+  raw_values: bool,
+  writer: csv::Writer<W>,
+}
+
+impl<W: io::Write> CSVExporter<W> {
+  /// Create an exporter writing into `writer`, and write the header.
+  pub fn new(
+    fields: Vec<Field>,
+    options: &CSVExportOptions,
+    mut writer: W,
+  ) -> Result<Self, CollabError> {
+    if options.with_bom {
+      writer.write_all(UTF8_BOM)?;
+    }
+    let mut writer = csv::WriterBuilder::new()
+      .delimiter(options.delimiter)
+      .from_writer(writer);
+    writer
+      .write_record(fields.iter().map(|field| field.name.as_str()))
+      .map_err(|err| CollabError::Internal(err.into()))?;
+    Ok(Self {
+      fields,
+      raw_values: options.raw_values,
+      writer,
+    })
+  }
+
+  /// Write the rows and flush them to the underlying writer.
+  pub fn write_rows<'a>(
+    &mut self,
+    rows: impl IntoIterator<Item = &'a Row>,
+    relation_titles: &RelationTitles,
+  ) -> Result<(), CollabError> {
+    let readers = self
+      .fields
+      .iter()
+      .map(|field| {
+        let field_type = FieldType::from(field.field_type);
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        type_option_cell_reader(type_option, &field_type)
+      })
+      .collect::<Vec<_>>();
+
+    for row in rows {
+      let record = self
+        .fields
+        .iter()
+        .zip(readers.iter())
+        .map(|(field, reader)| {
+          let titles = relation_titles
+            .get(&field.id)
+            .and_then(|titles| titles.get(&row.id));
+          self.cell_text(field, reader.as_ref(), row, titles)
+        })
+        .collect::<Vec<_>>();
+      self
+        .writer
+        .write_record(&record)
+        .map_err(|err| CollabError::Internal(err.into()))?;
+    }
+    self.writer.flush()?;
+    Ok(())
+  }
+
+  /// Flush the rows and return the underlying writer.
+  pub fn finish(self) -> Result<W, CollabError> {
+    self
+      .writer
+      .into_inner()
+      .map_err(|err| CollabError::Internal(anyhow::anyhow!("{}", err)))
+  }
+
+  fn cell_text(
+    &self,
+    field: &Field,
+    reader: &dyn TypeOptionCellReader,
+    row: &Row,
+    relation_titles: Option<&String>,
+  ) -> String {
+    let field_type = FieldType::from(field.field_type);
+    let timestamp = match field_type {
+      FieldType::CreatedTime => Some(row.created_at),
+      FieldType::LastEditedTime => Some(row.modified_at),
+      _ => None,
+    };
+    if let Some(timestamp) = timestamp {
+      return if self.raw_values {
+        timestamp.to_string()
+      } else {
+        reader.convert_raw_cell_data(&timestamp.to_string())
+      };
+    }
+
+    let Some(cell) = row.cells.get(&field.id) else {
+      return String::new();
+    };
+    match field_type {
+      FieldType::Relation => match relation_titles {
+        Some(titles) if !self.raw_values => titles.clone(),
+        _ => RelationCellData::from(cell).to_cell_string(),
+      },
+      _ if self.raw_values => cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+      _ => reader.stringify_cell(cell),
+    }
+  }
+}
@@ -0,0 +1,543 @@
+use crate::database::entity::FieldType;
+use crate::database::fields::select_type_option::{
+  SELECTION_IDS_SEPARATOR, SelectOption, SelectOptionColor, SelectTypeOption,
+};
+use crate::database::fields::{
+  Field, TypeOptionCellReader, TypeOptionCellWriter, TypeOptionData, type_option_cell_reader,
+  type_option_cell_writer,
+};
+use crate::database::rows::{Cell, Cells, Row};
+use crate::database::template::option_parse::SELECT_OPTION_COLOR_COUNT;
+use crate::database::validation::{ConstraintValidator, ViolationKind};
+use crate::entity::uuid_validation::RowId;
+use crate::error::CollabError;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use uuid::Uuid;
+
+/// How the rows of the CSV are matched with the rows of the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CSVImportMode {
+  /// Update the rows whose key matches a row of the CSV, and insert the other rows of the CSV.
+  #[default]
+  Upsert,
+  /// Like [CSVImportMode::Upsert], and delete the rows of the database that aren't in the CSV.
+  Sync,
+  /// Insert every row of the CSV.
+  AppendOnly,
+}
+
+/// The options of [Database::plan_csv_import](crate::database::database::Database::plan_csv_import).
+#[derive(Debug, Clone)]
+pub struct CSVImportOptions {
+  /// The field whose values identify the rows, required unless the mode is
+  /// [CSVImportMode::AppendOnly].
+  pub key_field_id: Option<String>,
+  /// The field id of the columns, by column name. The other columns are mapped to the field with
+  /// the same name.
+  pub field_mapping: HashMap<String, String>,
+  pub mode: CSVImportMode,
+  pub delimiter: u8,
+  /// Skip the lines whose values violate their fields'
+  /// [FieldConstraints](crate::database::fields::FieldConstraints), see
+  /// [CSVImportReport::violations].
+  pub strict: bool,
+}
+
+impl Default for CSVImportOptions {
+  fn default() -> Self {
+    Self {
+      key_field_id: None,
+      field_mapping: HashMap::new(),
+      mode: CSVImportMode::default(),
+      delimiter: b',',
+      strict: false,
+    }
+  }
+}
+
+impl CSVImportOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_key_field(mut self, field_id: &str) -> Self {
+    self.key_field_id = Some(field_id.to_string());
+    self
+  }
+
+  pub fn with_field_mapping(mut self, column: &str, field_id: &str) -> Self {
+    self
+      .field_mapping
+      .insert(column.to_string(), field_id.to_string());
+    self
+  }
+
+  pub fn with_mode(mut self, mode: CSVImportMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+
+  pub fn with_strict(mut self) -> Self {
+    self.strict = true;
+    self
+  }
+}
+
+/// A value of the CSV that couldn't be converted to the type of its field. The cell is skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CSVImportError {
+  /// The line of the CSV, starting from 1 for the header.
+  pub line: u64,
+  pub column: String,
+  pub value: String,
+}
+
+/// A row of a [CSVImportPlan] that couldn't be inserted, updated or deleted when the plan was
+/// applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CSVImportFailure {
+  /// The line of the CSV of an inserted row. None for the updated and the deleted rows.
+  pub line: Option<u64>,
+  pub row_id: RowId,
+  pub reason: String,
+}
+
+/// A value of the CSV that violates a constraint of its field, in a strict import. The line is
+/// skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CSVImportViolation {
+  /// The line of the CSV, starting from 1 for the header.
+  pub line: u64,
+  pub field_id: String,
+  pub kind: ViolationKind,
+}
+
+/// What an import does, or did once applied.
+#[derive(Debug, Clone, Default)]
+pub struct CSVImportReport {
+  /// The lines of the CSV that are inserted as new rows.
+  pub inserts: Vec<u64>,
+  /// The rows updated from a line of the CSV. The rows whose values are the same as the CSV's
+  /// aren't updated.
+  pub updates: Vec<RowId>,
+  /// The rows deleted because their key isn't in the CSV, see [CSVImportMode::Sync].
+  pub deletes: Vec<RowId>,
+  /// The select options added to the fields, as pairs of field id and option name.
+  pub new_options: Vec<(String, String)>,
+  /// The columns that don't map to a field, or map to a field whose values are computed.
+  pub unmapped_columns: Vec<String>,
+  /// The lines whose key was already used by a previous line of the CSV. They are skipped.
+  pub duplicate_keys: Vec<u64>,
+  pub errors: Vec<CSVImportError>,
+  /// The constraints violated by the lines of a strict import, see [CSVImportOptions::strict].
+  pub violations: Vec<CSVImportViolation>,
+  /// The ids of the inserted rows, set once the import is applied.
+  pub inserted_row_ids: Vec<RowId>,
+  /// The rows that couldn't be inserted, updated or deleted, set once the import is applied.
+  pub failures: Vec<CSVImportFailure>,
+}
+
+/// The changes of a CSV import, computed from the CSV and the rows of the database without
+/// changing the database. Inspect [CSVImportPlan::report] for a dry run, then give the plan to
+/// [Database::apply_csv_import](crate::database::database::Database::apply_csv_import).
+///
+/// The values are converted with the [TypeOptionCellWriter] of their field. The select values
+/// that don't match an option by name add a new option to the field.
+pub struct CSVImportPlan {
+  pub report: CSVImportReport,
+  /// The type options of the fields that get new select options.
+  pub(crate) type_options: Vec<(String, FieldType, TypeOptionData)>,
+  pub(crate) inserts: Vec<Cells>,
+  /// The cells of the updated rows. None clears the cell.
+  pub(crate) updates: Vec<(RowId, Vec<(String, Option<Cell>)>)>,
+}
+
+/// A column of the CSV mapped to a field.
+struct CSVColumn {
+  index: usize,
+  name: String,
+  field: Field,
+  field_type: FieldType,
+}
+
+impl CSVImportPlan {
+  /// Compute the changes of importing the CSV into a database with the given fields and rows.
+  pub(crate) fn new(
+    reader: impl io::Read,
+    options: &CSVImportOptions,
+    fields: &[Field],
+    rows: &[Row],
+  ) -> Result<Self, CollabError> {
+    let mut reader = csv::ReaderBuilder::new()
+      .delimiter(options.delimiter)
+      .from_reader(reader);
+    let headers = reader
+      .headers()
+      .map_err(|_| CollabError::DatabaseInvalidCsv("No header".to_string()))?
+      .iter()
+      .map(|header| header.trim_start_matches('\u{feff}').trim().to_string())
+      .collect::<Vec<_>>();
+    let records = reader
+      .records()
+      .map(|record| {
+        record
+          .map(|record| {
+            let line = record.position().map(|p| p.line()).unwrap_or_default();
+            (line, record.iter().map(str::to_string).collect::<Vec<_>>())
+          })
+          .map_err(|err| CollabError::DatabaseInvalidCsv(err.to_string()))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut report = CSVImportReport::default();
+    let mut columns: Vec<CSVColumn> = vec![];
+    for (index, name) in headers.iter().enumerate() {
+      let field = match options.field_mapping.get(name) {
+        Some(field_id) => fields.iter().find(|field| &field.id == field_id),
+        None => fields.iter().find(|field| &field.name == name).or_else(|| {
+          fields
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+        }),
+      };
+      let field = field.filter(|field| {
+        is_writable(FieldType::from(field.field_type))
+          && !columns.iter().any(|column| column.field.id == field.id)
+      });
+      match field {
+        Some(field) => columns.push(CSVColumn {
+          index,
+          name: name.clone(),
+          field: field.clone(),
+          field_type: FieldType::from(field.field_type),
+        }),
+        None => report.unmapped_columns.push(name.clone()),
+      }
+    }
+
+    let key_column = match (&options.key_field_id, options.mode) {
+      (_, CSVImportMode::AppendOnly) => None,
+      (Some(key_field_id), _) => Some(
+        columns
+          .iter()
+          .position(|column| &column.field.id == key_field_id)
+          .ok_or_else(|| {
+            CollabError::DatabaseImportData(format!(
+              "The key field {} isn't mapped to a column",
+              key_field_id
+            ))
+          })?,
+      ),
+      (None, _) => {
+        return Err(CollabError::DatabaseImportData(
+          "A key field is required to update the rows".to_string(),
+        ));
+      },
+    };
+
+    // Add the select options that don't exist yet, before converting the values
+    let mut type_options = vec![];
+    for column in columns.iter_mut() {
+      if !column.field_type.is_select_option() {
+        continue;
+      }
+      let mut select_type_option = SelectTypeOption::from(
+        column
+          .field
+          .get_any_type_option(column.field_type.type_id())
+          .unwrap_or_default(),
+      );
+      let mut added = false;
+      for (_, record) in records.iter() {
+        let text = record.get(column.index).map(String::as_str).unwrap_or("");
+        for name in option_names(column.field_type, text) {
+          let options = &mut select_type_option.options;
+          if !options.iter().any(|option| option.name == name) {
+            let color = SelectOptionColor::from(options.len() % SELECT_OPTION_COLOR_COUNT);
+            options.push(SelectOption::with_color(name, color));
+            report
+              .new_options
+              .push((column.field.id.clone(), name.to_string()));
+            added = true;
+          }
+        }
+      }
+      if added {
+        let type_option: TypeOptionData = select_type_option.into();
+        column
+          .field
+          .type_options
+          .insert(column.field_type.type_id(), type_option.clone());
+        type_options.push((column.field.id.clone(), column.field_type, type_option));
+      }
+    }
+
+    let readers = columns.iter().map(column_reader).collect::<Vec<_>>();
+    let writers = columns.iter().map(column_writer).collect::<Vec<_>>();
+
+    let mut row_ids_by_key = HashMap::new();
+    if let Some(key_column) = key_column {
+      let column = &columns[key_column];
+      for row in rows {
+        let key = row
+          .cells
+          .get(&column.field.id)
+          .map(|cell| readers[key_column].stringify_cell(cell))
+          .unwrap_or_default();
+        if !key.trim().is_empty() {
+          row_ids_by_key
+            .entry(key.trim().to_string())
+            .or_insert(row.id);
+        }
+      }
+    }
+    let rows_by_id = rows
+      .iter()
+      .map(|row| (row.id, row))
+      .collect::<HashMap<_, _>>();
+
+    let mut inserts = vec![];
+    let mut updates = vec![];
+    let mut update_lines = vec![];
+    let mut seen_keys = HashSet::new();
+    let mut matched_row_ids = HashSet::new();
+    for (line, record) in records.iter() {
+      let key = key_column
+        .and_then(|key_column| record.get(columns[key_column].index))
+        .map(|key| key.trim())
+        .unwrap_or("");
+      if !key.is_empty() && !seen_keys.insert(key.to_string()) {
+        report.duplicate_keys.push(*line);
+        continue;
+      }
+
+      let mut cells = vec![];
+      for ((column, reader), writer) in columns.iter().zip(readers.iter()).zip(writers.iter()) {
+        let text = record
+          .get(column.index)
+          .map(|text| text.trim())
+          .unwrap_or("");
+        if text.is_empty() {
+          cells.push((column, reader, None));
+          continue;
+        }
+        let cell = if column.field_type.is_select_option() {
+          writer.convert_json_to_cell(json!(option_names(column.field_type, text)))
+        } else {
+          writer.convert_json_to_cell(json!(text))
+        };
+        if reader.stringify_cell(&cell).is_empty() {
+          report.errors.push(CSVImportError {
+            line: *line,
+            column: column.name.clone(),
+            value: text.to_string(),
+          });
+          continue;
+        }
+        cells.push((column, reader, Some(cell)));
+      }
+
+      match row_ids_by_key
+        .get(key)
+        .and_then(|row_id| rows_by_id.get(row_id))
+      {
+        Some(row) => {
+          matched_row_ids.insert(row.id);
+          let changed_cells = cells
+            .into_iter()
+            .filter(|(column, reader, cell)| {
+              let current = row.cells.get(&column.field.id);
+              let current_text = current
+                .map(|cell| reader.stringify_cell(cell))
+                .unwrap_or_default();
+              let text = cell
+                .as_ref()
+                .map(|cell| reader.stringify_cell(cell))
+                .unwrap_or_default();
+              current_text != text
+            })
+            .map(|(column, _, cell)| (column.field.id.clone(), cell))
+            .collect::<Vec<_>>();
+          if !changed_cells.is_empty() {
+            report.updates.push(row.id);
+            updates.push((row.id, changed_cells));
+            update_lines.push(*line);
+          }
+        },
+        None => {
+          report.inserts.push(*line);
+          inserts.push(
+            cells
+              .into_iter()
+              .filter_map(|(column, _, cell)| Some((column.field.id.clone(), cell?)))
+              .collect::<Cells>(),
+          );
+        },
+      }
+    }
+
+    if options.mode == CSVImportMode::Sync {
+      report.deletes = rows
+        .iter()
+        .filter(|row| !matched_row_ids.contains(&row.id))
+        .map(|row| row.id)
+        .collect();
+    }
+
+    if options.strict {
+      let fields = fields
+        .iter()
+        .map(|field| {
+          columns
+            .iter()
+            .find(|column| column.field.id == field.id)
+            .map_or(field, |column| &column.field)
+            .clone()
+        })
+        .collect();
+      skip_violations(
+        &mut report,
+        fields,
+        rows,
+        &mut inserts,
+        &mut updates,
+        &update_lines,
+      );
+    }
+
+    Ok(Self {
+      report,
+      type_options,
+      inserts,
+      updates,
+    })
+  }
+}
+
+/// Remove the inserts and the updates that violate a constraint, checked against the rows of the
+/// database as the import leaves them, and report their violations. The updates only fail on the
+/// constraints of the cells they change.
+fn skip_violations(
+  report: &mut CSVImportReport,
+  fields: Vec<Field>,
+  rows: &[Row],
+  inserts: &mut Vec<Cells>,
+  updates: &mut Vec<(RowId, Vec<(String, Option<Cell>)>)>,
+  update_lines: &[u64],
+) {
+  let deletes = report.deletes.iter().collect::<HashSet<_>>();
+  let mut imported_rows = rows
+    .iter()
+    .filter(|row| !deletes.contains(&row.id))
+    .cloned()
+    .collect::<Vec<_>>();
+  for (row_id, cells) in updates.iter() {
+    if let Some(row) = imported_rows.iter_mut().find(|row| &row.id == row_id) {
+      for (field_id, cell) in cells {
+        match cell {
+          Some(cell) => row.cells.insert(field_id.clone(), cell.clone()),
+          None => row.cells.remove(field_id),
+        };
+      }
+    }
+  }
+  let insert_row_ids = inserts.iter().map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  for (row_id, cells) in insert_row_ids.iter().zip(inserts.iter()) {
+    let mut row = Row::empty(*row_id, Uuid::nil());
+    row.cells = cells.clone();
+    imported_rows.push(row);
+  }
+
+  let violations = ConstraintValidator::new(fields).validate_rows(&imported_rows);
+  let mut skipped_inserts = vec![false; inserts.len()];
+  let mut skipped_updates = vec![false; updates.len()];
+  for violation in violations {
+    let line = if let Some(index) = insert_row_ids
+      .iter()
+      .position(|row_id| row_id == &violation.row_id)
+    {
+      skipped_inserts[index] = true;
+      report.inserts[index]
+    } else if let Some(index) = updates.iter().position(|(row_id, cells)| {
+      row_id == &violation.row_id
+        && cells
+          .iter()
+          .any(|(field_id, _)| field_id == &violation.field_id)
+    }) {
+      skipped_updates[index] = true;
+      update_lines[index]
+    } else {
+      continue;
+    };
+    report.violations.push(CSVImportViolation {
+      line,
+      field_id: violation.field_id,
+      kind: violation.kind,
+    });
+  }
+  report.violations.sort_by_key(|violation| violation.line);
+
+  remove_skipped(inserts, &skipped_inserts);
+  remove_skipped(&mut report.inserts, &skipped_inserts);
+  remove_skipped(updates, &skipped_updates);
+  remove_skipped(&mut report.updates, &skipped_updates);
+}
+
+fn remove_skipped<T>(items: &mut Vec<T>, skipped: &[bool]) {
+  *items = std::mem::take(items)
+    .into_iter()
+    .zip(skipped)
+    .filter(|(_, skipped)| !**skipped)
+    .map(|(item, _)| item)
+    .collect();
+}
+
+/// Whether the values of the field can be imported. The other fields compute their values.
+fn is_writable(field_type: FieldType) -> bool {
+  !matches!(
+    field_type,
+    FieldType::CreatedTime
+      | FieldType::LastEditedTime
+      | FieldType::Formula
+      | FieldType::Rollup
+      | FieldType::AutoNumber
+  )
+}
+
+/// Return the names of the select options of a value. A multi-select value holds a name per
+/// comma, while a single-select value is a single name that may contain commas.
+fn option_names(field_type: FieldType, text: &str) -> Vec<&str> {
+  let names = if field_type == FieldType::MultiSelect {
+    text.split(SELECTION_IDS_SEPARATOR).collect()
+  } else {
+    vec![text]
+  };
+  names
+    .into_iter()
+    .map(str::trim)
+    .filter(|name| !name.is_empty())
+    .collect()
+}
+
+fn column_reader(column: &CSVColumn) -> Box<dyn TypeOptionCellReader> {
+  let type_option = column
+    .field
+    .get_any_type_option(column.field_type.type_id())
+    .unwrap_or_default();
+  type_option_cell_reader(type_option, &column.field_type)
+}
+
+fn column_writer(column: &CSVColumn) -> Box<dyn TypeOptionCellWriter> {
+  let type_option = column
+    .field
+    .get_any_type_option(column.field_type.type_id())
+    .unwrap_or_default();
+  type_option_cell_writer(type_option, &column.field_type)
+}
@@ -2,11 +2,14 @@ pub mod builder;
 pub mod check_list_parse;
 pub mod checkbox_parse;
 pub mod csv;
+pub mod csv_export;
+pub mod csv_import;
 pub mod date_parse;
 pub mod entity;
 pub mod media_parse;
 pub mod number_parse;
 pub mod option_parse;
+pub mod person_parse;
 pub mod relation_parse;
 pub mod summary_parse;
 pub mod time_parse;
@@ -0,0 +1,97 @@
+use crate::database::entity::FieldType;
+use crate::database::fields::person_type_option::DatabasePerson;
+use crate::database::fields::select_type_option::SELECTION_IDS_SEPARATOR;
+use crate::database::rows::{Cell, new_cell_builder};
+use crate::database::template::entity::CELL_DATA;
+use crate::database::template::util::{ToCellString, TypeOptionCellData};
+use crate::preclude::Any;
+use serde::{Deserialize, Serialize};
+
+/// The ids of the persons in a Person cell. The cell stores them as a string holding a JSON
+/// array.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersonCellData {
+  pub person_ids: Vec<String>,
+}
+
+impl PersonCellData {
+  pub fn new(person_ids: Vec<String>) -> Self {
+    Self { person_ids }
+  }
+
+  pub fn to_cell(&self) -> Cell {
+    let data = serde_json::to_string(&self.person_ids).unwrap_or_default();
+    let mut cell = new_cell_builder(FieldType::Person);
+    cell.insert(CELL_DATA.into(), data.into());
+    cell
+  }
+}
+
+impl TypeOptionCellData for PersonCellData {
+  fn is_cell_empty(&self) -> bool {
+    self.person_ids.is_empty()
+  }
+}
+
+impl From<&str> for PersonCellData {
+  /// Parse a JSON array of ids, or comma separated ids.
+  fn from(s: &str) -> Self {
+    let person_ids = serde_json::from_str::<Vec<String>>(s).unwrap_or_else(|_| {
+      s.split(SELECTION_IDS_SEPARATOR)
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .collect()
+    });
+    Self { person_ids }
+  }
+}
+
+impl From<&Cell> for PersonCellData {
+  /// Older cells store the ids as an array instead of a string.
+  fn from(cell: &Cell) -> Self {
+    match cell.get(CELL_DATA) {
+      Some(Any::Array(ids)) => Self {
+        person_ids: ids
+          .iter()
+          .flat_map(|id| match id {
+            Any::String(id) => Some(id.to_string()),
+            Any::BigInt(id) => Some(id.to_string()),
+            _ => None,
+          })
+          .collect(),
+      },
+      Some(Any::String(data)) => Self::from(data.as_ref()),
+      _ => Self::default(),
+    }
+  }
+}
+
+impl From<PersonCellData> for Cell {
+  fn from(value: PersonCellData) -> Self {
+    value.to_cell()
+  }
+}
+
+impl ToCellString for PersonCellData {
+  fn to_cell_string(&self) -> String {
+    self.person_ids.join(", ")
+  }
+}
+
+/// Collect the persons of the cells that hold a JSON array of [DatabasePerson]s, so that their
+/// names can be resolved. The cells holding ids only are skipped.
+pub(crate) fn build_persons_from_cells(cells: &[String]) -> Vec<DatabasePerson> {
+  let mut persons: Vec<DatabasePerson> = vec![];
+  for cell in cells {
+    let Ok(cell_persons) = serde_json::from_str::<Vec<DatabasePerson>>(cell) else {
+      continue;
+    };
+    for person in cell_persons {
+      if !person.id.is_empty() && persons.iter().all(|other| other.id != person.id) {
+        persons.push(person);
+      }
+    }
+  }
+  persons
+}
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::database::entity::FieldType;
+use crate::database::fields::Field;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::rows::{Cell, Row};
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::timeline::TimelineBar;
+use crate::database::views::TimelineLayoutSetting;
+use crate::entity::uuid_validation::RowId;
+
+/// Computes the bars of a timeline view from its [TimelineLayoutSetting], and keeps the rows'
+/// dates in line with their dependencies.
+///
+/// A row's bar starts at its start field's date and ends at its end field's date, or at the end
+/// of the start field's range when the setting has no end field. The rows of a row's dependency
+/// cell are its predecessors: the row is expected to start once they end.
+pub struct TimelineEvaluator {
+  start_field_id: String,
+  end_field_id: Option<String>,
+  dependency_field_id: Option<String>,
+}
+
+impl TimelineEvaluator {
+  /// Return None if the start field isn't a date field. An end field that isn't a date field,
+  /// or a dependency field that isn't a relation field, is ignored.
+  pub fn new(setting: &TimelineLayoutSetting, fields: &[Field]) -> Option<Self> {
+    let field_of_type = |field_id: &str, field_type: FieldType| {
+      fields
+        .iter()
+        .find(|field| field.id == field_id && FieldType::from(field.field_type) == field_type)
+        .map(|field| field.id.clone())
+    };
+    let start_field_id = field_of_type(&setting.start_field_id, FieldType::DateTime)?;
+    let end_field_id = field_of_type(&setting.end_field_id, FieldType::DateTime)
+      .filter(|field_id| field_id != &start_field_id);
+    let dependency_field_id = setting
+      .dependency_field_id
+      .as_ref()
+      .and_then(|field_id| field_of_type(field_id, FieldType::Relation));
+    Some(Self {
+      start_field_id,
+      end_field_id,
+      dependency_field_id,
+    })
+  }
+
+  /// Return the bars of the rows that have a start date, in the order of the rows. The
+  /// predecessors of a bar are limited to the given rows.
+  pub fn bars(&self, rows: &[Row]) -> Vec<TimelineBar> {
+    let row_ids = rows.iter().map(|row| row.id).collect::<HashSet<_>>();
+    rows
+      .iter()
+      .flat_map(|row| {
+        let start_cell = DateCellData::from(row.cells.get(&self.start_field_id)?);
+        let start = start_cell.timestamp?;
+        let end = match &self.end_field_id {
+          Some(field_id) => row
+            .cells
+            .get(field_id)
+            .and_then(|cell| DateCellData::from(cell).timestamp),
+          None => start_cell.end_timestamp.filter(|_| start_cell.is_range),
+        };
+        let predecessors = self
+          .predecessors(row)
+          .into_iter()
+          .filter(|row_id| row_ids.contains(row_id))
+          .collect();
+        Some(TimelineBar {
+          row_id: row.id,
+          start,
+          end: end.unwrap_or(start).max(start),
+          predecessors,
+        })
+      })
+      .collect()
+  }
+
+  /// Return the rows of a dependency cycle, each depending on the next one and the last one on
+  /// the first one, or None if the dependencies of the rows have no cycle.
+  pub fn find_cycle(&self, rows: &[Row]) -> Option<Vec<RowId>> {
+    let graph = rows
+      .iter()
+      .map(|row| (row.id, self.predecessors(row)))
+      .collect::<HashMap<_, _>>();
+    find_cycle(&graph, rows.iter().map(|row| row.id))
+  }
+
+  /// Move the bar of `row_id` to `[start, end]`, and push the bars that depend on it, directly or
+  /// not, so that each one starts once its predecessors end. The pushed bars keep their length,
+  /// and the bars that don't need to move are left untouched. Return the bars that changed,
+  /// starting with the moved one.
+  ///
+  /// Return the rows of a dependency cycle if the bars have one.
+  pub fn move_bar(
+    bars: &[TimelineBar],
+    row_id: &RowId,
+    start: i64,
+    end: i64,
+  ) -> Result<Vec<TimelineBar>, Vec<RowId>> {
+    let graph = bars
+      .iter()
+      .map(|bar| (bar.row_id, bar.predecessors.clone()))
+      .collect::<HashMap<_, _>>();
+    if let Some(cycle) = find_cycle(&graph, bars.iter().map(|bar| bar.row_id)) {
+      return Err(cycle);
+    }
+
+    let mut bars_by_id = bars
+      .iter()
+      .map(|bar| (bar.row_id, bar.clone()))
+      .collect::<HashMap<_, _>>();
+    let Some(moved) = bars_by_id.get_mut(row_id) else {
+      return Ok(vec![]);
+    };
+    moved.start = start;
+    moved.end = end.max(start);
+    let mut changed = vec![*row_id];
+
+    let mut successors = HashMap::<RowId, Vec<RowId>>::new();
+    for bar in bars {
+      for predecessor in &bar.predecessors {
+        successors.entry(*predecessor).or_default().push(bar.row_id);
+      }
+    }
+    // A bar is checked again each time one of its predecessors is pushed. The graph has no
+    // cycle, so this ends
+    let mut queue = VecDeque::from([*row_id]);
+    while let Some(predecessor) = queue.pop_front() {
+      for successor in successors.get(&predecessor).into_iter().flatten() {
+        let required_start = bars_by_id[successor]
+          .predecessors
+          .iter()
+          .flat_map(|row_id| bars_by_id.get(row_id))
+          .map(|bar| bar.end)
+          .max()
+          .unwrap_or(i64::MIN);
+        let Some(bar) = bars_by_id.get_mut(successor) else {
+          continue;
+        };
+        if bar.start < required_start {
+          let shift = required_start - bar.start;
+          bar.start += shift;
+          bar.end += shift;
+          if !changed.contains(successor) {
+            changed.push(*successor);
+          }
+          queue.push_back(*successor);
+        }
+      }
+    }
+    Ok(
+      changed
+        .into_iter()
+        .flat_map(|row_id| bars_by_id.remove(&row_id))
+        .collect(),
+    )
+  }
+
+  /// Return the cells to write to the row to move its bar to `[start, end]`. The other data of
+  /// the date cells, like whether they include the time, is kept.
+  pub fn cells_for_bar(&self, row: &Row, start: i64, end: i64) -> Vec<(String, Cell)> {
+    let date_cell = |field_id: &str| {
+      row
+        .cells
+        .get(field_id)
+        .map(DateCellData::from)
+        .unwrap_or_default()
+    };
+    let mut start_cell = date_cell(&self.start_field_id);
+    start_cell.timestamp = Some(start);
+    match &self.end_field_id {
+      Some(end_field_id) => {
+        let mut end_cell = date_cell(end_field_id);
+        end_cell.timestamp = Some(end);
+        vec![
+          (self.start_field_id.clone(), Cell::from(&start_cell)),
+          (end_field_id.clone(), Cell::from(&end_cell)),
+        ]
+      },
+      None => {
+        if start_cell.is_range || end > start {
+          start_cell.is_range = true;
+          start_cell.end_timestamp = Some(end.max(start));
+        }
+        vec![(self.start_field_id.clone(), Cell::from(&start_cell))]
+      },
+    }
+  }
+
+  fn predecessors(&self, row: &Row) -> Vec<RowId> {
+    self
+      .dependency_field_id
+      .as_ref()
+      .and_then(|field_id| row.cells.get(field_id))
+      .map(|cell| RelationCellData::from(cell).row_ids)
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|row_id| row_id != &row.id)
+      .collect()
+  }
+}
+
+/// Return a cycle of the graph, mapping each row to the rows it depends on. The rows are visited
+/// in the given order, so the same graph always gives the same cycle.
+fn find_cycle(
+  graph: &HashMap<RowId, Vec<RowId>>,
+  row_ids: impl IntoIterator<Item = RowId>,
+) -> Option<Vec<RowId>> {
+  let mut visited = HashSet::new();
+  for root in row_ids {
+    if visited.contains(&root) {
+      continue;
+    }
+    // Depth first search, keeping the path from the root and the next edge of each row of it
+    let mut path = vec![(root, 0)];
+    visited.insert(root);
+    while let Some((row_id, next)) = path.last_mut() {
+      let row_id = *row_id;
+      let Some(predecessor) = graph.get(&row_id).and_then(|edges| edges.get(*next)) else {
+        path.pop();
+        continue;
+      };
+      *next += 1;
+      if let Some(position) = path.iter().position(|(id, _)| id == predecessor) {
+        return Some(path[position..].iter().map(|(id, _)| *id).collect());
+      }
+      if visited.insert(*predecessor) {
+        path.push((*predecessor, 0));
+      }
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use uuid::Uuid;
+
+  fn bar(row_id: RowId, start: i64, end: i64, predecessors: Vec<RowId>) -> TimelineBar {
+    TimelineBar {
+      row_id,
+      start,
+      end,
+      predecessors,
+    }
+  }
+
+  #[test]
+  fn find_cycle_test() {
+    let ids = (0..4).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+    let mut graph = HashMap::from([
+      (ids[0], vec![ids[1]]),
+      (ids[1], vec![ids[2]]),
+      (ids[2], vec![]),
+      (ids[3], vec![ids[0], ids[2]]),
+    ]);
+    assert_eq!(find_cycle(&graph, ids.clone()), None);
+
+    graph.insert(ids[2], vec![ids[3]]);
+    assert_eq!(
+      find_cycle(&graph, ids.clone()),
+      Some(vec![ids[0], ids[1], ids[2], ids[3]])
+    );
+  }
+
+  #[test]
+  fn move_bar_pushes_successors_test() {
+    let ids = (0..4).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+    let bars = vec![
+      bar(ids[0], 0, 10, vec![]),
+      bar(ids[1], 10, 15, vec![ids[0]]),
+      // Starts well after its predecessor, so a small push of it has no effect
+      bar(ids[2], 30, 40, vec![ids[1]]),
+      bar(ids[3], 0, 5, vec![]),
+    ];
+    let changed = TimelineEvaluator::move_bar(&bars, &ids[0], 5, 20).unwrap();
+    assert_eq!(
+      changed,
+      vec![
+        bar(ids[0], 5, 20, vec![]),
+        bar(ids[1], 20, 25, vec![ids[0]])
+      ]
+    );
+
+    let changed = TimelineEvaluator::move_bar(&bars, &ids[0], 20, 30).unwrap();
+    assert_eq!(
+      changed.iter().map(|bar| bar.start).collect::<Vec<_>>(),
+      vec![20, 30, 35]
+    );
+  }
+}
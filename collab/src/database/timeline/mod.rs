@@ -0,0 +1,5 @@
+mod evaluator;
+mod timeline;
+
+pub use evaluator::*;
+pub use timeline::*;
@@ -0,0 +1,18 @@
+use crate::entity::uuid_validation::RowId;
+
+/// The bar of a row in a timeline view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineBar {
+  pub row_id: RowId,
+  pub start: i64,
+  /// Equal to `start` for the rows without an end date, shown as milestones.
+  pub end: i64,
+  /// The rows of the view this row depends on: it starts once they end.
+  pub predecessors: Vec<RowId>,
+}
+
+impl TimelineBar {
+  pub fn is_milestone(&self) -> bool {
+    self.start == self.end
+  }
+}
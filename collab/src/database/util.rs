@@ -1,7 +1,9 @@
 use crate::entity::CollabType;
 use crate::entity::EncodedCollab;
 use crate::error::CollabError;
-use crate::preclude::Collab;
+use crate::preclude::{Any, Collab};
+use std::collections::HashMap;
+
 pub(crate) fn encoded_collab(
   collab: &Collab,
   collab_type: &CollabType,
@@ -10,3 +12,14 @@ pub(crate) fn encoded_collab(
     collab.encode_collab_v1(|collab| collab_type.validate_require_data(collab))?;
   Ok(encoded_collab)
 }
+
+/// Read an integer from a map written by any client. Numbers may be stored as floats or
+/// strings depending on the client that wrote them.
+pub(crate) fn get_i64_lenient(map: &HashMap<String, Any>, key: &str) -> Option<i64> {
+  match map.get(key)? {
+    Any::BigInt(value) => Some(*value),
+    Any::Number(value) => Some(*value as i64),
+    Any::String(value) => value.parse().ok(),
+    _ => None,
+  }
+}
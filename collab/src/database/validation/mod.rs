@@ -0,0 +1,5 @@
+mod validator;
+mod violation;
+
+pub use validator::*;
+pub use violation::*;
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use fancy_regex::Regex;
+
+use crate::database::entity::FieldType;
+use crate::database::fields::date_type_option::DateCellData;
+use crate::database::fields::media_type_option::MediaCellData;
+use crate::database::fields::select_type_option::SelectOptionIds;
+use crate::database::fields::{
+  Field, FieldConstraints, TypeOptionCellReader, type_option_cell_reader,
+};
+use crate::database::rows::{Cell, Row};
+use crate::database::template::check_list_parse::ChecklistCellData;
+use crate::database::template::person_parse::PersonCellData;
+use crate::database::template::relation_parse::RelationCellData;
+use crate::database::template::util::TypeOptionCellData;
+use crate::database::validation::{ConstraintViolation, ViolationKind};
+use crate::entity::uuid_validation::RowId;
+
+/// Checks rows against the [FieldConstraints] of their fields.
+///
+/// The values of computed fields, like the created time or formulas, aren't checked. A pattern
+/// that isn't a valid regular expression fails every non-empty text with
+/// [ViolationKind::InvalidPattern]. Two values are the same for a unique field when their
+/// readable texts are equal, ignoring the surrounding whitespace.
+pub struct ConstraintValidator {
+  fields: Vec<ConstrainedField>,
+}
+
+struct ConstrainedField {
+  field_id: String,
+  field_type: FieldType,
+  constraints: FieldConstraints,
+  /// The compiled pattern, or the error of a pattern that isn't a valid regular expression.
+  pattern: Option<Result<Regex, String>>,
+  reader: Box<dyn TypeOptionCellReader>,
+}
+
+impl ConstraintValidator {
+  pub fn new(fields: Vec<Field>) -> Self {
+    let fields = fields
+      .into_iter()
+      .filter(|field| !field.constraints.is_empty())
+      .flat_map(|field| {
+        let field_type = FieldType::from(field.field_type);
+        if is_computed(&field_type) {
+          return None;
+        }
+        let pattern = field
+          .constraints
+          .pattern
+          .as_ref()
+          .filter(|_| is_text(&field_type))
+          .map(|pattern| compile_pattern(pattern));
+        let type_option = field
+          .get_any_type_option(field_type.type_id())
+          .unwrap_or_default();
+        let reader = type_option_cell_reader(type_option, &field_type);
+        Some(ConstrainedField {
+          field_id: field.id,
+          field_type,
+          constraints: field.constraints,
+          pattern,
+          reader,
+        })
+      })
+      .collect();
+    Self { fields }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Return true if a field is unique, so the other rows of the database are needed to check a
+  /// row.
+  pub fn has_unique_field(&self) -> bool {
+    self.fields.iter().any(|field| field.constraints.unique)
+  }
+
+  /// Return the violations of the row. `rows` are the rows of the database the row's unique
+  /// values are compared with; a row of the list with the row's id is skipped.
+  pub fn validate_row(&self, row: &Row, rows: &[Row]) -> Vec<ConstraintViolation> {
+    let rows = rows
+      .iter()
+      .filter(|other| other.id != row.id)
+      .chain(std::iter::once(row));
+    let unique_values = self.unique_values(rows);
+    self.row_violations(row, &unique_values)
+  }
+
+  /// Return the violations of the rows, in the order of the rows and of the fields.
+  pub fn validate_rows(&self, rows: &[Row]) -> Vec<ConstraintViolation> {
+    let unique_values = self.unique_values(rows.iter());
+    rows
+      .iter()
+      .flat_map(|row| self.row_violations(row, &unique_values))
+      .collect()
+  }
+
+  /// Return the rows of each value of the unique fields, by field id.
+  fn unique_values<'a>(
+    &self,
+    rows: impl Iterator<Item = &'a Row>,
+  ) -> HashMap<&str, HashMap<String, Vec<RowId>>> {
+    let mut unique_values = HashMap::<&str, HashMap<String, Vec<RowId>>>::new();
+    for row in rows {
+      for field in self.fields.iter().filter(|field| field.constraints.unique) {
+        if let Some(value) = field.unique_value(row.cells.get(&field.field_id)) {
+          unique_values
+            .entry(field.field_id.as_str())
+            .or_default()
+            .entry(value)
+            .or_default()
+            .push(row.id);
+        }
+      }
+    }
+    unique_values
+  }
+
+  fn row_violations(
+    &self,
+    row: &Row,
+    unique_values: &HashMap<&str, HashMap<String, Vec<RowId>>>,
+  ) -> Vec<ConstraintViolation> {
+    let mut violations = vec![];
+    for field in &self.fields {
+      let cell = row
+        .cells
+        .get(&field.field_id)
+        .filter(|cell| !field.is_empty(cell));
+      let kinds = match cell {
+        None if field.constraints.required => vec![ViolationKind::Required],
+        None => vec![],
+        Some(cell) => {
+          let mut kinds = field.value_violations(cell);
+          let duplicates = field
+            .unique_value(Some(cell))
+            .and_then(|value| unique_values.get(field.field_id.as_str())?.get(&value))
+            .map(|row_ids| {
+              row_ids
+                .iter()
+                .filter(|row_id| *row_id != &row.id)
+                .copied()
+                .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+          if !duplicates.is_empty() {
+            kinds.push(ViolationKind::Duplicate {
+              row_ids: duplicates,
+            });
+          }
+          kinds
+        },
+      };
+      violations.extend(kinds.into_iter().map(|kind| ConstraintViolation {
+        row_id: row.id,
+        field_id: field.field_id.clone(),
+        kind,
+      }));
+    }
+    violations
+  }
+}
+
+impl ConstrainedField {
+  fn is_empty(&self, cell: &Cell) -> bool {
+    match self.field_type {
+      FieldType::DateTime => DateCellData::from(cell).timestamp.is_none(),
+      FieldType::Checkbox => self.reader.numeric_cell(cell) != Some(1.0),
+      FieldType::SingleSelect | FieldType::MultiSelect => SelectOptionIds::from(cell).is_empty(),
+      FieldType::Checklist => ChecklistCellData::from(cell).options.is_empty(),
+      FieldType::Media => MediaCellData::from(cell).files.is_empty(),
+      FieldType::Relation => RelationCellData::from(cell).row_ids.is_empty(),
+      FieldType::Person => PersonCellData::from(cell).is_cell_empty(),
+      _ => self.reader.stringify_cell(cell).trim().is_empty(),
+    }
+  }
+
+  /// Return the value compared for uniqueness, or None if the field isn't unique or the cell is
+  /// empty.
+  fn unique_value(&self, cell: Option<&Cell>) -> Option<String> {
+    if !self.constraints.unique {
+      return None;
+    }
+    let cell = cell.filter(|cell| !self.is_empty(cell))?;
+    Some(self.reader.stringify_cell(cell).trim().to_string())
+  }
+
+  /// Return the violations of the non-empty cell's value.
+  fn value_violations(&self, cell: &Cell) -> Vec<ViolationKind> {
+    let constraints = &self.constraints;
+    let mut kinds = vec![];
+    match self.field_type {
+      FieldType::Number => {
+        if let Some(value) = self.reader.numeric_cell(cell) {
+          if let Some(min) = constraints.min.filter(|min| value < *min) {
+            kinds.push(ViolationKind::BelowMin { min, value });
+          }
+          if let Some(max) = constraints.max.filter(|max| value > *max) {
+            kinds.push(ViolationKind::AboveMax { max, value });
+          }
+        }
+      },
+      FieldType::RichText | FieldType::URL => {
+        let text = self.reader.stringify_cell(cell);
+        match &self.pattern {
+          Some(Ok(pattern)) => {
+            if !pattern.is_match(&text).unwrap_or(true) {
+              kinds.push(ViolationKind::PatternMismatch {
+                pattern: constraints.pattern.clone().unwrap_or_default(),
+              });
+            }
+          },
+          Some(Err(error)) => kinds.push(ViolationKind::InvalidPattern {
+            pattern: constraints.pattern.clone().unwrap_or_default(),
+            error: error.clone(),
+          }),
+          None => {},
+        }
+        let length = text.chars().count();
+        if let Some(max_length) = constraints
+          .max_length
+          .filter(|max_length| length > *max_length as usize)
+        {
+          kinds.push(ViolationKind::TooLong { max_length, length });
+        }
+      },
+      FieldType::DateTime => {
+        let cell_data = DateCellData::from(cell);
+        let timestamps = cell_data
+          .timestamp
+          .into_iter()
+          .chain(cell_data.end_timestamp.filter(|_| cell_data.is_range));
+        for timestamp in timestamps {
+          if let Some(min_date) = constraints.min_date.filter(|min| timestamp < *min) {
+            kinds.push(ViolationKind::DateBeforeMin {
+              min_date,
+              timestamp,
+            });
+          }
+          if let Some(max_date) = constraints.max_date.filter(|max| timestamp > *max) {
+            kinds.push(ViolationKind::DateAfterMax {
+              max_date,
+              timestamp,
+            });
+          }
+        }
+      },
+      FieldType::MultiSelect => {
+        let count = SelectOptionIds::from(cell).len();
+        if let Some(max_selected_options) = constraints
+          .max_selected_options
+          .filter(|max| count > *max as usize)
+        {
+          kinds.push(ViolationKind::TooManyOptions {
+            max_selected_options,
+            count,
+          });
+        }
+      },
+      _ => {},
+    }
+    kinds
+  }
+}
+
+fn is_computed(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::CreatedTime
+      | FieldType::LastEditedTime
+      | FieldType::AutoNumber
+      | FieldType::Formula
+      | FieldType::Rollup
+  )
+}
+
+fn is_text(field_type: &FieldType) -> bool {
+  matches!(field_type, FieldType::RichText | FieldType::URL)
+}
+
+/// Compile the pattern so that it must match the whole text.
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+  Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::rows::new_cell_builder;
+  use crate::database::template::entity::CELL_DATA;
+  use uuid::Uuid;
+
+  fn text_cell(field_type: FieldType, text: &str) -> Cell {
+    let mut cell = new_cell_builder(field_type);
+    cell.insert(CELL_DATA.into(), text.into());
+    cell
+  }
+
+  fn row(cells: Vec<(&str, Cell)>) -> Row {
+    let mut row = Row::empty(Uuid::new_v4(), Uuid::new_v4());
+    for (field_id, cell) in cells {
+      row.cells.insert(field_id.to_string(), cell);
+    }
+    row
+  }
+
+  #[test]
+  fn text_constraints_test() {
+    let field = Field::new(
+      "code".to_string(),
+      "Code".to_string(),
+      FieldType::RichText.into(),
+      false,
+    )
+    .with_constraints(
+      FieldConstraints::new()
+        .with_required()
+        .with_pattern("[A-Z]{2}-\\d+")
+        .with_max_length(6),
+    );
+    let validator = ConstraintValidator::new(vec![field]);
+    let kinds = |row: &Row| {
+      validator
+        .validate_row(row, &[])
+        .into_iter()
+        .map(|violation| violation.kind)
+        .collect::<Vec<_>>()
+    };
+
+    assert!(
+      kinds(&row(vec![(
+        "code",
+        text_cell(FieldType::RichText, "AB-12")
+      )]))
+      .is_empty()
+    );
+    assert_eq!(
+      kinds(&row(vec![("code", text_cell(FieldType::RichText, " "))])),
+      vec![ViolationKind::Required]
+    );
+    assert_eq!(kinds(&row(vec![])), vec![ViolationKind::Required]);
+    // The pattern must match the whole text
+    assert_eq!(
+      kinds(&row(vec![(
+        "code",
+        text_cell(FieldType::RichText, "AB-1234")
+      )])),
+      vec![ViolationKind::TooLong {
+        max_length: 6,
+        length: 7
+      }]
+    );
+    assert_eq!(
+      kinds(&row(vec![(
+        "code",
+        text_cell(FieldType::RichText, "x AB-1")
+      )])),
+      vec![ViolationKind::PatternMismatch {
+        pattern: "[A-Z]{2}-\\d+".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn invalid_pattern_is_reported_test() {
+    let field = Field::new(
+      "code".to_string(),
+      "Code".to_string(),
+      FieldType::RichText.into(),
+      false,
+    )
+    .with_constraints(FieldConstraints::new().with_pattern("(unclosed"));
+    let validator = ConstraintValidator::new(vec![field]);
+    let violations = validator.validate_row(
+      &row(vec![("code", text_cell(FieldType::RichText, "anything"))]),
+      &[],
+    );
+    assert_eq!(violations.len(), 1);
+    assert!(matches!(
+      &violations[0].kind,
+      ViolationKind::InvalidPattern { pattern, .. } if pattern == "(unclosed"
+    ));
+    // Empty cells aren't checked against the pattern
+    assert!(validator.validate_row(&row(vec![]), &[]).is_empty());
+  }
+}
@@ -0,0 +1,53 @@
+use crate::entity::uuid_validation::RowId;
+
+/// A cell that doesn't satisfy one of its field's
+/// [FieldConstraints](crate::database::fields::FieldConstraints).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintViolation {
+  pub row_id: RowId,
+  pub field_id: String,
+  pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+  /// The cell of a required field is empty.
+  Required,
+  /// Other rows have the same value in a unique field.
+  Duplicate {
+    row_ids: Vec<RowId>,
+  },
+  BelowMin {
+    min: f64,
+    value: f64,
+  },
+  AboveMax {
+    max: f64,
+    value: f64,
+  },
+  /// The text doesn't match the field's pattern.
+  PatternMismatch {
+    pattern: String,
+  },
+  /// The field's pattern isn't a valid regular expression, so the text can't be checked.
+  InvalidPattern {
+    pattern: String,
+    error: String,
+  },
+  TooLong {
+    max_length: u32,
+    length: usize,
+  },
+  DateBeforeMin {
+    min_date: i64,
+    timestamp: i64,
+  },
+  DateAfterMax {
+    max_date: i64,
+    timestamp: i64,
+  },
+  TooManyOptions {
+    max_selected_options: u32,
+    count: usize,
+  },
+}
@@ -21,6 +21,7 @@ pub enum DatabaseLayout {
   List = 4,
   Gallery = 5,
   Feed = 6,
+  Timeline = 7,
 }
 
 impl DatabaseLayout {
@@ -39,6 +40,10 @@ impl DatabaseLayout {
   pub fn is_feed(&self) -> bool {
     matches!(self, DatabaseLayout::Feed)
   }
+
+  pub fn is_timeline(&self) -> bool {
+    matches!(self, DatabaseLayout::Timeline)
+  }
 }
 
 impl AsRef<str> for DatabaseLayout {
@@ -51,6 +56,7 @@ impl AsRef<str> for DatabaseLayout {
       DatabaseLayout::List => "4",
       DatabaseLayout::Gallery => "5",
       DatabaseLayout::Feed => "6",
+      DatabaseLayout::Timeline => "7",
     }
   }
 }
@@ -67,6 +73,7 @@ impl FromStr for DatabaseLayout {
       "4" => Ok(DatabaseLayout::List),
       "5" => Ok(DatabaseLayout::Gallery),
       "6" => Ok(DatabaseLayout::Feed),
+      "7" => Ok(DatabaseLayout::Timeline),
       _ => bail!("Invalid layout type"),
     }
   }
@@ -88,6 +95,7 @@ impl From<i64> for DatabaseLayout {
       4 => DatabaseLayout::List,
       5 => DatabaseLayout::Gallery,
       6 => DatabaseLayout::Feed,
+      7 => DatabaseLayout::Timeline,
       _ => Self::default(),
     }
   }
@@ -138,6 +138,13 @@ pub struct ChartLayoutSetting {
   pub aggregation_type: ChartAggregationType,
   #[serde(default)]
   pub y_field_id: String,
+  /// How the rows are bucketed when the X field is a date field.
+  #[serde(default)]
+  pub date_granularity: ChartDateGranularity,
+  /// The size of the ranges the rows are bucketed by when the X field is a number field. A size
+  /// of 0 lets the chart pick a round size giving about ten ranges.
+  #[serde(default)]
+  pub number_range_size: f64,
 }
 
 impl ChartLayoutSetting {
@@ -148,6 +155,8 @@ impl ChartLayoutSetting {
       show_empty_values: false,
       aggregation_type: ChartAggregationType::Count,
       y_field_id: String::new(),
+      date_granularity: ChartDateGranularity::Month,
+      number_range_size: 0.0,
     }
   }
 }
@@ -169,6 +178,14 @@ impl From<ChartLayoutSetting> for LayoutSetting {
         Any::BigInt(setting.aggregation_type.value()),
       ),
       ("y_field_id".into(), setting.y_field_id.into()),
+      (
+        "date_granularity".into(),
+        Any::BigInt(setting.date_granularity.value()),
+      ),
+      (
+        "number_range_size".into(),
+        Any::Number(setting.number_range_size),
+      ),
     ])
   }
 }
@@ -231,6 +248,35 @@ impl ChartAggregationType {
   }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum ChartDateGranularity {
+  Day = 0,
+  /// Weeks start on Monday.
+  Week = 1,
+  #[default]
+  Month = 2,
+  Year = 3,
+}
+
+impl From<i64> for ChartDateGranularity {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => ChartDateGranularity::Day,
+      1 => ChartDateGranularity::Week,
+      2 => ChartDateGranularity::Month,
+      3 => ChartDateGranularity::Year,
+      _ => ChartDateGranularity::Month,
+    }
+  }
+}
+
+impl ChartDateGranularity {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum GalleryCardSize {
@@ -420,3 +466,86 @@ impl ListDisplayMode {
     *self as i64
   }
 }
+
+/// Layout settings for Timeline view.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TimelineLayoutSetting {
+  /// The date field the bars start at.
+  #[serde(default)]
+  pub start_field_id: String,
+  /// The date field the bars end at. When empty, the bars end at the end of the start field's
+  /// date range.
+  #[serde(default)]
+  pub end_field_id: String,
+  #[serde(default)]
+  pub zoom: TimelineZoom,
+  /// The relation field, relating the database to itself, holding the rows a row depends on.
+  #[serde(default)]
+  pub dependency_field_id: Option<String>,
+}
+
+impl TimelineLayoutSetting {
+  pub fn new(start_field_id: String) -> Self {
+    Self {
+      start_field_id,
+      end_field_id: String::new(),
+      zoom: TimelineZoom::Week,
+      dependency_field_id: None,
+    }
+  }
+}
+
+impl From<LayoutSetting> for TimelineLayoutSetting {
+  fn from(setting: LayoutSetting) -> Self {
+    from_any(&Any::from(setting)).unwrap_or_default()
+  }
+}
+
+impl From<TimelineLayoutSetting> for LayoutSetting {
+  fn from(setting: TimelineLayoutSetting) -> Self {
+    let mut result = LayoutSetting::from([
+      ("start_field_id".into(), setting.start_field_id.into()),
+      ("end_field_id".into(), setting.end_field_id.into()),
+      ("zoom".into(), Any::BigInt(setting.zoom.value())),
+    ]);
+
+    if let Some(dependency_field_id) = setting.dependency_field_id {
+      result.insert(
+        "dependency_field_id".to_string(),
+        dependency_field_id.into(),
+      );
+    }
+
+    result
+  }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum TimelineZoom {
+  Day = 0,
+  #[default]
+  Week = 1,
+  Month = 2,
+  Quarter = 3,
+  Year = 4,
+}
+
+impl From<i64> for TimelineZoom {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => TimelineZoom::Day,
+      1 => TimelineZoom::Week,
+      2 => TimelineZoom::Month,
+      3 => TimelineZoom::Quarter,
+      4 => TimelineZoom::Year,
+      _ => TimelineZoom::Week,
+    }
+  }
+}
+
+impl TimelineZoom {
+  pub fn value(&self) -> i64 {
+    *self as i64
+  }
+}
@@ -162,6 +162,13 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
     self
   }
 
+  /// Replace the row orders of the view, unlike [DatabaseViewUpdate::set_row_orders] which
+  /// appends them.
+  pub fn replace_row_orders(self, orders: Vec<RowOrder>) -> Self {
+    self.map_ref.remove(self.txn, DATABASE_VIEW_ROW_ORDERS);
+    self.set_row_orders(orders)
+  }
+
   pub fn remove_row_order(self, id: &str) -> Self {
     if let Some(array) = self
       .map_ref
@@ -234,6 +241,13 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
     self
   }
 
+  /// Replace the field orders of the view, unlike [DatabaseViewUpdate::set_field_orders] which
+  /// appends them.
+  pub fn replace_field_orders(self, orders: Vec<FieldOrder>) -> Self {
+    self.map_ref.remove(self.txn, DATABASE_VIEW_FIELD_ORDERS);
+    self.set_field_orders(orders)
+  }
+
   pub fn remove_field_order(self, id: &str) -> Self {
     if let Some(array) = self
       .map_ref
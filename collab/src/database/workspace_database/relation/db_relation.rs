@@ -1,11 +1,13 @@
 use crate::lock::Mutex;
 use crate::preclude::{Collab, Map};
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::database::workspace_database::relation::RowRelationMap;
+use crate::database::workspace_database::relation::{LinkedByRow, RowRelationMap};
 
+/// Records the links between the rows of the databases of a workspace, so that the rows linking
+/// to a row can be found without opening every database.
 pub struct DatabaseRelation {
-  #[allow(dead_code)]
   inner: Arc<Mutex<Collab>>,
   row_relation_map: RowRelationMap,
 }
@@ -26,7 +28,58 @@ impl DatabaseRelation {
     }
   }
 
+  /// Create the relation from a collab that isn't shared yet, without locking it. Unlike
+  /// [DatabaseRelation::new], it can be called from an async context.
+  pub fn from_collab(mut collab: Collab) -> DatabaseRelation {
+    let relation_map = {
+      let mut txn = collab.context.transact_mut();
+      collab.data.get_or_init(&mut txn, ROW_RELATION_MAP)
+    };
+
+    Self {
+      inner: Arc::new(Mutex::new(collab)),
+      row_relation_map: RowRelationMap::from_map_ref(relation_map),
+    }
+  }
+
   pub fn row_relations(&self) -> &RowRelationMap {
     &self.row_relation_map
   }
+
+  /// See [RowRelationMap::set_linking_rows_with_txn].
+  pub async fn set_linking_rows(
+    &self,
+    linking_database_id: &str,
+    linked_by_database_id: &str,
+    row_id: &str,
+    field_id: &str,
+    linked_row_ids: &[String],
+  ) {
+    let mut lock = self.inner.lock().await;
+    let mut txn = lock.context.transact_mut();
+    self.row_relation_map.set_linking_rows_with_txn(
+      &mut txn,
+      linking_database_id,
+      linked_by_database_id,
+      row_id,
+      field_id,
+      linked_row_ids,
+    );
+  }
+
+  /// See [RowRelationMap::remove_row_with_txn].
+  pub async fn remove_row(&self, row_id: &str) {
+    let mut lock = self.inner.lock().await;
+    let mut txn = lock.context.transact_mut();
+    self.row_relation_map.remove_row_with_txn(&mut txn, row_id);
+  }
+
+  /// Return the rows that link to the row, by the id of their database.
+  pub async fn get_linked_by_rows(&self, row_id: &str) -> HashMap<String, Vec<LinkedByRow>> {
+    let lock = self.inner.lock().await;
+    let txn = lock.context.transact();
+    self
+      .row_relation_map
+      .get_linked_by_rows_with_txn(&txn, row_id)
+  }
 }
@@ -4,6 +4,8 @@ use crate::preclude::{
   Array, ArrayRef, Map, MapExt, MapPrelim, MapRef, ReadTxn, TransactionMut, YrsValue,
 };
 
+/// The links between the rows of two databases: the rows of the linking database link to the
+/// rows of the linked by database through a relation field. The connections are keyed by row id.
 #[derive(Debug, Clone)]
 pub struct RowRelation {
   pub linking_database_id: String,
@@ -16,14 +18,23 @@ const LINKED_BY_DB_ID: &str = "linked_db";
 const ROW_CONNECTIONS: &str = "row_connections";
 
 impl RowRelation {
+  pub fn new(linking_database_id: &str, linked_by_database_id: &str) -> Self {
+    Self {
+      linking_database_id: linking_database_id.to_string(),
+      linked_by_database_id: linked_by_database_id.to_string(),
+      row_connections: HashMap::new(),
+    }
+  }
+
   pub fn id(&self) -> String {
-    format!(
-      "{}-{}",
-      self.linking_database_id, self.linked_by_database_id
-    )
+    row_relation_id(&self.linking_database_id, &self.linked_by_database_id)
   }
 }
 
+pub fn row_relation_id(linking_database_id: &str, linked_by_database_id: &str) -> String {
+  format!("{}-{}", linking_database_id, linked_by_database_id)
+}
+
 pub struct RowRelationBuilder<'a, 'b> {
   map_ref: MapRef,
   txn: &'a mut TransactionMut<'b>,
@@ -63,8 +74,9 @@ impl<'a, 'b> RowRelationUpdate<'a, 'b> {
   }
 
   pub fn set_row_connections(self, connections: HashMap<String, RowConnection>) -> Self {
+    let connections_map: MapRef = self.map_ref.get_or_init(self.txn, ROW_CONNECTIONS);
     connections.into_iter().for_each(|(k, v)| {
-      let map_ref: MapRef = self.map_ref.get_or_init(self.txn, k);
+      let map_ref: MapRef = connections_map.insert(self.txn, k, MapPrelim::default());
       RowConnectionBuilder::new(&v.row_id, self.txn, map_ref).update(|update| {
         update
           .set_linking_rows(v.linking_rows)
@@ -103,11 +115,76 @@ pub fn row_relation_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Optio
   })
 }
 
+/// Return the map of the connection of the row in the map of a relation.
+pub(crate) fn get_row_connection<T: ReadTxn>(
+  txn: &T,
+  relation: &MapRef,
+  row_id: &str,
+) -> Option<MapRef> {
+  relation
+    .get_with_txn::<_, MapRef>(txn, ROW_CONNECTIONS)?
+    .get_with_txn(txn, row_id)
+}
+
+/// Return the map of the connection of the row in the map of a relation, inserting an empty one
+/// when the row has none.
+pub(crate) fn get_or_init_row_connection(
+  txn: &mut TransactionMut,
+  relation: &MapRef,
+  row_id: &str,
+) -> MapRef {
+  let connections: MapRef = relation.get_or_init(txn, ROW_CONNECTIONS);
+  match connections.get_with_txn::<_, MapRef>(txn, row_id) {
+    Some(map_ref) => map_ref,
+    None => {
+      let map_ref: MapRef = connections.insert(txn, row_id, MapPrelim::default());
+      map_ref.insert(txn, ROW_ID, row_id);
+      map_ref
+    },
+  }
+}
+
+/// Remove the connection of the row from the map of a relation when it has no link left.
+pub(crate) fn remove_row_connection_if_empty(
+  txn: &mut TransactionMut,
+  relation: &MapRef,
+  row_id: &str,
+) {
+  let is_empty = get_row_connection(txn, relation, row_id)
+    .and_then(|map_ref| row_connection_from_map_ref(txn, &map_ref))
+    .is_some_and(|connection| connection.is_empty());
+  if is_empty {
+    remove_row_connection(txn, relation, row_id);
+  }
+}
+
+/// Remove the connection of the row from the map of a relation.
+pub(crate) fn remove_row_connection(txn: &mut TransactionMut, relation: &MapRef, row_id: &str) {
+  if let Some(connections) = relation.get_with_txn::<_, MapRef>(txn, ROW_CONNECTIONS) {
+    connections.remove(txn, row_id);
+  }
+}
+
+/// The rows that a row links to, and the rows that link to it.
 #[derive(Debug, Clone)]
 pub struct RowConnection {
-  row_id: String,
-  linking_rows: Vec<LinkingRow>,
-  linked_by_rows: Vec<LinkedByRow>,
+  pub row_id: String,
+  pub linking_rows: Vec<LinkingRow>,
+  pub linked_by_rows: Vec<LinkedByRow>,
+}
+
+impl RowConnection {
+  pub fn new(row_id: &str) -> Self {
+    Self {
+      row_id: row_id.to_string(),
+      linking_rows: vec![],
+      linked_by_rows: vec![],
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.linking_rows.is_empty() && self.linked_by_rows.is_empty()
+  }
 }
 
 const ROW_ID: &str = "row_id";
@@ -168,20 +245,102 @@ impl<'a, 'b> RowConnectionUpdate<'a, 'b> {
   pub fn done(self) -> Option<RowConnection> {
     row_connection_from_map_ref(self.txn, self.map_ref)
   }
+
+  /// Append the row to the linking rows, unless the field already links to it.
+  pub fn push_linking_row(self, row: LinkingRow) -> Self {
+    let array_ref: ArrayRef = self.map_ref.get_or_init(self.txn, LINKING_ROWS);
+    let exists = array_ref
+      .iter(self.txn)
+      .flat_map(|value| LinkingRow::from_yrs_value(self.txn, value))
+      .any(|linking_row| linking_row == row);
+    if !exists {
+      let map_ref = array_ref.push_back(self.txn, MapPrelim::default());
+      row.fill_map_with_txn(self.txn, map_ref);
+    }
+    self
+  }
+
+  /// Remove the linking rows matching the predicate.
+  pub fn remove_linking_rows<F>(self, predicate: F) -> Self
+  where
+    F: Fn(&LinkingRow) -> bool,
+  {
+    if let Some(array_ref) = self
+      .map_ref
+      .get_with_txn::<_, ArrayRef>(self.txn, LINKING_ROWS)
+    {
+      let indexes = array_ref
+        .iter(self.txn)
+        .enumerate()
+        .filter(|(_, value)| {
+          LinkingRow::from_yrs_value(self.txn, value.clone()).is_some_and(|row| predicate(&row))
+        })
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+      for index in indexes.into_iter().rev() {
+        array_ref.remove(self.txn, index);
+      }
+    }
+    self
+  }
+
+  /// Append the row to the linked by rows, unless it's already one of them.
+  pub fn push_linked_by_row(self, row: LinkedByRow) -> Self {
+    let array_ref: ArrayRef = self.map_ref.get_or_init(self.txn, LINKED_BY_ROWS);
+    let exists = array_ref
+      .iter(self.txn)
+      .flat_map(|value| LinkedByRow::from_yrs_value(self.txn, value))
+      .any(|linked_by_row| linked_by_row == row);
+    if !exists {
+      let map_ref = array_ref.push_back(self.txn, MapPrelim::default());
+      row.fill_map_with_txn(self.txn, map_ref);
+    }
+    self
+  }
+
+  /// Remove the row from the linked by rows.
+  pub fn remove_linked_by_row(self, row_id: &str) -> Self {
+    if let Some(array_ref) = self
+      .map_ref
+      .get_with_txn::<_, ArrayRef>(self.txn, LINKED_BY_ROWS)
+    {
+      let indexes = array_ref
+        .iter(self.txn)
+        .enumerate()
+        .filter(|(_, value)| {
+          LinkedByRow::from_yrs_value(self.txn, value.clone())
+            .is_some_and(|row| row.row_id == row_id)
+        })
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+      for index in indexes.into_iter().rev() {
+        array_ref.remove(self.txn, index);
+      }
+    }
+    self
+  }
 }
 
 pub fn row_connection_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Option<RowConnection> {
   let row_id: String = map_ref.get_with_txn(txn, ROW_ID)?;
   let linking_rows = map_ref
-    .get_with_txn::<_, ArrayRef>(txn, LINKING_ROWS)?
-    .iter(txn)
-    .flat_map(|value| LinkingRow::from_yrs_value(txn, value))
-    .collect::<Vec<_>>();
+    .get_with_txn::<_, ArrayRef>(txn, LINKING_ROWS)
+    .map(|array| {
+      array
+        .iter(txn)
+        .flat_map(|value| LinkingRow::from_yrs_value(txn, value))
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
   let linked_by_rows = map_ref
-    .get_with_txn::<_, ArrayRef>(txn, LINKED_BY_ROWS)?
-    .iter(txn)
-    .flat_map(|value| LinkedByRow::from_yrs_value(txn, value))
-    .collect::<Vec<_>>();
+    .get_with_txn::<_, ArrayRef>(txn, LINKED_BY_ROWS)
+    .map(|array| {
+      array
+        .iter(txn)
+        .flat_map(|value| LinkedByRow::from_yrs_value(txn, value))
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
   Some(RowConnection {
     row_id,
     linking_rows,
@@ -189,7 +348,8 @@ pub fn row_connection_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Opt
   })
 }
 
-#[derive(Debug, Clone)]
+/// A row that a row links to. The content holds the id of the relation field holding the link.
+#[derive(Debug, Clone, PartialEq)]
 pub struct LinkingRow {
   pub row_id: String,
   pub content: String,
@@ -209,7 +369,7 @@ impl LinkingRow {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LinkedByRow {
   pub row_id: String,
 }
@@ -1,17 +1,23 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
 use crate::preclude::{
-  DeepObservable, EntryChange, Event, Map, MapPrelim, MapRef, Subscription, TransactionMut,
-  YrsValue,
+  DeepObservable, EntryChange, Event, Map, MapExt, MapPrelim, MapRef, PathSegment, ReadTxn,
+  Subscription, TransactionMut, YrsValue,
 };
 use tokio::sync::broadcast;
 
-use crate::database::workspace_database::relation::{RowRelation, RowRelationBuilder};
+use crate::database::workspace_database::relation::{
+  LinkedByRow, LinkingRow, RowConnectionUpdate, RowRelation, RowRelationBuilder,
+  get_or_init_row_connection, get_row_connection, remove_row_connection,
+  remove_row_connection_if_empty, row_connection_from_map_ref, row_relation_id,
+};
 use crate::database::workspace_database::row_relation_from_map_ref;
 
 #[derive(Debug, Clone)]
 pub enum RowRelationChange {
   NewRelation(RowRelation),
+  UpdateRelation(RowRelation),
   DeleteRelation(RowRelation),
 }
 
@@ -58,11 +64,172 @@ impl RowRelationMap {
   pub fn remove_relation_with_txn(&self, txn: &mut TransactionMut, relation_id: &str) {
     self.container.remove(txn, relation_id);
   }
+
+  pub fn get_relation_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    linking_database_id: &str,
+    linked_by_database_id: &str,
+  ) -> Option<RowRelation> {
+    let id = row_relation_id(linking_database_id, linked_by_database_id);
+    let map_ref: MapRef = self.container.get_with_txn(txn, &id)?;
+    row_relation_from_map_ref(txn, &map_ref)
+  }
+
+  pub fn get_all_relations_with_txn<T: ReadTxn>(&self, txn: &T) -> Vec<RowRelation> {
+    self
+      .container
+      .iter(txn)
+      .flat_map(|(_, value)| {
+        let map_ref: MapRef = value.cast().ok()?;
+        row_relation_from_map_ref(txn, &map_ref)
+      })
+      .collect()
+  }
+
+  /// Return the map of the relation between the two databases, inserting an empty one when
+  /// there is none.
+  fn get_or_init_relation_map(
+    &self,
+    txn: &mut TransactionMut,
+    linking_database_id: &str,
+    linked_by_database_id: &str,
+  ) -> MapRef {
+    let id = row_relation_id(linking_database_id, linked_by_database_id);
+    if let Some(map_ref) = self.container.get_with_txn::<_, MapRef>(txn, &id) {
+      return map_ref;
+    }
+    let map_ref: MapRef = self.container.insert(txn, id, MapPrelim::default());
+    RowRelationBuilder::new(
+      linking_database_id,
+      linked_by_database_id,
+      txn,
+      map_ref.clone(),
+    )
+    .done();
+    map_ref
+  }
+
+  /// Set the rows that the row of the linking database links to through the relation field,
+  /// replacing the ones it linked to through that field, and update the linked by rows of the
+  /// rows of the linked by database accordingly.
+  ///
+  /// Only the links that changed are written, in the connections of the rows involved, so the
+  /// links set by other clients at the same time are kept.
+  #[allow(clippy::too_many_arguments)]
+  pub fn set_linking_rows_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    linking_database_id: &str,
+    linked_by_database_id: &str,
+    row_id: &str,
+    field_id: &str,
+    linked_row_ids: &[String],
+  ) {
+    let relation = self.get_or_init_relation_map(txn, linking_database_id, linked_by_database_id);
+    let connection = get_or_init_row_connection(txn, &relation, row_id);
+    let linking_rows = row_connection_from_map_ref(txn, &connection)
+      .map(|connection| connection.linking_rows)
+      .unwrap_or_default();
+    let old_linked_ids = linking_rows
+      .iter()
+      .map(|linking_row| linking_row.row_id.clone())
+      .collect::<HashSet<_>>();
+    let new_linked_ids = linking_rows
+      .iter()
+      .filter(|linking_row| linking_row.content != field_id)
+      .map(|linking_row| linking_row.row_id.clone())
+      .chain(linked_row_ids.iter().cloned())
+      .collect::<HashSet<_>>();
+
+    RowConnectionUpdate::new(txn, &connection).remove_linking_rows(|linking_row| {
+      linking_row.content == field_id && !linked_row_ids.contains(&linking_row.row_id)
+    });
+    for linked_row_id in linked_row_ids {
+      RowConnectionUpdate::new(txn, &connection).push_linking_row(LinkingRow {
+        row_id: linked_row_id.clone(),
+        content: field_id.to_string(),
+      });
+    }
+
+    // Only the rows of the linked by database can be linked by the row
+    for id in old_linked_ids.difference(&new_linked_ids) {
+      if let Some(connection) = get_row_connection(txn, &relation, id) {
+        RowConnectionUpdate::new(txn, &connection).remove_linked_by_row(row_id);
+        remove_row_connection_if_empty(txn, &relation, id);
+      }
+    }
+    for id in new_linked_ids.difference(&old_linked_ids) {
+      let connection = get_or_init_row_connection(txn, &relation, id);
+      RowConnectionUpdate::new(txn, &connection).push_linked_by_row(LinkedByRow {
+        row_id: row_id.to_string(),
+      });
+    }
+    remove_row_connection_if_empty(txn, &relation, row_id);
+  }
+
+  /// Remove the row from every relation: the links of the row to other rows and the links of
+  /// other rows to it. Only the connections of the rows involved are written.
+  pub fn remove_row_with_txn(&self, txn: &mut TransactionMut, row_id: &str) {
+    let relations = self
+      .container
+      .iter(txn)
+      .flat_map(|(_, value)| value.cast::<MapRef>().ok())
+      .collect::<Vec<_>>();
+    for relation in relations {
+      let Some(connection) = get_row_connection(txn, &relation, row_id)
+        .and_then(|map_ref| row_connection_from_map_ref(txn, &map_ref))
+      else {
+        continue;
+      };
+      remove_row_connection(txn, &relation, row_id);
+      for linking_row in connection.linking_rows {
+        if let Some(map_ref) = get_row_connection(txn, &relation, &linking_row.row_id) {
+          RowConnectionUpdate::new(txn, &map_ref).remove_linked_by_row(row_id);
+          remove_row_connection_if_empty(txn, &relation, &linking_row.row_id);
+        }
+      }
+      for linked_by_row in connection.linked_by_rows {
+        if let Some(map_ref) = get_row_connection(txn, &relation, &linked_by_row.row_id) {
+          RowConnectionUpdate::new(txn, &map_ref)
+            .remove_linking_rows(|linking_row| linking_row.row_id == row_id);
+          remove_row_connection_if_empty(txn, &relation, &linked_by_row.row_id);
+        }
+      }
+    }
+  }
+
+  /// Return the rows that link to the row, by the id of their database.
+  pub fn get_linked_by_rows_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    row_id: &str,
+  ) -> HashMap<String, Vec<LinkedByRow>> {
+    let mut linked_by_rows = HashMap::new();
+    for mut relation in self.get_all_relations_with_txn(txn) {
+      if let Some(connection) = relation.row_connections.remove(row_id) {
+        if !connection.linked_by_rows.is_empty() {
+          linked_by_rows
+            .entry(relation.linking_database_id)
+            .or_insert_with(Vec::new)
+            .extend(connection.linked_by_rows);
+        }
+      }
+    }
+    linked_by_rows
+  }
 }
 
 fn subscription_changes(tx: RowRelationUpdateSender, container: &MapRef) -> Subscription {
+  let relations = container.clone();
   container.observe_deep(move |txn, events| {
+    // The links are updated inside the map of a relation, which is reported once per relation
+    let mut updated_relation_ids = HashSet::new();
     for deep_event in events.iter() {
+      if let Some(PathSegment::Key(relation_id)) = deep_event.path().front() {
+        updated_relation_ids.insert(relation_id.clone());
+        continue;
+      }
       match deep_event {
         Event::Text(_) => {},
         Event::Array(_) => {},
@@ -77,8 +244,13 @@ fn subscription_changes(tx: RowRelationUpdateSender, container: &MapRef) -> Subs
                   }
                 }
               },
-              EntryChange::Updated(_k, _v) => {
-                //println!("update: {}", event.target().to_json(txn));
+              EntryChange::Updated(_, v) => {
+                if let YrsValue::YMap(map_ref) = v {
+                  if let Some(row_relation) = row_relation_from_map_ref(txn, map_ref) {
+                    tracing::trace!("update: {:?}", row_relation);
+                    let _ = tx.send(RowRelationChange::UpdateRelation(row_relation));
+                  }
+                }
               },
               EntryChange::Removed(v) => {
                 //println!("remove: {}", event.target().to_json(txn));
@@ -96,6 +268,15 @@ fn subscription_changes(tx: RowRelationUpdateSender, container: &MapRef) -> Subs
         Event::XmlText(_) => {},
       }
     }
+    for relation_id in updated_relation_ids {
+      if let Some(row_relation) = relations
+        .get_with_txn::<_, MapRef>(txn, &relation_id)
+        .and_then(|map_ref| row_relation_from_map_ref(txn, &map_ref))
+      {
+        tracing::trace!("update: {:?}", row_relation);
+        let _ = tx.send(RowRelationChange::UpdateRelation(row_relation));
+      }
+    }
   })
 }
 
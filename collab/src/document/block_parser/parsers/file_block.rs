@@ -38,7 +38,10 @@ impl BlockParser for FileBlockParser {
     let formatted_content = match context.format {
       OutputFormat::Markdown => {
         let indent = context.get_indent();
-        if url.is_empty() {
+        if let Some(link) = context.block_link(block) {
+          let text = link.text.unwrap_or(name);
+          format!("{}[{}]({})", indent, text, link.url)
+        } else if url.is_empty() {
           format!("{}{}", indent, name)
         } else {
           format!("{}[{}]({})", indent, name, url)
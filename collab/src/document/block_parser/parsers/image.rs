@@ -22,7 +22,10 @@ impl BlockParser for ImageParser {
 
     let formatted_content = match context.format {
       crate::document::OutputFormat::Markdown => {
-        if url.is_empty() {
+        if let Some(link) = context.block_link(block) {
+          let text = link.text.unwrap_or_else(|| "Image".to_string());
+          format!("![{}]({})", text, link.url)
+        } else if url.is_empty() {
           "![Image]()".to_string()
         } else {
           format!("![Image]({})", url)
@@ -1,7 +1,7 @@
 use serde_json::Value;
 
 use super::super::{BlockParser, OutputFormat, ParseContext, ParseResult};
-use crate::document::blocks::{Block, BlockType};
+use crate::document::blocks::{Block, BlockType, SUB_PAGE_VIEW_ID_KEYS};
 use crate::error::CollabError;
 
 /// Parse the subpage block.
@@ -10,16 +10,12 @@ use crate::error::CollabError;
 ///   viewId: string
 pub struct SubpageParser;
 
-// do not change the key value, it comes from the flutter code.
-const VIEW_ID_KEY: &str = "viewId";
-
 impl BlockParser for SubpageParser {
   fn parse(&self, block: &Block, context: &ParseContext) -> Result<ParseResult, CollabError> {
-    let view_id = block
-      .data
-      .get(VIEW_ID_KEY)
-      .and_then(|v| match v {
-        Value::String(s) => Some(s.clone()),
+    let view_id = SUB_PAGE_VIEW_ID_KEYS
+      .iter()
+      .find_map(|key| match block.data.get(*key) {
+        Some(Value::String(s)) => Some(s.clone()),
         _ => None,
       })
       .unwrap_or_default();
@@ -27,7 +23,10 @@ impl BlockParser for SubpageParser {
     let formatted_content = match context.format {
       OutputFormat::Markdown => {
         let indent = context.get_indent();
-        if view_id.is_empty() {
+        if let Some(link) = context.block_link(block) {
+          let text = link.text.unwrap_or_else(|| "Subpage".to_string());
+          format!("{}[{}]({})", indent, text, link.url)
+        } else if view_id.is_empty() {
           format!("{}[Subpage]", indent)
         } else {
           format!("{}[Subpage]({})", indent, view_id)
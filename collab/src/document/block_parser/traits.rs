@@ -93,6 +93,11 @@ impl<'a> ParseContext<'a> {
   pub fn plain_text_resolver(&self) -> Option<&Arc<dyn super::PlainTextResolver + Send + Sync>> {
     self.parser.get_plain_text_resolver()
   }
+
+  /// Returns the link of the block provided by [DocumentParserDelegate::handle_block_link].
+  pub fn block_link(&self, block: &Block) -> Option<BlockLink> {
+    self.parser.get_delegate()?.handle_block_link(block, self)
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -178,6 +183,14 @@ pub trait BlockParser {
   }
 }
 
+/// The link of a sub page, image or file block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockLink {
+  /// The text of the link. `None` keeps the text of the block.
+  pub text: Option<String>,
+  pub url: String,
+}
+
 pub trait DocumentParserDelegate: Debug {
   /// Delegate the text delta to the caller.
   ///
@@ -190,4 +203,12 @@ pub trait DocumentParserDelegate: Debug {
   ) -> Option<String> {
     None
   }
+
+  /// Delegate the markdown link of a sub page, image or file block to the caller.
+  ///
+  /// For example, when exporting a document, the caller can point the link to the exported file.
+  /// Returning `None` keeps the link stored in the block.
+  fn handle_block_link(&self, _block: &Block, _context: &ParseContext) -> Option<BlockLink> {
+    None
+  }
 }
@@ -4,6 +4,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::CollabError;
 
+/// The keys a [BlockType::SubPage] block may store its view id under. The editor writes
+/// `viewId`, while documents created by older versions use `view_id`.
+pub const SUB_PAGE_VIEW_ID_KEYS: &[&str] = &["viewId", "view_id"];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BlockType {
   Page,
@@ -475,6 +475,15 @@ impl Document {
     let txn = self.collab.transact();
     self.body.to_markdown_text(txn)
   }
+
+  /// Get the markdown text of the document using the given parser.
+  ///
+  /// Use a parser with a [super::block_parser::DocumentParserDelegate] to customize how mentions
+  /// are rendered.
+  pub fn to_markdown_text_with_parser(&self, parser: &DocumentParser) -> Vec<String> {
+    let txn = self.collab.transact();
+    self.body.to_markdown_text_with_parser(txn, parser)
+  }
 }
 
 impl Deref for Document {
@@ -681,7 +690,14 @@ impl DocumentBody {
 
   /// Get the markdown text of the document.
   pub fn to_markdown_text<T: ReadTxn>(&self, txn: T) -> Vec<String> {
-    let document_parser = DocumentParser::with_default_parsers();
+    self.to_markdown_text_with_parser(txn, &DocumentParser::with_default_parsers())
+  }
+
+  pub fn to_markdown_text_with_parser<T: ReadTxn>(
+    &self,
+    txn: T,
+    document_parser: &DocumentParser,
+  ) -> Vec<String> {
     let document_data = self.get_document_data(&txn);
     if let Ok(document_data) = document_data {
       let markdown_text = document_parser
@@ -6,11 +6,12 @@ use crate::preclude::*;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use super::blocks::{Block, DocumentData, DocumentMeta, TextDelta};
+use super::blocks::{Block, DocumentData, DocumentMeta, SUB_PAGE_VIEW_ID_KEYS, TextDelta};
 use crate::document::Document;
 use crate::error::CollabError;
 
 const INLINE_DATABASE_BLOCK_TYPES: &[&str] = &["grid", "board", "calendar"];
+const SUB_PAGE_BLOCK_TYPE: &str = "sub_page";
 const PARENT_ID_KEY: &str = "parent_id";
 const VIEW_ID_KEY: &str = "view_id";
 
@@ -30,7 +31,7 @@ impl DocumentCollabRemapper {
   //
   // 1. replace all the inline database page id
   // 2. replace all the mentioned page id
-  // 3. replace all the reference page (not supported yet)
+  // 3. replace all the sub page view id
   // 4. replace all the image url (not supported yet)
   // 5. replace all the file url (not supported yet)
   pub fn remap_collab_doc(
@@ -116,6 +117,18 @@ impl DocumentCollabRemapper {
       }
     }
 
+    if block.ty == SUB_PAGE_BLOCK_TYPE {
+      for key in SUB_PAGE_VIEW_ID_KEYS {
+        if let Some(view_id) = block.data.get(*key).and_then(|v| v.as_str()) {
+          if let Some(new_view_id) = self.id_mapping.get(view_id) {
+            block
+              .data
+              .insert(key.to_string(), new_view_id.clone().into());
+          }
+        }
+      }
+    }
+
     block
   }
 
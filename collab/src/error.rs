@@ -4,6 +4,7 @@ use markdown::message::Message;
 use std::time::Duration;
 use yrs::TransactionAcqError;
 
+use crate::database::validation::ConstraintViolation;
 use crate::entity::uuid_validation::RowId;
 
 #[derive(Debug, thiserror::Error)]
@@ -183,6 +184,18 @@ pub enum CollabError {
   #[error("Database: Import data failed: {0}")]
   DatabaseImportData(String),
 
+  #[error("Database: Invalid formula: {0}")]
+  DatabaseInvalidFormula(String),
+
+  #[error("Database: Dependency cycle between rows: {0:?}")]
+  DatabaseDependencyCycle(Vec<RowId>),
+
+  #[error("Database: Cells violate field constraints: {0:?}")]
+  DatabaseConstraintViolation(Vec<ConstraintViolation>),
+
+  #[error("Database: Invalid query: {0}")]
+  DatabaseInvalidQuery(String),
+
   #[error("Collab version could not be determined")]
   InvalidVersion,
 
@@ -0,0 +1,517 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::core::collab::CollabOptions;
+use crate::core::origin::CollabOrigin;
+use crate::database::database::DatabaseData;
+use crate::database::template::csv_export::{CSVExportOptions, CSVExporter, RelationTitles};
+use crate::document::blocks::Block;
+use crate::document::blocks::{BlockType, SUB_PAGE_VIEW_ID_KEYS, mention_keys, mention_types};
+use crate::document::{BlockLink, Document, DocumentParser, DocumentParserDelegate, ParseContext};
+use crate::entity::EncodedCollab;
+use crate::entity::uuid_validation::DatabaseId;
+use crate::error::CollabError;
+use crate::exporter::util::{ExportedFile, path_to_link, write_files_to_dir, write_files_to_zip};
+use crate::folder::{Folder, View, ViewId};
+use crate::preclude::{Attrs, ClientID, Collab};
+
+const MARKDOWN_EXTENSION: &str = "md";
+const CSV_EXTENSION: &str = "csv";
+const UNTITLED: &str = "Untitled";
+const BLOCK_URL_KEY: &str = "url";
+
+/// Provides the collab objects and the attachments of the views that are exported.
+#[async_trait]
+pub trait ExportCollabProvider: Send + Sync {
+  /// Returns the encoded collab of the document view.
+  async fn get_document_collab(
+    &self,
+    view_id: &ViewId,
+  ) -> Result<Option<EncodedCollab>, CollabError>;
+
+  /// Returns the id of the database that the database view belongs to.
+  async fn get_database_id(&self, view_id: &ViewId) -> Result<Option<DatabaseId>, CollabError>;
+
+  /// Returns the data of the database, including its rows.
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError>;
+
+  /// Returns the content of an attachment referenced by a file or image block.
+  /// Returning `None` keeps the original url in the exported markdown.
+  async fn get_attachment(&self, _url: &str) -> Result<Option<Vec<u8>>, CollabError> {
+    Ok(None)
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportedViews {
+  /// The exported files, in the order they were generated.
+  pub files: Vec<ExportedFile>,
+  /// The path of the exported file of each view, relative to the export root.
+  pub view_paths: HashMap<ViewId, PathBuf>,
+}
+
+/// Exports a view and its descendants to a tree of Markdown and CSV files.
+///
+/// 1. documents are written as Markdown. Mentions and sub pages that point to views inside the
+///    exported subtree are rewritten into relative links
+/// 2. database views are written as CSV, with cells rendered by
+///    [crate::database::fields::TypeOptionCellReader::stringify_cell]
+/// 3. the children of a view are written into a directory named after the view
+/// 4. attachments returned by [ExportCollabProvider::get_attachment] are copied next to the
+///    document, otherwise the original url is kept
+pub struct FolderExporter<P> {
+  provider: P,
+  client_id: ClientID,
+}
+
+impl<P> FolderExporter<P>
+where
+  P: ExportCollabProvider,
+{
+  pub fn new(provider: P, client_id: ClientID) -> Self {
+    Self {
+      provider,
+      client_id,
+    }
+  }
+
+  /// Exports the view and its descendants in memory.
+  pub async fn export(
+    &self,
+    folder: &Folder,
+    view_id: &ViewId,
+    uid: Option<i64>,
+  ) -> Result<ExportedViews, CollabError> {
+    let views = folder.get_view_recursively(view_id, uid);
+    if views.is_empty() {
+      return Err(CollabError::NoRequiredData(format!(
+        "view {} not found",
+        view_id
+      )));
+    }
+    let layout = Arc::new(ExportLayout::new(
+      &views,
+      view_id,
+      &folder.get_all_views(uid),
+    ));
+    let mut exported = ExportedViews {
+      files: vec![],
+      view_paths: layout.file_paths.clone(),
+    };
+
+    for view in &views {
+      let Some(path) = layout.file_paths.get(&view.id) else {
+        continue;
+      };
+      if view.layout.is_document() {
+        if let Some(files) = self.export_document(view, path, &layout).await? {
+          exported.files.extend(files);
+        }
+      } else if view.layout.is_database() {
+        if let Some(file) = self.export_database(view, path).await? {
+          exported.files.push(file);
+        }
+      }
+    }
+    Ok(exported)
+  }
+
+  /// Exports the view and its descendants into `output_dir`.
+  pub async fn export_to_dir(
+    &self,
+    folder: &Folder,
+    view_id: &ViewId,
+    output_dir: &Path,
+    uid: Option<i64>,
+  ) -> Result<ExportedViews, CollabError> {
+    let exported = self.export(folder, view_id, uid).await?;
+    write_files_to_dir(&exported.files, output_dir).await?;
+    Ok(exported)
+  }
+
+  /// Exports the view and its descendants into a zip archive at `zip_path`.
+  pub async fn export_to_zip(
+    &self,
+    folder: &Folder,
+    view_id: &ViewId,
+    zip_path: &Path,
+    uid: Option<i64>,
+  ) -> Result<ExportedViews, CollabError> {
+    let exported = self.export(folder, view_id, uid).await?;
+    write_files_to_zip(&exported.files, zip_path).await?;
+    Ok(exported)
+  }
+
+  async fn export_document(
+    &self,
+    view: &View,
+    path: &Path,
+    layout: &Arc<ExportLayout>,
+  ) -> Result<Option<Vec<ExportedFile>>, CollabError> {
+    let Some(encoded_collab) = self.provider.get_document_collab(&view.id).await? else {
+      warn!("Skip exporting document {}: collab not found", view.id);
+      return Ok(None);
+    };
+    let options =
+      CollabOptions::new(view.id, self.client_id).with_data_source(encoded_collab.into());
+    let collab = Collab::new_with_options(CollabOrigin::Empty, options)
+      .map_err(|e| CollabError::Internal(anyhow::Error::new(e)))?;
+    let document = Document::open(collab)?;
+    let document_data = document.get_document_data()?;
+
+    // Copy the attachments first, so the parser can link the blocks to the copied files
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let attachment_dir = path.with_extension("").join("files");
+    let mut files = vec![];
+    let mut attachments = HashMap::new();
+    let mut used_names = HashSet::new();
+    for block in document_data.blocks.values() {
+      if !matches!(
+        BlockType::from_block_ty(&block.ty),
+        BlockType::Image | BlockType::File
+      ) {
+        continue;
+      }
+      let Some(url) = block_url(block) else {
+        continue;
+      };
+      if url.is_empty() || attachments.contains_key(url) {
+        continue;
+      }
+      if let Some(content) = self.provider.get_attachment(url).await? {
+        let file_name = unique_name(&mut used_names, &attachment_file_name(url));
+        let file_path = attachment_dir.join(file_name);
+        attachments.insert(
+          url.to_string(),
+          path_to_link(&relative_path(&base_dir, &file_path)),
+        );
+        files.push(ExportedFile {
+          path: file_path,
+          content,
+        });
+      }
+    }
+
+    let delegate = ExportLinkDelegate {
+      layout: layout.clone(),
+      base_dir,
+      attachments,
+    };
+    let parser = DocumentParser::with_default_parsers().with_delegate(Arc::new(delegate));
+    let markdown = document.to_markdown_text_with_parser(&parser).join("\n");
+    files.insert(
+      0,
+      ExportedFile {
+        path: path.to_path_buf(),
+        content: markdown.into_bytes(),
+      },
+    );
+    Ok(Some(files))
+  }
+
+  async fn export_database(
+    &self,
+    view: &View,
+    path: &Path,
+  ) -> Result<Option<ExportedFile>, CollabError> {
+    let Some(database_id) = self.provider.get_database_id(&view.id).await? else {
+      warn!(
+        "Skip exporting database view {}: database not found",
+        view.id
+      );
+      return Ok(None);
+    };
+    let Some(data) = self.provider.get_database_data(&database_id).await? else {
+      warn!("Skip exporting database {}: data not found", database_id);
+      return Ok(None);
+    };
+    let content = database_view_to_csv(&data, &view.id)?;
+    Ok(Some(ExportedFile {
+      path: path.to_path_buf(),
+      content,
+    }))
+  }
+}
+
+/// Renders the rows of a database view as CSV. The columns follow the field order of the view and
+/// the rows follow the row order of the view. If the view doesn't exist in `data`, all fields and
+/// rows of the database are exported.
+pub fn database_view_to_csv(data: &DatabaseData, view_id: &Uuid) -> Result<Vec<u8>, CollabError> {
+  let view = data.views.iter().find(|view| &view.id == view_id);
+  let fields = match view {
+    Some(view) if !view.field_orders.is_empty() => view
+      .field_orders
+      .iter()
+      .filter_map(|order| data.fields.iter().find(|field| field.id == order.id))
+      .collect::<Vec<_>>(),
+    _ => data.fields.iter().collect(),
+  };
+  let rows = match view {
+    Some(view) if !view.row_orders.is_empty() => view
+      .row_orders
+      .iter()
+      .filter_map(|order| data.rows.iter().find(|row| row.id == order.id))
+      .collect::<Vec<_>>(),
+    _ => data.rows.iter().collect(),
+  };
+
+  let fields = fields.into_iter().cloned().collect::<Vec<_>>();
+  let mut exporter = CSVExporter::new(fields, &CSVExportOptions::default(), vec![])?;
+  exporter.write_rows(rows, &RelationTitles::new())?;
+  exporter.finish()
+}
+
+/// The location of each exported view, relative to the export root.
+#[derive(Debug)]
+struct ExportLayout {
+  file_paths: HashMap<ViewId, PathBuf>,
+  names: HashMap<ViewId, String>,
+  /// The names of all the views of the folder, used to label the views that are not exported.
+  folder_names: HashMap<ViewId, String>,
+}
+
+impl ExportLayout {
+  fn new(views: &[View], root_view_id: &ViewId, folder_views: &[Arc<View>]) -> Self {
+    let views_by_id = views
+      .iter()
+      .map(|view| (view.id, view))
+      .collect::<HashMap<_, _>>();
+    let mut layout = Self {
+      file_paths: HashMap::new(),
+      names: HashMap::new(),
+      folder_names: folder_views
+        .iter()
+        .map(|view| (view.id, view.name.clone()))
+        .collect(),
+    };
+    let mut used_names = HashMap::<PathBuf, HashSet<String>>::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(*root_view_id, PathBuf::new())];
+    while let Some((view_id, dir)) = stack.pop() {
+      if !visited.insert(view_id) {
+        continue;
+      }
+      let Some(view) = views_by_id.get(&view_id) else {
+        continue;
+      };
+      let stem = unique_name(
+        used_names.entry(dir.clone()).or_default(),
+        &sanitize_name(&view.name),
+      );
+      let base = dir.join(&stem);
+      let extension = match &view.layout {
+        view_layout if view_layout.is_document() => Some(MARKDOWN_EXTENSION),
+        view_layout if view_layout.is_database() => Some(CSV_EXTENSION),
+        _ => None,
+      };
+      if let Some(extension) = extension {
+        layout
+          .file_paths
+          .insert(view.id, base.with_extension(extension));
+      }
+      layout.names.insert(view.id, view.name.clone());
+      for child in view.children.items.iter().rev() {
+        stack.push((child.id, base.clone()));
+      }
+    }
+    layout
+  }
+}
+
+#[derive(Debug, Clone)]
+struct ExportLink {
+  name: String,
+  /// Empty if the view is not part of the export.
+  path: String,
+}
+
+impl ExportLink {
+  fn display_name(&self) -> &str {
+    if self.name.is_empty() {
+      UNTITLED
+    } else {
+      &self.name
+    }
+  }
+}
+
+/// Renders page mentions and sub pages as links to the exported files, and image and file
+/// blocks as links to the copied attachments.
+#[derive(Debug)]
+struct ExportLinkDelegate {
+  layout: Arc<ExportLayout>,
+  /// The directory of the exported document, relative to the export root.
+  base_dir: PathBuf,
+  /// The links of the copied attachments relative to `base_dir`, by their original url.
+  attachments: HashMap<String, String>,
+}
+
+impl ExportLinkDelegate {
+  /// Returns the link of an exported view relative to the document, or a link with an empty path
+  /// for the views of the folder that are not exported.
+  fn view_link(&self, view_id: &str) -> Option<ExportLink> {
+    let view_id = Uuid::parse_str(view_id).ok()?;
+    match self.layout.file_paths.get(&view_id) {
+      Some(path) => Some(ExportLink {
+        name: self.layout.names.get(&view_id).cloned().unwrap_or_default(),
+        path: path_to_link(&relative_path(&self.base_dir, path)),
+      }),
+      None => self
+        .layout
+        .folder_names
+        .get(&view_id)
+        .map(|name| ExportLink {
+          name: name.clone(),
+          path: String::new(),
+        }),
+    }
+  }
+}
+
+impl DocumentParserDelegate for ExportLinkDelegate {
+  fn handle_text_delta(
+    &self,
+    _text: &str,
+    attributes: Option<&Attrs>,
+    _context: &ParseContext,
+  ) -> Option<String> {
+    let mention = attributes?.get(mention_keys::MENTION)?;
+    let mention = serde_json::to_value(mention).ok()?;
+    let mention = match mention {
+      JsonValue::String(s) => serde_json::from_str::<JsonMap<String, JsonValue>>(&s).ok()?,
+      JsonValue::Object(map) => map,
+      _ => return None,
+    };
+    let mention_type = mention.get(mention_keys::TYPE).and_then(|v| v.as_str());
+    if !matches!(
+      mention_type,
+      Some(mention_types::PAGE) | Some(mention_types::CHILD_PAGE)
+    ) {
+      return None;
+    }
+    let page_id = mention.get(mention_keys::PAGE_ID)?.as_str()?;
+    let link = self.view_link(page_id)?;
+    let name = link.display_name();
+    if link.path.is_empty() {
+      Some(format!("[[{}]]", name))
+    } else {
+      Some(format!("[{}](<{}>)", name, link.path))
+    }
+  }
+
+  fn handle_block_link(&self, block: &Block, _context: &ParseContext) -> Option<BlockLink> {
+    match BlockType::from_block_ty(&block.ty) {
+      BlockType::SubPage => {
+        let view_id = SUB_PAGE_VIEW_ID_KEYS
+          .iter()
+          .find_map(|key| block.data.get(*key).and_then(|v| v.as_str()))?;
+        let link = self
+          .view_link(view_id)
+          .filter(|link| !link.path.is_empty())?;
+        Some(BlockLink {
+          text: Some(link.display_name().to_string()),
+          url: format!("<{}>", link.path),
+        })
+      },
+      BlockType::Image | BlockType::File => {
+        let path = self.attachments.get(block_url(block)?)?;
+        Some(BlockLink {
+          text: None,
+          url: format!("<{}>", path),
+        })
+      },
+      _ => None,
+    }
+  }
+}
+
+fn block_url(block: &Block) -> Option<&str> {
+  block.data.get(BLOCK_URL_KEY).and_then(|v| v.as_str())
+}
+
+fn sanitize_name(name: &str) -> String {
+  let name = sanitize_filename::sanitize(name.trim());
+  if name.is_empty() {
+    UNTITLED.to_string()
+  } else {
+    name
+  }
+}
+
+/// Returns `name`, or `name (n)` if the name is already used.
+fn unique_name(used_names: &mut HashSet<String>, name: &str) -> String {
+  let mut candidate = name.to_string();
+  let mut index = 1;
+  while !used_names.insert(candidate.to_lowercase()) {
+    candidate = format!("{} ({})", name, index);
+    index += 1;
+  }
+  candidate
+}
+
+fn attachment_file_name(url: &str) -> String {
+  let name = url
+    .split(['?', '#'])
+    .next()
+    .and_then(|url| url.rsplit('/').next())
+    .unwrap_or_default();
+  sanitize_name(name)
+}
+
+/// Returns the path of `target` relative to the directory `base_dir`. Both paths are relative to
+/// the export root.
+fn relative_path(base_dir: &Path, target: &Path) -> PathBuf {
+  let base = base_dir.components().collect::<Vec<Component>>();
+  let target_components = target.components().collect::<Vec<Component>>();
+  let common = base
+    .iter()
+    .zip(target_components.iter())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let mut path = PathBuf::new();
+  for _ in common..base.len() {
+    path.push("..");
+  }
+  for component in &target_components[common..] {
+    path.push(component);
+  }
+  path
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn relative_path_test() {
+    assert_eq!(
+      relative_path(Path::new("a/b"), Path::new("a/c/d.md")),
+      PathBuf::from("../c/d.md")
+    );
+    assert_eq!(
+      relative_path(Path::new(""), Path::new("a/d.md")),
+      PathBuf::from("a/d.md")
+    );
+    assert_eq!(
+      relative_path(Path::new("a"), Path::new("a.md")),
+      PathBuf::from("../a.md")
+    );
+  }
+
+  #[test]
+  fn unique_name_test() {
+    let mut used = HashSet::new();
+    assert_eq!(unique_name(&mut used, "Page"), "Page");
+    assert_eq!(unique_name(&mut used, "page"), "page (1)");
+    assert_eq!(unique_name(&mut used, "Page"), "Page (2)");
+  }
+}
@@ -0,0 +1,7 @@
+pub mod folder_exporter;
+mod util;
+pub mod workspace_exporter;
+
+pub use folder_exporter::*;
+pub use util::ExportedFile;
+pub use workspace_exporter::*;
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use tokio::fs;
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+
+use crate::error::CollabError;
+
+/// A file produced by an exporter. The path is relative to the export root.
+#[derive(Debug, Clone)]
+pub struct ExportedFile {
+  pub path: PathBuf,
+  pub content: Vec<u8>,
+}
+
+/// Writes the files into `output_dir`, creating the intermediate directories.
+pub(crate) async fn write_files_to_dir(
+  files: &[ExportedFile],
+  output_dir: &Path,
+) -> Result<(), CollabError> {
+  for file in files {
+    let path = output_dir.join(&file.path);
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).await?;
+    }
+    fs::write(&path, &file.content).await?;
+  }
+  Ok(())
+}
+
+/// Writes the files into a zip archive at `zip_path`.
+pub(crate) async fn write_files_to_zip(
+  files: &[ExportedFile],
+  zip_path: &Path,
+) -> Result<(), CollabError> {
+  if let Some(parent) = zip_path.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+  let zip_file = fs::File::create(zip_path).await?;
+  let mut writer = ZipFileWriter::new(zip_file.compat_write());
+  for file in files {
+    let builder = ZipEntryBuilder::new(path_to_link(&file.path).into(), Compression::Deflate);
+    writer
+      .write_entry_whole(builder, &file.content)
+      .await
+      .map_err(|err| CollabError::Internal(err.into()))?;
+  }
+  writer
+    .close()
+    .await
+    .map_err(|err| CollabError::Internal(err.into()))?;
+  Ok(())
+}
+
+/// Joins the components of the path with `/`, regardless of the platform.
+pub(crate) fn path_to_link(path: &Path) -> String {
+  path
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy().to_string())
+    .collect::<Vec<_>>()
+    .join("/")
+}
@@ -0,0 +1,620 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::core::collab::CollabOptions;
+use crate::core::origin::CollabOrigin;
+use crate::database::database::{DatabaseData, get_row_document_id};
+use crate::database::entity::FieldType;
+use crate::database::fields::relation_type_option::RelationTypeOption;
+use crate::database::workspace_database::WorkspaceDatabase;
+use crate::document::Document;
+use crate::document::blocks::{
+  BlockType, DocumentData, SUB_PAGE_VIEW_ID_KEYS, mention_keys, mention_types,
+};
+use crate::entity::uuid_validation::DatabaseId;
+use crate::entity::{CollabType, EncodedCollab};
+use crate::error::CollabError;
+use crate::exporter::util::{ExportedFile, path_to_link, write_files_to_dir, write_files_to_zip};
+use crate::folder::{Folder, View, timestamp};
+use crate::importer::workspace::entities::{
+  CollabMetadata, DependencyType, ViewDependency, ViewMetadata, WorkspaceDatabaseMeta,
+  WorkspaceRelationMap,
+};
+use crate::preclude::{ClientID, Collab};
+
+pub const RELATION_MAP_FILE: &str = "relation_map.json";
+pub const EXPORT_METADATA_FILE: &str = "metadata.json";
+const EXPORT_VERSION: &str = "1.0";
+const COLLAB_JSONS_DIR: &str = "collab_jsons";
+const COLLAB_OBJECTS_DIR: &str = "collab_objects";
+const DOCUMENTS_DIR: &str = "documents";
+const DATABASES_DIR: &str = "databases";
+const ROWS_DIR: &str = "rows";
+const ROW_DOCUMENTS_DIR: &str = "row_documents";
+const BLOCK_URL_KEY: &str = "url";
+
+/// Loads the collab objects of the workspace that is exported.
+#[async_trait]
+pub trait WorkspaceCollabProvider: Send + Sync {
+  /// Returns the encoded collab of the object. Documents, row documents, databases and database
+  /// rows are requested with [CollabType::Document], [CollabType::Database] and
+  /// [CollabType::DatabaseRow].
+  async fn get_encoded_collab(
+    &self,
+    object_id: &Uuid,
+    collab_type: CollabType,
+  ) -> Result<Option<EncodedCollab>, CollabError>;
+
+  /// Returns the data of the database, including its rows and views.
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMetadata {
+  pub export_info: ExportInfo,
+  /// The `.collab` files, relative to the `collab_objects` directory.
+  pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportInfo {
+  pub archive_format: String,
+  pub export_timestamp: i64,
+  pub total_collab_files: usize,
+  pub total_dependencies: usize,
+  pub total_views: usize,
+  pub version: String,
+  pub workspace_id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportedWorkspace {
+  pub relation_map: WorkspaceRelationMap,
+  /// All the exported files, including `relation_map.json` and `metadata.json`.
+  pub files: Vec<ExportedFile>,
+}
+
+/// Exports a workspace into the format that is read by
+/// [crate::importer::workspace::WorkspaceRemapper]:
+///
+/// ```text
+/// relation_map.json
+/// metadata.json
+/// collab_jsons/documents/<view_id>.json
+/// collab_jsons/databases/<view_id>.json
+/// collab_jsons/databases/<view_id>/row_documents/<row_document_id>.json
+/// collab_objects/documents/<view_id>.collab
+/// collab_objects/databases/<view_id>.collab
+/// collab_objects/databases/<view_id>/rows/<row_id>.collab
+/// collab_objects/databases/<view_id>/row_documents/<row_document_id>.collab
+/// ```
+///
+/// Every database view gets its own database json. The rows and the row documents of a database
+/// are only written once, under the first exported view of the database.
+///
+/// Views whose collab can't be loaded are skipped together with their descendants.
+pub struct WorkspaceExporter<P> {
+  provider: P,
+  client_id: ClientID,
+}
+
+impl<P> WorkspaceExporter<P>
+where
+  P: WorkspaceCollabProvider,
+{
+  pub fn new(provider: P, client_id: ClientID) -> Self {
+    Self {
+      provider,
+      client_id,
+    }
+  }
+
+  /// Exports the workspace in memory.
+  pub async fn export(
+    &self,
+    folder: &Folder,
+    workspace_database: &WorkspaceDatabase,
+    uid: Option<i64>,
+  ) -> Result<ExportedWorkspace, CollabError> {
+    let workspace_id = folder
+      .get_workspace_id()
+      .ok_or_else(|| CollabError::NoRequiredData("workspace id".to_string()))?;
+    let export_timestamp = timestamp();
+    let mut state = ExportState::default();
+
+    let views = folder.get_view_recursively(&workspace_id, uid);
+    let mut skipped = HashSet::new();
+    for view in views.iter().filter(|view| view.id != workspace_id) {
+      let parent_skipped = view
+        .parent_view_id
+        .is_some_and(|parent_id| skipped.contains(&parent_id));
+      if parent_skipped
+        || !self
+          .export_view(view, workspace_database, &mut state)
+          .await?
+      {
+        skipped.insert(view.id);
+        continue;
+      }
+      state.views.insert(view.id, view_metadata(view, &state));
+    }
+
+    // Children that are not exported must not be referenced by their parent.
+    let exported_view_ids = state.views.keys().copied().collect::<HashSet<_>>();
+    for metadata in state.views.values_mut() {
+      metadata
+        .children
+        .retain(|child_id| exported_view_ids.contains(child_id));
+    }
+    state.add_relation_dependencies(workspace_database, &exported_view_ids);
+
+    let workspace_database_meta = state
+      .database_view_ids
+      .iter()
+      .map(|(database_id, view_ids)| WorkspaceDatabaseMeta {
+        database_id: *database_id,
+        view_ids: view_ids.clone(),
+      })
+      .collect::<Vec<_>>();
+    let relation_map = WorkspaceRelationMap {
+      workspace_id,
+      export_timestamp,
+      views: state.views,
+      collab_objects: state.collab_objects,
+      dependencies: state.dependencies,
+      workspace_database_meta: if workspace_database_meta.is_empty() {
+        None
+      } else {
+        Some(workspace_database_meta)
+      },
+    };
+
+    let mut files = state.files;
+    let collab_files = files
+      .iter()
+      .filter_map(|file| file.path.strip_prefix(COLLAB_OBJECTS_DIR).ok())
+      .map(path_to_link)
+      .collect::<Vec<_>>();
+    let metadata = ExportMetadata {
+      export_info: ExportInfo {
+        archive_format: "zip".to_string(),
+        export_timestamp,
+        total_collab_files: collab_files.len(),
+        total_dependencies: relation_map.dependencies.len(),
+        total_views: relation_map.views.len(),
+        version: EXPORT_VERSION.to_string(),
+        workspace_id,
+      },
+      files: collab_files,
+    };
+    files.push(ExportedFile {
+      path: PathBuf::from(EXPORT_METADATA_FILE),
+      content: serde_json::to_vec_pretty(&metadata)?,
+    });
+    files.push(ExportedFile {
+      path: PathBuf::from(RELATION_MAP_FILE),
+      content: serde_json::to_vec_pretty(&relation_map)?,
+    });
+
+    Ok(ExportedWorkspace {
+      relation_map,
+      files,
+    })
+  }
+
+  /// Exports the workspace into `output_dir`.
+  pub async fn export_to_dir(
+    &self,
+    folder: &Folder,
+    workspace_database: &WorkspaceDatabase,
+    output_dir: &Path,
+    uid: Option<i64>,
+  ) -> Result<ExportedWorkspace, CollabError> {
+    let exported = self.export(folder, workspace_database, uid).await?;
+    write_files_to_dir(&exported.files, output_dir).await?;
+    Ok(exported)
+  }
+
+  /// Exports the workspace into a zip archive at `zip_path`.
+  pub async fn export_to_zip(
+    &self,
+    folder: &Folder,
+    workspace_database: &WorkspaceDatabase,
+    zip_path: &Path,
+    uid: Option<i64>,
+  ) -> Result<ExportedWorkspace, CollabError> {
+    let exported = self.export(folder, workspace_database, uid).await?;
+    write_files_to_zip(&exported.files, zip_path).await?;
+    Ok(exported)
+  }
+
+  /// Returns false if the view can't be exported.
+  async fn export_view(
+    &self,
+    view: &View,
+    workspace_database: &WorkspaceDatabase,
+    state: &mut ExportState,
+  ) -> Result<bool, CollabError> {
+    if view.layout.is_document() {
+      self.export_document_view(view, state).await
+    } else if view.layout.is_database() {
+      self
+        .export_database_view(view, workspace_database, state)
+        .await
+    } else {
+      warn!(
+        "Skip exporting view {}: unsupported layout {:?}",
+        view.id, view.layout
+      );
+      Ok(false)
+    }
+  }
+
+  async fn export_document_view(
+    &self,
+    view: &View,
+    state: &mut ExportState,
+  ) -> Result<bool, CollabError> {
+    let Some((document_data, size_bytes)) = self.load_document(&view.id).await? else {
+      warn!("Skip exporting document {}: collab not found", view.id);
+      return Ok(false);
+    };
+    let file_name = format!("{}.json", view.id);
+    state.add_json(
+      PathBuf::from(COLLAB_JSONS_DIR)
+        .join(DOCUMENTS_DIR)
+        .join(&file_name),
+      &document_data,
+    )?;
+    state.add_collab(
+      view.id,
+      CollabType::Document,
+      PathBuf::from(COLLAB_OBJECTS_DIR)
+        .join(DOCUMENTS_DIR)
+        .join(format!("{}.collab", view.id)),
+      size_bytes,
+    );
+    state.add_document_dependencies(&view.id.to_string(), &document_data);
+    Ok(true)
+  }
+
+  async fn export_database_view(
+    &self,
+    view: &View,
+    workspace_database: &WorkspaceDatabase,
+    state: &mut ExportState,
+  ) -> Result<bool, CollabError> {
+    let Some(database_id) = workspace_database
+      .get_database_meta_with_view_id(&view.id.to_string())
+      .and_then(|meta| Uuid::parse_str(&meta.database_id).ok())
+    else {
+      warn!(
+        "Skip exporting database view {}: database not found",
+        view.id
+      );
+      return Ok(false);
+    };
+    let Some(database_collab) = self
+      .provider
+      .get_encoded_collab(&database_id, CollabType::Database)
+      .await?
+    else {
+      warn!("Skip exporting database {}: collab not found", database_id);
+      return Ok(false);
+    };
+    let Some(data) = self.provider.get_database_data(&database_id).await? else {
+      warn!("Skip exporting database {}: data not found", database_id);
+      return Ok(false);
+    };
+
+    let view_dir = PathBuf::from(DATABASES_DIR).join(view.id.to_string());
+    state.add_json(
+      PathBuf::from(COLLAB_JSONS_DIR)
+        .join(DATABASES_DIR)
+        .join(format!("{}.json", view.id)),
+      &data,
+    )?;
+    state.add_collab(
+      view.id,
+      CollabType::Database,
+      PathBuf::from(COLLAB_OBJECTS_DIR)
+        .join(DATABASES_DIR)
+        .join(format!("{}.collab", view.id)),
+      database_collab.encode_to_bytes()?,
+    );
+
+    let source_view_id = view.id.to_string();
+    for row in &data.rows {
+      state.dependencies.push(ViewDependency {
+        source_view_id: source_view_id.clone(),
+        target_view_id: row.id.to_string(),
+        dependency_type: DependencyType::DatabaseRow,
+      });
+    }
+
+    let view_ids = state.database_view_ids.entry(database_id).or_default();
+    view_ids.push(view.id);
+    if view_ids.len() > 1 {
+      // The rows were exported with the first view of the database.
+      return Ok(true);
+    }
+    for row in &data.rows {
+      if let Some(row_collab) = self
+        .provider
+        .get_encoded_collab(&row.id, CollabType::DatabaseRow)
+        .await?
+      {
+        state.add_collab(
+          row.id,
+          CollabType::DatabaseRow,
+          PathBuf::from(COLLAB_OBJECTS_DIR)
+            .join(&view_dir)
+            .join(ROWS_DIR)
+            .join(format!("{}.collab", row.id)),
+          row_collab.encode_to_bytes()?,
+        );
+      }
+
+      let Some(row_document_id) = get_row_document_id(&row.id)
+        .ok()
+        .and_then(|id| Uuid::parse_str(&id).ok())
+      else {
+        continue;
+      };
+      let Some((document_data, size_bytes)) = self.load_document(&row_document_id).await? else {
+        continue;
+      };
+      state.add_json(
+        PathBuf::from(COLLAB_JSONS_DIR)
+          .join(&view_dir)
+          .join(ROW_DOCUMENTS_DIR)
+          .join(format!("{}.json", row_document_id)),
+        &document_data,
+      )?;
+      state.add_collab(
+        row_document_id,
+        CollabType::Document,
+        PathBuf::from(COLLAB_OBJECTS_DIR)
+          .join(&view_dir)
+          .join(ROW_DOCUMENTS_DIR)
+          .join(format!("{}.collab", row_document_id)),
+        size_bytes,
+      );
+      state.dependencies.push(ViewDependency {
+        source_view_id: row.id.to_string(),
+        target_view_id: row_document_id.to_string(),
+        dependency_type: DependencyType::DatabaseRowDocument,
+      });
+      state.add_document_dependencies(&row.id.to_string(), &document_data);
+    }
+    state.primary_database_data.insert(view.id, data);
+    Ok(true)
+  }
+
+  /// Returns the document data and the encoded collab bytes of the document.
+  async fn load_document(
+    &self,
+    document_id: &Uuid,
+  ) -> Result<Option<(DocumentData, Vec<u8>)>, CollabError> {
+    let Some(encoded_collab) = self
+      .provider
+      .get_encoded_collab(document_id, CollabType::Document)
+      .await?
+    else {
+      return Ok(None);
+    };
+    let bytes = encoded_collab.encode_to_bytes()?;
+    let options =
+      CollabOptions::new(*document_id, self.client_id).with_data_source(encoded_collab.into());
+    let collab = Collab::new_with_options(CollabOrigin::Empty, options)
+      .map_err(|e| CollabError::Internal(anyhow::Error::new(e)))?;
+    let document = Document::open(collab)?;
+    Ok(Some((document.get_document_data()?, bytes)))
+  }
+}
+
+#[derive(Default)]
+struct ExportState {
+  views: IndexMap<Uuid, ViewMetadata>,
+  collab_objects: HashMap<Uuid, CollabMetadata>,
+  dependencies: Vec<ViewDependency>,
+  /// The exported views of each database, in export order.
+  database_view_ids: IndexMap<Uuid, Vec<Uuid>>,
+  /// The database data, keyed by the first exported view of each database.
+  primary_database_data: IndexMap<Uuid, DatabaseData>,
+  files: Vec<ExportedFile>,
+}
+
+impl ExportState {
+  fn add_json<T: Serialize>(&mut self, path: PathBuf, value: &T) -> Result<(), CollabError> {
+    self.files.push(ExportedFile {
+      path,
+      content: serde_json::to_vec_pretty(value)?,
+    });
+    Ok(())
+  }
+
+  fn add_collab(
+    &mut self,
+    object_id: Uuid,
+    collab_type: CollabType,
+    path: PathBuf,
+    bytes: Vec<u8>,
+  ) {
+    self.collab_objects.insert(
+      object_id,
+      CollabMetadata {
+        object_id,
+        collab_type,
+        size_bytes: bytes.len() as u64,
+      },
+    );
+    self.files.push(ExportedFile {
+      path,
+      content: bytes,
+    });
+  }
+
+  /// Adds a [DependencyType::DocumentReference] for each page mention and sub page block, and a
+  /// [DependencyType::FileAttachment] for each image and file block.
+  fn add_document_dependencies(&mut self, source_id: &str, document_data: &DocumentData) {
+    let mut references = vec![];
+    let mut attachments = vec![];
+    for block in document_data.blocks.values() {
+      match BlockType::from_block_ty(&block.ty) {
+        BlockType::SubPage => references.extend(
+          SUB_PAGE_VIEW_ID_KEYS
+            .iter()
+            .find_map(|key| block.data.get(*key).and_then(|v| v.as_str()))
+            .map(|id| id.to_string()),
+        ),
+        BlockType::Image | BlockType::File => attachments.extend(
+          block
+            .data
+            .get(BLOCK_URL_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(attachment_file_id),
+        ),
+        _ => {},
+      }
+    }
+    if let Some(text_map) = &document_data.meta.text_map {
+      for delta in text_map.values() {
+        references.extend(page_mentions_in_delta(delta));
+      }
+    }
+
+    let mut seen = HashSet::new();
+    for target in references {
+      if Uuid::parse_str(&target).is_err() || !seen.insert(target.clone()) {
+        continue;
+      }
+      self.dependencies.push(ViewDependency {
+        source_view_id: source_id.to_string(),
+        target_view_id: target,
+        dependency_type: DependencyType::DocumentReference,
+      });
+    }
+    for target in attachments {
+      if !seen.insert(target.clone()) {
+        continue;
+      }
+      self.dependencies.push(ViewDependency {
+        source_view_id: source_id.to_string(),
+        target_view_id: target,
+        dependency_type: DependencyType::FileAttachment,
+      });
+    }
+  }
+
+  /// Adds a [DependencyType::DatabaseRelation] from the first exported view of a database to a view
+  /// of each database that it relates to.
+  fn add_relation_dependencies(
+    &mut self,
+    workspace_database: &WorkspaceDatabase,
+    exported_view_ids: &HashSet<Uuid>,
+  ) {
+    let type_id = FieldType::Relation.type_id();
+    for (view_id, data) in &self.primary_database_data {
+      let mut seen = HashSet::new();
+      for field in &data.fields {
+        if FieldType::from(field.field_type) != FieldType::Relation {
+          continue;
+        }
+        let type_option =
+          RelationTypeOption::from(field.get_any_type_option(&type_id).unwrap_or_default());
+        if !seen.insert(type_option.database_id.clone()) {
+          continue;
+        }
+        let Some(meta) = workspace_database.get_database_meta(&type_option.database_id) else {
+          continue;
+        };
+        let linked_views = meta
+          .linked_views
+          .iter()
+          .filter_map(|id| Uuid::parse_str(id).ok())
+          .collect::<Vec<_>>();
+        let target = linked_views
+          .iter()
+          .find(|id| exported_view_ids.contains(id))
+          .or(linked_views.first());
+        if let Some(target) = target {
+          self.dependencies.push(ViewDependency {
+            source_view_id: view_id.to_string(),
+            target_view_id: target.to_string(),
+            dependency_type: DependencyType::DatabaseRelation,
+          });
+        }
+      }
+    }
+  }
+}
+
+fn view_metadata(view: &View, state: &ExportState) -> ViewMetadata {
+  let collab_object_id = state
+    .database_view_ids
+    .iter()
+    .find(|(_, view_ids)| view_ids.contains(&view.id))
+    .map(|(database_id, _)| *database_id)
+    .unwrap_or(view.id);
+  ViewMetadata {
+    view_id: view.id,
+    name: view.name.clone(),
+    layout: view.layout.clone(),
+    parent_id: view.parent_view_id,
+    children: view.children.items.iter().map(|child| child.id).collect(),
+    collab_object_id,
+    created_at: view.created_at,
+    updated_at: view.last_edited_time,
+    extra: view.extra.clone(),
+    icon: view.icon.clone(),
+  }
+}
+
+/// Returns the page ids of the page mentions in a text delta json string.
+fn page_mentions_in_delta(delta: &str) -> Vec<String> {
+  let Ok(JsonValue::Array(ops)) = serde_json::from_str::<JsonValue>(delta) else {
+    return vec![];
+  };
+  ops
+    .iter()
+    .filter_map(|op| op.get("attributes")?.get(mention_keys::MENTION))
+    .filter_map(|mention| match mention {
+      JsonValue::String(s) => serde_json::from_str::<JsonValue>(s).ok(),
+      JsonValue::Object(_) => Some(mention.clone()),
+      _ => None,
+    })
+    .filter(|mention| {
+      matches!(
+        mention.get(mention_keys::TYPE).and_then(|v| v.as_str()),
+        Some(mention_types::PAGE) | Some(mention_types::CHILD_PAGE)
+      )
+    })
+    .filter_map(|mention| {
+      mention
+        .get(mention_keys::PAGE_ID)
+        .and_then(|v| v.as_str())
+        .map(|id| id.to_string())
+    })
+    .collect()
+}
+
+/// The file id of an uploaded file is the last segment of its url.
+fn attachment_file_id(url: &str) -> Option<String> {
+  let id = url.split(['?', '#']).next()?.rsplit('/').next()?;
+  if id.is_empty() {
+    None
+  } else {
+    Some(id.to_string())
+  }
+}
@@ -16,10 +16,10 @@ use uuid::Uuid;
 
 use super::folder_observe::ViewChangeSender;
 use super::hierarchy_builder::{FlattedViews, ParentChildViews};
-use super::section::{Section, SectionItem, SectionMap};
+use super::section::{CustomSection, Section, SectionItem, SectionMap, SectionMeta};
 use super::{
-  FolderData, ParentChildRelations, SectionChangeSender, SpacePermission, TrashInfo, View,
-  ViewChangeReceiver, ViewId, ViewUpdate, ViewsMap, Workspace,
+  FolderData, ParentChildRelations, SectionChangeSender, SpaceInfo, SpacePermission, TrashInfo,
+  View, ViewChangeReceiver, ViewId, ViewLayout, ViewUpdate, ViewsMap, Workspace, timestamp,
 };
 use crate::entity::uuid_validation::WorkspaceId;
 use crate::error::CollabError;
@@ -984,6 +984,169 @@ impl Folder {
     }
   }
 
+  /// Creates a custom section, or updates the display metadata of an existing one.
+  ///
+  /// Custom sections are user-defined sidebar groups such as "Pinned" or "Reading list". The
+  /// section metadata is shared by all users of the folder while the items are stored per user,
+  /// like the predefined sections.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if `section_id` is the key of a predefined section (favorite, recent,
+  /// trash or private).
+  pub fn create_custom_section(
+    &mut self,
+    section_id: &str,
+    name: &str,
+    icon: Option<String>,
+    uid: i64,
+  ) -> Result<Section, CollabError> {
+    let section = Section::from(section_id.to_string());
+    if section.is_predefined() {
+      return Err(CollabError::Internal(anyhow!(
+        "{} is a predefined section",
+        section_id
+      )));
+    }
+
+    let mut txn = self.collab.transact_mut();
+    self.body.section.create_section(&mut txn, section.clone());
+    let op = self
+      .body
+      .section
+      .section_op(&txn, section.clone(), Some(uid))
+      .ok_or_else(|| CollabError::NoRequiredData(format!("section {} not found", section_id)))?;
+    let meta = match op.get_meta(&txn) {
+      Some(meta) => SectionMeta {
+        name: name.to_string(),
+        icon,
+        ..meta
+      },
+      None => SectionMeta {
+        name: name.to_string(),
+        icon,
+        created_by: uid,
+        created_at: timestamp(),
+      },
+    };
+    op.set_meta(&mut txn, meta);
+    Ok(section)
+  }
+
+  /// Renames an existing custom section.
+  pub fn rename_custom_section(&mut self, section_id: &str, name: &str) -> Result<(), CollabError> {
+    let section = Section::from(section_id.to_string());
+    let mut txn = self.collab.transact_mut();
+    let op = self
+      .body
+      .section
+      .section_op(&txn, section, None)
+      .filter(|op| !op.section().is_predefined())
+      .ok_or_else(|| CollabError::NoRequiredData(format!("section {} not found", section_id)))?;
+    let mut meta = op.get_meta(&txn).ok_or_else(|| {
+      CollabError::NoRequiredData(format!("section {} has no metadata", section_id))
+    })?;
+    meta.name = name.to_string();
+    op.set_meta(&mut txn, meta);
+    Ok(())
+  }
+
+  /// Deletes a custom section and the items of all users in it.
+  /// Returns false if the section doesn't exist or is a predefined section.
+  pub fn delete_custom_section(&mut self, section_id: &str) -> bool {
+    let section = Section::from(section_id.to_string());
+    let mut txn = self.collab.transact_mut();
+    self.body.section.delete_section(&mut txn, &section)
+  }
+
+  pub fn add_custom_section_items(&mut self, section_id: &str, ids: Vec<ViewId>, uid: i64) {
+    let section = Section::from(section_id.to_string());
+    if section.is_predefined() {
+      return;
+    }
+    let mut txn = self.collab.transact_mut();
+    if let Some(op) = self.body.section.section_op(&txn, section, Some(uid)) {
+      let existing = op.get_all_section_item(&txn);
+      let items = ids
+        .into_iter()
+        .filter(|id| !existing.iter().any(|item| item.id == *id))
+        .map(SectionItem::new)
+        .collect::<Vec<_>>();
+      if !items.is_empty() {
+        op.add_sections_item(&mut txn, items);
+      }
+    }
+  }
+
+  pub fn remove_custom_section_items(&mut self, section_id: &str, ids: Vec<String>, uid: i64) {
+    let section = Section::from(section_id.to_string());
+    if section.is_predefined() {
+      return;
+    }
+    let mut txn = self.collab.transact_mut();
+    if let Some(op) = self.body.section.section_op(&txn, section, Some(uid)) {
+      op.delete_section_items_with_txn(&mut txn, ids);
+    }
+  }
+
+  pub fn move_custom_section_item(
+    &mut self,
+    section_id: &str,
+    id: &str,
+    prev_id: Option<&str>,
+    uid: i64,
+  ) {
+    let section = Section::from(section_id.to_string());
+    if section.is_predefined() {
+      return;
+    }
+    let mut txn = self.collab.transact_mut();
+    if let Some(op) = self.body.section.section_op(&txn, section, Some(uid)) {
+      op.move_section_item_with_txn(&mut txn, id, prev_id);
+    }
+  }
+
+  /// Returns the items of the custom section for the given user.
+  pub fn get_custom_section_items(&self, section_id: &str, uid: i64) -> Vec<SectionItem> {
+    let section = Section::from(section_id.to_string());
+    if section.is_predefined() {
+      return vec![];
+    }
+    let txn = self.collab.transact();
+    self
+      .body
+      .section
+      .section_op(&txn, section, Some(uid))
+      .map(|op| op.get_all_section_item(&txn))
+      .unwrap_or_default()
+  }
+
+  /// Returns the custom sections of a user: the sections the user created and the sections the
+  /// user has items in. Each [CustomSection] only contains the items of the given user.
+  pub fn get_my_custom_sections(&self, uid: i64) -> Vec<CustomSection> {
+    let txn = self.collab.transact();
+    self
+      .body
+      .section
+      .get_custom_sections(&txn)
+      .into_iter()
+      .filter_map(|section| {
+        let op = self.body.section.section_op(&txn, section, Some(uid))?;
+        let meta = op.get_meta(&txn);
+        let items = op.get_all_section_item(&txn);
+        let is_creator = meta.as_ref().is_some_and(|meta| meta.created_by == uid);
+        if !is_creator && items.is_empty() {
+          return None;
+        }
+        Some(CustomSection {
+          section: op.section().clone(),
+          meta,
+          items,
+        })
+      })
+      .collect()
+  }
+
   /// Retrieves enriched trash information for a specific user.
   ///
   /// This is an enhanced version of [`get_my_trash_sections`] that includes additional
@@ -1219,6 +1382,103 @@ impl Folder {
     );
     views
   }
+
+  /// Returns the path from the workspace view down to the view with the given `view_id`.
+  ///
+  /// The first element is the top most ancestor (usually the workspace view) and the last element
+  /// is the view itself. Returns an empty vector if the view doesn't exist. The walk stops when a
+  /// parent can't be found or when a circular parent relation is detected.
+  pub fn get_view_path(&self, view_id: &ViewId, uid: Option<i64>) -> Vec<Arc<View>> {
+    let txn = self.collab.transact();
+    self.body.get_view_path_with_txn(&txn, view_id, uid)
+  }
+
+  /// Returns the breadcrumbs of the view: the path from the nearest space down to the view.
+  /// If the view doesn't belong to a space, the path starts right below the workspace view.
+  pub fn get_view_breadcrumbs(&self, view_id: &ViewId, uid: Option<i64>) -> Vec<Arc<View>> {
+    let txn = self.collab.transact();
+    let mut path = self.body.get_view_path_with_txn(&txn, view_id, uid);
+    let workspace_id = self.body.get_workspace_id(&txn);
+    let start = path
+      .iter()
+      .rposition(|view| view.space_info().is_some_and(|info| info.is_space))
+      .unwrap_or_else(|| {
+        path
+          .iter()
+          .position(|view| Some(view.id.to_string()) != workspace_id)
+          .unwrap_or(path.len())
+      });
+    path.drain(..start);
+    path
+  }
+
+  /// Returns the depth of the view. The workspace view has depth 0, its children have depth 1 and
+  /// so on. Returns `None` if the view doesn't exist.
+  pub fn get_view_depth(&self, view_id: &ViewId) -> Option<usize> {
+    let txn = self.collab.transact();
+    let path = self.body.get_view_path_with_txn(&txn, view_id, None);
+    path.len().checked_sub(1)
+  }
+
+  /// Returns the index of the view within the children of its parent.
+  pub fn get_view_sibling_index(&self, view_id: &ViewId) -> Option<usize> {
+    let txn = self.collab.transact();
+    self.body.get_view_sibling_index_with_txn(&txn, view_id)
+  }
+
+  /// Returns the nearest space that contains the view, including the view itself, together with
+  /// its [SpaceInfo].
+  pub fn get_view_space(
+    &self,
+    view_id: &ViewId,
+    uid: Option<i64>,
+  ) -> Option<(Arc<View>, SpaceInfo)> {
+    let txn = self.collab.transact();
+    self
+      .body
+      .get_view_path_with_txn(&txn, view_id, uid)
+      .into_iter()
+      .rev()
+      .find_map(|view| {
+        let space_info = view.space_info().filter(|info| info.is_space)?;
+        Some((view, space_info))
+      })
+  }
+
+  /// Returns the view and its descendants whose layout is one of the given `layouts`.
+  /// The views are returned in depth-first order.
+  pub fn get_view_recursively_with_layouts(
+    &self,
+    view_id: &ViewId,
+    layouts: &[ViewLayout],
+    uid: Option<i64>,
+  ) -> Vec<View> {
+    let mut views = self.get_view_recursively(view_id, uid);
+    views.retain(|view| layouts.contains(&view.layout));
+    views
+  }
+
+  /// Returns all the views of the workspace, most recently edited first.
+  /// The workspace view itself is not included.
+  pub fn get_views_sorted_by_last_edited_time(
+    &self,
+    limit: Option<usize>,
+    uid: Option<i64>,
+  ) -> Vec<Arc<View>> {
+    let txn = self.collab.transact();
+    let workspace_id = self.body.get_workspace_id(&txn);
+    let mut views = self.body.views.get_all_views(&txn, uid);
+    views.retain(|view| Some(view.id.to_string()) != workspace_id);
+    views.sort_by(|a, b| {
+      b.last_edited_time
+        .cmp(&a.last_edited_time)
+        .then_with(|| b.created_at.cmp(&a.created_at))
+    });
+    if let Some(limit) = limit {
+      views.truncate(limit);
+    }
+    views
+  }
 }
 
 impl Deref for Folder {
@@ -1409,6 +1669,49 @@ impl FolderBody {
     }
   }
 
+  /// Returns the path from the top most ancestor down to the view with the given `view_id`.
+  /// Walking up stops when a parent can't be found or a view is visited twice.
+  pub fn get_view_path_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    view_id: &ViewId,
+    uid: Option<i64>,
+  ) -> Vec<Arc<View>> {
+    let mut path = vec![];
+    let mut visited = HashSet::new();
+    let mut current_id = Some(*view_id);
+    while let Some(id) = current_id {
+      if !visited.insert(id) {
+        break;
+      }
+      match self.views.get_view_with_txn(txn, &id, uid) {
+        Some(view) => {
+          current_id = view.parent_view_id;
+          path.push(view);
+        },
+        None => break,
+      }
+    }
+    path.reverse();
+    path
+  }
+
+  pub fn get_view_sibling_index_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    view_id: &ViewId,
+  ) -> Option<usize> {
+    let view = self.views.get_view_with_txn(txn, view_id, None)?;
+    let parent = self
+      .views
+      .get_view_with_txn(txn, view.parent_view_id.as_ref()?, None)?;
+    parent
+      .children
+      .items
+      .iter()
+      .position(|child| child.id == *view_id)
+  }
+
   pub fn get_workspace_info<T: ReadTxn>(
     &self,
     txn: &T,
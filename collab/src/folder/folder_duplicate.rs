@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::CollabError;
+use crate::preclude::ReadTxn;
+
+use super::hierarchy_builder::{NestedChildViewBuilder, ParentChildViews};
+use super::{Folder, FolderBody, ViewId};
+
+/// The result of [Folder::duplicate_view_hierarchy].
+#[derive(Debug, Clone)]
+pub struct DuplicatedViewHierarchy {
+  /// The duplicated views. Every view in the hierarchy has a newly generated id.
+  pub views: ParentChildViews,
+  /// Maps the id of each original view to the id of its duplicate.
+  pub id_map: HashMap<ViewId, ViewId>,
+}
+
+impl DuplicatedViewHierarchy {
+  pub fn new_view_id(&self, old_view_id: &ViewId) -> Option<ViewId> {
+    self.id_map.get(old_view_id).copied()
+  }
+}
+
+impl Folder {
+  /// Duplicates the view with the given `view_id` and all of its descendants.
+  ///
+  /// Each duplicated view gets a new id and keeps the name, layout, icon, lock state and extra
+  /// of the original view. The duplicated root view is placed under `parent_view_id`, or under the
+  /// parent of the original view when `parent_view_id` is `None`.
+  ///
+  /// The views are not inserted into the folder. Use [Folder::insert_nested_views] to insert them
+  /// once the collab objects of the views have been duplicated as well.
+  pub fn duplicate_view_hierarchy(
+    &self,
+    view_id: &ViewId,
+    parent_view_id: Option<ViewId>,
+    uid: i64,
+  ) -> Result<DuplicatedViewHierarchy, CollabError> {
+    let txn = self.collab.transact();
+    let view = self
+      .body
+      .views
+      .get_view_with_txn(&txn, view_id, Some(uid))
+      .ok_or_else(|| CollabError::NoRequiredData(format!("view {} not found", view_id)))?;
+    let parent_view_id = parent_view_id
+      .or(view.parent_view_id)
+      .ok_or_else(|| CollabError::NoRequiredData(format!("view {} has no parent", view_id)))?;
+
+    let mut id_map = HashMap::new();
+    let mut visited = HashSet::new();
+    let views = self
+      .body
+      .duplicate_view_with_txn(
+        &txn,
+        view_id,
+        parent_view_id,
+        uid,
+        &mut visited,
+        &mut id_map,
+      )
+      .ok_or_else(|| CollabError::NoRequiredData(format!("view {} not found", view_id)))?;
+    Ok(DuplicatedViewHierarchy { views, id_map })
+  }
+}
+
+impl FolderBody {
+  fn duplicate_view_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    view_id: &ViewId,
+    parent_view_id: ViewId,
+    uid: i64,
+    visited: &mut HashSet<ViewId>,
+    id_map: &mut HashMap<ViewId, ViewId>,
+  ) -> Option<ParentChildViews> {
+    // Guard against circular parent/child relations.
+    if !visited.insert(*view_id) {
+      return None;
+    }
+
+    let view = self.views.get_view_with_txn(txn, view_id, Some(uid))?;
+    let builder = NestedChildViewBuilder::new(uid, parent_view_id)
+      .with_name(&view.name)
+      .with_layout(view.layout.clone())
+      .with_view_icon(view.icon.clone())
+      .with_is_locked(view.is_locked)
+      .with_extra_string(view.extra.clone());
+    let new_view_id = builder.view_id();
+    id_map.insert(*view_id, new_view_id);
+
+    let children = view
+      .children
+      .items
+      .iter()
+      .filter_map(|child| {
+        self.duplicate_view_with_txn(txn, &child.id, new_view_id, uid, visited, id_map)
+      })
+      .collect::<Vec<_>>();
+    Some(builder.with_children(children).build())
+  }
+}
@@ -189,6 +189,22 @@ impl NestedChildViewBuilder {
     self
   }
 
+  pub fn with_view_icon(mut self, icon: Option<ViewIcon>) -> Self {
+    self.icon = icon;
+    self
+  }
+
+  pub fn with_is_locked(mut self, is_locked: Option<bool>) -> Self {
+    self.is_locked = is_locked;
+    self
+  }
+
+  /// Set the raw extra string of the view, e.g. when copying it from an existing view.
+  pub fn with_extra_string(mut self, extra: Option<String>) -> Self {
+    self.extra = extra;
+    self
+  }
+
   pub fn with_extra<F: FnOnce(ViewExtraBuilder) -> serde_json::Value>(mut self, extra: F) -> Self {
     let builder = ViewExtraBuilder::new();
     let extra_json = extra(builder);
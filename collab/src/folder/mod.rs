@@ -3,6 +3,7 @@
 pub use crate::entity::define::ViewId;
 pub use entities::*;
 pub use folder::*;
+pub use folder_duplicate::*;
 pub use folder_migration::*;
 pub use folder_observe::*;
 pub use relation::*;
@@ -14,6 +15,7 @@ pub use workspace::*;
 mod entities;
 mod folder;
 pub mod folder_diff;
+mod folder_duplicate;
 mod folder_migration;
 mod folder_observe;
 pub mod hierarchy_builder;
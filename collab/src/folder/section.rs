@@ -60,6 +60,35 @@ impl SectionMap {
     self.container.get_or_init_map(txn, section.as_ref())
   }
 
+  /// Returns all the sections that are not predefined.
+  pub fn get_custom_sections<T: ReadTxn>(&self, txn: &T) -> Vec<Section> {
+    self
+      .container
+      .iter(txn)
+      .filter_map(|(key, value)| match value {
+        YrsValue::YMap(_) => Some(Section::from(key.to_string())),
+        _ => None,
+      })
+      .filter(|section| !section.is_predefined())
+      .collect()
+  }
+
+  /// Deletes a custom section together with the items of all users.
+  /// Predefined sections can't be deleted. Returns true if the section was deleted.
+  pub fn delete_section(&self, txn: &mut TransactionMut, section: &Section) -> bool {
+    if section.is_predefined() || self.get_section(txn, section.as_ref()).is_none() {
+      return false;
+    }
+
+    self.container.remove(txn, section.as_ref());
+    if let Some(change_tx) = self.change_tx.as_ref() {
+      let _ = change_tx.send(SectionChange::Custom(CustomSectionChange::SectionDeleted {
+        section_id: section.as_ref().to_string(),
+      }));
+    }
+    true
+  }
+
   fn get_section<T: ReadTxn>(&self, txn: &T, section_id: &str) -> Option<MapRef> {
     self.container.get_with_txn(txn, section_id)
   }
@@ -167,9 +196,20 @@ impl AsRef<str> for Section {
   }
 }
 
+impl Section {
+  /// Returns true if the section uses the key of a predefined section. `Custom("trash")` is
+  /// treated as predefined because it shares the storage of [Section::Trash].
+  pub fn is_predefined(&self) -> bool {
+    predefined_sections()
+      .iter()
+      .any(|section| section.as_ref() == self.as_ref())
+  }
+}
+
 #[derive(Clone, Debug)]
 pub enum SectionChange {
   Trash(TrashSectionChange),
+  Custom(CustomSectionChange),
 }
 
 pub type SectionChangeSender = broadcast::Sender<SectionChange>;
@@ -181,6 +221,51 @@ pub enum TrashSectionChange {
   TrashItemRemoved { ids: Vec<ViewId> },
 }
 
+#[derive(Clone, Debug)]
+pub enum CustomSectionChange {
+  SectionUpdated {
+    section_id: String,
+  },
+  SectionDeleted {
+    section_id: String,
+  },
+  ItemsAdded {
+    section_id: String,
+    ids: Vec<ViewId>,
+  },
+  ItemsRemoved {
+    section_id: String,
+    ids: Vec<ViewId>,
+  },
+  ItemMoved {
+    section_id: String,
+    id: ViewId,
+  },
+}
+
+/// Key of the display metadata stored next to the per-user arrays of a custom section.
+const SECTION_META: &str = "meta";
+
+/// Display metadata of a custom section, e.g. "Pinned" or "Reading list".
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SectionMeta {
+  pub name: String,
+  #[serde(default)]
+  pub icon: Option<String>,
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub created_by: i64,
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub created_at: i64,
+}
+
+/// A custom section and the items of a single user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomSection {
+  pub section: Section,
+  pub meta: Option<SectionMeta>,
+  pub items: Vec<SectionItem>,
+}
+
 pub type SectionsByUid = HashMap<UserId, Vec<SectionItem>>;
 
 pub struct SectionOperation {
@@ -199,6 +284,36 @@ impl SectionOperation {
     self.uid.as_ref()
   }
 
+  pub fn section(&self) -> &Section {
+    &self.section
+  }
+
+  pub fn get_meta<T: ReadTxn>(&self, txn: &T) -> Option<SectionMeta> {
+    match self.container().get(txn, SECTION_META)? {
+      YrsValue::Any(any) => from_any(&any).ok(),
+      _ => None,
+    }
+  }
+
+  pub fn set_meta(&self, txn: &mut TransactionMut, meta: SectionMeta) {
+    match to_any(&meta) {
+      Ok(any) => {
+        self.container().insert(txn, SECTION_META, any);
+        self.send_custom_change(|section_id| CustomSectionChange::SectionUpdated { section_id });
+      },
+      Err(err) => tracing::error!("Failed to serialize section meta: {}", err),
+    }
+  }
+
+  fn send_custom_change<F>(&self, f: F)
+  where
+    F: FnOnce(String) -> CustomSectionChange,
+  {
+    if let (Section::Custom(section_id), Some(change_tx)) = (&self.section, &self.change_tx) {
+      let _ = change_tx.send(SectionChange::Custom(f(section_id.clone())));
+    }
+  }
+
   pub fn get_sections<T: ReadTxn>(&self, txn: &T) -> SectionsByUid {
     let mut section_id_by_uid = HashMap::new();
     for (uid, value) in self.container().iter(txn) {
@@ -299,6 +414,10 @@ impl SectionOperation {
 
     if let (Some(old_pos), Some(section_array)) = (old_pos, section_array) {
       section_array.move_to(txn, old_pos, new_pos);
+      if let Some(item) = section_items.get(old_pos as usize) {
+        let id = item.id;
+        self.send_custom_change(|section_id| CustomSectionChange::ItemMoved { section_id, id });
+      }
     }
   }
 
@@ -325,7 +444,7 @@ impl SectionOperation {
       }
 
       if let Some(change_tx) = self.change_tx.as_ref() {
-        match self.section {
+        match &self.section {
           Section::Favorite => {},
           Section::Recent => {},
           Section::Trash => {
@@ -336,7 +455,15 @@ impl SectionOperation {
                 .collect(),
             }));
           },
-          Section::Custom(_) => {},
+          Section::Custom(section_id) => {
+            let _ = change_tx.send(SectionChange::Custom(CustomSectionChange::ItemsRemoved {
+              section_id: section_id.clone(),
+              ids: ids
+                .into_iter()
+                .filter_map(|id| Uuid::parse_str(id.as_ref()).ok())
+                .collect(),
+            }));
+          },
           Section::Private => {},
         }
       }
@@ -350,7 +477,7 @@ impl SectionOperation {
     let item_ids = items.iter().map(|item| item.id).collect::<Vec<_>>();
     self.add_sections_for_user_with_txn(txn, uid, items);
     if let Some(change_tx) = self.change_tx.as_ref() {
-      match self.section {
+      match &self.section {
         Section::Favorite => {},
         Section::Recent => {},
         Section::Trash => {
@@ -358,7 +485,12 @@ impl SectionOperation {
             ids: item_ids,
           }));
         },
-        Section::Custom(_) => {},
+        Section::Custom(section_id) => {
+          let _ = change_tx.send(SectionChange::Custom(CustomSectionChange::ItemsAdded {
+            section_id: section_id.clone(),
+            ids: item_ids,
+          }));
+        },
         Section::Private => {},
       }
     }
@@ -982,6 +982,7 @@ pub enum ViewLayout {
   List = 6,
   Gallery = 7,
   Feed = 8,
+  Timeline = 9,
 }
 
 impl ViewLayout {
@@ -999,6 +1000,7 @@ impl ViewLayout {
         | ViewLayout::List
         | ViewLayout::Gallery
         | ViewLayout::Feed
+        | ViewLayout::Timeline
     )
   }
 }
@@ -1017,6 +1019,7 @@ impl TryFrom<i64> for ViewLayout {
       6 => Ok(ViewLayout::List),
       7 => Ok(ViewLayout::Gallery),
       8 => Ok(ViewLayout::Feed),
+      9 => Ok(ViewLayout::Timeline),
       _ => bail!("Unknown layout {}", value),
     }
   }
@@ -6,6 +6,7 @@ pub mod id_mapper;
 pub mod id_remapper;
 pub mod relation_map_parser;
 pub mod space_view_edge_case_handler;
+pub mod view_duplicator;
 pub mod workspace_database_remapper;
 pub mod workspace_remapper;
 
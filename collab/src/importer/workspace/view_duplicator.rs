@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::core::collab::CollabOptions;
+use crate::core::origin::CollabOrigin;
+use crate::database::database::{
+  Database, DatabaseContext, DatabaseData, database_inline_view_id, gen_database_id, gen_row_id,
+  get_row_document_id,
+};
+use crate::database::database_remapper::DatabaseCollabRemapper;
+use crate::database::database_trait::NoPersistenceDatabaseCollabService;
+use crate::database::entity::{DatabaseView, EncodedCollabInfo, EncodedDatabase};
+use crate::database::views::DatabaseLayout;
+use crate::document::Document;
+use crate::document::document_remapper::DocumentCollabRemapper;
+use crate::entity::uuid_validation::DatabaseId;
+use crate::entity::{CollabType, EncodedCollab};
+use crate::error::CollabError;
+use crate::folder::hierarchy_builder::{FlattedViews, ParentChildViews};
+use crate::folder::{Folder, View, ViewId, ViewLayout};
+use crate::importer::workspace::id_mapper::IdMapper;
+use crate::importer::workspace::id_remapper::JsonIdRemapper;
+use crate::preclude::{ClientID, Collab};
+
+/// Provides the collab objects of the views that are going to be duplicated.
+#[async_trait]
+pub trait ViewCollabProvider: Send + Sync {
+  /// Returns the encoded collab of the document view.
+  async fn get_document_collab(
+    &self,
+    view_id: &ViewId,
+  ) -> Result<Option<EncodedCollab>, CollabError>;
+
+  /// Returns the id of the database that the database view belongs to.
+  async fn get_database_id(&self, view_id: &ViewId) -> Result<Option<DatabaseId>, CollabError>;
+
+  /// Returns the data of the database, including its rows and row metas.
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError>;
+
+  /// Returns the id of the inline view of the database. Defaults to the id derived from the
+  /// database id, which is the inline view id of every database created by this crate.
+  async fn get_database_inline_view_id(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<ViewId>, CollabError> {
+    Ok(Some(database_inline_view_id(database_id)))
+  }
+
+  /// Returns the encoded collab of a row document. Returns `None` if the row has no document.
+  async fn get_row_document_collab(
+    &self,
+    row_document_id: &Uuid,
+  ) -> Result<Option<EncodedCollab>, CollabError>;
+}
+
+/// The views and collab objects created by [ViewHierarchyDuplicator::duplicate].
+pub struct DuplicatedViews {
+  /// The duplicated views, ready to be inserted with [Folder::insert_nested_views].
+  pub views: ParentChildViews,
+  /// Maps every original id (views, databases, rows and row documents) to its new id.
+  pub id_mapper: IdMapper,
+  pub documents: Vec<EncodedCollabInfo>,
+  pub databases: Vec<EncodedDatabase>,
+  /// The linked views of each new database, used to register the database in the workspace
+  /// database.
+  pub database_view_ids: HashMap<DatabaseId, Vec<ViewId>>,
+}
+
+/// Duplicates a view and its descendants together with the collab objects behind them.
+///
+/// 1. every view gets a new id
+/// 2. every database referenced by a database view gets a new database id, and every row of the
+///    database gets a new row id. The inline view id is derived from the database id, so it is
+///    regenerated as well. When the inline view is itself one of the duplicated views, as in
+///    databases created by older versions, the duplicated view becomes a linked view of the new
+///    database
+/// 3. mentions, sub pages and inline databases that point to views inside the subtree are
+///    rewritten to the duplicated views. References to views outside the subtree are kept
+pub struct ViewHierarchyDuplicator<P> {
+  provider: P,
+  uid: i64,
+  client_id: ClientID,
+}
+
+impl<P> ViewHierarchyDuplicator<P>
+where
+  P: ViewCollabProvider,
+{
+  pub fn new(provider: P, uid: i64, client_id: ClientID) -> Self {
+    Self {
+      provider,
+      uid,
+      client_id,
+    }
+  }
+
+  pub async fn duplicate(
+    &self,
+    folder: &Folder,
+    view_id: &ViewId,
+    parent_view_id: Option<ViewId>,
+  ) -> Result<DuplicatedViews, CollabError> {
+    let hierarchy = folder.duplicate_view_hierarchy(view_id, parent_view_id, self.uid)?;
+    let old_view_ids: HashMap<ViewId, ViewId> = hierarchy
+      .id_map
+      .iter()
+      .map(|(old_id, new_id)| (*new_id, *old_id))
+      .collect();
+    let new_views = FlattedViews::flatten_views(vec![hierarchy.views.clone()]);
+
+    let mut id_map = hierarchy.id_map.clone();
+
+    // Group the database views by database, so each database is duplicated only once.
+    let mut database_views: Vec<(DatabaseId, Vec<ViewId>)> = vec![];
+    let mut document_views = vec![];
+    for view in &new_views {
+      let old_view_id = old_view_ids[&view.id];
+      if view.layout.is_database() {
+        let database_id = self
+          .provider
+          .get_database_id(&old_view_id)
+          .await?
+          .ok_or(CollabError::DatabaseViewNotExist)?;
+        match database_views.iter_mut().find(|(id, _)| *id == database_id) {
+          Some((_, view_ids)) => view_ids.push(old_view_id),
+          None => database_views.push((database_id, vec![old_view_id])),
+        }
+      } else if view.layout.is_document() {
+        document_views.push(old_view_id);
+      }
+    }
+
+    let mut database_data_list = vec![];
+    for (database_id, view_ids) in database_views {
+      let mut data = self
+        .provider
+        .get_database_data(&database_id)
+        .await?
+        .ok_or(CollabError::DatabaseNotExist)?;
+      // Views of the database that live outside the subtree are not duplicated.
+      data.views.retain(|view| view_ids.contains(&view.id));
+
+      let new_database_id = gen_database_id();
+      id_map.insert(database_id, new_database_id);
+      // The new database always has an inline view holding the row and field orders of the
+      // original inline view.
+      if let Some(inline_view_id) = self
+        .provider
+        .get_database_inline_view_id(&database_id)
+        .await?
+      {
+        match new_views
+          .iter()
+          .find(|view| old_view_ids[&view.id] == inline_view_id)
+        {
+          Some(view) => {
+            if !data.views.iter().any(|view| view.id == inline_view_id) {
+              data
+                .views
+                .push(linked_inline_view(database_id, inline_view_id, view));
+            }
+          },
+          None => {
+            id_map.insert(inline_view_id, database_inline_view_id(&new_database_id));
+          },
+        }
+      }
+      for row in &data.rows {
+        let new_row_id = gen_row_id();
+        id_map.insert(row.id, new_row_id);
+        let old_document_id = Uuid::parse_str(&get_row_document_id(&row.id)?)?;
+        let new_document_id = Uuid::parse_str(&get_row_document_id(&new_row_id)?)?;
+        id_map.insert(old_document_id, new_document_id);
+      }
+      database_data_list.push(data);
+    }
+
+    let id_mapper = IdMapper { id_map };
+    let string_id_map = id_mapper.get_id_map_as_strings();
+
+    let mut databases = vec![];
+    let mut database_view_ids = HashMap::new();
+    for data in database_data_list {
+      let old_rows = data.rows.iter().map(|row| row.id).collect::<Vec<_>>();
+      let data = self.remap_database_data(data, &string_id_map)?;
+      database_view_ids.insert(
+        data.database_id,
+        data.views.iter().map(|view| view.id).collect::<Vec<_>>(),
+      );
+
+      let mut encoded_database = self.create_encoded_database(data).await?;
+      for old_row_id in old_rows {
+        let old_document_id = Uuid::parse_str(&get_row_document_id(&old_row_id)?)?;
+        if let Some(encoded_collab) = self
+          .provider
+          .get_row_document_collab(&old_document_id)
+          .await?
+        {
+          let new_document_id = id_mapper
+            .get_new_id_from_uuid(&old_document_id)
+            .ok_or_else(|| CollabError::NoRequiredData("row document id".to_string()))?;
+          let encoded_collab = self.remap_document(
+            &old_document_id,
+            &new_document_id,
+            encoded_collab,
+            &string_id_map,
+          )?;
+          encoded_database
+            .encoded_row_document_collabs
+            .push(EncodedCollabInfo {
+              object_id: new_document_id,
+              collab_type: CollabType::Document,
+              encoded_collab,
+            });
+        }
+      }
+      databases.push(encoded_database);
+    }
+
+    let mut documents = vec![];
+    for old_view_id in document_views {
+      if let Some(encoded_collab) = self.provider.get_document_collab(&old_view_id).await? {
+        let new_view_id = hierarchy.id_map[&old_view_id];
+        let encoded_collab =
+          self.remap_document(&old_view_id, &new_view_id, encoded_collab, &string_id_map)?;
+        documents.push(EncodedCollabInfo {
+          object_id: new_view_id,
+          collab_type: CollabType::Document,
+          encoded_collab,
+        });
+      }
+    }
+
+    Ok(DuplicatedViews {
+      views: hierarchy.views,
+      id_mapper,
+      documents,
+      databases,
+      database_view_ids,
+    })
+  }
+
+  fn remap_database_data(
+    &self,
+    data: DatabaseData,
+    id_mapping: &HashMap<String, String>,
+  ) -> Result<DatabaseData, CollabError> {
+    // Remapping the json representation also rewrites the row ids stored in relation cells and
+    // the database id stored in relation type options.
+    let mut json_value = serde_json::to_value(&data)?;
+    JsonIdRemapper::new(id_mapping).remap_json_value(&mut json_value);
+    Ok(serde_json::from_value(json_value)?)
+  }
+
+  async fn create_encoded_database(
+    &self,
+    data: DatabaseData,
+  ) -> Result<EncodedDatabase, CollabError> {
+    let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new(self.client_id));
+    let context = DatabaseContext::new(collab_service.clone(), collab_service);
+    let params = DatabaseCollabRemapper::create_database_params_with_mapped_ids(data);
+    let database = Database::create_with_view(params, context).await?;
+    database.encode_database_collabs().await
+  }
+
+  fn remap_document(
+    &self,
+    old_document_id: &Uuid,
+    new_document_id: &Uuid,
+    encoded_collab: EncodedCollab,
+    id_mapping: &HashMap<String, String>,
+  ) -> Result<EncodedCollab, CollabError> {
+    let options =
+      CollabOptions::new(*old_document_id, self.client_id).with_data_source(encoded_collab.into());
+    let collab = Collab::new_with_options(CollabOrigin::Empty, options)
+      .map_err(|e| CollabError::Internal(anyhow::Error::new(e)))?;
+    let document = Document::open(collab)?;
+    let remapper = DocumentCollabRemapper::new(id_mapping.clone());
+    let document = remapper.remap_collab_doc(
+      &new_document_id.to_string(),
+      &self.client_id.to_string(),
+      document,
+    )?;
+    document.encode_collab()
+  }
+}
+
+/// The view that replaces an inline view shown in the folder. The inline view of the duplicated
+/// database is derived from the new database id, so the folder view is kept as a linked view.
+fn linked_inline_view(
+  database_id: DatabaseId,
+  inline_view_id: ViewId,
+  folder_view: &View,
+) -> DatabaseView {
+  let layout = match folder_view.layout {
+    ViewLayout::Board => DatabaseLayout::Board,
+    ViewLayout::Calendar => DatabaseLayout::Calendar,
+    ViewLayout::Chart => DatabaseLayout::Chart,
+    ViewLayout::List => DatabaseLayout::List,
+    ViewLayout::Gallery => DatabaseLayout::Gallery,
+    ViewLayout::Feed => DatabaseLayout::Feed,
+    ViewLayout::Timeline => DatabaseLayout::Timeline,
+    _ => DatabaseLayout::Grid,
+  };
+  DatabaseView::new(
+    database_id,
+    inline_view_id,
+    folder_view.name.clone(),
+    layout,
+  )
+}
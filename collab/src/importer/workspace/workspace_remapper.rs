@@ -5,7 +5,7 @@ use crate::entity::CollabType;
 use crate::folder::Folder;
 use anyhow::{Result, anyhow};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -98,6 +98,8 @@ impl WorkspaceRemapper {
 
   pub async fn build_database_collabs(&self) -> Result<Vec<Database>> {
     let mut databases = Vec::new();
+    // Linked views of the same database share the same database json.
+    let mut database_ids = HashSet::new();
 
     for (view_id, collab_metadata) in &self.relation_map.collab_objects {
       if collab_metadata.collab_type == CollabType::Database {
@@ -113,6 +115,11 @@ impl WorkspaceRemapper {
 
         let json_content = fs::read_to_string(&json_path)?;
         let database_json: serde_json::Value = serde_json::from_str(&json_content)?;
+        if let Some(database_id) = database_json.get("database_id").and_then(|v| v.as_str()) {
+          if !database_ids.insert(database_id.to_string()) {
+            continue;
+          }
+        }
 
         let remapper = DatabaseCollabRemapper::new(database_json, self.get_id_mapping_as_strings());
         let database = remapper.build_database().await?;
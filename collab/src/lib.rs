@@ -4,6 +4,7 @@ pub mod database;
 pub mod document;
 pub mod entity;
 pub mod error;
+pub mod exporter;
 pub mod folder;
 pub mod importer;
 pub mod lock;
@@ -0,0 +1,245 @@
+use collab::database::auto_number::{AutoNumberRange, AutoNumberReservations};
+use collab::database::database::gen_row_id;
+use collab::database::entity::FieldType;
+use collab::database::fields::auto_number_type_option::{AutoNumberCellData, AutoNumberTypeOption};
+use collab::database::fields::{Field, TypeOptionCellReader, TypeOptionCellWriter};
+use collab::database::rows::{Cells, CreateRowParams};
+use collab::entity::uuid_validation::RowId;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, TEST_VIEW_ID_V1};
+
+#[test]
+fn auto_number_reservations_test() {
+  let mut reservations = AutoNumberReservations::new();
+  assert_eq!(reservations.reserve(2, 1), 1);
+  assert_eq!(reservations.reserve(2, 1), 2);
+  assert_eq!(reservations.reserve(5, 1), 3);
+  assert_eq!(reservations.reserve(2, 1), 4);
+  assert_eq!(
+    reservations.ranges(2),
+    [AutoNumberRange::new(1, 3), AutoNumberRange::new(4, 5)]
+  );
+  assert_eq!(reservations.next_number(100), 100);
+
+  // Client 5 issued 3 to 6 while client 2 was issuing 4 to 5 offline
+  reservations.insert(5, vec![AutoNumberRange::new(3, 7)]);
+  assert_eq!(reservations.conflicts(2), Vec::<i64>::new());
+  assert_eq!(reservations.conflicts(5), vec![4]);
+  reservations.release(5, &[4]);
+  assert_eq!(
+    reservations.ranges(5),
+    [AutoNumberRange::new(3, 4), AutoNumberRange::new(5, 7)]
+  );
+  assert_eq!(reservations.conflicts(5), Vec::<i64>::new());
+  assert_eq!(reservations.reserve(5, 1), 7);
+}
+
+#[tokio::test]
+async fn assign_auto_number_on_create_row_test() {
+  let (mut database_test, _) = create_database_with_auto_number(vec![]).await;
+  let first = create_row(&mut database_test).await;
+  let second = create_row(&mut database_test).await;
+  let (_, third) = database_test
+    .create_row_in_view(
+      TEST_VIEW_ID_V1,
+      CreateRowParams::new(gen_row_id(), database_id(&database_test)),
+    )
+    .await
+    .unwrap();
+  assert_eq!(auto_number(&database_test, &first).await, Some(1));
+  assert_eq!(auto_number(&database_test, &second).await, Some(2));
+  assert_eq!(auto_number(&database_test, &third.id).await, Some(3));
+
+  let params = database_test.duplicate_row(&second).await.unwrap().unwrap();
+  assert!(!params.cells.contains_key("id"));
+  let (_, duplicated) = database_test
+    .create_row_in_view(TEST_VIEW_ID_V1, params)
+    .await
+    .unwrap();
+  assert_eq!(auto_number(&database_test, &duplicated.id).await, Some(4));
+
+  // A number given by the params is kept
+  let row_id = gen_row_id();
+  let params =
+    CreateRowParams::new(row_id, database_id(&database_test)).with_cells(Cells::from([(
+      "id".into(),
+      type_option().convert_json_to_cell(json!("TASK-042")),
+    )]));
+  database_test.create_row(params).await.unwrap();
+  assert_eq!(auto_number(&database_test, &row_id).await, Some(42));
+
+  let cell = database_test
+    .get_cell("id", &duplicated.id)
+    .await
+    .cell
+    .unwrap();
+  assert_eq!(type_option().json_cell(&cell), json!("TASK-004"));
+  assert_eq!(type_option().stringify_cell(&cell), "TASK-004");
+}
+
+#[tokio::test]
+async fn renumber_auto_number_field_test() {
+  let imported = vec![Some("TASK-007"), None, Some("12")];
+  let (mut database_test, row_ids) = create_database_with_auto_number(imported).await;
+  assert_eq!(auto_number(&database_test, &row_ids[0]).await, Some(7));
+  assert_eq!(auto_number(&database_test, &row_ids[1]).await, None);
+
+  let updated_row_ids = database_test
+    .renumber_auto_number_field("id")
+    .await
+    .unwrap();
+  assert_eq!(updated_row_ids, row_ids);
+  for (index, row_id) in row_ids.iter().enumerate() {
+    assert_eq!(
+      auto_number(&database_test, row_id).await,
+      Some(index as i64 + 1)
+    );
+  }
+
+  let row_id = create_row(&mut database_test).await;
+  assert_eq!(auto_number(&database_test, &row_id).await, Some(4));
+
+  // Renumbering again only writes the rows whose number changed
+  let updated_row_ids = database_test
+    .renumber_auto_number_field("id")
+    .await
+    .unwrap();
+  assert!(updated_row_ids.is_empty());
+  assert!(
+    database_test
+      .renumber_auto_number_field("name")
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn resolve_auto_number_conflicts_test() {
+  let (mut database_test, _) = create_database_with_auto_number(vec![]).await;
+  let client_id = database_test.collab.client_id();
+  let first = create_row(&mut database_test).await;
+  let second = create_row(&mut database_test).await;
+  assert_eq!(auto_number(&database_test, &second).await, Some(2));
+
+  // A client with a lower id issued 2 and 3 while offline
+  let other_client_id = client_id - 1;
+  {
+    let database = &mut database_test.database;
+    let mut txn = database.collab.transact_mut();
+    database.body.metas.set_auto_number_ranges(
+      &mut txn,
+      "id",
+      other_client_id,
+      &[AutoNumberRange::new(2, 4)],
+    );
+  }
+
+  // Creating rows doesn't resolve the conflicts, the new numbers follow the highest one
+  let report = database_test
+    .create_rows(
+      vec![
+        CreateRowParams::new(gen_row_id(), database_id(&database_test)),
+        CreateRowParams::new(gen_row_id(), database_id(&database_test)),
+      ],
+      10,
+    )
+    .await;
+  assert_eq!(
+    auto_number(&database_test, &report.row_ids[0]).await,
+    Some(4)
+  );
+  assert_eq!(
+    auto_number(&database_test, &report.row_ids[1]).await,
+    Some(5)
+  );
+  assert_eq!(auto_number(&database_test, &second).await, Some(2));
+
+  let updated_row_ids = database_test.resolve_auto_number_conflicts().await.unwrap();
+  assert_eq!(updated_row_ids, vec![second]);
+  let third = create_row(&mut database_test).await;
+  assert_eq!(auto_number(&database_test, &first).await, Some(1));
+  assert_eq!(auto_number(&database_test, &second).await, Some(6));
+  assert_eq!(auto_number(&database_test, &third).await, Some(7));
+
+  let reservations = {
+    let txn = database_test.collab.transact();
+    database_test
+      .body
+      .metas
+      .get_auto_number_reservations(&txn, "id")
+  };
+  assert_eq!(
+    reservations.ranges(client_id),
+    [AutoNumberRange::new(1, 2), AutoNumberRange::new(4, 8)]
+  );
+  assert!(reservations.conflicts(client_id).is_empty());
+  assert!(
+    database_test
+      .resolve_auto_number_conflicts()
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+fn type_option() -> AutoNumberTypeOption {
+  AutoNumberTypeOption::new("TASK-", 3)
+}
+
+fn database_id(database_test: &DatabaseTest) -> Uuid {
+  database_test.get_database_id().unwrap()
+}
+
+async fn create_row(database_test: &mut DatabaseTest) -> RowId {
+  let params = CreateRowParams::new(gen_row_id(), database_id(database_test));
+  database_test.create_row(params).await.unwrap().id
+}
+
+async fn auto_number(database_test: &DatabaseTest, row_id: &RowId) -> Option<i64> {
+  let row = database_test.get_row(row_id).await.unwrap();
+  row
+    .cells
+    .get("id")
+    .and_then(|cell| AutoNumberCellData::from(cell).number)
+}
+
+/// A database with a text field and an auto number field, and a row for each of the given
+/// auto number values.
+async fn create_database_with_auto_number(
+  numbers: Vec<Option<&str>>,
+) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let mut builder = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(Field::new(
+      "name".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    ))
+    .with_field(
+      Field::new(
+        "id".to_string(),
+        "ID".to_string(),
+        FieldType::AutoNumber.into(),
+        false,
+      )
+      .with_type_option_data(FieldType::AutoNumber, type_option().into()),
+    );
+  let mut row_ids = vec![];
+  for number in numbers {
+    let row_id = gen_row_id();
+    let mut cells = Cells::new();
+    if let Some(number) = number {
+      cells.insert(
+        "id".into(),
+        type_option().convert_json_to_cell(json!(number)),
+      );
+    }
+    builder = builder.with_row(CreateRowParams::new(row_id, database_id).with_cells(cells));
+    row_ids.push(row_id);
+  }
+  (builder.build().await, row_ids)
+}
@@ -0,0 +1,219 @@
+use collab::database::calculation::{Calculation, CalculationEvaluator, CalculationType};
+use collab::database::entity::FieldType;
+use collab::database::fields::date_type_option::DateCellData;
+use collab::database::filter::{FieldFilter, Filter, NumberFilter, NumberFilterCondition};
+use collab::database::rows::{Cell, Cells, Row, RowChange};
+use collab::entity::uuid_validation::RowId;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn calculate_view_footer_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  for (id, field_id, calculation_type) in [
+    ("c1", "amount", CalculationType::Sum),
+    ("c2", "name", CalculationType::CountEmpty),
+    ("c3", "due", CalculationType::DateRange),
+  ] {
+    insert_calculation(&mut database_test, id, field_id, calculation_type);
+  }
+
+  let evaluator = database_test
+    .get_calculation_evaluator_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(evaluator.result("amount").unwrap().value, "21");
+  assert_eq!(evaluator.result("name").unwrap().value, "1");
+  assert_eq!(evaluator.result("due").unwrap().value, "9");
+
+  // The computed values can be stored in the view
+  for calculation in evaluator.results() {
+    database_test.update_calculation(TEST_VIEW_ID_V1, calculation);
+  }
+  let calculation = database_test
+    .get_calculation::<Calculation>(TEST_VIEW_ID_V1, "amount")
+    .unwrap();
+  assert_eq!(calculation.calculation_type, CalculationType::Sum);
+  assert_eq!(calculation.value, "21");
+}
+
+#[tokio::test]
+async fn calculate_only_visible_rows_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  insert_calculation(&mut database_test, "c1", "amount", CalculationType::Count);
+  insert_calculation(
+    &mut database_test,
+    "c2",
+    "name",
+    CalculationType::UniqueCount,
+  );
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    Filter::new_data(
+      "f1".to_string(),
+      "amount".to_string(),
+      FieldType::Number,
+      FieldFilter::Number(NumberFilter {
+        condition: NumberFilterCondition::GreaterThanOrEqualTo,
+        content: "8".to_string(),
+      }),
+    ),
+  );
+
+  let evaluator = database_test
+    .get_calculation_evaluator_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(evaluator.result("amount").unwrap().value, "2");
+  // "apple" and the empty name of the last row, which isn't counted
+  assert_eq!(evaluator.result("name").unwrap().value, "1");
+}
+
+#[tokio::test]
+async fn recalculate_from_row_changes_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  for (id, field_id, calculation_type) in [
+    ("c1", "amount", CalculationType::Median),
+    ("c2", "name", CalculationType::PercentEmpty),
+  ] {
+    insert_calculation(&mut database_test, id, field_id, calculation_type);
+  }
+  let mut evaluator = database_test
+    .get_calculation_evaluator_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(evaluator.result("amount").unwrap().value, "8");
+  assert_eq!(evaluator.result("name").unwrap().value, "25%");
+
+  assert!(evaluator.apply_row_change(&update_cell_change(
+    row_ids[1],
+    "amount",
+    text_cell(FieldType::Number, "20"),
+  )));
+  assert_eq!(evaluator.result("amount").unwrap().value, "10");
+
+  assert!(evaluator.apply_row_change(&update_cell_change(
+    row_ids[3],
+    "name",
+    text_cell(FieldType::RichText, "date"),
+  )));
+  assert_eq!(evaluator.result("name").unwrap().value, "0%");
+
+  // Changes of rows the evaluator doesn't hold are ignored
+  assert!(!evaluator.apply_row_change(&update_cell_change(
+    Uuid::new_v4(),
+    "amount",
+    text_cell(FieldType::Number, "100"),
+  )));
+
+  evaluator.remove_row(&row_ids[0]);
+  assert_eq!(evaluator.result("amount").unwrap().value, "14");
+}
+
+#[test]
+fn recalculate_last_edited_time_from_row_test() {
+  let mut evaluator = CalculationEvaluator::new(
+    vec![Calculation::new(
+      "c1".to_string(),
+      "edited".to_string(),
+      CalculationType::DateRange,
+    )],
+    vec![
+      test_field("name", "Name", FieldType::RichText, true),
+      test_field("edited", "Edited", FieldType::LastEditedTime, false),
+    ],
+  );
+  let database_id = Uuid::new_v4();
+  let mut rows = [Uuid::new_v4(), Uuid::new_v4()].map(|row_id| {
+    let mut row = Row::empty(row_id, database_id);
+    row.modified_at = 1_700_000_000;
+    row
+  });
+  evaluator.add_rows(&rows);
+  assert_eq!(evaluator.result("edited").unwrap().value, "0");
+
+  // A cell change leaves the last edited time to the row's modified time
+  assert!(!evaluator.update_cell(&rows[0].id, "name", &text_cell(FieldType::RichText, "a")));
+  assert_eq!(evaluator.result("edited").unwrap().value, "0");
+
+  rows[0].modified_at += 3 * DAY;
+  assert!(evaluator.update_row(&rows[0]));
+  assert_eq!(evaluator.result("edited").unwrap().value, "3");
+  assert!(!evaluator.update_row(&rows[0]));
+}
+
+#[tokio::test]
+async fn skip_unsupported_calculations_test() {
+  let (database_test, _) = create_database_with_rows().await;
+  let evaluator = CalculationEvaluator::new(
+    vec![
+      Calculation::new("c1".to_string(), "name".to_string(), CalculationType::Sum),
+      Calculation::new(
+        "c2".to_string(),
+        "deleted".to_string(),
+        CalculationType::Count,
+      ),
+    ],
+    database_test.get_all_fields(),
+  );
+  assert!(evaluator.is_empty());
+}
+
+fn insert_calculation(
+  database_test: &mut DatabaseTest,
+  id: &str,
+  field_id: &str,
+  calculation_type: CalculationType,
+) {
+  database_test.update_calculation(
+    TEST_VIEW_ID_V1,
+    Calculation::new(id.to_string(), field_id.to_string(), calculation_type),
+  );
+}
+
+fn update_cell_change(row_id: RowId, field_id: &str, value: Cell) -> RowChange {
+  RowChange::DidUpdateCell {
+    row_id,
+    field_id: field_id.to_string(),
+    value,
+    is_local_change: true,
+    in_batch: false,
+  }
+}
+
+fn date_cell(timestamp: i64) -> Cell {
+  Cell::from(&DateCellData::from_timestamp(timestamp))
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  let start = 1_700_000_000;
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "apple")),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+      ("due".into(), date_cell(start)),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "banana")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      ("due".into(), date_cell(start + 9 * DAY + 60)),
+    ]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, "cherry"))]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "")),
+      ("amount".into(), text_cell(FieldType::Number, "8")),
+      ("due".into(), date_cell(start + 2 * DAY)),
+    ]),
+  ];
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("amount", "Amount", FieldType::Number, false),
+    test_field("due", "Due", FieldType::DateTime, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
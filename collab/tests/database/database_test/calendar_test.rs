@@ -0,0 +1,138 @@
+use collab::database::calendar::{
+  CalendarEvent, RecurrenceFrequency, RecurrenceRule, RecurrenceWeekday,
+};
+use collab::database::entity::FieldType;
+use collab::database::fields::Field;
+use collab::database::fields::date_type_option::{DateCellData, DateTypeOption};
+use collab::database::rows::{Cell, Cells, CreateRowParams};
+use collab::database::views::{CalendarLayoutSetting, DatabaseLayout};
+use collab::entity::uuid_validation::RowId;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, TEST_VIEW_ID_V1};
+
+// 2024-03-04, 2024-03-11 and 2024-03-18 00:00 UTC
+const MARCH_4: i64 = 1709510400;
+const MARCH_11: i64 = 1710115200;
+const MARCH_18: i64 = 1710720000;
+
+#[tokio::test]
+async fn expand_recurring_events_test() {
+  let (database_test, row_ids) = create_calendar_database().await;
+
+  let events = get_events(&database_test, MARCH_4, MARCH_11).await;
+  assert_eq!(
+    event_summary(&events),
+    vec![
+      // The single event and the first meeting at 9:00 in New York, 2024-03-05 14:00 UTC
+      (row_ids[0], 1709647200),
+      (row_ids[1], 1709647200),
+      // The monthly event at 5:00 in New York, after the switch to daylight saving time
+      (row_ids[2], 1710061200),
+    ]
+  );
+  assert!(!events[0].is_recurring);
+  assert!(events[1].is_recurring);
+  assert_eq!(events[2].end_timestamp, Some(1710068400));
+
+  // The meeting of Thursday 2024-03-07 was cancelled, and the next ones are at 9:00 EDT
+  let events = get_events(&database_test, MARCH_11, MARCH_18).await;
+  assert_eq!(
+    event_summary(&events),
+    vec![(row_ids[1], 1710248400), (row_ids[1], 1710421200)]
+  );
+}
+
+#[tokio::test]
+async fn range_event_overlapping_window_test() {
+  let (database_test, row_ids) = create_calendar_database().await;
+  // The monthly event runs from 9:00 to 11:00 UTC on 2024-03-10
+  let events = get_events(&database_test, 1710064800, MARCH_11).await;
+  assert_eq!(event_summary(&events), vec![(row_ids[2], 1710061200)]);
+
+  let events = get_events(&database_test, 1710068400, MARCH_11).await;
+  assert!(events.is_empty());
+}
+
+#[test]
+fn recurrence_round_trip_in_cell_test() {
+  let rule = RecurrenceRule::new(RecurrenceFrequency::Weekly)
+    .with_interval(2)
+    .with_weekdays(vec![RecurrenceWeekday::Friday])
+    .with_until(MARCH_18)
+    .with_exception(MARCH_11);
+  let cell = Cell::from(&DateCellData::from_timestamp(MARCH_4).with_recurrence(rule.clone()));
+  assert_eq!(DateCellData::from(&cell).recurrence, Some(rule));
+
+  // Writing a cell without recurrence clears it
+  let cell = Cell::from(&DateCellData::from_timestamp(MARCH_4));
+  assert_eq!(DateCellData::from(&cell).recurrence, None);
+}
+
+async fn get_events(database_test: &DatabaseTest, from: i64, to: i64) -> Vec<CalendarEvent> {
+  database_test
+    .get_calendar_events_for_view(TEST_VIEW_ID_V1, from, to, false)
+    .await
+    .unwrap()
+}
+
+fn event_summary(events: &[CalendarEvent]) -> Vec<(RowId, i64)> {
+  events
+    .iter()
+    .map(|event| (event.row_id, event.timestamp))
+    .collect()
+}
+
+async fn create_calendar_database() -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = (0..3).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+
+  // Every Tuesday and Thursday at 9:00 in New York from 2024-03-05, six times
+  let meeting = RecurrenceRule::new(RecurrenceFrequency::Weekly)
+    .with_weekdays(vec![
+      RecurrenceWeekday::Tuesday,
+      RecurrenceWeekday::Thursday,
+    ])
+    .with_count(6)
+    .with_exception(1709820000);
+  // Two hours every month from 2024-02-10 10:00 UTC
+  let mut monthly = DateCellData::from_timestamp_include_time(1707559200)
+    .with_recurrence(RecurrenceRule::new(RecurrenceFrequency::Monthly));
+  monthly.end_timestamp = Some(1707566400);
+  monthly.is_range = true;
+  let cells = vec![
+    DateCellData::from_timestamp_include_time(1709647200),
+    DateCellData::from_timestamp_include_time(1709647200).with_recurrence(meeting),
+    monthly,
+  ];
+
+  let mut type_option = DateTypeOption::default_utc();
+  type_option.timezone_id = "America/New_York".to_string();
+  let mut builder = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(Field::new(
+      "name".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    ))
+    .with_field(
+      Field::new(
+        "date".to_string(),
+        "Date".to_string(),
+        FieldType::DateTime.into(),
+        false,
+      )
+      .with_type_option_data(FieldType::DateTime, type_option.into()),
+    );
+  for (row_id, cell_data) in row_ids.iter().zip(cells) {
+    let cells = Cells::from([("date".into(), Cell::from(&cell_data))]);
+    builder = builder.with_row(CreateRowParams::new(*row_id, database_id).with_cells(cells));
+  }
+  let mut database_test = builder.build().await;
+  database_test.insert_layout_setting(
+    TEST_VIEW_ID_V1,
+    &DatabaseLayout::Calendar,
+    CalendarLayoutSetting::new("date".to_string()),
+  );
+  (database_test, row_ids)
+}
@@ -0,0 +1,239 @@
+use collab::database::chart::{ChartBucket, ChartData};
+use collab::database::entity::FieldType;
+use collab::database::fields::date_type_option::DateTypeOption;
+use collab::database::fields::select_type_option::{
+  SelectOption, SelectOptionColor, SelectOptionIds, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab::database::rows::{Cell, Cells};
+use collab::database::views::{
+  ChartAggregationType, ChartDateGranularity, ChartLayoutSetting, DatabaseLayout,
+};
+use collab::entity::uuid_validation::RowId;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn chart_sum_by_select_option_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let mut setting = chart_setting("status", ChartAggregationType::Sum, "amount");
+  insert_chart_setting(&mut database_test, setting.clone());
+
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![("todo", 3.0), ("doing", 35.0), ("done", 8.0)]
+  );
+  assert_eq!(chart.buckets[1].row_ids, vec![row_ids[0], row_ids[3]]);
+  assert_eq!(chart.buckets[1].color, Some(SelectOptionColor::Green));
+  assert_eq!(chart.buckets[0].label, "todo");
+
+  setting.show_empty_values = true;
+  insert_chart_setting(&mut database_test, setting);
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![
+      ("todo", 3.0),
+      ("doing", 35.0),
+      ("done", 8.0),
+      ("status", 4.0)
+    ]
+  );
+  let empty_bucket = chart.buckets.last().unwrap();
+  assert!(empty_bucket.is_empty_value);
+  assert_eq!(empty_bucket.label, "No Status");
+  assert_eq!(empty_bucket.row_ids, vec![row_ids[4]]);
+}
+
+#[tokio::test]
+async fn chart_by_date_granularity_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let mut setting = chart_setting("due", ChartAggregationType::Count, "");
+  insert_chart_setting(&mut database_test, setting.clone());
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![("2024/01", 1.0), ("2024/03", 2.0)]
+  );
+  assert_eq!(chart.buckets[1].label, "Mar 2024");
+
+  setting.aggregation_type = ChartAggregationType::Average;
+  setting.y_field_id = "amount".to_string();
+  setting.date_granularity = ChartDateGranularity::Week;
+  insert_chart_setting(&mut database_test, setting);
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![
+      ("2024/01/15", 3.0),
+      ("2024/03/04", 10.0),
+      ("2024/03/18", 8.0)
+    ]
+  );
+  assert_eq!(chart.buckets[0].label, "Week of Jan 15, 2024");
+}
+
+#[tokio::test]
+async fn chart_by_date_in_field_timezone_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let mut setting = chart_setting("due", ChartAggregationType::Count, "");
+  setting.date_granularity = ChartDateGranularity::Day;
+  insert_chart_setting(&mut database_test, setting);
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![
+      ("2024/01/20", 1.0),
+      ("2024/03/05", 1.0),
+      ("2024/03/20", 1.0)
+    ]
+  );
+
+  // The rows are due at 8:00 and 10:00 UTC, the day before in Honolulu for the first one
+  let mut type_option = DateTypeOption::default_utc();
+  type_option.timezone_id = "Pacific/Honolulu".to_string();
+  database_test.update_field("due", |update| {
+    update.set_type_option(FieldType::DateTime.into(), Some(type_option.into()));
+  });
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![
+      ("2024/01/19", 1.0),
+      ("2024/03/05", 1.0),
+      ("2024/03/20", 1.0)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn chart_by_number_range_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let mut setting = chart_setting("amount", ChartAggregationType::Max, "amount");
+  setting.number_range_size = 10.0;
+  insert_chart_setting(&mut database_test, setting.clone());
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![("0", 8.0), ("10", 10.0), ("20", 25.0)]
+  );
+  assert_eq!(chart.buckets[0].label, "0 - 10");
+
+  // The amounts span 22, so ranges of 5 are picked
+  setting.number_range_size = 0.0;
+  setting.aggregation_type = ChartAggregationType::Count;
+  insert_chart_setting(&mut database_test, setting);
+  let chart = get_chart_data(&database_test).await;
+  assert_eq!(
+    bucket_summary(&chart.buckets),
+    vec![("0", 2.0), ("5", 1.0), ("10", 1.0), ("25", 1.0)]
+  );
+}
+
+#[tokio::test]
+async fn chart_without_fields_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let no_chart = database_test
+    .get_chart_data_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert!(no_chart.is_none());
+
+  // Only a count can be computed without a Y field
+  insert_chart_setting(
+    &mut database_test,
+    chart_setting("status", ChartAggregationType::Sum, "missing"),
+  );
+  let no_chart = database_test
+    .get_chart_data_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert!(no_chart.is_none());
+}
+
+fn chart_setting(
+  x_field_id: &str,
+  aggregation_type: ChartAggregationType,
+  y_field_id: &str,
+) -> ChartLayoutSetting {
+  let mut setting = ChartLayoutSetting::new();
+  setting.x_field_id = x_field_id.to_string();
+  setting.aggregation_type = aggregation_type;
+  setting.y_field_id = y_field_id.to_string();
+  setting
+}
+
+fn insert_chart_setting(database_test: &mut DatabaseTest, setting: ChartLayoutSetting) {
+  database_test.insert_layout_setting(TEST_VIEW_ID_V1, &DatabaseLayout::Chart, setting);
+}
+
+async fn get_chart_data(database_test: &DatabaseTest) -> ChartData {
+  database_test
+    .get_chart_data_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap()
+    .unwrap()
+}
+
+fn bucket_summary(buckets: &[ChartBucket]) -> Vec<(&str, f64)> {
+  buckets
+    .iter()
+    .map(|bucket| (bucket.id.as_str(), bucket.value))
+    .collect()
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  // 2024-03-05, 2024-01-20 and 2024-03-20 UTC
+  let rows = vec![
+    Cells::from([
+      ("status".into(), status_cell("doing")),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+      ("due".into(), text_cell(FieldType::DateTime, "1709632800")),
+    ]),
+    Cells::from([
+      ("status".into(), status_cell("todo")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      ("due".into(), text_cell(FieldType::DateTime, "1705737600")),
+    ]),
+    Cells::from([
+      ("status".into(), status_cell("done")),
+      ("amount".into(), text_cell(FieldType::Number, "8")),
+      ("due".into(), text_cell(FieldType::DateTime, "1710928800")),
+    ]),
+    Cells::from([
+      ("status".into(), status_cell("doing")),
+      ("amount".into(), text_cell(FieldType::Number, "25")),
+    ]),
+    Cells::from([("amount".into(), text_cell(FieldType::Number, "4"))]),
+  ];
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("status", "Status", FieldType::SingleSelect, false).with_type_option_data(
+      FieldType::SingleSelect,
+      SingleSelectTypeOption(status_type_option()).into(),
+    ),
+    test_field("amount", "Amount", FieldType::Number, false),
+    test_field("due", "Due", FieldType::DateTime, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
+
+fn status_type_option() -> SelectTypeOption {
+  let mut type_option = SelectTypeOption::default();
+  for (option_id, color) in [
+    ("todo", SelectOptionColor::Blue),
+    ("doing", SelectOptionColor::Green),
+    ("done", SelectOptionColor::Purple),
+  ] {
+    let mut option = SelectOption::with_color(option_id, color);
+    option.id = option_id.to_string();
+    type_option.options.push(option);
+  }
+  type_option
+}
+
+fn status_cell(option_id: &str) -> Cell {
+  SelectOptionIds::from(vec![option_id.to_string()]).to_cell(FieldType::SingleSelect)
+}
@@ -0,0 +1,129 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::select_type_option::{
+  SelectOption, SelectOptionIds, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab::database::fields::{FieldSettingsBuilder, FieldVisibility};
+use collab::database::rows::{Cell, Cells};
+use collab::database::sort::{Sort, SortCondition};
+use collab::database::template::csv_export::CSVExportOptions;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn export_view_to_csv_test() {
+  let database_test = create_database_with_rows().await;
+  let csv = export(&database_test, &CSVExportOptions::new()).await;
+  assert_eq!(
+    csv.lines().collect::<Vec<_>>(),
+    vec![
+      "Name,Status,Amount",
+      "banana,todo,3",
+      "\"cherry, ripe\",done,10",
+      "Apple,,",
+    ]
+  );
+}
+
+#[tokio::test]
+async fn export_view_to_csv_with_options_test() {
+  let database_test = create_database_with_rows().await;
+  let options = CSVExportOptions::new()
+    .with_raw_values(true)
+    .with_delimiter(b';')
+    .with_bom(true)
+    .with_hidden_fields(true);
+  let csv = export(&database_test, &options).await;
+  let csv = csv.strip_prefix('\u{feff}').unwrap();
+  let lines = csv.lines().collect::<Vec<_>>();
+  assert_eq!(lines[0], "Name;Status;Amount;Note");
+  assert_eq!(lines[1], "banana;todo-id;3;secret");
+  assert_eq!(lines[2], "cherry, ripe;done-id;10;");
+}
+
+#[tokio::test]
+async fn export_view_to_csv_with_filters_and_sorts_test() {
+  let mut database_test = create_database_with_rows().await;
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s1".to_string(),
+      "amount".to_string(),
+      FieldType::Number,
+      SortCondition::Descending,
+    ),
+  );
+
+  // The rows are written one at a time, in the order of the sort
+  let options = CSVExportOptions::new()
+    .with_filters_and_sorts(true)
+    .with_chunk_size(1);
+  let csv = export(&database_test, &options).await;
+  assert_eq!(
+    csv.lines().skip(1).collect::<Vec<_>>(),
+    vec!["\"cherry, ripe\",done,10", "banana,todo,3", "Apple,,"]
+  );
+
+  // Without the option, the rows keep the order of the view
+  let csv = export(&database_test, &CSVExportOptions::new()).await;
+  assert!(csv.lines().nth(1).unwrap().starts_with("banana"));
+}
+
+async fn export(database_test: &DatabaseTest, options: &CSVExportOptions) -> String {
+  let bytes = database_test
+    .export_view_to_csv(TEST_VIEW_ID_V1, options, vec![], false)
+    .await
+    .unwrap();
+  String::from_utf8(bytes).unwrap()
+}
+
+fn status_cell(option_id: &str) -> Cell {
+  SelectOptionIds::from(vec![option_id.to_string()]).to_cell(FieldType::SingleSelect)
+}
+
+/// A database with a name, a status, an amount and a note field hidden in the view.
+async fn create_database_with_rows() -> DatabaseTest {
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "banana")),
+      ("status".into(), status_cell("todo-id")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      ("note".into(), text_cell(FieldType::RichText, "secret")),
+    ]),
+    Cells::from([
+      (
+        "name".into(),
+        text_cell(FieldType::RichText, "cherry, ripe"),
+      ),
+      ("status".into(), status_cell("done-id")),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+    ]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, "Apple"))]),
+  ];
+
+  let mut select_type_option = SelectTypeOption::default();
+  for name in ["todo", "done"] {
+    let mut option = SelectOption::new(name);
+    option.id = format!("{}-id", name);
+    select_type_option.options.push(option);
+  }
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("status", "Status", FieldType::SingleSelect, false).with_type_option_data(
+      FieldType::SingleSelect,
+      SingleSelectTypeOption(select_type_option).into(),
+    ),
+    test_field("amount", "Amount", FieldType::Number, false),
+    test_field("note", "Note", FieldType::RichText, false),
+  ];
+  let (mut database_test, _) = create_database_with_fields_and_rows(fields, rows).await;
+  database_test.update_field_settings(
+    TEST_VIEW_ID_V1,
+    Some(vec!["note".to_string()]),
+    FieldSettingsBuilder::new("note")
+      .visibility(FieldVisibility::AlwaysHidden)
+      .build(),
+  );
+  database_test
+}
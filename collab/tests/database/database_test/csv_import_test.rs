@@ -0,0 +1,260 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::select_type_option::{
+  SelectOption, SelectOptionIds, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab::database::rows::Cells;
+use collab::database::template::csv_import::{CSVImportError, CSVImportMode, CSVImportOptions};
+use collab::database::template::entity::CELL_DATA;
+use collab::entity::uuid_validation::RowId;
+use collab::util::AnyMapExt;
+
+use crate::database_test::helper::{
+  DatabaseTest, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+const CSV: &str = "SKU,Name,Status,Quantity,Extra
+A1,apple,todo,4,x
+B2,banana,todo,5,y
+D4,date,done,abc,z
+";
+
+#[tokio::test]
+async fn upsert_csv_import_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let options = upsert_options(CSVImportMode::Upsert);
+  let plan = database_test
+    .plan_csv_import(CSV.as_bytes(), &options, false)
+    .await
+    .unwrap();
+
+  // The plan is a dry run
+  let report = &plan.report;
+  assert_eq!(report.inserts, vec![4]);
+  assert_eq!(report.updates, vec![row_ids[0]]);
+  assert!(report.deletes.is_empty());
+  assert_eq!(
+    report.new_options,
+    vec![("status".to_string(), "done".to_string())]
+  );
+  assert_eq!(report.unmapped_columns, vec!["Extra".to_string()]);
+  assert_eq!(
+    report.errors,
+    vec![CSVImportError {
+      line: 4,
+      column: "Quantity".to_string(),
+      value: "abc".to_string(),
+    }]
+  );
+  assert_eq!(cell_data(&database_test, &row_ids[0], "amount").await, "3");
+  assert_eq!(database_test.get_all_row_orders().await.len(), 3);
+
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(report.inserted_row_ids.len(), 1);
+  assert_eq!(cell_data(&database_test, &row_ids[0], "amount").await, "4");
+  assert_eq!(
+    cell_data(&database_test, &row_ids[2], "name").await,
+    "cherry"
+  );
+
+  let inserted_row_id = report.inserted_row_ids[0];
+  assert_eq!(
+    cell_data(&database_test, &inserted_row_id, "sku").await,
+    "D4"
+  );
+  assert_eq!(
+    cell_data(&database_test, &inserted_row_id, "amount").await,
+    ""
+  );
+  let status = database_test
+    .get_field("status")
+    .unwrap()
+    .get_type_option::<SingleSelectTypeOption>(FieldType::SingleSelect.type_id())
+    .unwrap();
+  let done = status
+    .options
+    .iter()
+    .find(|option| option.name == "done")
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, &inserted_row_id, "status").await,
+    done.id
+  );
+
+  // Importing the same CSV again changes nothing
+  let plan = database_test
+    .plan_csv_import(CSV.as_bytes(), &options, false)
+    .await
+    .unwrap();
+  assert!(plan.report.inserts.is_empty());
+  assert!(plan.report.updates.is_empty());
+  assert!(plan.report.new_options.is_empty());
+}
+
+#[tokio::test]
+async fn sync_csv_import_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let csv = "SKU,Name\nB2,banana split\nB2,banana bread\n";
+  let plan = database_test
+    .plan_csv_import(csv.as_bytes(), &upsert_options(CSVImportMode::Sync), false)
+    .await
+    .unwrap();
+  assert_eq!(plan.report.updates, vec![row_ids[1]]);
+  assert_eq!(plan.report.deletes, vec![row_ids[0], row_ids[2]]);
+  assert_eq!(plan.report.duplicate_keys, vec![3]);
+
+  database_test.apply_csv_import(plan).await.unwrap();
+  let row_orders = database_test.get_all_row_orders().await;
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1]]
+  );
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1], "name").await,
+    "banana split"
+  );
+}
+
+#[tokio::test]
+async fn partially_applied_csv_import_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let csv = "SKU,Name\nB2,banana split\nD4,date\n";
+  let plan = database_test
+    .plan_csv_import(csv.as_bytes(), &upsert_options(CSVImportMode::Sync), false)
+    .await
+    .unwrap();
+  assert_eq!(plan.report.deletes, vec![row_ids[0], row_ids[2]]);
+
+  // The first row is gone before the plan is applied
+  database_test.remove_row(&row_ids[0]).await;
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(report.failures.len(), 1);
+  assert_eq!(report.failures[0].row_id, row_ids[0]);
+  assert_eq!(report.failures[0].line, None);
+
+  // The rest of the plan is applied
+  assert_eq!(report.inserted_row_ids.len(), 1);
+  let row_orders = database_test.get_all_row_orders().await;
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1], report.inserted_row_ids[0]]
+  );
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1], "name").await,
+    "banana split"
+  );
+}
+
+#[tokio::test]
+async fn append_only_csv_import_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+
+  // Updating the rows requires a key field
+  let options = CSVImportOptions::new().with_field_mapping("Quantity", "amount");
+  assert!(
+    database_test
+      .plan_csv_import(CSV.as_bytes(), &options, false)
+      .await
+      .is_err()
+  );
+
+  let options = options.with_mode(CSVImportMode::AppendOnly);
+  let plan = database_test
+    .plan_csv_import(CSV.as_bytes(), &options, false)
+    .await
+    .unwrap();
+  assert_eq!(plan.report.inserts, vec![2, 3, 4]);
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(report.inserted_row_ids.len(), 3);
+  assert_eq!(database_test.get_all_row_orders().await.len(), 6);
+}
+
+#[tokio::test]
+async fn single_select_value_with_comma_csv_import_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let csv = "SKU,Status\nA1,\"blocked, waiting\"\n";
+  let plan = database_test
+    .plan_csv_import(
+      csv.as_bytes(),
+      &upsert_options(CSVImportMode::Upsert),
+      false,
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    plan.report.new_options,
+    vec![("status".to_string(), "blocked, waiting".to_string())]
+  );
+  assert_eq!(plan.report.updates, vec![row_ids[0]]);
+
+  database_test.apply_csv_import(plan).await.unwrap();
+  let status = database_test
+    .get_field("status")
+    .unwrap()
+    .get_type_option::<SingleSelectTypeOption>(FieldType::SingleSelect.type_id())
+    .unwrap();
+  let blocked = status
+    .options
+    .iter()
+    .find(|option| option.name == "blocked, waiting")
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0], "status").await,
+    blocked.id
+  );
+}
+
+fn upsert_options(mode: CSVImportMode) -> CSVImportOptions {
+  CSVImportOptions::new()
+    .with_key_field("sku")
+    .with_field_mapping("Quantity", "amount")
+    .with_mode(mode)
+}
+
+async fn cell_data(database_test: &DatabaseTest, row_id: &RowId, field_id: &str) -> String {
+  database_test
+    .get_cell(field_id, row_id)
+    .await
+    .cell
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .unwrap_or_default()
+}
+
+/// A database with a name, a SKU, a status and an amount field, and the rows A1, B2 and C3.
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  let mut rows = vec![];
+  for (sku, name, status, amount) in [
+    ("A1", "apple", Some("todo"), "3"),
+    ("B2", "banana", Some("todo"), "5"),
+    ("C3", "cherry", None, ""),
+  ] {
+    let mut cells = Cells::from([
+      ("sku".into(), text_cell(FieldType::RichText, sku)),
+      ("name".into(), text_cell(FieldType::RichText, name)),
+    ]);
+    if let Some(status) = status {
+      cells.insert(
+        "status".into(),
+        SelectOptionIds::from(vec![status.to_string()]).to_cell(FieldType::SingleSelect),
+      );
+    }
+    if !amount.is_empty() {
+      cells.insert("amount".into(), text_cell(FieldType::Number, amount));
+    }
+    rows.push(cells);
+  }
+
+  let mut select_type_option = SelectTypeOption::default();
+  let mut option = SelectOption::new("todo");
+  option.id = "todo".to_string();
+  select_type_option.options.push(option);
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("sku", "SKU", FieldType::RichText, false),
+    test_field("status", "Status", FieldType::SingleSelect, false).with_type_option_data(
+      FieldType::SingleSelect,
+      SingleSelectTypeOption(select_type_option).into(),
+    ),
+    test_field("amount", "Amount", FieldType::Number, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::core::collab::{CollabOptions, default_client_id};
+use collab::core::origin::CollabOrigin;
+use collab::database::calculation::{Calculation, CalculationType};
+use collab::database::database::{Database, DatabaseBody};
+use collab::database::database_duplicate::DuplicateDatabaseParams;
+use collab::database::database_trait::{DatabaseCollabService, NoPersistenceDatabaseCollabService};
+use collab::database::entity::{CreateViewParams, EncodedDatabase, FieldType};
+use collab::database::fields::auto_number_type_option::AutoNumberCellData;
+use collab::database::fields::relation_type_option::RelationTypeOption;
+use collab::database::rows::{Cells, CreateRowParams, DatabaseRow, RowComment};
+use collab::database::template::relation_parse::RelationCellData;
+use collab::database::views::{DatabaseLayout, OrderObjectPosition, RowOrder};
+use collab::document::{Document, default_document_data};
+use collab::entity::uuid_validation::RowId;
+use collab::folder::ViewLayout;
+use collab::lock::RwLock;
+use collab::preclude::Collab;
+use dashmap::DashMap;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, DatabaseTestBuilder, TEST_VIEW_ID_V1, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn duplicate_database_test() {
+  let (mut database_test, row_ids) = create_database_for_duplicate().await;
+  let database_id = database_test.get_database_id().unwrap();
+  let grid_view_id = Uuid::parse_str(TEST_VIEW_ID_V1).unwrap();
+  let board_view_id = Uuid::new_v4();
+  database_test
+    .create_linked_view(CreateViewParams {
+      database_id,
+      view_id: board_view_id,
+      name: "board".to_string(),
+      layout: DatabaseLayout::Board,
+      ..Default::default()
+    })
+    .unwrap();
+  // Only the grid view shows the last row first
+  database_test.update_database_view(TEST_VIEW_ID_V1, |update| {
+    update.move_row_order(&row_ids[2].to_string(), &row_ids[0].to_string());
+  });
+  database_test
+    .update_relation_cell(&row_ids[1], "related", vec![row_ids[0]])
+    .await
+    .unwrap();
+  database_test.update_calculation(
+    TEST_VIEW_ID_V1,
+    Calculation::new("c1".to_string(), "name".to_string(), CalculationType::Count),
+  );
+  database_test
+    .get_database_row(&row_ids[0])
+    .await
+    .unwrap()
+    .write()
+    .await
+    .add_comment(RowComment::new("Looks good".to_string(), "1".to_string()));
+  let document_id = database_test.get_row_document_id(&row_ids[0]).unwrap();
+  let document = Document::create(
+    &document_id,
+    default_document_data(&document_id),
+    database_test.client_id,
+  )
+  .unwrap();
+  database_test
+    .collab_service
+    .persistence()
+    .unwrap()
+    .upsert_collab(
+      &Uuid::parse_str(&document_id).unwrap(),
+      document.encode_collab().unwrap(),
+    )
+    .unwrap();
+
+  // The second row has a document that isn't stored locally
+  database_test
+    .update_row_meta(&row_ids[1], |meta| {
+      meta.update_is_document_empty(false);
+    })
+    .await;
+
+  let parent_view_id = Uuid::new_v4();
+  let duplicated = database_test
+    .duplicate_database(DuplicateDatabaseParams::new(1, parent_view_id).with_comments(true))
+    .await
+    .unwrap();
+  let id_map = &duplicated.id_map;
+  assert_ne!(duplicated.database_id, database_id);
+  assert_eq!(id_map[&database_id], duplicated.database_id);
+
+  // The first view holds the other views in the folder
+  let views = &duplicated.views;
+  assert_eq!(views.view.id, id_map[&grid_view_id]);
+  assert_eq!(views.view.parent_view_id, Some(parent_view_id));
+  assert_eq!(views.view.layout, ViewLayout::Grid);
+  assert_eq!(views.children.len(), 1);
+  assert_eq!(views.children[0].view.id, id_map[&board_view_id]);
+  assert_eq!(views.children[0].view.parent_view_id, Some(views.view.id));
+  assert_eq!(views.children[0].view.layout, ViewLayout::Board);
+
+  let row_documents = &duplicated.encoded_database.encoded_row_document_collabs;
+  assert_eq!(row_documents.len(), 1);
+  assert_eq!(
+    row_documents[0].object_id,
+    id_map[&Uuid::parse_str(&document_id).unwrap()]
+  );
+  assert_eq!(duplicated.skipped_row_documents, vec![row_ids[1]]);
+  assert!(duplicated.skipped_comments.is_empty());
+
+  let duplicate = open_database(&duplicated.encoded_database);
+  let new_row_ids = row_ids
+    .iter()
+    .map(|row_id| id_map[row_id])
+    .collect::<Vec<_>>();
+  // Each view keeps its own row order
+  assert_eq!(
+    row_ids_of(duplicate.get_row_orders_for_view(&id_map[&grid_view_id].to_string())),
+    vec![new_row_ids[2], new_row_ids[0], new_row_ids[1]]
+  );
+  assert_eq!(
+    row_ids_of(duplicate.get_row_orders_for_view(&id_map[&board_view_id].to_string())),
+    new_row_ids
+  );
+
+  // The relation to the database itself links the duplicated rows
+  let field = duplicate.get_field("related").unwrap();
+  let type_option = RelationTypeOption::from(
+    field
+      .get_any_type_option(FieldType::Relation.type_id())
+      .unwrap(),
+  );
+  assert_eq!(type_option.database_id, duplicated.database_id.to_string());
+  let row = duplicate.get_row(&new_row_ids[1]).await.unwrap();
+  assert_eq!(
+    RelationCellData::from(row.cells.get("related").unwrap()).row_ids,
+    vec![new_row_ids[0]]
+  );
+
+  let calculations =
+    duplicate.get_all_calculations::<Calculation>(&id_map[&grid_view_id].to_string());
+  assert_eq!(calculations.len(), 1);
+  assert_eq!(calculations[0].field_id, "name");
+  let comments = duplicate
+    .get_database_row(&new_row_ids[0])
+    .await
+    .unwrap()
+    .read()
+    .await
+    .get_comments();
+  assert_eq!(comments.len(), 1);
+  assert_eq!(comments[0].content, "Looks good");
+}
+
+#[tokio::test]
+async fn duplicate_database_with_auto_numbers_test() {
+  let (mut database_test, _) = create_database_for_duplicate().await;
+  database_test.create_field(
+    None,
+    test_field("id", "ID", FieldType::AutoNumber, false),
+    &OrderObjectPosition::End,
+    HashMap::new(),
+  );
+  let database_id = database_test.get_database_id().unwrap();
+  for _ in 0..2 {
+    database_test
+      .create_row(CreateRowParams::new(Uuid::new_v4(), database_id))
+      .await
+      .unwrap();
+  }
+
+  let duplicated = database_test
+    .duplicate_database(DuplicateDatabaseParams::new(1, Uuid::new_v4()))
+    .await
+    .unwrap();
+  let mut duplicate = open_database(&duplicated.encoded_database);
+  // The new rows of the duplicate are numbered after the copied ones
+  let row_order = duplicate
+    .create_row(CreateRowParams::new(Uuid::new_v4(), duplicated.database_id))
+    .await
+    .unwrap();
+  let row = duplicate.get_row(&row_order.id).await.unwrap();
+  assert_eq!(
+    row
+      .cells
+      .get("id")
+      .and_then(|cell| AutoNumberCellData::from(cell).number),
+    Some(3)
+  );
+}
+
+fn row_ids_of(row_orders: Vec<RowOrder>) -> Vec<RowId> {
+  row_orders
+    .into_iter()
+    .map(|row_order| row_order.id)
+    .collect()
+}
+
+/// Open the database from its encoded collabs, the way a client receiving them would.
+fn open_database(encoded_database: &EncodedDatabase) -> Database {
+  let client_id = default_client_id();
+  let cache = Arc::new(DashMap::new());
+  for row in &encoded_database.encoded_row_collabs {
+    let options = CollabOptions::new(row.object_id, client_id)
+      .with_data_source(row.encoded_collab.clone().into());
+    let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+    let database_row = DatabaseRow::open(row.object_id, collab, None).unwrap();
+    cache.insert(row.object_id, Arc::new(RwLock::new(database_row)));
+  }
+  let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new_with_cache(
+    client_id, cache,
+  ));
+
+  let encoded_database_collab = &encoded_database.encoded_database_collab;
+  let options = CollabOptions::new(encoded_database_collab.object_id, client_id)
+    .with_data_source(encoded_database_collab.encoded_collab.clone().into());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  let body = DatabaseBody::from_collab(&collab, collab_service.clone(), None).unwrap();
+  Database {
+    collab,
+    body,
+    collab_service,
+  }
+}
+
+async fn create_database_for_duplicate() -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = (0..3).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  let rows = ["a", "b", "c"]
+    .map(|name| Cells::from([("name".into(), text_cell(FieldType::RichText, name))]));
+  let database_test = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(test_field("name", "Name", FieldType::RichText, true))
+    .with_field(
+      test_field("related", "Related", FieldType::Relation, false).with_type_option_data(
+        FieldType::Relation,
+        RelationTypeOption::new(&database_id.to_string()).into(),
+      ),
+    )
+    .with_rows(row_ids.iter().copied().zip(rows))
+    .build()
+    .await;
+  (database_test, row_ids)
+}
@@ -0,0 +1,309 @@
+use collab::database::conversion::ConversionFailure;
+use collab::database::database::gen_row_id;
+use collab::database::entity::FieldType;
+use collab::database::fields::date_type_option::DateCellData;
+use collab::database::fields::select_type_option::{SelectOptionIds, SingleSelectTypeOption};
+use collab::database::fields::text_type_option::RichTextTypeOption;
+use collab::database::fields::{Field, TypeOptionCellWriter, type_option_cell_reader};
+use collab::database::rows::{Cell, Cells, CreateRowParams};
+use collab::database::template::check_list_parse::ChecklistCellData;
+use collab::database::template::entity::CELL_DATA;
+use collab::database::template::number_parse::NumberCellData;
+use collab::entity::uuid_validation::RowId;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder};
+
+#[tokio::test]
+async fn convert_text_to_single_select_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  let conversion = database_test
+    .convert_field_type("status", FieldType::SingleSelect)
+    .await
+    .unwrap();
+  assert!(conversion.is_lossless());
+  assert_eq!(conversion.updated_row_ids, row_ids);
+
+  let field = database_test.get_field("status").unwrap();
+  assert_eq!(field.field_type, i64::from(FieldType::SingleSelect));
+  let type_option = field
+    .get_type_option::<SingleSelectTypeOption>(FieldType::SingleSelect.type_id())
+    .unwrap();
+  let names = type_option
+    .options
+    .iter()
+    .map(|option| option.name.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(names, vec!["Done", "Todo"]);
+
+  let first = option_ids(&database_test, "status", &row_ids[0]).await;
+  let third = option_ids(&database_test, "status", &row_ids[2]).await;
+  assert_eq!(first, vec![type_option.options[0].id.clone()]);
+  assert_eq!(first, third);
+  assert_eq!(
+    stringify(&database_test, "status", &row_ids[1]).await,
+    "Todo"
+  );
+
+  // Back to text, the cells read as the option names
+  let conversion = database_test
+    .convert_field_type("status", FieldType::RichText)
+    .await
+    .unwrap();
+  assert!(conversion.is_lossless());
+  assert_eq!(
+    stringify(&database_test, "status", &row_ids[0]).await,
+    "Done"
+  );
+  assert_eq!(
+    stringify(&database_test, "status", &row_ids[1]).await,
+    "Todo"
+  );
+}
+
+#[tokio::test]
+async fn convert_number_to_text_and_back_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  database_test
+    .convert_field_type("amount", FieldType::RichText)
+    .await
+    .unwrap();
+  assert_eq!(
+    stringify(&database_test, "amount", &row_ids[0]).await,
+    "12.5"
+  );
+
+  let conversion = database_test
+    .convert_field_type("amount", FieldType::Number)
+    .await
+    .unwrap();
+  assert!(conversion.is_lossless());
+  let cell = get_cell(&database_test, "amount", &row_ids[0])
+    .await
+    .unwrap();
+  assert_eq!(NumberCellData::from(&cell).0, "12.5");
+  assert!(
+    get_cell(&database_test, "amount", &row_ids[1])
+      .await
+      .is_none()
+  );
+}
+
+#[tokio::test]
+async fn convert_text_to_date_reports_failures_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  let conversion = database_test
+    .convert_field_type("due", FieldType::DateTime)
+    .await
+    .unwrap();
+  assert_eq!(
+    conversion.failures,
+    vec![ConversionFailure {
+      row_id: row_ids[1],
+      value: "not a date".to_string(),
+    }]
+  );
+
+  let cell = get_cell(&database_test, "due", &row_ids[0]).await.unwrap();
+  assert_eq!(DateCellData::from(&cell).timestamp, Some(1704412800));
+  assert!(get_cell(&database_test, "due", &row_ids[1]).await.is_none());
+}
+
+#[tokio::test]
+async fn convert_formatted_text_to_number_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  for (row_id, text) in row_ids.iter().zip(["$1,234.50", "Room 101", "1.234,50"]) {
+    database_test
+      .update_row(*row_id, |row_update| {
+        row_update.update_cells(|cells_update| {
+          cells_update.insert("due", RichTextTypeOption.convert_json_to_cell(json!(text)));
+        });
+      })
+      .await;
+  }
+
+  let conversion = database_test
+    .convert_field_type("due", FieldType::Number)
+    .await
+    .unwrap();
+  assert_eq!(
+    conversion.failures,
+    vec![
+      ConversionFailure {
+        row_id: row_ids[1],
+        value: "Room 101".to_string(),
+      },
+      ConversionFailure {
+        row_id: row_ids[2],
+        value: "1.234,50".to_string(),
+      },
+    ]
+  );
+  let cell = get_cell(&database_test, "due", &row_ids[0]).await.unwrap();
+  assert_eq!(NumberCellData::from(&cell).0, "1234.50");
+}
+
+#[tokio::test]
+async fn convert_checklist_to_text_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  database_test
+    .convert_field_type("tasks", FieldType::RichText)
+    .await
+    .unwrap();
+  assert_eq!(
+    stringify(&database_test, "tasks", &row_ids[0]).await,
+    "Draft,Review"
+  );
+
+  database_test
+    .convert_field_type("tasks", FieldType::Checklist)
+    .await
+    .unwrap();
+  let cell = get_cell(&database_test, "tasks", &row_ids[0])
+    .await
+    .unwrap();
+  let names = ChecklistCellData::from(&cell)
+    .options
+    .into_iter()
+    .map(|option| option.name)
+    .collect::<Vec<_>>();
+  assert_eq!(names, vec!["Draft", "Review"]);
+}
+
+#[tokio::test]
+async fn undo_field_conversion_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  let conversion = database_test
+    .convert_field_type("due", FieldType::DateTime)
+    .await
+    .unwrap();
+  let restored_row_ids = database_test.undo_field_conversion(conversion).await;
+  assert_eq!(restored_row_ids, vec![row_ids[0], row_ids[1]]);
+
+  let field = database_test.get_field("due").unwrap();
+  assert_eq!(field.field_type, i64::from(FieldType::RichText));
+  assert!(
+    field
+      .get_any_type_option(FieldType::DateTime.type_id())
+      .is_none()
+  );
+  assert_eq!(
+    stringify(&database_test, "due", &row_ids[0]).await,
+    "2024-01-05"
+  );
+  assert_eq!(
+    stringify(&database_test, "due", &row_ids[1]).await,
+    "not a date"
+  );
+  assert!(get_cell(&database_test, "due", &row_ids[2]).await.is_none());
+}
+
+#[tokio::test]
+async fn undo_field_conversion_keeps_edited_cells_test() {
+  let (mut database_test, row_ids) = create_database().await;
+  let conversion = database_test
+    .convert_field_type("due", FieldType::DateTime)
+    .await
+    .unwrap();
+  // The conversion can be kept outside of the database to undo it later
+  let conversion = serde_json::to_string(&conversion).unwrap();
+
+  // 2024-02-01 00:00 UTC
+  database_test
+    .update_row(row_ids[0], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert("due", Cell::from(&DateCellData::from_timestamp(1706745600)));
+      });
+    })
+    .await;
+  let restored_row_ids = database_test
+    .undo_field_conversion(serde_json::from_str(&conversion).unwrap())
+    .await;
+  assert_eq!(restored_row_ids, vec![row_ids[1]]);
+  assert_eq!(
+    stringify(&database_test, "due", &row_ids[1]).await,
+    "not a date"
+  );
+  // The edited cell isn't overwritten with the value it had before the conversion
+  let cell = get_cell(&database_test, "due", &row_ids[0]).await.unwrap();
+  assert_eq!(DateCellData::from(&cell).timestamp, Some(1706745600));
+}
+
+async fn get_cell(database_test: &DatabaseTest, field_id: &str, row_id: &RowId) -> Option<Cell> {
+  database_test
+    .get_cell(field_id, row_id)
+    .await
+    .cell
+    .filter(|cell| cell.contains_key(CELL_DATA))
+}
+
+async fn stringify(database_test: &DatabaseTest, field_id: &str, row_id: &RowId) -> String {
+  let field = database_test.get_field(field_id).unwrap();
+  let field_type = FieldType::from(field.field_type);
+  let reader = type_option_cell_reader(
+    field
+      .get_any_type_option(field_type.type_id())
+      .unwrap_or_default(),
+    &field_type,
+  );
+  get_cell(database_test, field_id, row_id)
+    .await
+    .map(|cell| reader.stringify_cell(&cell))
+    .unwrap_or_default()
+}
+
+async fn option_ids(database_test: &DatabaseTest, field_id: &str, row_id: &RowId) -> Vec<String> {
+  let cell = get_cell(database_test, field_id, row_id).await.unwrap();
+  SelectOptionIds::from(&cell).into_inner()
+}
+
+/// A database with text fields, a number field and a checklist field, and three rows:
+///
+/// | status | amount | due        | tasks         |
+/// |--------|--------|------------|---------------|
+/// | Done   | 12.5   | 2024-01-05 | Draft, Review |
+/// | Todo   |        | not a date |               |
+/// | Done   |        |            |               |
+async fn create_database() -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let mut builder = DatabaseTestBuilder::new(1, &database_id.to_string());
+  for (field_id, field_type) in [
+    ("status", FieldType::RichText),
+    ("amount", FieldType::Number),
+    ("due", FieldType::RichText),
+    ("tasks", FieldType::Checklist),
+  ] {
+    builder = builder.with_field(Field::new(
+      field_id.to_string(),
+      field_id.to_string(),
+      field_type.into(),
+      field_id == "status",
+    ));
+  }
+
+  let text = |value: &str| RichTextTypeOption.convert_json_to_cell(json!(value));
+  let rows = vec![
+    Cells::from([
+      ("status".into(), text("Done")),
+      ("amount".into(), NumberCellData("12.5".to_string()).into()),
+      ("due".into(), text("2024-01-05")),
+      (
+        "tasks".into(),
+        ChecklistCellData::from((vec!["Draft".to_string(), "Review".to_string()], vec![])).into(),
+      ),
+    ]),
+    Cells::from([
+      ("status".into(), text("Todo")),
+      ("due".into(), text("not a date")),
+    ]),
+    Cells::from([("status".into(), text("Done"))]),
+  ];
+  let mut row_ids = vec![];
+  for cells in rows {
+    let row_id = gen_row_id();
+    builder = builder.with_row(CreateRowParams::new(row_id, database_id).with_cells(cells));
+    row_ids.push(row_id);
+  }
+  (builder.build().await, row_ids)
+}
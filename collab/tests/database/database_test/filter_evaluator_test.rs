@@ -0,0 +1,201 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::select_type_option::SelectOptionIds;
+use collab::database::filter::{
+  CheckboxFilter, CheckboxFilterCondition, FieldFilter, Filter, FilterInner, NumberFilter,
+  NumberFilterCondition, SelectOptionFilter, SelectOptionFilterCondition, TextFilter,
+  TextFilterCondition,
+};
+use collab::database::rows::Cells;
+use collab::database::views::{OrderObjectPosition, RowOrder};
+use collab::entity::uuid_validation::RowId;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn filter_rows_without_filters_test() {
+  let (database_test, row_ids) = create_database_with_rows().await;
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    row_ids
+  );
+}
+
+#[tokio::test]
+async fn filter_rows_with_text_filter_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    text_filter("filter_1", TextFilterCondition::Contains, "AN"),
+  );
+
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1]]
+  );
+}
+
+#[tokio::test]
+async fn filter_rows_with_nested_filters_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  // (amount > 5 OR status is s2) AND name doesn't contain "apple"
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    Filter::new_or(
+      "filter_1".to_string(),
+      vec![
+        Filter::new_data(
+          "filter_2".to_string(),
+          "amount".to_string(),
+          FieldType::Number,
+          FieldFilter::Number(NumberFilter {
+            condition: NumberFilterCondition::GreaterThan,
+            content: "5".to_string(),
+          }),
+        ),
+        Filter::new_data(
+          "filter_3".to_string(),
+          "status".to_string(),
+          FieldType::SingleSelect,
+          FieldFilter::SelectOption(SelectOptionFilter {
+            condition: SelectOptionFilterCondition::Is,
+            option_ids: vec!["s2".to_string()],
+          }),
+        ),
+      ],
+    ),
+  );
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    text_filter("filter_4", TextFilterCondition::DoesNotContain, "apple"),
+  );
+
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1]]
+  );
+
+  // The typed filters survive a round trip through the view
+  let filters = database_test.get_all_filters::<Filter>(TEST_VIEW_ID_V1);
+  assert_eq!(filters.len(), 2);
+  let or_filter = filters
+    .iter()
+    .find(|filter| filter.id == "filter_1")
+    .unwrap();
+  match &or_filter.inner {
+    FilterInner::Or { children } => assert_eq!(children.len(), 2),
+    _ => panic!("expected an OR filter"),
+  }
+}
+
+#[tokio::test]
+async fn filter_keeps_rows_that_cannot_be_loaded_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let missing_row_id = Uuid::new_v4();
+  database_test.update_database_view(TEST_VIEW_ID_V1, |update| {
+    update.insert_row_order(RowOrder::new(missing_row_id, 60), &OrderObjectPosition::End);
+  });
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    text_filter("filter_1", TextFilterCondition::Contains, "AN"),
+  );
+
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1], missing_row_id]
+  );
+}
+
+#[tokio::test]
+async fn filter_ignores_deleted_field_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    Filter::new_data(
+      "filter_1".to_string(),
+      "done".to_string(),
+      FieldType::Checkbox,
+      FieldFilter::Checkbox(CheckboxFilter {
+        condition: CheckboxFilterCondition::IsChecked,
+      }),
+    ),
+  );
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[0], row_ids[2]]
+  );
+
+  database_test.delete_field("done");
+  let row_orders = database_test
+    .get_filtered_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(row_orders.len(), 3);
+}
+
+fn text_filter(id: &str, condition: TextFilterCondition, content: &str) -> Filter {
+  Filter::new_data(
+    id.to_string(),
+    "name".to_string(),
+    FieldType::RichText,
+    FieldFilter::Text(TextFilter {
+      condition,
+      content: content.to_string(),
+    }),
+  )
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "Apple")),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+      (
+        "status".into(),
+        SelectOptionIds::from(vec!["s1".to_string()]).to_cell(FieldType::SingleSelect),
+      ),
+      ("done".into(), text_cell(FieldType::Checkbox, "Yes")),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "banana")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      (
+        "status".into(),
+        SelectOptionIds::from(vec!["s2".to_string()]).to_cell(FieldType::SingleSelect),
+      ),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "Cherry")),
+      ("done".into(), text_cell(FieldType::Checkbox, "Yes")),
+    ]),
+  ];
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("amount", "Amount", FieldType::Number, false),
+    test_field("status", "Status", FieldType::SingleSelect, false),
+    test_field("done", "Done", FieldType::Checkbox, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
@@ -0,0 +1,315 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::Field;
+use collab::database::fields::formula_type_option::{FormulaResultType, FormulaTypeOption};
+use collab::database::fields::relation_type_option::RelationTypeOption;
+use collab::database::fields::{TypeOptionCellReader, TypeOptionCellWriter, TypeOptionData};
+use collab::database::rows::{Cell, Cells};
+use collab::database::template::entity::CELL_DATA;
+use collab::database::template::relation_parse::RelationCellData;
+use collab::entity::uuid_validation::RowId;
+use collab::util::AnyMapExt;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, test_field, text_cell};
+
+#[tokio::test]
+async fn check_formula_test() {
+  let (database_test, _) = create_database_with_rows(vec![]).await;
+  assert_eq!(
+    database_test.check_formula("{Price} * 2").unwrap(),
+    FormulaResultType::Number
+  );
+  assert_eq!(
+    database_test
+      .check_formula(r#"concat({name}, " ", {due})"#)
+      .unwrap(),
+    FormulaResultType::Text
+  );
+  assert_eq!(
+    database_test
+      .check_formula(r#"IF({done} and not false, {due}, date_add({due}, 2, "weeks"))"#)
+      .unwrap(),
+    FormulaResultType::Date
+  );
+  assert_eq!(
+    database_test.check_formula("{Price} >= 2.5").unwrap(),
+    FormulaResultType::Checkbox
+  );
+
+  for expression in [
+    "{Unknown} + 1",
+    r#"if({done}, 1, "x")"#,
+    "round(1.234, 2",
+    "{related} + 1",
+    r#"sum({Price}, "Price")"#,
+    r#"date_add({due}, 1, "fortnight")"#,
+    "unknown(1)",
+    "{name} * 2",
+  ] {
+    assert!(
+      database_test.check_formula(expression).is_err(),
+      "{}",
+      expression
+    );
+  }
+}
+
+#[tokio::test]
+async fn refresh_formula_cells_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![
+    formula_field("total", "{Price} * {qty}", FormulaResultType::Number),
+    formula_field(
+      "label",
+      r#"upper({name}) + ": " + to_text({total})"#,
+      FormulaResultType::Text,
+    ),
+    formula_field(
+      "due_next",
+      r#"date_add({due}, 1, "month")"#,
+      FormulaResultType::Date,
+    ),
+    formula_field(
+      "days",
+      r#"date_between({due_next}, {due}, "days")"#,
+      FormulaResultType::Number,
+    ),
+    formula_field(
+      "due_text",
+      r#"format_date({due}, "%Y/%m/%d")"#,
+      FormulaResultType::Text,
+    ),
+    formula_field(
+      "expensive",
+      "{total} > 15 or {done}",
+      FormulaResultType::Checkbox,
+    ),
+  ])
+  .await;
+  let mut updated = database_test
+    .refresh_formula_cells(None, false)
+    .await
+    .unwrap();
+  updated.sort();
+  let mut expected = row_ids.clone();
+  expected.sort();
+  assert_eq!(updated, expected);
+
+  assert_eq!(cell_data(&database_test, "total", &row_ids[0]).await, "20");
+  assert_eq!(cell_data(&database_test, "total", &row_ids[1]).await, "10");
+  // The quantity of c is empty
+  assert_eq!(cell_data(&database_test, "total", &row_ids[2]).await, "");
+  assert_eq!(
+    cell_data(&database_test, "label", &row_ids[0]).await,
+    "A: 20"
+  );
+  assert_eq!(cell_data(&database_test, "label", &row_ids[2]).await, "C: ");
+  // 2024-01-31 plus one month is 2024-02-29
+  assert_eq!(
+    cell_data(&database_test, "due_next", &row_ids[0]).await,
+    "1709164800"
+  );
+  assert_eq!(cell_data(&database_test, "days", &row_ids[0]).await, "29");
+  assert_eq!(
+    cell_data(&database_test, "due_text", &row_ids[0]).await,
+    "2024/01/31"
+  );
+  assert_eq!(
+    cell_data(&database_test, "expensive", &row_ids[0]).await,
+    "true"
+  );
+  assert_eq!(
+    cell_data(&database_test, "expensive", &row_ids[1]).await,
+    "false"
+  );
+  assert_eq!(
+    cell_data(&database_test, "expensive", &row_ids[2]).await,
+    "true"
+  );
+
+  // Nothing changed, so nothing is written
+  let updated = database_test
+    .refresh_formula_cells(None, false)
+    .await
+    .unwrap();
+  assert!(updated.is_empty());
+
+  // Only the given rows are refreshed
+  database_test
+    .update_row(row_ids[1], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("qty", text_cell(FieldType::Number, "8"));
+      });
+    })
+    .await;
+  let updated = database_test
+    .refresh_formula_cells(Some(vec![row_ids[1]]), false)
+    .await
+    .unwrap();
+  assert_eq!(updated, vec![row_ids[1]]);
+  assert_eq!(cell_data(&database_test, "total", &row_ids[1]).await, "20");
+  assert_eq!(
+    cell_data(&database_test, "label", &row_ids[1]).await,
+    "B: 20"
+  );
+}
+
+#[tokio::test]
+async fn formula_relation_aggregates_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![
+    formula_field("count", "count({related})", FormulaResultType::Number),
+    formula_field(
+      "sum",
+      r#"sum({related}, "Price")"#,
+      FormulaResultType::Number,
+    ),
+    formula_field(
+      "max",
+      r#"max({related}, "price") + min(1, 2)"#,
+      FormulaResultType::Number,
+    ),
+    formula_field(
+      "average",
+      r#"average({related}, "Price")"#,
+      FormulaResultType::Number,
+    ),
+  ])
+  .await;
+  database_test
+    .refresh_formula_cells(None, false)
+    .await
+    .unwrap();
+
+  assert_eq!(cell_data(&database_test, "count", &row_ids[0]).await, "2");
+  assert_eq!(cell_data(&database_test, "sum", &row_ids[0]).await, "3.5");
+  assert_eq!(cell_data(&database_test, "max", &row_ids[0]).await, "3.5");
+  assert_eq!(
+    cell_data(&database_test, "average", &row_ids[0]).await,
+    "1.75"
+  );
+  assert_eq!(cell_data(&database_test, "count", &row_ids[1]).await, "0");
+  assert_eq!(cell_data(&database_test, "sum", &row_ids[1]).await, "0");
+  // No related rows, so no average
+  assert_eq!(cell_data(&database_test, "average", &row_ids[1]).await, "");
+}
+
+#[tokio::test]
+async fn formula_cycle_and_type_errors_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![
+    formula_field("loop1", "{loop2} + 1", FormulaResultType::Number),
+    formula_field("loop2", "{loop1} * 2", FormulaResultType::Number),
+    formula_field("after_loop", "{loop2} - 1", FormulaResultType::Number),
+    formula_field("self", "{self} + 1", FormulaResultType::Number),
+    formula_field("mismatch", "{Price} + 1", FormulaResultType::Text),
+    formula_field("valid", "{Price} + 1", FormulaResultType::Number),
+  ])
+  .await;
+  let evaluator = database_test.get_formula_evaluator();
+  for field_id in ["loop1", "loop2", "after_loop", "self", "mismatch"] {
+    assert!(evaluator.error(field_id).is_some(), "{}", field_id);
+  }
+  assert!(evaluator.error("valid").is_none());
+  assert_eq!(
+    evaluator.dependent_fields(&["price".to_string()]),
+    vec!["valid".to_string()]
+  );
+
+  database_test
+    .refresh_formula_cells(None, false)
+    .await
+    .unwrap();
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert!(!row.cells.contains_key("loop1"));
+  assert!(!row.cells.contains_key("mismatch"));
+  assert_eq!(cell_data(&database_test, "valid", &row_ids[0]).await, "11");
+}
+
+#[test]
+fn formula_type_option_reader_test() {
+  let type_option = FormulaTypeOption::new("{Price} * 2", FormulaResultType::Number);
+  let cell = type_option.convert_json_to_cell(serde_json::json!("12.5"));
+  assert_eq!(type_option.numeric_cell(&cell), Some(12.5));
+  assert_eq!(type_option.stringify_cell(&cell), "12.5");
+  assert_eq!(type_option.json_cell(&cell), serde_json::json!(12.5));
+
+  let type_option = FormulaTypeOption::new("{done}", FormulaResultType::Checkbox);
+  let cell = type_option.convert_json_to_cell(serde_json::json!("true"));
+  assert_eq!(type_option.numeric_cell(&cell), Some(1.0));
+  assert_eq!(type_option.json_cell(&cell), serde_json::json!(true));
+
+  let type_option = FormulaTypeOption::new("{name}", FormulaResultType::Text);
+  let cell = type_option.convert_json_to_cell(serde_json::Value::Null);
+  assert_eq!(type_option.numeric_cell(&cell), None);
+  assert_eq!(type_option.stringify_cell(&cell), "");
+
+  let data: TypeOptionData =
+    FormulaTypeOption::new("{Price} * 2", FormulaResultType::Number).into();
+  assert_eq!(
+    FormulaTypeOption::from(data),
+    FormulaTypeOption::new("{Price} * 2", FormulaResultType::Number)
+  );
+}
+
+async fn cell_data(database_test: &DatabaseTest, field_id: &str, row_id: &RowId) -> String {
+  let row = database_test.get_row(row_id).await.unwrap();
+  row
+    .cells
+    .get(field_id)
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .unwrap_or_default()
+}
+
+fn formula_field(field_id: &str, expression: &str, result_type: FormulaResultType) -> Field {
+  test_field(field_id, field_id, FieldType::Formula, false).with_type_option_data(
+    FieldType::Formula,
+    FormulaTypeOption::new(expression, result_type).into(),
+  )
+}
+
+/// Rows a, b and c of a database related to itself. Row a relates to b and c.
+async fn create_database_with_rows(fields: Vec<Field>) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+  // 2024-01-31 00:00 UTC
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "a")),
+      ("price".into(), text_cell(FieldType::Number, "10")),
+      ("qty".into(), text_cell(FieldType::Number, "2")),
+      ("due".into(), text_cell(FieldType::DateTime, "1706659200")),
+      (
+        "related".into(),
+        Cell::from(RelationCellData {
+          row_ids: vec![row_ids[1], row_ids[2]],
+        }),
+      ),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "b")),
+      ("price".into(), text_cell(FieldType::Number, "2.5")),
+      ("qty".into(), text_cell(FieldType::Number, "4")),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "c")),
+      ("price".into(), text_cell(FieldType::Number, "1")),
+      ("done".into(), text_cell(FieldType::Checkbox, "true")),
+    ]),
+  ];
+
+  let database_test = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_fields([
+      test_field("name", "Name", FieldType::RichText, true),
+      test_field("price", "Price", FieldType::Number, false),
+      test_field("qty", "Quantity", FieldType::Number, false),
+      test_field("due", "Due", FieldType::DateTime, false),
+      test_field("done", "Done", FieldType::Checkbox, false),
+      test_field("related", "Related", FieldType::Relation, false).with_type_option_data(
+        FieldType::Relation,
+        RelationTypeOption::new(&database_id.to_string()).into(),
+      ),
+    ])
+    .with_fields(fields)
+    .with_rows(row_ids.iter().copied().zip(rows))
+    .build()
+    .await;
+  (database_test, row_ids)
+}
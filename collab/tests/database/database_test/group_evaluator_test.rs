@@ -0,0 +1,249 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::select_type_option::{
+  SelectOption, SelectOptionIds, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab::database::group::{DateGroupCondition, GroupContent, GroupData};
+use collab::database::rows::{Cell, Cells};
+use collab::database::views::{Group, GroupSetting};
+use collab::entity::uuid_validation::RowId;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn group_rows_by_select_option_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_group_setting(TEST_VIEW_ID_V1, status_group_setting(vec![]));
+
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    group_summary(&groups),
+    vec![
+      ("status", vec![row_ids[3]]),
+      ("todo", vec![row_ids[1]]),
+      ("doing", vec![row_ids[0]]),
+      ("done", vec![row_ids[2]]),
+    ]
+  );
+  assert!(groups[0].is_default);
+  assert_eq!(groups[0].name, "No Status");
+  assert_eq!(groups[1].name, "todo");
+}
+
+#[tokio::test]
+async fn group_order_and_visibility_follow_setting_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let mut hidden_group = Group::new("doing".to_string());
+  hidden_group.visible = false;
+  database_test.insert_group_setting(
+    TEST_VIEW_ID_V1,
+    status_group_setting(vec![Group::new("done".to_string()), hidden_group]),
+  );
+
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    groups
+      .iter()
+      .map(|group| (group.id.as_str(), group.visible))
+      .collect::<Vec<_>>(),
+    vec![
+      ("status", true),
+      ("done", true),
+      ("doing", false),
+      ("todo", true)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn move_row_between_groups_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_group_setting(TEST_VIEW_ID_V1, status_group_setting(vec![]));
+
+  assert!(
+    database_test
+      .move_row_to_group(TEST_VIEW_ID_V1, row_ids[1], Some("todo"), "done")
+      .await
+      .unwrap()
+  );
+  assert!(
+    database_test
+      .move_row_to_group(TEST_VIEW_ID_V1, row_ids[0], Some("doing"), "status")
+      .await
+      .unwrap()
+  );
+  // Not an option of the field
+  assert!(
+    !database_test
+      .move_row_to_group(TEST_VIEW_ID_V1, row_ids[2], Some("done"), "unknown")
+      .await
+      .unwrap()
+  );
+
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    group_summary(&groups),
+    vec![
+      ("status", vec![row_ids[0], row_ids[3]]),
+      ("todo", vec![]),
+      ("doing", vec![]),
+      ("done", vec![row_ids[1], row_ids[2]]),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn group_rows_by_month_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let mut setting = GroupSetting::new(
+    "due".to_string(),
+    FieldType::DateTime.into(),
+    "".to_string(),
+  );
+  setting.content = GroupContent {
+    hide_empty: true,
+    condition: DateGroupCondition::Month,
+  }
+  .to_json();
+  database_test.insert_group_setting(TEST_VIEW_ID_V1, setting);
+
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    group_summary(&groups),
+    vec![
+      ("due", vec![row_ids[2], row_ids[3]]),
+      ("2024/01", vec![row_ids[1]]),
+      ("2024/03", vec![row_ids[0]]),
+    ]
+  );
+  assert_eq!(groups[2].name, "Mar 2024");
+
+  // Moving a card to a month keeps the day's time
+  assert!(
+    database_test
+      .move_row_to_group(TEST_VIEW_ID_V1, row_ids[0], Some("2024/03"), "2024/01")
+      .await
+      .unwrap()
+  );
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(groups[1].row_ids, vec![row_ids[0], row_ids[1]]);
+}
+
+#[tokio::test]
+async fn sync_groups_with_select_options_test() {
+  let (mut database_test, _) = create_database_with_rows().await;
+  let mut setting = status_group_setting(vec![]);
+  setting.id = "g1".to_string();
+  database_test.insert_group_setting(TEST_VIEW_ID_V1, setting);
+  database_test.sync_group_settings_with_field("status");
+  assert_eq!(
+    stored_group_ids(&database_test),
+    vec!["status", "todo", "doing", "done"]
+  );
+
+  // Delete "doing", rename "todo" and add "blocked"
+  let mut type_option = status_type_option();
+  type_option.options.remove(1);
+  type_option.options[0].name = "backlog".to_string();
+  let mut option = SelectOption::new("blocked");
+  option.id = "blocked".to_string();
+  type_option.options.push(option);
+  database_test.update_field("status", |field_update| {
+    field_update.update_type_options(|type_option_update| {
+      type_option_update.insert(
+        &FieldType::SingleSelect.type_id(),
+        SingleSelectTypeOption(type_option),
+      );
+    });
+  });
+  assert_eq!(
+    stored_group_ids(&database_test),
+    vec!["status", "todo", "done", "blocked"]
+  );
+
+  let groups = database_test
+    .get_groups_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(groups[1].name, "backlog");
+}
+
+fn stored_group_ids(database_test: &DatabaseTest) -> Vec<String> {
+  database_test.get_all_group_setting::<GroupSetting>(TEST_VIEW_ID_V1)[0]
+    .groups
+    .iter()
+    .map(|group| group.id.clone())
+    .collect()
+}
+
+fn group_summary(groups: &[GroupData]) -> Vec<(&str, Vec<RowId>)> {
+  groups
+    .iter()
+    .map(|group| (group.id.as_str(), group.row_ids.clone()))
+    .collect()
+}
+
+fn status_group_setting(groups: Vec<Group>) -> GroupSetting {
+  let mut setting = GroupSetting::new(
+    "status".to_string(),
+    FieldType::SingleSelect.into(),
+    "".to_string(),
+  );
+  setting.groups = groups;
+  setting
+}
+
+fn status_type_option() -> SelectTypeOption {
+  let mut type_option = SelectTypeOption::default();
+  for option_id in ["todo", "doing", "done"] {
+    let mut option = SelectOption::new(option_id);
+    option.id = option_id.to_string();
+    type_option.options.push(option);
+  }
+  type_option
+}
+
+fn status_cell(option_id: &str) -> Cell {
+  SelectOptionIds::from(vec![option_id.to_string()]).to_cell(FieldType::SingleSelect)
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  // 2024-03-05 10:00 and 2024-01-20 08:00 UTC
+  let rows = vec![
+    Cells::from([
+      ("status".into(), status_cell("doing")),
+      ("due".into(), text_cell(FieldType::DateTime, "1709632800")),
+    ]),
+    Cells::from([
+      ("status".into(), status_cell("todo")),
+      ("due".into(), text_cell(FieldType::DateTime, "1705737600")),
+    ]),
+    Cells::from([("status".into(), status_cell("done"))]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, "no status"))]),
+  ];
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("status", "Status", FieldType::SingleSelect, false).with_type_option_data(
+      FieldType::SingleSelect,
+      SingleSelectTypeOption(status_type_option()).into(),
+    ),
+    test_field("due", "Due", FieldType::DateTime, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
@@ -2,11 +2,13 @@ use collab::core::collab::{CollabOptions, default_client_id};
 use collab::core::origin::CollabOrigin;
 use collab::database::database::{Database, DatabaseContext};
 use collab::database::fields::Field;
-use collab::database::rows::{Cells, CreateRowParams, DatabaseRow, Row};
+use collab::database::rows::{Cell, Cells, CreateRowParams, DatabaseRow, Row, new_cell_builder};
+use collab::database::template::entity::CELL_DATA;
 use collab::database::views::{
   DatabaseLayout, FieldSettingsByFieldIdMap, FieldSettingsMap, LayoutSetting, LayoutSettings,
   OrderObjectPosition,
 };
+use collab::database::workspace_database::DatabaseRelation;
 use collab::entity::uuid_validation::RowId;
 use collab::preclude::Collab;
 use futures::StreamExt;
@@ -18,7 +20,7 @@ use std::time::Duration;
 
 use crate::helper::{TestFieldSetting, TestTextCell, make_rocks_db, setup_log};
 use crate::user_test::helper::TestUserDatabaseServiceImpl;
-use collab::database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab::database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
 
 use collab::plugins::CollabKVDB;
 use tempfile::TempDir;
@@ -227,6 +229,7 @@ pub struct DatabaseTestBuilder {
   fields: Vec<Field>,
   layout: DatabaseLayout,
   field_settings: FieldSettingsByFieldIdMap,
+  database_relation: Option<Arc<DatabaseRelation>>,
 }
 
 impl DatabaseTestBuilder {
@@ -240,6 +243,7 @@ impl DatabaseTestBuilder {
       fields: vec![],
       layout: DatabaseLayout::Grid,
       field_settings: Default::default(),
+      database_relation: None,
     }
   }
 
@@ -258,23 +262,48 @@ impl DatabaseTestBuilder {
     self
   }
 
+  pub fn with_fields(mut self, fields: impl IntoIterator<Item = Field>) -> Self {
+    self.fields.extend(fields);
+    self
+  }
+
+  /// Adds a row with the given id and cells for each of `rows`, in order.
+  pub fn with_rows(mut self, rows: impl IntoIterator<Item = (RowId, Cells)>) -> Self {
+    let database_id = Uuid::parse_str(&self.database_id).unwrap();
+    self.rows.extend(
+      rows
+        .into_iter()
+        .map(|(row_id, cells)| CreateRowParams::new(row_id, database_id).with_cells(cells)),
+    );
+    self
+  }
+
   pub fn with_layout(mut self, layout: DatabaseLayout) -> Self {
     self.layout = layout;
     self
   }
 
+  pub fn with_database_relation(mut self, database_relation: Arc<DatabaseRelation>) -> Self {
+    self.database_relation = Some(database_relation);
+    self
+  }
+
   pub async fn build(self) -> DatabaseTest {
     let client_id = default_client_id();
     let workspace_id = Uuid::new_v4().to_string();
     let tempdir = TempDir::new().unwrap();
     let path = tempdir.into_path();
     let collab_db = Arc::new(CollabKVDB::open(path).unwrap());
-    let collab_service = Arc::new(TestUserDatabaseServiceImpl::new(
+    let mut collab_service = TestUserDatabaseServiceImpl::new(
       self.uid,
       workspace_id.clone(),
       collab_db.clone(),
       client_id,
-    ));
+    );
+    if let Some(database_relation) = self.database_relation {
+      collab_service = collab_service.with_database_relation(database_relation);
+    }
+    let collab_service = Arc::new(collab_service);
     let context = DatabaseContext::new(collab_service.clone(), collab_service);
     let database_uuid = Uuid::parse_str(&self.database_id).unwrap_or_else(|_| Uuid::new_v4());
     let view_uuid = Uuid::parse_str(&self.view_id).unwrap_or_else(|_| Uuid::new_v4());
@@ -303,6 +332,40 @@ impl DatabaseTestBuilder {
   }
 }
 
+/// Create a database with `fields` and a row for each of `rows`, in order.
+/// Returns the ids of the rows.
+pub async fn create_database_with_fields_and_rows(
+  fields: Vec<Field>,
+  rows: Vec<Cells>,
+) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = rows.iter().map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  let database_test = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_fields(fields)
+    .with_rows(row_ids.iter().copied().zip(rows))
+    .build()
+    .await;
+  (database_test, row_ids)
+}
+
+/// Returns a field of `field_type` without any type option.
+pub fn test_field(id: &str, name: &str, field_type: FieldType, is_primary: bool) -> Field {
+  Field::new(
+    id.to_string(),
+    name.to_string(),
+    field_type.into(),
+    is_primary,
+  )
+}
+
+/// Returns a cell of `field_type` that stores `data` as its string data, the way text, number,
+/// checkbox and date cells do.
+pub fn text_cell(field_type: FieldType, data: &str) -> Cell {
+  let mut cell = new_cell_builder(field_type);
+  cell.insert(CELL_DATA.into(), data.into());
+  cell
+}
+
 /// Create a database with default data
 /// It will create a default view with id 'v1'
 pub async fn create_database_with_default_data(uid: i64, database_id: &str) -> DatabaseTest {
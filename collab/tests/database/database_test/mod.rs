@@ -1,20 +1,39 @@
+mod auto_number_test;
 mod block_test;
+mod calculation_evaluator_test;
+mod calendar_test;
 mod cell_test;
 mod cell_type_option_test;
+mod chart_test;
+mod csv_export_test;
+mod csv_import_test;
+mod duplicate_test;
 mod encode_collab_test;
+mod field_conversion_test;
 mod field_observe_test;
 mod field_setting_test;
 mod field_test;
+mod filter_evaluator_test;
 mod filter_test;
+mod formula_test;
+mod group_evaluator_test;
 mod group_test;
 pub mod helper;
 mod layout_test;
+mod person_test;
+mod query_test;
+mod relation_test;
 // mod restore_test;
+mod rollup_evaluator_test;
 mod rollup_type_option_test;
+mod row_batch_test;
 mod row_init_test;
 mod row_observe_test;
 mod row_test;
+mod sort_evaluator_test;
 mod sort_test;
+mod timeline_test;
 mod type_option_test;
+mod validation_test;
 mod view_observe_test;
 mod view_test;
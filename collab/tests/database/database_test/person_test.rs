@@ -0,0 +1,165 @@
+use collab::database::database::gen_row_id;
+use collab::database::entity::FieldType;
+use collab::database::fields::Field;
+use collab::database::fields::person_type_option::{DatabasePerson, PersonTypeOption};
+use collab::database::rows::{Cells, CreateRowParams};
+use collab::database::template::csv_export::CSVExportOptions;
+use collab::database::template::csv_import::{CSVImportError, CSVImportMode, CSVImportOptions};
+use collab::database::template::person_parse::PersonCellData;
+use collab::entity::uuid_validation::RowId;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, TEST_VIEW_ID_V1};
+
+#[tokio::test]
+async fn fill_person_with_creator_test() {
+  let mut database_test = create_database_with_persons().await;
+  let database_id = database_test.get_database_id().unwrap();
+
+  let row_order = database_test
+    .create_row(CreateRowParams::new_with_creator(
+      gen_row_id(),
+      database_id,
+      2,
+    ))
+    .await
+    .unwrap();
+  assert_eq!(person_ids(&database_test, &row_order.id).await, vec!["2"]);
+
+  // A row with a person keeps it
+  let cells = Cells::from([(
+    "owner".into(),
+    PersonCellData::new(vec!["1".to_string()]).to_cell(),
+  )]);
+  let (_, row_order) = database_test
+    .create_row_in_view(
+      TEST_VIEW_ID_V1,
+      CreateRowParams::new_with_creator(gen_row_id(), database_id, 2).with_cells(cells),
+    )
+    .await
+    .unwrap();
+  assert_eq!(person_ids(&database_test, &row_order.id).await, vec!["1"]);
+
+  // A row without creator is left empty
+  let row_order = database_test
+    .create_row(CreateRowParams::new(gen_row_id(), database_id))
+    .await
+    .unwrap();
+  assert!(person_ids(&database_test, &row_order.id).await.is_empty());
+}
+
+#[tokio::test]
+async fn person_csv_round_trip_test() {
+  let mut database_test = create_database_with_persons().await;
+  let csv = "Name,Owner\nfirst,lucas@appflowy.io\nsecond,\"Nathan, Lucas\"\n";
+  let options = CSVImportOptions::new()
+    .with_key_field("name")
+    .with_mode(CSVImportMode::AppendOnly);
+  let plan = database_test
+    .plan_csv_import(csv.as_bytes(), &options, false)
+    .await
+    .unwrap();
+  assert!(plan.report.errors.is_empty());
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(
+    person_ids(&database_test, &report.inserted_row_ids[0]).await,
+    vec!["1"]
+  );
+  // The field is single select
+  assert_eq!(
+    person_ids(&database_test, &report.inserted_row_ids[1]).await,
+    vec!["2"]
+  );
+
+  let bytes = database_test
+    .export_view_to_csv(TEST_VIEW_ID_V1, &CSVExportOptions::new(), vec![], false)
+    .await
+    .unwrap();
+  assert_eq!(
+    String::from_utf8(bytes).unwrap(),
+    "Name,Owner\nfirst,Lucas\nsecond,Nathan\n"
+  );
+}
+
+#[tokio::test]
+async fn person_csv_import_rejects_unknown_persons_test() {
+  let mut database_test = create_database_with_persons().await;
+  let csv = "Name,Owner\nfirst,Someone Else\nsecond,42\nthird,nobody@appflowy.io\n";
+  let options = CSVImportOptions::new()
+    .with_key_field("name")
+    .with_mode(CSVImportMode::AppendOnly);
+  let plan = database_test
+    .plan_csv_import(csv.as_bytes(), &options, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    plan.report.errors,
+    vec![
+      CSVImportError {
+        line: 2,
+        column: "Owner".to_string(),
+        value: "Someone Else".to_string(),
+      },
+      CSVImportError {
+        line: 4,
+        column: "Owner".to_string(),
+        value: "nobody@appflowy.io".to_string(),
+      },
+    ]
+  );
+
+  // A user id is kept even when the person isn't known yet
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(
+    person_ids(&database_test, &report.inserted_row_ids[1]).await,
+    vec!["42"]
+  );
+  assert!(
+    person_ids(&database_test, &report.inserted_row_ids[0])
+      .await
+      .is_empty()
+  );
+}
+
+async fn person_ids(database_test: &DatabaseTest, row_id: &RowId) -> Vec<String> {
+  database_test
+    .get_cell("owner", row_id)
+    .await
+    .cell
+    .map(|cell| PersonCellData::from(&cell).person_ids)
+    .unwrap_or_default()
+}
+
+/// A database with a name and a single select owner field filled with the creator.
+async fn create_database_with_persons() -> DatabaseTest {
+  let database_id = Uuid::new_v4();
+  let type_option = PersonTypeOption {
+    is_single_select: true,
+    fill_with_creator: true,
+    disable_notification: false,
+    persons: vec![
+      DatabasePerson::new(1)
+        .with_name("Lucas")
+        .with_email("lucas@appflowy.io"),
+      DatabasePerson::new(2).with_name("Nathan"),
+    ],
+  };
+  DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(Field::new(
+      "name".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    ))
+    .with_field(
+      Field::new(
+        "owner".to_string(),
+        "Owner".to_string(),
+        FieldType::Person.into(),
+        false,
+      )
+      .with_type_option_data(FieldType::Person, type_option.into()),
+    )
+    .build()
+    .await
+}
@@ -0,0 +1,207 @@
+use collab::database::entity::FieldType;
+use collab::database::filter::{NumberFilterCondition, TextFilterCondition};
+use collab::database::query::{DatabaseQuery, FieldRef, QueryPage, QueryPredicate};
+use collab::database::rows::Cells;
+use collab::database::sort::SortCondition;
+use collab::entity::uuid_validation::RowId;
+use collab::error::CollabError;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn query_select_and_predicates_test() {
+  let (database_test, row_ids) = create_database_for_query().await;
+  let query = DatabaseQuery::new()
+    .with_select(vec![FieldRef::name("Name")])
+    .with_predicate(QueryPredicate::number(
+      FieldRef::id("score"),
+      NumberFilterCondition::GreaterThan,
+      15.0,
+    ))
+    .with_predicate(QueryPredicate::or(vec![
+      QueryPredicate::text(FieldRef::name("Name"), TextFilterCondition::StartsWith, "b"),
+      QueryPredicate::text(FieldRef::name("Name"), TextFilterCondition::Is, "dave"),
+    ]));
+  let page = database_test
+    .query_rows(query, 2, None, false)
+    .await
+    .unwrap();
+
+  assert_eq!(page.next_cursor, None);
+  assert_eq!(
+    page.rows.iter().map(|row| row.row_id).collect::<Vec<_>>(),
+    vec![row_ids[1], row_ids[3]]
+  );
+  // Only the selected field is returned
+  assert_eq!(page.rows[0].cells.len(), 1);
+  assert_eq!(page.rows[0].get("name"), Some(&json!("Bob")));
+  assert_eq!(page.rows[1].get("name"), Some(&json!("Dave")));
+  assert_eq!(page.rows[1].get("score"), None);
+}
+
+#[tokio::test]
+async fn query_order_by_test() {
+  let (database_test, _) = create_database_for_query().await;
+  let query = DatabaseQuery::new()
+    .with_select(vec![FieldRef::id("name"), FieldRef::id("note")])
+    .with_order_by(FieldRef::name("Score"), SortCondition::Descending);
+  let page = database_test
+    .query_rows(query, 10, None, false)
+    .await
+    .unwrap();
+  assert_eq!(names(&page), vec!["Dave", "Bob", "Carol", "Alice"]);
+  // A row without a cell for a selected field returns null
+  assert_eq!(page.rows[0].get("note"), Some(&json!(null)));
+  assert_eq!(page.rows[3].get("note"), Some(&json!("first")));
+}
+
+#[tokio::test]
+async fn query_pagination_test() {
+  let (database_test, _) = create_database_for_query().await;
+  for order_by in [None, Some(SortCondition::Ascending)] {
+    let mut pages = vec![];
+    let mut cursor = None;
+    loop {
+      let mut query = DatabaseQuery::new().with_limit(3);
+      if let Some(condition) = order_by {
+        query = query.with_order_by(FieldRef::id("name"), condition);
+      }
+      if let Some(cursor) = cursor {
+        query = query.with_cursor(cursor);
+      }
+      let page = database_test
+        .query_rows(query, 2, None, false)
+        .await
+        .unwrap();
+      cursor = page.next_cursor;
+      pages.push(names(&page));
+      if cursor.is_none() {
+        break;
+      }
+    }
+    assert_eq!(
+      pages,
+      vec![vec!["Alice", "Bob", "Carol"], vec!["Dave"]],
+      "order by {:?}",
+      order_by
+    );
+  }
+
+  // A page that exactly fits the rows has no next page
+  let page = database_test
+    .query_rows(DatabaseQuery::new().with_limit(4), 2, None, false)
+    .await
+    .unwrap();
+  assert_eq!(page.rows.len(), 4);
+  assert_eq!(page.next_cursor, None);
+}
+
+#[tokio::test]
+async fn query_pagination_with_equal_values_test() {
+  let (database_test, _) = create_database_for_query().await;
+  let mut pages = vec![];
+  let mut cursor = None;
+  loop {
+    // The rows without a note are equal, so they keep the order of the view
+    let mut query = DatabaseQuery::new()
+      .with_limit(1)
+      .with_order_by(FieldRef::id("note"), SortCondition::Ascending)
+      .with_predicate(QueryPredicate::number(
+        FieldRef::id("score"),
+        NumberFilterCondition::GreaterThan,
+        15.0,
+      ));
+    if let Some(cursor) = cursor {
+      query = query.with_cursor(cursor);
+    }
+    let page = database_test
+      .query_rows(query, 3, None, false)
+      .await
+      .unwrap();
+    cursor = page.next_cursor;
+    pages.push(names(&page));
+    if cursor.is_none() {
+      break;
+    }
+  }
+  assert_eq!(pages, vec![vec!["Bob"], vec!["Carol"], vec!["Dave"]]);
+}
+
+#[tokio::test]
+async fn invalid_query_test() {
+  let (database_test, _) = create_database_for_query().await;
+  let queries = vec![
+    DatabaseQuery::new().with_select(vec![FieldRef::name("Unknown")]),
+    DatabaseQuery::new().with_predicate(QueryPredicate::text(
+      FieldRef::id("unknown"),
+      TextFilterCondition::Is,
+      "a",
+    )),
+    // A text filter on a number field
+    DatabaseQuery::new().with_predicate(QueryPredicate::text(
+      FieldRef::id("score"),
+      TextFilterCondition::Contains,
+      "1",
+    )),
+    DatabaseQuery::new().with_order_by(FieldRef::name("Unknown"), SortCondition::Ascending),
+    DatabaseQuery::new().with_cursor(Uuid::new_v4()),
+  ];
+  for query in queries {
+    let result = database_test.query_rows(query, 10, None, false).await;
+    assert!(
+      matches!(result, Err(CollabError::DatabaseInvalidQuery(_))),
+      "{:?}",
+      result
+    );
+  }
+
+  let result = database_test
+    .query_rows(
+      DatabaseQuery::new().with_view_id(Uuid::new_v4()),
+      10,
+      None,
+      false,
+    )
+    .await;
+  assert!(matches!(result, Err(CollabError::DatabaseViewNotExist)));
+}
+
+fn names(page: &QueryPage) -> Vec<String> {
+  page
+    .rows
+    .iter()
+    .map(|row| {
+      row
+        .get("name")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string()
+    })
+    .collect()
+}
+
+async fn create_database_for_query() -> (DatabaseTest, Vec<RowId>) {
+  let mut rows = vec![];
+  for (name, score) in [
+    ("Alice", "10"),
+    ("Bob", "30"),
+    ("Carol", "20"),
+    ("Dave", "40"),
+  ] {
+    rows.push(Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, name)),
+      ("score".into(), text_cell(FieldType::Number, score)),
+    ]));
+  }
+  rows[0].insert("note".into(), text_cell(FieldType::RichText, "first"));
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("score", "Score", FieldType::Number, false),
+    test_field("note", "Note", FieldType::RichText, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
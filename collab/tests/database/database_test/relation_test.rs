@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use collab::core::collab::{CollabOptions, default_client_id};
+use collab::core::origin::CollabOrigin;
+use collab::database::entity::FieldType;
+use collab::database::fields::Field;
+use collab::database::fields::relation_type_option::RelationTypeOption;
+use collab::database::rows::Cells;
+use collab::database::template::relation_parse::RelationCellData;
+use collab::database::workspace_database::{DatabaseRelation, LinkedByRow, RowRelationMap};
+use collab::entity::uuid_validation::RowId;
+use collab::preclude::{Collab, Map, MapRef, ReadTxn, Update};
+use uuid::Uuid;
+use yrs::updates::decoder::Decode;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, test_field, text_cell};
+
+#[tokio::test]
+async fn row_relation_map_test() {
+  let database_relation = create_database_relation();
+  database_relation
+    .set_linking_rows("d1", "d2", "r1", "f1", &["a".to_string(), "b".to_string()])
+    .await;
+  database_relation
+    .set_linking_rows("d3", "d2", "r3", "f3", &["b".to_string()])
+    .await;
+
+  let linked_by_rows = database_relation.get_linked_by_rows("a").await;
+  assert_eq!(linked_by_rows.len(), 1);
+  assert_eq!(linked_by_rows["d1"], vec![linked_by_row("r1")]);
+  let linked_by_rows = database_relation.get_linked_by_rows("b").await;
+  assert_eq!(linked_by_rows.len(), 2);
+  assert_eq!(linked_by_rows["d3"], vec![linked_by_row("r3")]);
+
+  // The links of the field are replaced
+  database_relation
+    .set_linking_rows("d1", "d2", "r1", "f1", &["b".to_string()])
+    .await;
+  assert!(database_relation.get_linked_by_rows("a").await.is_empty());
+  assert_eq!(
+    database_relation.get_linked_by_rows("b").await["d1"],
+    vec![linked_by_row("r1")]
+  );
+
+  database_relation.remove_row("r3").await;
+  let linked_by_rows = database_relation.get_linked_by_rows("b").await;
+  assert_eq!(linked_by_rows.len(), 1);
+  assert!(linked_by_rows.contains_key("d1"));
+}
+
+#[test]
+fn concurrent_row_relation_edits_test() {
+  let object_id = Uuid::new_v4();
+  let mut first =
+    Collab::new_with_options(CollabOrigin::Empty, CollabOptions::new(object_id, 1)).unwrap();
+  let first_relations = row_relation_map(&mut first);
+  first_relations.set_linking_rows_with_txn(
+    &mut first.transact_mut(),
+    "d1",
+    "d2",
+    "r1",
+    "f1",
+    &["a".to_string()],
+  );
+  let mut second =
+    Collab::new_with_options(CollabOrigin::Empty, CollabOptions::new(object_id, 2)).unwrap();
+  sync_collab(&first, &mut second);
+  let second_relations = row_relation_map(&mut second);
+
+  // Both clients link rows of the same relation at the same time
+  first_relations.set_linking_rows_with_txn(
+    &mut first.transact_mut(),
+    "d1",
+    "d2",
+    "r1",
+    "f1",
+    &["a".to_string(), "b".to_string()],
+  );
+  second_relations.set_linking_rows_with_txn(
+    &mut second.transact_mut(),
+    "d1",
+    "d2",
+    "r2",
+    "f1",
+    &["a".to_string()],
+  );
+  sync_collab(&first, &mut second);
+  sync_collab(&second, &mut first);
+
+  for (collab, relations) in [(&first, &first_relations), (&second, &second_relations)] {
+    let txn = collab.transact();
+    let mut linked_by_rows = relations.get_linked_by_rows_with_txn(&txn, "a")["d1"].clone();
+    linked_by_rows.sort_by(|a, b| a.row_id.cmp(&b.row_id));
+    assert_eq!(
+      linked_by_rows,
+      vec![linked_by_row("r1"), linked_by_row("r2")]
+    );
+    assert_eq!(
+      relations.get_linked_by_rows_with_txn(&txn, "b")["d1"],
+      vec![linked_by_row("r1")]
+    );
+  }
+}
+
+#[tokio::test]
+async fn two_way_relation_test() {
+  let database_relation = Arc::new(create_database_relation());
+  let (mut database_test, row_ids) =
+    create_database_with_relations(database_relation.clone()).await;
+  let database_id = database_test.get_database_id().unwrap().to_string();
+
+  database_test
+    .update_relation_cell(&row_ids[0], "related", vec![row_ids[1], row_ids[2]])
+    .await
+    .unwrap();
+  assert_eq!(
+    relation_row_ids(&database_test, &row_ids[1], "related_by").await,
+    vec![row_ids[0]]
+  );
+  assert_eq!(
+    relation_row_ids(&database_test, &row_ids[2], "related_by").await,
+    vec![row_ids[0]]
+  );
+  assert_eq!(
+    database_relation
+      .get_linked_by_rows(&row_ids[1].to_string())
+      .await[&database_id],
+    vec![linked_by_row(&row_ids[0].to_string())]
+  );
+
+  // The reverse cell of the unlinked row is cleared
+  database_test
+    .update_relation_cell(&row_ids[0], "related", vec![row_ids[2]])
+    .await
+    .unwrap();
+  assert!(
+    relation_row_ids(&database_test, &row_ids[1], "related_by")
+      .await
+      .is_empty()
+  );
+  assert_eq!(
+    relation_row_ids(&database_test, &row_ids[2], "related_by").await,
+    vec![row_ids[0]]
+  );
+
+  let linked_by_rows = database_test
+    .get_linked_by_rows(&row_ids[2], false)
+    .await
+    .unwrap();
+  assert_eq!(linked_by_rows[&database_id], vec![row_ids[0]]);
+  let linked_by_rows = database_test
+    .get_linked_by_rows(&row_ids[0], false)
+    .await
+    .unwrap();
+  assert_eq!(linked_by_rows[&database_id], vec![row_ids[2]]);
+}
+
+#[tokio::test]
+async fn remove_linked_row_test() {
+  let database_relation = Arc::new(create_database_relation());
+  let (mut database_test, row_ids) =
+    create_database_with_relations(database_relation.clone()).await;
+  database_test
+    .update_relation_cell(&row_ids[0], "related", vec![row_ids[1], row_ids[2]])
+    .await
+    .unwrap();
+
+  // Removing a row removes the links to it
+  database_test.remove_rows(&[row_ids[1]]).await;
+  assert_eq!(
+    relation_row_ids(&database_test, &row_ids[0], "related").await,
+    vec![row_ids[2]]
+  );
+  assert!(
+    database_relation
+      .get_linked_by_rows(&row_ids[0].to_string())
+      .await
+      .values()
+      .flatten()
+      .all(|row| row.row_id != row_ids[1].to_string())
+  );
+
+  // The cell holding the last link is removed
+  database_test.remove_row(&row_ids[2]).await;
+  assert!(
+    database_test
+      .get_cell("related", &row_ids[0])
+      .await
+      .cell
+      .is_none()
+  );
+  assert!(
+    database_test
+      .get_linked_by_rows(&row_ids[0], false)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn remove_row_clears_forward_and_reverse_cells_test() {
+  let database_relation = Arc::new(create_database_relation());
+  let (mut database_test, row_ids) = create_database_with_relations(database_relation).await;
+  database_test
+    .update_relation_cell(&row_ids[0], "related", vec![row_ids[1]])
+    .await
+    .unwrap();
+  database_test
+    .update_relation_cell(&row_ids[1], "related", vec![row_ids[2]])
+    .await
+    .unwrap();
+  assert_eq!(
+    relation_row_ids(&database_test, &row_ids[2], "related_by").await,
+    vec![row_ids[1]]
+  );
+
+  // The first row links to the removed row, and the last row is linked by it
+  database_test.remove_row(&row_ids[1]).await;
+  assert!(
+    relation_row_ids(&database_test, &row_ids[0], "related")
+      .await
+      .is_empty()
+  );
+  assert!(
+    relation_row_ids(&database_test, &row_ids[2], "related_by")
+      .await
+      .is_empty()
+  );
+}
+
+fn linked_by_row(row_id: &str) -> LinkedByRow {
+  LinkedByRow {
+    row_id: row_id.to_string(),
+  }
+}
+
+fn row_relation_map(collab: &mut Collab) -> RowRelationMap {
+  let map_ref: MapRef = {
+    let mut txn = collab.context.transact_mut();
+    collab.data.get_or_init(&mut txn, "row_relations")
+  };
+  RowRelationMap::from_map_ref(map_ref)
+}
+
+fn sync_collab(from: &Collab, to: &mut Collab) {
+  let state_vector = to.transact().state_vector();
+  let update = from.transact().encode_state_as_update_v1(&state_vector);
+  to.apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+}
+
+fn create_database_relation() -> DatabaseRelation {
+  let options = CollabOptions::new(Uuid::new_v4(), default_client_id());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  DatabaseRelation::from_collab(collab)
+}
+
+async fn relation_row_ids(
+  database_test: &DatabaseTest,
+  row_id: &RowId,
+  field_id: &str,
+) -> Vec<RowId> {
+  database_test
+    .get_cell(field_id, row_id)
+    .await
+    .cell
+    .map(|cell| RelationCellData::from(&cell).row_ids)
+    .unwrap_or_default()
+}
+
+fn relation_field(field_id: &str, database_id: &str, reverse_field_id: &str) -> Field {
+  test_field(field_id, field_id, FieldType::Relation, false).with_type_option_data(
+    FieldType::Relation,
+    RelationTypeOption::new(database_id)
+      .with_reverse_field(reverse_field_id)
+      .into(),
+  )
+}
+
+/// A database whose "related" field relates to its own rows, with "related_by" as reverse field.
+async fn create_database_with_relations(
+  database_relation: Arc<DatabaseRelation>,
+) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = (0..3).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  let rows = ["a", "b", "c"]
+    .map(|name| Cells::from([("name".into(), text_cell(FieldType::RichText, name))]));
+  let database_test = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_database_relation(database_relation)
+    .with_field(test_field("name", "Name", FieldType::RichText, true))
+    .with_field(relation_field(
+      "related",
+      &database_id.to_string(),
+      "related_by",
+    ))
+    .with_field(relation_field(
+      "related_by",
+      &database_id.to_string(),
+      "related",
+    ))
+    .with_rows(row_ids.iter().copied().zip(rows))
+    .build()
+    .await;
+  (database_test, row_ids)
+}
@@ -0,0 +1,244 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::Field;
+use collab::database::fields::relation_type_option::RelationTypeOption;
+use collab::database::fields::rollup_type_option::{RollupDisplayMode, RollupTypeOption};
+use collab::database::rows::{Cell, Cells};
+use collab::database::template::entity::CELL_DATA;
+use collab::database::template::relation_parse::RelationCellData;
+use collab::entity::uuid_validation::RowId;
+use collab::util::AnyMapExt;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder, test_field, text_cell};
+
+#[tokio::test]
+async fn refresh_rollup_cells_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![]).await;
+  let mut updated = database_test
+    .refresh_rollup_cells(None, false)
+    .await
+    .unwrap();
+  updated.sort();
+  let mut expected = row_ids.clone();
+  expected.sort();
+  assert_eq!(updated, expected);
+
+  assert_eq!(
+    rollup_value(&database_test, "total", &row_ids[0]).await,
+    "13"
+  );
+  assert_eq!(
+    rollup_value(&database_test, "names", &row_ids[0]).await,
+    "b, c"
+  );
+  assert_eq!(
+    rollup_value(&database_test, "total", &row_ids[1]).await,
+    "0"
+  );
+  assert_eq!(rollup_value(&database_test, "names", &row_ids[1]).await, "");
+
+  // Nothing changed, so nothing is written
+  let updated = database_test
+    .refresh_rollup_cells(None, false)
+    .await
+    .unwrap();
+  assert!(updated.is_empty());
+}
+
+#[tokio::test]
+async fn rollup_list_and_condition_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![
+    rollup_field("unique", "name", RollupDisplayMode::UniqueList, ""),
+    rollup_field("only_b", "name", RollupDisplayMode::Calculated, "B"),
+  ])
+  .await;
+  database_test
+    .refresh_rollup_cells(Some(vec![row_ids[0]]), false)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    rollup_value(&database_test, "unique", &row_ids[0]).await,
+    "b, c"
+  );
+  // Count of the related rows named "b"
+  assert_eq!(
+    rollup_value(&database_test, "only_b", &row_ids[0]).await,
+    "1"
+  );
+  // Only the given rows are refreshed
+  assert!(
+    !database_test
+      .get_row(&row_ids[1])
+      .await
+      .unwrap()
+      .cells
+      .contains_key("unique")
+  );
+}
+
+#[tokio::test]
+async fn refresh_rollups_for_related_rows_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![]).await;
+  database_test
+    .refresh_rollup_cells(None, false)
+    .await
+    .unwrap();
+
+  database_test
+    .update_row(row_ids[2], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("amount", text_cell(FieldType::Number, "20"));
+      });
+    })
+    .await;
+  let database_id = database_test.get_database_id().unwrap().to_string();
+  let updated = database_test
+    .refresh_rollups_for_related_rows(&database_id, &[row_ids[2]], false)
+    .await
+    .unwrap();
+  assert_eq!(updated, vec![row_ids[0]]);
+  assert_eq!(
+    rollup_value(&database_test, "total", &row_ids[0]).await,
+    "30"
+  );
+}
+
+#[tokio::test]
+async fn rollup_of_rollup_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![rollup_field(
+    "nested",
+    "total",
+    RollupDisplayMode::Calculated,
+    "",
+  )])
+  .await;
+  // a -> b, c and b -> d, so the nested rollup of a sums the totals of b and c
+  database_test
+    .update_row(row_ids[1], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("related", relation_cell(vec![row_ids[3]]));
+      });
+    })
+    .await;
+  database_test
+    .refresh_rollup_cells(None, false)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    rollup_value(&database_test, "total", &row_ids[1]).await,
+    "4"
+  );
+  assert_eq!(
+    rollup_value(&database_test, "nested", &row_ids[0]).await,
+    "4"
+  );
+}
+
+#[tokio::test]
+async fn rollup_cycle_is_not_resolved_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(vec![rollup_field(
+    "loop",
+    "loop",
+    RollupDisplayMode::Calculated,
+    "",
+  )])
+  .await;
+  // a -> b, c and b -> a
+  database_test
+    .update_row(row_ids[1], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("related", relation_cell(vec![row_ids[0]]));
+      });
+    })
+    .await;
+  database_test
+    .refresh_rollup_cells(None, false)
+    .await
+    .unwrap();
+
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert!(!row.cells.contains_key("loop"));
+  assert_eq!(
+    rollup_value(&database_test, "total", &row_ids[0]).await,
+    "13"
+  );
+}
+
+async fn rollup_value(database_test: &DatabaseTest, field_id: &str, row_id: &RowId) -> String {
+  let row = database_test.get_row(row_id).await.unwrap();
+  row
+    .cells
+    .get(field_id)
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .unwrap_or_default()
+}
+
+fn rollup_field(
+  field_id: &str,
+  target_field_id: &str,
+  show_as: RollupDisplayMode,
+  condition_value: &str,
+) -> Field {
+  let calculation_type = if condition_value.is_empty() { 4 } else { 5 };
+  test_field(field_id, field_id, FieldType::Rollup, false).with_type_option_data(
+    FieldType::Rollup,
+    RollupTypeOption {
+      relation_field_id: "related".to_string(),
+      target_field_id: target_field_id.to_string(),
+      calculation_type,
+      show_as,
+      condition_value: condition_value.to_string(),
+    }
+    .into(),
+  )
+}
+
+fn relation_cell(row_ids: Vec<RowId>) -> Cell {
+  Cell::from(RelationCellData { row_ids })
+}
+
+/// Rows a, b, c and d of a database related to itself. Row a relates to b and c.
+async fn create_database_with_rows(fields: Vec<Field>) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = (0..4).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  let mut rows = vec![];
+  for (name, amount) in [("a", "1"), ("b", "10"), ("c", "3"), ("d", "4")] {
+    rows.push(Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, name)),
+      ("amount".into(), text_cell(FieldType::Number, amount)),
+    ]));
+  }
+  rows[0].insert(
+    "related".into(),
+    relation_cell(vec![row_ids[1], row_ids[2]]),
+  );
+
+  let database_test = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(test_field("name", "Name", FieldType::RichText, true))
+    .with_field(test_field("amount", "Amount", FieldType::Number, false))
+    .with_field(
+      test_field("related", "Related", FieldType::Relation, false).with_type_option_data(
+        FieldType::Relation,
+        RelationTypeOption::new(&database_id.to_string()).into(),
+      ),
+    )
+    .with_field(rollup_field(
+      "total",
+      "amount",
+      RollupDisplayMode::Calculated,
+      "",
+    ))
+    .with_field(rollup_field(
+      "names",
+      "name",
+      RollupDisplayMode::OriginalList,
+      "",
+    ))
+    .with_fields(fields)
+    .with_rows(row_ids.iter().copied().zip(rows))
+    .build()
+    .await;
+  (database_test, row_ids)
+}
@@ -0,0 +1,137 @@
+use collab::database::entity::FieldType;
+use collab::database::rows::{Cell, Cells, CreateRowParams, RowChange, RowPatch};
+use collab::database::template::entity::CELL_DATA;
+use collab::database::views::OrderObjectPosition;
+use collab::entity::uuid_validation::RowId;
+use collab::util::AnyMapExt;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+  wait_for_specific_event,
+};
+
+#[tokio::test]
+async fn create_rows_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let database_id = database_test.get_database_id().unwrap();
+  let new_row_ids = vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+  let params = vec![
+    CreateRowParams::new(new_row_ids[0], database_id).with_cells(Cells::from([(
+      "name".into(),
+      text_cell(FieldType::RichText, "d"),
+    )])),
+    CreateRowParams::new(new_row_ids[1], database_id).with_row_position(OrderObjectPosition::Start),
+    CreateRowParams::new(new_row_ids[2], database_id)
+      .with_row_position(OrderObjectPosition::After(row_ids[0].to_string())),
+  ];
+  let report = database_test.create_rows(params, 2).await;
+  assert!(report.is_success());
+  assert_eq!(report.row_ids, new_row_ids);
+
+  let row_orders = database_test.get_row_orders_for_view(TEST_VIEW_ID_V1);
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![
+      new_row_ids[1],
+      row_ids[0],
+      new_row_ids[2],
+      row_ids[1],
+      row_ids[2],
+      new_row_ids[0]
+    ]
+  );
+  let row = database_test.get_row(&new_row_ids[0]).await.unwrap();
+  assert_eq!(cell_text(row.cells.get("name")), Some("d".to_string()));
+}
+
+#[tokio::test]
+async fn update_rows_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let row_change_rx = database_test.subscribe_row_change().unwrap();
+  let cell_change_rx = database_test.subscribe_row_change().unwrap();
+  let missing_row_id = Uuid::new_v4();
+  let patches = vec![
+    RowPatch::new(row_ids[0])
+      .with_replaced_cell("name", text_cell(FieldType::RichText, "a2"))
+      .with_removed_cell("note"),
+    RowPatch::new(missing_row_id).with_cell("name", text_cell(FieldType::RichText, "x")),
+    RowPatch::new(row_ids[2]).with_cell("note", text_cell(FieldType::RichText, "c")),
+    // The patches of a row are merged into the first patch of the row
+    RowPatch::new(row_ids[0]).with_cell("note", text_cell(FieldType::RichText, "a")),
+  ];
+  let report = database_test.update_rows(patches, 2).await;
+  assert_eq!(report.row_ids, vec![row_ids[0], row_ids[2]]);
+  assert_eq!(report.failures.len(), 1);
+  assert_eq!(report.failures[0].row_id, missing_row_id);
+
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert_eq!(cell_text(row.cells.get("name")), Some("a2".to_string()));
+  assert_eq!(cell_text(row.cells.get("note")), Some("a".to_string()));
+  let row = database_test.get_row(&row_ids[1]).await.unwrap();
+  assert_eq!(cell_text(row.cells.get("name")), Some("b".to_string()));
+  let row = database_test.get_row(&row_ids[2]).await.unwrap();
+  assert_eq!(cell_text(row.cells.get("note")), Some("c".to_string()));
+
+  let expected_row_ids = report.row_ids.clone();
+  wait_for_specific_event(row_change_rx, |change| match change {
+    RowChange::DidUpdateRows {
+      row_ids,
+      is_local_change,
+    } => row_ids == &expected_row_ids && *is_local_change,
+    _ => false,
+  })
+  .await
+  .unwrap();
+  // The changes of the cells are marked as part of the batch
+  let batch_row_id = row_ids[2];
+  wait_for_specific_event(cell_change_rx, |change| match change {
+    RowChange::DidUpdateCell {
+      row_id,
+      field_id,
+      in_batch,
+      ..
+    } => row_id == &batch_row_id && field_id == "note" && *in_batch,
+    _ => false,
+  })
+  .await
+  .unwrap();
+}
+
+#[tokio::test]
+async fn remove_rows_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  let missing_row_id = Uuid::new_v4();
+  let report = database_test
+    .remove_rows(&[row_ids[2], missing_row_id, row_ids[0]])
+    .await;
+  assert_eq!(report.row_ids, vec![row_ids[2], row_ids[0]]);
+  assert_eq!(report.failures.len(), 1);
+  assert_eq!(report.failures[0].row_id, missing_row_id);
+
+  let row_orders = database_test.get_row_orders_for_view(TEST_VIEW_ID_V1);
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1]]
+  );
+}
+
+fn cell_text(cell: Option<&Cell>) -> Option<String> {
+  cell?.get_as::<String>(CELL_DATA)
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "a")),
+      ("note".into(), text_cell(FieldType::RichText, "first")),
+    ]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, "b"))]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, "c"))]),
+  ];
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("note", "Note", FieldType::RichText, false),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
@@ -0,0 +1,166 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::select_type_option::{
+  SelectOption, SelectOptionIds, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab::database::filter::{FieldFilter, Filter, TextFilter, TextFilterCondition};
+use collab::database::rows::{Cell, Cells};
+use collab::database::sort::{Sort, SortCondition};
+use collab::entity::uuid_validation::RowId;
+
+use crate::database_test::helper::{
+  DatabaseTest, TEST_VIEW_ID_V1, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+#[tokio::test]
+async fn sort_rows_by_multiple_sorts_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s1".to_string(),
+      "amount".to_string(),
+      FieldType::Number,
+      SortCondition::Descending,
+    ),
+  );
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s2".to_string(),
+      "name".to_string(),
+      FieldType::RichText,
+      SortCondition::Ascending,
+    ),
+  );
+
+  let row_orders = database_test
+    .get_sorted_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  // cherry(10), Apple(3), banana(3), then the row without amount
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[2], row_ids[1], row_ids[0], row_ids[3]]
+  );
+}
+
+#[tokio::test]
+async fn sort_rows_by_select_option_order_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s1".to_string(),
+      "status".to_string(),
+      FieldType::SingleSelect,
+      SortCondition::Ascending,
+    ),
+  );
+
+  // Options are ordered "todo", "doing", "done". Rows without status keep their manual order
+  // at the end.
+  let row_orders = database_test
+    .get_sorted_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[1], row_ids[0], row_ids[2], row_ids[3]]
+  );
+
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s1".to_string(),
+      "status".to_string(),
+      FieldType::SingleSelect,
+      SortCondition::Descending,
+    ),
+  );
+  let row_orders = database_test
+    .get_sorted_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[0], row_ids[1], row_ids[2], row_ids[3]]
+  );
+}
+
+#[tokio::test]
+async fn sort_filtered_rows_test() {
+  let (mut database_test, row_ids) = create_database_with_rows().await;
+  database_test.insert_filter(
+    TEST_VIEW_ID_V1,
+    Filter::new_data(
+      "f1".to_string(),
+      "name".to_string(),
+      FieldType::RichText,
+      FieldFilter::Text(TextFilter {
+        condition: TextFilterCondition::IsNotEmpty,
+        content: "".to_string(),
+      }),
+    ),
+  );
+  database_test.insert_sort(
+    TEST_VIEW_ID_V1,
+    Sort::new(
+      "s1".to_string(),
+      "name".to_string(),
+      FieldType::RichText,
+      SortCondition::Descending,
+    ),
+  );
+
+  let row_orders = database_test
+    .get_sorted_row_orders_for_view(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    row_orders.iter().map(|order| order.id).collect::<Vec<_>>(),
+    vec![row_ids[2], row_ids[0], row_ids[1]]
+  );
+
+  let sorts = database_test.get_all_sorts::<Sort>(TEST_VIEW_ID_V1);
+  assert_eq!(sorts[0].condition, SortCondition::Descending);
+}
+
+fn status_cell(option_id: &str) -> Cell {
+  SelectOptionIds::from(vec![option_id.to_string()]).to_cell(FieldType::SingleSelect)
+}
+
+async fn create_database_with_rows() -> (DatabaseTest, Vec<RowId>) {
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "banana")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      ("status".into(), status_cell("doing")),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "Apple")),
+      ("amount".into(), text_cell(FieldType::Number, "3")),
+      ("status".into(), status_cell("todo")),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "cherry")),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+    ]),
+    Cells::from([("name".into(), text_cell(FieldType::RichText, ""))]),
+  ];
+
+  let mut select_type_option = SelectTypeOption::default();
+  for option_id in ["todo", "doing", "done"] {
+    let mut option = SelectOption::new(option_id);
+    option.id = option_id.to_string();
+    select_type_option.options.push(option);
+  }
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true),
+    test_field("amount", "Amount", FieldType::Number, false),
+    test_field("status", "Status", FieldType::SingleSelect, false).with_type_option_data(
+      FieldType::SingleSelect,
+      SingleSelectTypeOption(select_type_option).into(),
+    ),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
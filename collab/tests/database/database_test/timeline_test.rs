@@ -0,0 +1,253 @@
+use collab::database::entity::{CreateViewParams, FieldType};
+use collab::database::fields::Field;
+use collab::database::fields::date_type_option::DateCellData;
+use collab::database::fields::relation_type_option::RelationTypeOption;
+use collab::database::rows::{Cell, Cells, CreateRowParams};
+use collab::database::template::relation_parse::RelationCellData;
+use collab::database::timeline::TimelineBar;
+use collab::database::views::{DatabaseLayout, TimelineLayoutSetting, TimelineZoom};
+use collab::entity::uuid_validation::RowId;
+use collab::error::CollabError;
+use uuid::Uuid;
+
+use crate::database_test::helper::{
+  DatabaseTest, DatabaseTestBuilder, TEST_VIEW_ID_V1, TEST_VIEW_ID_V2,
+};
+
+// 2024-03-04 00:00 UTC
+const MARCH_4: i64 = 1709510400;
+const DAY: i64 = 86400;
+
+#[tokio::test]
+async fn timeline_bars_test() {
+  let (database_test, row_ids) = create_timeline_database().await;
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V1).await;
+  assert_eq!(
+    bar_summary(&bars),
+    vec![
+      (row_ids[0], 0, 2),
+      (row_ids[1], 2, 5),
+      (row_ids[2], 10, 11),
+      (row_ids[3], 1, 1),
+    ]
+  );
+  assert_eq!(bars[1].predecessors, vec![row_ids[0]]);
+  assert_eq!(bars[2].predecessors, vec![row_ids[1]]);
+  assert!(bars[3].is_milestone());
+  assert!(!bars[0].is_milestone());
+}
+
+#[tokio::test]
+async fn move_timeline_row_shifts_dependents_test() {
+  let (mut database_test, row_ids) = create_timeline_database().await;
+  let updated_row_ids = database_test
+    .move_timeline_row(
+      TEST_VIEW_ID_V1,
+      row_ids[0],
+      MARCH_4 + 3 * DAY,
+      MARCH_4 + 6 * DAY,
+      true,
+      false,
+    )
+    .await
+    .unwrap();
+  // The second row is pushed after the first one, the third one already starts after it
+  assert_eq!(updated_row_ids, vec![row_ids[0], row_ids[1]]);
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V1).await;
+  assert_eq!(
+    bar_summary(&bars)[..3],
+    [(row_ids[0], 3, 6), (row_ids[1], 6, 9), (row_ids[2], 10, 11)]
+  );
+
+  let updated_row_ids = database_test
+    .move_timeline_row(
+      TEST_VIEW_ID_V1,
+      row_ids[0],
+      MARCH_4 + 8 * DAY,
+      MARCH_4 + 9 * DAY,
+      false,
+      false,
+    )
+    .await
+    .unwrap();
+  assert_eq!(updated_row_ids, vec![row_ids[0]]);
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V1).await;
+  assert_eq!(
+    bar_summary(&bars)[..2],
+    [(row_ids[0], 8, 9), (row_ids[1], 6, 9)]
+  );
+}
+
+#[tokio::test]
+async fn timeline_dependency_cycle_test() {
+  let (mut database_test, row_ids) = create_timeline_database().await;
+  database_test
+    .validate_timeline_dependencies(TEST_VIEW_ID_V1, false)
+    .await
+    .unwrap();
+
+  // The first row now depends on the third one, which depends on the second one
+  database_test
+    .update_row(row_ids[0], |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("deps", relation_cell(vec![row_ids[2]]));
+      });
+    })
+    .await;
+  let result = database_test
+    .validate_timeline_dependencies(TEST_VIEW_ID_V1, false)
+    .await;
+  assert!(matches!(
+    result,
+    Err(CollabError::DatabaseDependencyCycle(cycle))
+      if cycle == vec![row_ids[0], row_ids[2], row_ids[1]]
+  ));
+
+  let result = database_test
+    .move_timeline_row(TEST_VIEW_ID_V1, row_ids[1], MARCH_4, MARCH_4, true, false)
+    .await;
+  assert!(matches!(
+    result,
+    Err(CollabError::DatabaseDependencyCycle(_))
+  ));
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V1).await;
+  assert_eq!(bar_summary(&bars)[1], (row_ids[1], 2, 5));
+}
+
+#[tokio::test]
+async fn create_linked_timeline_view_test() {
+  let (mut database_test, row_ids) = create_timeline_database().await;
+  let database_id = database_test.get_database_id().unwrap();
+  let mut setting = TimelineLayoutSetting::new("start".to_string());
+  setting.zoom = TimelineZoom::Month;
+  let params = CreateViewParams::new(
+    database_id,
+    Uuid::parse_str(TEST_VIEW_ID_V2).unwrap(),
+    "Timeline".to_string(),
+    DatabaseLayout::Timeline,
+  )
+  .with_layout_setting(setting.into());
+  database_test.create_linked_view(params).unwrap();
+
+  let view = database_test.get_view(TEST_VIEW_ID_V2).unwrap();
+  assert_eq!(view.layout, DatabaseLayout::Timeline);
+  let setting = database_test
+    .get_layout_setting::<TimelineLayoutSetting>(TEST_VIEW_ID_V2, &DatabaseLayout::Timeline)
+    .unwrap();
+  assert_eq!(setting.start_field_id, "start");
+  assert!(setting.end_field_id.is_empty());
+  assert_eq!(setting.zoom, TimelineZoom::Month);
+  assert_eq!(setting.dependency_field_id, None);
+
+  // Without an end field, the bars are milestones until the start cells become ranges
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V2).await;
+  assert!(bars.iter().all(|bar| bar.is_milestone()));
+  database_test
+    .move_timeline_row(
+      TEST_VIEW_ID_V2,
+      row_ids[3],
+      MARCH_4 + DAY,
+      MARCH_4 + 4 * DAY,
+      true,
+      false,
+    )
+    .await
+    .unwrap();
+  let row = database_test.get_row(&row_ids[3]).await.unwrap();
+  let cell_data = DateCellData::from(row.cells.get("start").unwrap());
+  assert!(cell_data.is_range);
+  assert_eq!(cell_data.end_timestamp, Some(MARCH_4 + 4 * DAY));
+  let bars = get_bars(&database_test, TEST_VIEW_ID_V2).await;
+  assert_eq!(bar_summary(&bars)[3], (row_ids[3], 1, 4));
+}
+
+async fn get_bars(database_test: &DatabaseTest, view_id: &str) -> Vec<TimelineBar> {
+  database_test
+    .get_timeline_bars_for_view(view_id, false)
+    .await
+    .unwrap()
+}
+
+/// The row, start and end of the bars, in days from 2024-03-04.
+fn bar_summary(bars: &[TimelineBar]) -> Vec<(RowId, i64, i64)> {
+  bars
+    .iter()
+    .map(|bar| {
+      (
+        bar.row_id,
+        (bar.start - MARCH_4) / DAY,
+        (bar.end - MARCH_4) / DAY,
+      )
+    })
+    .collect()
+}
+
+fn relation_cell(row_ids: Vec<RowId>) -> Cell {
+  Cell::from(RelationCellData { row_ids })
+}
+
+async fn create_timeline_database() -> (DatabaseTest, Vec<RowId>) {
+  let database_id = Uuid::new_v4();
+  let row_ids = (0..4).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+  // The start and end days of the rows, and the row they depend on
+  let rows = [
+    (0, Some(2), None),
+    (2, Some(5), Some(row_ids[0])),
+    (10, Some(11), Some(row_ids[1])),
+    (1, None, None),
+  ];
+
+  let mut builder = DatabaseTestBuilder::new(1, &database_id.to_string())
+    .with_field(Field::new(
+      "name".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    ))
+    .with_field(Field::new(
+      "start".to_string(),
+      "Start".to_string(),
+      FieldType::DateTime.into(),
+      false,
+    ))
+    .with_field(Field::new(
+      "end".to_string(),
+      "End".to_string(),
+      FieldType::DateTime.into(),
+      false,
+    ))
+    .with_field(
+      Field::new(
+        "deps".to_string(),
+        "Blocked by".to_string(),
+        FieldType::Relation.into(),
+        false,
+      )
+      .with_type_option_data(
+        FieldType::Relation,
+        RelationTypeOption::new(&database_id.to_string()).into(),
+      ),
+    );
+  for (row_id, (start, end, predecessor)) in row_ids.iter().zip(rows) {
+    let mut cells = Cells::from([(
+      "start".into(),
+      Cell::from(&DateCellData::from_timestamp(MARCH_4 + start * DAY)),
+    )]);
+    if let Some(end) = end {
+      cells.insert(
+        "end".into(),
+        Cell::from(&DateCellData::from_timestamp(MARCH_4 + end * DAY)),
+      );
+    }
+    if let Some(predecessor) = predecessor {
+      cells.insert("deps".into(), relation_cell(vec![predecessor]));
+    }
+    builder = builder.with_row(CreateRowParams::new(*row_id, database_id).with_cells(cells));
+  }
+  let mut database_test = builder.build().await;
+  let mut setting = TimelineLayoutSetting::new("start".to_string());
+  setting.end_field_id = "end".to_string();
+  setting.dependency_field_id = Some("deps".to_string());
+  database_test.insert_layout_setting(TEST_VIEW_ID_V1, &DatabaseLayout::Timeline, setting);
+  (database_test, row_ids)
+}
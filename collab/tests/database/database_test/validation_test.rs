@@ -0,0 +1,340 @@
+use collab::database::entity::FieldType;
+use collab::database::fields::FieldConstraints;
+use collab::database::fields::date_type_option::DateCellData;
+use collab::database::fields::select_type_option::SelectOptionIds;
+use collab::database::rows::{Cell, Cells, RowPatch};
+use collab::database::template::csv_import::{CSVImportOptions, CSVImportViolation};
+use collab::database::template::entity::CELL_DATA;
+use collab::database::validation::{ConstraintViolation, ViolationKind};
+use collab::entity::uuid_validation::RowId;
+use collab::error::CollabError;
+use collab::util::AnyMapExt;
+
+use crate::database_test::helper::{
+  DatabaseTest, create_database_with_fields_and_rows, test_field, text_cell,
+};
+
+// 2024-01-01 and 2025-01-01 00:00 UTC
+const START_OF_2024: i64 = 1704067200;
+const START_OF_2025: i64 = 1735689600;
+
+#[tokio::test]
+async fn validate_database_test() {
+  let (database_test, row_ids) = create_database_with_constraints().await;
+  let violations = database_test.validate_database(false).await.unwrap();
+  // The fields, so the violations of a row, aren't in the order they were created in
+  let expected = vec![
+    (
+      row_ids[1],
+      "email",
+      ViolationKind::Duplicate {
+        row_ids: vec![row_ids[2]],
+      },
+    ),
+    (
+      row_ids[1],
+      "amount",
+      ViolationKind::AboveMax {
+        max: 100.0,
+        value: 120.0,
+      },
+    ),
+    (row_ids[2], "name", ViolationKind::Required),
+    (
+      row_ids[2],
+      "email",
+      ViolationKind::Duplicate {
+        row_ids: vec![row_ids[1]],
+      },
+    ),
+    (
+      row_ids[2],
+      "due",
+      ViolationKind::DateAfterMax {
+        max_date: START_OF_2025,
+        timestamp: START_OF_2025 + 86400,
+      },
+    ),
+    (
+      row_ids[2],
+      "tags",
+      ViolationKind::TooManyOptions {
+        max_selected_options: 2,
+        count: 3,
+      },
+    ),
+  ];
+  let summary = violation_summary(&violations);
+  assert_eq!(summary.len(), expected.len());
+  assert!(expected.iter().all(|violation| summary.contains(violation)));
+
+  let violations = database_test
+    .validate_row(&row_ids[0], false)
+    .await
+    .unwrap();
+  assert!(violations.is_empty());
+  let violations = database_test
+    .validate_row(&row_ids[1], false)
+    .await
+    .unwrap();
+  assert_eq!(violations.len(), 2);
+}
+
+#[tokio::test]
+async fn update_row_strict_test() {
+  let (mut database_test, row_ids) = create_database_with_constraints().await;
+  let result = database_test
+    .update_row_strict(
+      row_ids[0],
+      Cells::from([
+        (
+          "email".into(),
+          text_cell(FieldType::RichText, "not an email"),
+        ),
+        ("amount".into(), text_cell(FieldType::Number, "50")),
+      ]),
+      false,
+    )
+    .await;
+  let Err(CollabError::DatabaseConstraintViolation(violations)) = result else {
+    panic!("expected a constraint violation");
+  };
+  assert_eq!(
+    violation_summary(&violations),
+    vec![(
+      row_ids[0],
+      "email",
+      ViolationKind::PatternMismatch {
+        pattern: EMAIL_PATTERN.to_string()
+      }
+    )]
+  );
+  // Nothing was written
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "amount"), "10");
+
+  // The second row's other violations don't prevent editing its name
+  database_test
+    .update_row_strict(
+      row_ids[1],
+      Cells::from([("name".into(), text_cell(FieldType::RichText, "Renamed"))]),
+      false,
+    )
+    .await
+    .unwrap();
+  let row = database_test.get_row(&row_ids[1]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "name"), "Renamed");
+
+  // Taking the value of another row breaks the unique constraint
+  let result = database_test
+    .update_row_strict(
+      row_ids[0],
+      Cells::from([(
+        "email".into(),
+        text_cell(FieldType::RichText, "b@example.com"),
+      )]),
+      false,
+    )
+    .await;
+  assert!(matches!(
+    result,
+    Err(CollabError::DatabaseConstraintViolation(_))
+  ));
+}
+
+#[tokio::test]
+async fn update_rows_strict_test() {
+  let (mut database_test, row_ids) = create_database_with_constraints().await;
+  let patches = vec![
+    RowPatch::new(row_ids[0])
+      .with_cell("amount", text_cell(FieldType::Number, "50"))
+      .with_cell("email", text_cell(FieldType::RichText, "c@example.com")),
+    // The email was taken by the first row earlier in the batch
+    RowPatch::new(row_ids[1]).with_cell("email", text_cell(FieldType::RichText, "c@example.com")),
+    // The third row's other violations don't prevent setting its name
+    RowPatch::new(row_ids[2]).with_cell("name", text_cell(FieldType::RichText, "Third")),
+  ];
+  let report = database_test
+    .update_rows_strict(patches, 2, false)
+    .await
+    .unwrap();
+  assert_eq!(report.row_ids, vec![row_ids[0], row_ids[2]]);
+  assert_eq!(report.failures.len(), 1);
+  assert_eq!(report.failures[0].row_id, row_ids[1]);
+
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "amount"), "50");
+  assert_eq!(cell_text(&row.cells, "email"), "c@example.com");
+  let row = database_test.get_row(&row_ids[1]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "email"), "b@example.com");
+  let row = database_test.get_row(&row_ids[2]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "name"), "Third");
+}
+
+#[tokio::test]
+async fn strict_csv_import_test() {
+  let (mut database_test, row_ids) = create_database_with_constraints().await;
+  let csv = "Name,Email,Amount\n\
+    First,a@example.com,500\n\
+    Fourth,d@example.com,5\n\
+    Fifth,a@example.com,5\n\
+    ,e@example.com,5\n";
+  let options = CSVImportOptions::new().with_key_field("name").with_strict();
+  let plan = database_test
+    .plan_csv_import(csv.as_bytes(), &options, false)
+    .await
+    .unwrap();
+  assert_eq!(
+    plan.report.violations,
+    vec![
+      CSVImportViolation {
+        line: 2,
+        field_id: "amount".to_string(),
+        kind: ViolationKind::AboveMax {
+          max: 100.0,
+          value: 500.0
+        },
+      },
+      CSVImportViolation {
+        line: 4,
+        field_id: "email".to_string(),
+        kind: ViolationKind::Duplicate {
+          row_ids: vec![row_ids[0]]
+        },
+      },
+      CSVImportViolation {
+        line: 5,
+        field_id: "name".to_string(),
+        kind: ViolationKind::Required,
+      },
+    ]
+  );
+  assert_eq!(plan.report.inserts, vec![3]);
+  assert!(plan.report.updates.is_empty());
+
+  let report = database_test.apply_csv_import(plan).await.unwrap();
+  assert_eq!(report.inserted_row_ids.len(), 1);
+  let row = database_test.get_row(&row_ids[0]).await.unwrap();
+  assert_eq!(cell_text(&row.cells, "amount"), "10");
+  let row = database_test
+    .get_row(&report.inserted_row_ids[0])
+    .await
+    .unwrap();
+  assert_eq!(cell_text(&row.cells, "name"), "Fourth");
+}
+
+#[tokio::test]
+async fn update_field_constraints_test() {
+  let (mut database_test, row_ids) = create_database_with_constraints().await;
+  let field = database_test.get_field("amount").unwrap();
+  assert_eq!(
+    field.constraints,
+    FieldConstraints::new().with_number_range(Some(0.0), Some(100.0))
+  );
+
+  database_test.update_field("amount", |update| {
+    update.set_constraints(FieldConstraints::new().with_number_range(None, Some(200.0)));
+  });
+  let field = database_test.get_field("amount").unwrap();
+  assert_eq!(field.constraints.min, None);
+  assert_eq!(field.constraints.max, Some(200.0));
+  let violations = database_test
+    .validate_row(&row_ids[1], false)
+    .await
+    .unwrap();
+  assert_eq!(violations.len(), 1);
+
+  database_test.update_field("amount", |update| {
+    update.set_constraints(FieldConstraints::new());
+  });
+  assert!(
+    database_test
+      .get_field("amount")
+      .unwrap()
+      .constraints
+      .is_empty()
+  );
+}
+
+const EMAIL_PATTERN: &str = "[^@\\s]+@[^@\\s]+";
+
+fn violation_summary(violations: &[ConstraintViolation]) -> Vec<(RowId, &str, ViolationKind)> {
+  violations
+    .iter()
+    .map(|violation| {
+      (
+        violation.row_id,
+        violation.field_id.as_str(),
+        violation.kind.clone(),
+      )
+    })
+    .collect()
+}
+
+fn cell_text(cells: &Cells, field_id: &str) -> String {
+  cells
+    .get(field_id)
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .unwrap_or_default()
+}
+
+fn tags_cell(tags: &[&str]) -> Cell {
+  SelectOptionIds::from(tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>())
+    .to_cell(FieldType::MultiSelect)
+}
+
+async fn create_database_with_constraints() -> (DatabaseTest, Vec<RowId>) {
+  let rows = vec![
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "First")),
+      (
+        "email".into(),
+        text_cell(FieldType::RichText, "a@example.com"),
+      ),
+      ("amount".into(), text_cell(FieldType::Number, "10")),
+      (
+        "due".into(),
+        Cell::from(&DateCellData::from_timestamp(START_OF_2024)),
+      ),
+      ("tags".into(), tags_cell(&["a", "b"])),
+    ]),
+    Cells::from([
+      ("name".into(), text_cell(FieldType::RichText, "Second")),
+      (
+        "email".into(),
+        text_cell(FieldType::RichText, "b@example.com"),
+      ),
+      ("amount".into(), text_cell(FieldType::Number, "120")),
+    ]),
+    Cells::from([
+      (
+        "email".into(),
+        text_cell(FieldType::RichText, "b@example.com"),
+      ),
+      ("amount".into(), text_cell(FieldType::Number, "0")),
+      (
+        "due".into(),
+        Cell::from(&DateCellData::from_timestamp(START_OF_2025 + 86400)),
+      ),
+      ("tags".into(), tags_cell(&["a", "b", "c"])),
+    ]),
+  ];
+
+  let fields = vec![
+    test_field("name", "Name", FieldType::RichText, true)
+      .with_constraints(FieldConstraints::new().with_required()),
+    test_field("email", "Email", FieldType::RichText, false).with_constraints(
+      FieldConstraints::new()
+        .with_unique()
+        .with_pattern(EMAIL_PATTERN),
+    ),
+    test_field("amount", "Amount", FieldType::Number, false)
+      .with_constraints(FieldConstraints::new().with_number_range(Some(0.0), Some(100.0))),
+    test_field("due", "Due", FieldType::DateTime, false).with_constraints(
+      FieldConstraints::new().with_date_range(Some(START_OF_2024), Some(START_OF_2025)),
+    ),
+    test_field("tags", "Tags", FieldType::MultiSelect, false)
+      .with_constraints(FieldConstraints::new().with_max_selected_options(2)),
+  ];
+  create_database_with_fields_and_rows(fields, rows).await
+}
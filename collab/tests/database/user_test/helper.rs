@@ -9,7 +9,9 @@ use collab::database::database::{gen_database_id, gen_field_id};
 use collab::database::fields::Field;
 use collab::database::rows::{CreateRowParams, DatabaseRow};
 use collab::database::views::DatabaseLayout;
-use collab::database::workspace_database::{RowRelationChange, RowRelationUpdateReceiver};
+use collab::database::workspace_database::{
+  DatabaseRelation, RowRelationChange, RowRelationUpdateReceiver,
+};
 use collab::entity::CollabType;
 use collab::entity::uuid_validation::{ObjectId, RowId};
 use collab::error::CollabError;
@@ -46,6 +48,7 @@ pub struct TestUserDatabaseServiceImpl {
   pub db: Arc<CollabKVDB>,
   pub client_id: ClientID,
   pub cache: Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>,
+  pub database_relation: Option<Arc<DatabaseRelation>>,
 }
 
 impl TestUserDatabaseServiceImpl {
@@ -56,8 +59,14 @@ impl TestUserDatabaseServiceImpl {
       db,
       client_id,
       cache: Arc::new(Default::default()),
+      database_relation: None,
     }
   }
+
+  pub fn with_database_relation(mut self, database_relation: Arc<DatabaseRelation>) -> Self {
+    self.database_relation = Some(database_relation);
+    self
+  }
 }
 
 pub struct TestUserDatabasePersistenceImpl {
@@ -181,6 +190,10 @@ impl DatabaseCollabReader for TestUserDatabaseServiceImpl {
   fn reader_row_cache(&self) -> Option<Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>> {
     Some(self.cache.clone())
   }
+
+  fn reader_database_relation(&self) -> Option<Arc<DatabaseRelation>> {
+    self.database_relation.clone()
+  }
 }
 
 pub fn poll_row_relation_rx(mut rx: RowRelationUpdateReceiver) -> Receiver<RowRelationChange> {
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::core::collab::{CollabOptions, default_client_id};
+use collab::core::origin::CollabOrigin;
+use collab::database::database::{Database, DatabaseContext, DatabaseData, gen_row_id};
+use collab::database::database_trait::NoPersistenceDatabaseCollabService;
+use collab::database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab::database::fields::Field;
+use collab::database::rows::{CreateRowParams, new_cell_builder};
+use collab::database::template::entity::CELL_DATA;
+use collab::database::views::DatabaseLayout;
+use collab::document::blocks::Block;
+use collab::document::document::Document;
+use collab::document::document_data::default_document_data;
+use collab::entity::EncodedCollab;
+use collab::entity::uuid_validation::DatabaseId;
+use collab::error::CollabError;
+use collab::exporter::{ExportCollabProvider, FolderExporter};
+use collab::folder::{Folder, FolderData, UserId, View, ViewId, ViewLayout, Workspace};
+use collab::preclude::Collab;
+use serde_json::json;
+use uuid::Uuid;
+
+struct TestExportProvider {
+  documents: HashMap<ViewId, EncodedCollab>,
+  database_ids: HashMap<ViewId, DatabaseId>,
+  databases: HashMap<DatabaseId, DatabaseData>,
+  attachments: HashMap<String, Vec<u8>>,
+}
+
+#[async_trait]
+impl ExportCollabProvider for TestExportProvider {
+  async fn get_document_collab(
+    &self,
+    view_id: &ViewId,
+  ) -> Result<Option<EncodedCollab>, CollabError> {
+    Ok(self.documents.get(view_id).cloned())
+  }
+
+  async fn get_database_id(&self, view_id: &ViewId) -> Result<Option<DatabaseId>, CollabError> {
+    Ok(self.database_ids.get(view_id).copied())
+  }
+
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError> {
+    Ok(self.databases.get(database_id).cloned())
+  }
+
+  async fn get_attachment(&self, url: &str) -> Result<Option<Vec<u8>>, CollabError> {
+    Ok(self.attachments.get(url).cloned())
+  }
+}
+
+fn test_view(id: ViewId, parent_id: ViewId, name: &str, layout: ViewLayout) -> View {
+  View::new(id, parent_id, name.to_string(), layout, Some(1))
+}
+
+fn insert_block(document: &mut Document, ty: &str, data: HashMap<String, serde_json::Value>) {
+  let page_id = document.get_page_id().unwrap();
+  let block = Block {
+    id: Uuid::new_v4().to_string(),
+    ty: ty.to_string(),
+    parent: page_id,
+    children: "".to_string(),
+    external_id: None,
+    external_type: None,
+    data,
+  };
+  document.insert_block(block, None).unwrap();
+}
+
+fn insert_text_block(document: &mut Document, delta: serde_json::Value) {
+  let page_id = document.get_page_id().unwrap();
+  let text_id = Uuid::new_v4().to_string();
+  let block = Block {
+    id: Uuid::new_v4().to_string(),
+    ty: "paragraph".to_string(),
+    parent: page_id,
+    children: "".to_string(),
+    external_id: Some(text_id.clone()),
+    external_type: Some("text".to_string()),
+    data: Default::default(),
+  };
+  document.insert_block(block, None).unwrap();
+  document.apply_text_delta(&text_id, delta.to_string());
+}
+
+fn page_document(page_id: &ViewId, sub_page_id: &ViewId, other_page_id: &ViewId) -> EncodedCollab {
+  let data = default_document_data(&page_id.to_string());
+  let mut document = Document::create(&page_id.to_string(), data, default_client_id()).unwrap();
+  insert_block(
+    &mut document,
+    "image",
+    HashMap::from([("url".to_string(), json!("https://appflowy.io/cat.png"))]),
+  );
+  insert_block(
+    &mut document,
+    "sub_page",
+    HashMap::from([("viewId".to_string(), json!(sub_page_id.to_string()))]),
+  );
+  insert_text_block(
+    &mut document,
+    json!([
+      {"insert": "See "},
+      {"insert": "$", "attributes": {"mention": {"type": "page", "page_id": other_page_id.to_string()}}}
+    ]),
+  );
+  insert_text_block(
+    &mut document,
+    json!([
+      {"insert": "Go to "},
+      {"insert": "$", "attributes": {"mention": {"type": "page", "page_id": sub_page_id.to_string()}}}
+    ]),
+  );
+  // Text that looks like a rewritten link is left as is
+  insert_text_block(
+    &mut document,
+    json!([{"insert": "Source: [cat](https://appflowy.io/cat.png)"}]),
+  );
+  document.encode_collab().unwrap()
+}
+
+fn empty_document(document_id: &ViewId) -> EncodedCollab {
+  let data = default_document_data(&document_id.to_string());
+  Document::create(&document_id.to_string(), data, default_client_id())
+    .unwrap()
+    .encode_collab()
+    .unwrap()
+}
+
+async fn database_data(database_id: DatabaseId, view_id: ViewId) -> DatabaseData {
+  let name_field = Field::new(
+    "name".to_string(),
+    "Name".to_string(),
+    FieldType::RichText.into(),
+    true,
+  );
+  let note_field = Field::new(
+    "note".to_string(),
+    "Note".to_string(),
+    FieldType::RichText.into(),
+    false,
+  );
+  let rows = [("apple", "red, sweet"), ("banana", "yellow")]
+    .into_iter()
+    .map(|(name, note)| {
+      let mut name_cell = new_cell_builder(FieldType::RichText);
+      name_cell.insert(CELL_DATA.to_string(), name.into());
+      let mut note_cell = new_cell_builder(FieldType::RichText);
+      note_cell.insert(CELL_DATA.to_string(), note.into());
+      CreateRowParams::new(gen_row_id(), database_id).with_cells(HashMap::from([
+        ("name".to_string(), name_cell),
+        ("note".to_string(), note_cell),
+      ]))
+    })
+    .collect();
+  let params = CreateDatabaseParams {
+    database_id,
+    views: vec![CreateViewParams {
+      database_id,
+      view_id,
+      name: "grid".to_string(),
+      layout: DatabaseLayout::Grid,
+      ..Default::default()
+    }],
+    fields: vec![name_field, note_field],
+    rows,
+    ..Default::default()
+  };
+  let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new(default_client_id()));
+  let context = DatabaseContext::new(collab_service.clone(), collab_service);
+  let database = Database::create_with_view(params, context).await.unwrap();
+  database.get_database_data(20, false, true).await.unwrap()
+}
+
+struct ExportTest {
+  folder: Folder,
+  provider: TestExportProvider,
+  page_id: ViewId,
+}
+
+async fn create_export_test() -> ExportTest {
+  let uid = UserId::from(1);
+  let workspace_id = Uuid::new_v4();
+  let workspace = Workspace::new(workspace_id, "".to_string(), uid.as_i64());
+  let options = CollabOptions::new(workspace_id, default_client_id());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  let mut folder = Folder::create(collab, None, FolderData::new(uid.as_i64(), workspace));
+
+  // page
+  //   ├── sub page
+  //   ├── Fruits (grid)
+  //   └── sub page (duplicated name)
+  // other page
+  let page_id = Uuid::new_v4();
+  let sub_page_id = Uuid::new_v4();
+  let grid_id = Uuid::new_v4();
+  let duplicated_name_id = Uuid::new_v4();
+  let other_page_id = Uuid::new_v4();
+  for view in [
+    test_view(page_id, workspace_id, "page", ViewLayout::Document),
+    test_view(sub_page_id, page_id, "sub page", ViewLayout::Document),
+    test_view(grid_id, page_id, "Fruits", ViewLayout::Grid),
+    test_view(
+      duplicated_name_id,
+      page_id,
+      "sub page",
+      ViewLayout::Document,
+    ),
+    test_view(
+      other_page_id,
+      workspace_id,
+      "other page",
+      ViewLayout::Document,
+    ),
+  ] {
+    folder.insert_view(view, None, uid.as_i64());
+  }
+
+  let database_id = Uuid::new_v4();
+  let provider = TestExportProvider {
+    documents: HashMap::from([
+      (
+        page_id,
+        page_document(&page_id, &sub_page_id, &other_page_id),
+      ),
+      (sub_page_id, empty_document(&sub_page_id)),
+      (duplicated_name_id, empty_document(&duplicated_name_id)),
+    ]),
+    database_ids: HashMap::from([(grid_id, database_id)]),
+    databases: HashMap::from([(database_id, database_data(database_id, grid_id).await)]),
+    attachments: HashMap::from([("https://appflowy.io/cat.png".to_string(), b"cat".to_vec())]),
+  };
+  ExportTest {
+    folder,
+    provider,
+    page_id,
+  }
+}
+
+#[tokio::test]
+async fn export_view_hierarchy_to_dir_test() {
+  let test = create_export_test().await;
+  let output_dir = tempfile::tempdir().unwrap();
+  let exporter = FolderExporter::new(test.provider, default_client_id());
+  exporter
+    .export_to_dir(&test.folder, &test.page_id, output_dir.path(), None)
+    .await
+    .unwrap();
+
+  let root = output_dir.path();
+  assert!(root.join("page.md").exists());
+  assert!(root.join("page/sub page.md").exists());
+  assert!(root.join("page/sub page (1).md").exists());
+  assert!(!root.join("other page.md").exists());
+  assert_eq!(
+    std::fs::read(root.join("page/files/cat.png")).unwrap(),
+    b"cat".to_vec()
+  );
+
+  let markdown = std::fs::read_to_string(root.join("page.md")).unwrap();
+  assert!(markdown.contains("![Image](<page/files/cat.png>)"));
+  assert!(markdown.contains("[sub page](<page/sub page.md>)"));
+  assert!(markdown.contains("Go to [sub page](<page/sub page.md>)"));
+  // Views outside the exported subtree are kept as plain references.
+  assert!(markdown.contains("See [[other page]]"));
+  assert!(markdown.contains("Source: [cat](https://appflowy.io/cat.png)"));
+
+  let csv = std::fs::read_to_string(root.join("page/Fruits.csv")).unwrap();
+  let lines = csv.lines().collect::<Vec<_>>();
+  assert_eq!(lines[0], "Name,Note");
+  assert_eq!(lines[1], "apple,\"red, sweet\"");
+  assert_eq!(lines[2], "banana,yellow");
+}
+
+#[tokio::test]
+async fn export_view_hierarchy_to_zip_test() {
+  let test = create_export_test().await;
+  let output_dir = tempfile::tempdir().unwrap();
+  let zip_path = output_dir.path().join("export.zip");
+  let exporter = FolderExporter::new(test.provider, default_client_id());
+  let exported = exporter
+    .export_to_zip(&test.folder, &test.page_id, &zip_path, None)
+    .await
+    .unwrap();
+
+  assert_eq!(exported.view_paths.len(), 4);
+  assert!(zip_path.exists());
+
+  let unzip_dir = output_dir.path().join("unzip");
+  let file = std::fs::File::open(&zip_path).unwrap();
+  let mut archive = zip::ZipArchive::new(file).unwrap();
+  archive.extract(&unzip_dir).unwrap();
+  assert!(unzip_dir.join("page.md").exists());
+  assert!(unzip_dir.join("page/Fruits.csv").exists());
+  assert!(unzip_dir.join("page/files/cat.png").exists());
+}
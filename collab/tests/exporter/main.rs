@@ -0,0 +1,2 @@
+mod folder_exporter_test;
+mod workspace_exporter_test;
@@ -0,0 +1,241 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::core::collab::{CollabOptions, default_client_id};
+use collab::core::origin::CollabOrigin;
+use collab::database::database::{Database, DatabaseContext, DatabaseData, gen_row_id};
+use collab::database::database_trait::NoPersistenceDatabaseCollabService;
+use collab::database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab::database::rows::CreateRowParams;
+use collab::database::views::DatabaseLayout;
+use collab::database::workspace_database::WorkspaceDatabase;
+use collab::document::blocks::Block;
+use collab::document::document::Document;
+use collab::document::document_data::default_document_data;
+use collab::entity::uuid_validation::DatabaseId;
+use collab::entity::{CollabType, EncodedCollab};
+use collab::error::CollabError;
+use collab::exporter::{RELATION_MAP_FILE, WorkspaceCollabProvider, WorkspaceExporter};
+use collab::folder::hierarchy_builder::ViewExtraBuilder;
+use collab::folder::{Folder, FolderData, UserId, View, ViewId, ViewLayout, Workspace};
+use collab::importer::workspace::WorkspaceRemapper;
+use collab::importer::workspace::entities::DependencyType;
+use collab::preclude::Collab;
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Default)]
+struct TestWorkspaceProvider {
+  collabs: HashMap<Uuid, EncodedCollab>,
+  databases: HashMap<DatabaseId, DatabaseData>,
+}
+
+#[async_trait]
+impl WorkspaceCollabProvider for TestWorkspaceProvider {
+  async fn get_encoded_collab(
+    &self,
+    object_id: &Uuid,
+    _collab_type: CollabType,
+  ) -> Result<Option<EncodedCollab>, CollabError> {
+    Ok(self.collabs.get(object_id).cloned())
+  }
+
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError> {
+    Ok(self.databases.get(database_id).cloned())
+  }
+}
+
+fn test_view(id: ViewId, parent_id: ViewId, name: &str, layout: ViewLayout) -> View {
+  View::new(id, parent_id, name.to_string(), layout, Some(1))
+}
+
+fn document_with_mention(document_id: &ViewId, mentioned_page_id: &ViewId) -> EncodedCollab {
+  let data = default_document_data(&document_id.to_string());
+  let mut document = Document::create(&document_id.to_string(), data, default_client_id()).unwrap();
+  let page_id = document.get_page_id().unwrap();
+  let text_id = Uuid::new_v4().to_string();
+  let block = Block {
+    id: Uuid::new_v4().to_string(),
+    ty: "paragraph".to_string(),
+    parent: page_id,
+    children: "".to_string(),
+    external_id: Some(text_id.clone()),
+    external_type: Some("text".to_string()),
+    data: Default::default(),
+  };
+  document.insert_block(block, None).unwrap();
+  document.apply_text_delta(
+    &text_id,
+    json!([
+      {"insert": "$", "attributes": {"mention": {"type": "page", "page_id": mentioned_page_id.to_string()}}}
+    ])
+    .to_string(),
+  );
+  document.encode_collab().unwrap()
+}
+
+fn empty_document(document_id: &Uuid) -> EncodedCollab {
+  let data = default_document_data(&document_id.to_string());
+  Document::create(&document_id.to_string(), data, default_client_id())
+    .unwrap()
+    .encode_collab()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn export_workspace_and_import_with_remapper_test() {
+  let uid = UserId::from(1);
+  let workspace_id = Uuid::new_v4();
+  let workspace = Workspace::new(workspace_id, "".to_string(), uid.as_i64());
+  let options = CollabOptions::new(workspace_id, default_client_id());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  let mut folder = Folder::create(collab, None, FolderData::new(uid.as_i64(), workspace));
+
+  // General (space)
+  //   ├── page
+  //   │     ├── To-dos (grid)
+  //   │     └── Board (linked view of To-dos)
+  //   └── chat (not exported)
+  let space_id = Uuid::new_v4();
+  let page_id = Uuid::new_v4();
+  let grid_id = Uuid::new_v4();
+  let board_id = Uuid::new_v4();
+  let chat_id = Uuid::new_v4();
+  let mut space = test_view(space_id, workspace_id, "General", ViewLayout::Document);
+  space.extra = Some(ViewExtraBuilder::new().is_space(true).build().to_string());
+  for view in [
+    space,
+    test_view(page_id, space_id, "page", ViewLayout::Document),
+    test_view(grid_id, page_id, "To-dos", ViewLayout::Grid),
+    test_view(board_id, page_id, "Board", ViewLayout::Board),
+    test_view(chat_id, space_id, "chat", ViewLayout::Chat),
+  ] {
+    folder.insert_view(view, None, uid.as_i64());
+  }
+
+  let database_id = Uuid::new_v4();
+  let params = CreateDatabaseParams {
+    database_id,
+    views: vec![
+      CreateViewParams {
+        database_id,
+        view_id: grid_id,
+        name: "To-dos".to_string(),
+        layout: DatabaseLayout::Grid,
+        ..Default::default()
+      },
+      CreateViewParams {
+        database_id,
+        view_id: board_id,
+        name: "Board".to_string(),
+        layout: DatabaseLayout::Board,
+        ..Default::default()
+      },
+    ],
+    rows: vec![
+      CreateRowParams::new(gen_row_id(), database_id),
+      CreateRowParams::new(gen_row_id(), database_id),
+    ],
+    ..Default::default()
+  };
+  let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new(default_client_id()));
+  let context = DatabaseContext::new(collab_service.clone(), collab_service);
+  let database = Database::create_with_view(params, context).await.unwrap();
+  let database_data = database.get_database_data(20, false, true).await.unwrap();
+  let encoded_database = database.encode_database_collabs().await.unwrap();
+
+  let options = CollabOptions::new(Uuid::new_v4(), default_client_id());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  let mut workspace_database = WorkspaceDatabase::create(collab);
+  let _ = workspace_database.add_database(
+    &database_id.to_string(),
+    vec![grid_id.to_string(), board_id.to_string()],
+  );
+
+  let mut provider = TestWorkspaceProvider::default();
+  provider.collabs.insert(space_id, empty_document(&space_id));
+  provider
+    .collabs
+    .insert(page_id, document_with_mention(&page_id, &grid_id));
+  provider.collabs.insert(
+    database_id,
+    encoded_database.encoded_database_collab.encoded_collab,
+  );
+  for row in encoded_database.encoded_row_collabs {
+    provider.collabs.insert(row.object_id, row.encoded_collab);
+  }
+  provider.databases.insert(database_id, database_data);
+
+  let output_dir = tempfile::tempdir().unwrap();
+  let exporter = WorkspaceExporter::new(provider, default_client_id());
+  let exported = exporter
+    .export_to_dir(&folder, &workspace_database, output_dir.path(), None)
+    .await
+    .unwrap();
+
+  let relation_map = &exported.relation_map;
+  assert_eq!(relation_map.workspace_id, workspace_id);
+  assert_eq!(
+    relation_map.views.keys().copied().collect::<Vec<_>>(),
+    vec![space_id, page_id, grid_id, board_id]
+  );
+  assert_eq!(
+    relation_map.views.get(&space_id).unwrap().children,
+    vec![page_id]
+  );
+  assert_eq!(
+    relation_map.views.get(&grid_id).unwrap().collab_object_id,
+    database_id
+  );
+  assert_eq!(
+    relation_map
+      .collab_objects
+      .values()
+      .filter(|object| object.collab_type == CollabType::DatabaseRow)
+      .count(),
+    2
+  );
+  assert!(
+    relation_map
+      .collab_objects
+      .values()
+      .all(|object| object.size_bytes > 0)
+  );
+  assert!(relation_map.dependencies.iter().any(|dependency| {
+    dependency.dependency_type == DependencyType::DocumentReference
+      && dependency.source_view_id == page_id.to_string()
+      && dependency.target_view_id == grid_id.to_string()
+  }));
+  assert!(output_dir.path().join(RELATION_MAP_FILE).exists());
+
+  // The exported directory can be imported back.
+  let remapper = WorkspaceRemapper::new(output_dir.path(), None)
+    .await
+    .unwrap();
+  let collabs = remapper
+    .build_all_collabs(uid.as_i64(), "workspace", "workspace_database_storage_id")
+    .await
+    .unwrap();
+  let names = collabs
+    .folder
+    .get_all_views(Some(uid.as_i64()))
+    .into_iter()
+    .map(|view| view.name.clone())
+    .collect::<HashSet<_>>();
+  for name in ["General", "page", "To-dos", "Board"] {
+    assert!(names.contains(name));
+  }
+  assert!(!names.contains("chat"));
+  assert_eq!(collabs.documents.len(), 2);
+  assert_eq!(collabs.databases.len(), 1);
+  let imported_data = collabs.databases[0]
+    .get_database_data(20, false, true)
+    .await
+    .unwrap();
+  assert_ne!(imported_data.database_id, database_id);
+  assert_eq!(imported_data.rows.len(), 2);
+}
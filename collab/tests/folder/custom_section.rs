@@ -1,7 +1,9 @@
-use crate::util::create_folder_with_workspace;
+use crate::util::{create_folder_with_workspace, test_uuid};
 use assert_json_diff::assert_json_include;
 use collab::entity::uuid_validation::view_id_from_any_string;
-use collab::folder::{Section, SectionItem, UserId, timestamp};
+use collab::folder::{
+  CustomSectionChange, Folder, Section, SectionChange, SectionItem, UserId, timestamp,
+};
 use collab::preclude::Any;
 use serde_json::json;
 use std::collections::HashMap;
@@ -85,3 +87,117 @@ fn section_serde_test() {
     item, elapsed
   );
 }
+
+#[test]
+fn create_and_rename_custom_section_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), view_id_from_any_string("w1"));
+
+  let section = folder_test
+    .create_custom_section("pinned", "Pinned", Some("📌".to_string()), uid.as_i64())
+    .unwrap();
+  assert_eq!(section, Section::Custom("pinned".to_string()));
+  assert!(
+    folder_test
+      .create_custom_section("trash", "Trash", None, uid.as_i64())
+      .is_err()
+  );
+
+  folder_test
+    .rename_custom_section("pinned", "Pinned pages")
+    .unwrap();
+  assert!(
+    folder_test
+      .rename_custom_section("unknown", "name")
+      .is_err()
+  );
+
+  let sections = folder_test.get_my_custom_sections(uid.as_i64());
+  assert_eq!(sections.len(), 1);
+  let meta = sections[0].meta.clone().unwrap();
+  assert_eq!(meta.name, "Pinned pages");
+  assert_eq!(meta.icon, Some("📌".to_string()));
+  assert_eq!(meta.created_by, uid.as_i64());
+
+  // Other users only see the section once they have items in it.
+  assert!(folder_test.get_my_custom_sections(2).is_empty());
+  folder_test.add_custom_section_items("pinned", vec![test_uuid("1")], 2);
+  assert_eq!(folder_test.get_my_custom_sections(2).len(), 1);
+
+  assert!(folder_test.delete_custom_section("pinned"));
+  assert!(!folder_test.delete_custom_section("pinned"));
+  assert!(!folder_test.delete_custom_section("favorite"));
+  assert!(folder_test.get_my_custom_sections(uid.as_i64()).is_empty());
+}
+
+#[test]
+fn custom_section_items_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), view_id_from_any_string("w1"));
+  folder_test
+    .create_custom_section("reading_list", "Reading list", None, uid.as_i64())
+    .unwrap();
+
+  let ids = vec![test_uuid("1"), test_uuid("2"), test_uuid("3")];
+  folder_test.add_custom_section_items("reading_list", ids.clone(), uid.as_i64());
+  // Adding an existing item is a no-op.
+  folder_test.add_custom_section_items("reading_list", vec![test_uuid("1")], uid.as_i64());
+  let item_ids = |folder: &Folder| {
+    folder
+      .get_custom_section_items("reading_list", uid.as_i64())
+      .into_iter()
+      .map(|item| item.id)
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(item_ids(&folder_test), ids);
+
+  let id_3 = test_uuid("3").to_string();
+  let id_1 = test_uuid("1").to_string();
+  folder_test.move_custom_section_item("reading_list", &id_3, Some(&id_1), uid.as_i64());
+  assert_eq!(
+    item_ids(&folder_test),
+    vec![test_uuid("1"), test_uuid("3"), test_uuid("2")]
+  );
+
+  folder_test.remove_custom_section_items("reading_list", vec![id_1], uid.as_i64());
+  assert_eq!(item_ids(&folder_test), vec![test_uuid("3"), test_uuid("2")]);
+
+  // Items are stored per user.
+  assert!(
+    folder_test
+      .get_custom_section_items("reading_list", 2)
+      .is_empty()
+  );
+}
+
+#[test]
+fn custom_section_change_event_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), view_id_from_any_string("w1"));
+  let mut section_rx = folder_test.section_rx.take().unwrap();
+
+  folder_test
+    .create_custom_section("pinned", "Pinned", None, uid.as_i64())
+    .unwrap();
+  folder_test.add_custom_section_items("pinned", vec![test_uuid("1")], uid.as_i64());
+  folder_test.delete_custom_section("pinned");
+
+  let mut changes = vec![];
+  while let Ok(SectionChange::Custom(change)) = section_rx.try_recv() {
+    changes.push(change);
+  }
+  assert_eq!(changes.len(), 3);
+  assert!(matches!(
+    &changes[0],
+    CustomSectionChange::SectionUpdated { section_id } if section_id == "pinned"
+  ));
+  assert!(matches!(
+    &changes[1],
+    CustomSectionChange::ItemsAdded { section_id, ids }
+      if section_id == "pinned" && ids == &vec![test_uuid("1")]
+  ));
+  assert!(matches!(
+    &changes[2],
+    CustomSectionChange::SectionDeleted { section_id } if section_id == "pinned"
+  ));
+}
@@ -0,0 +1,81 @@
+use crate::util::{create_folder_with_workspace, make_test_view};
+use collab::entity::uuid_validation::view_id_from_any_string;
+use collab::folder::{IconType, UserId, ViewIcon, ViewLayout};
+
+#[test]
+fn duplicate_view_hierarchy_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+
+  let mut v_1 = make_test_view("1", workspace_id, vec![]);
+  v_1.name = "page".to_string();
+  v_1.icon = Some(ViewIcon {
+    ty: IconType::Url,
+    value: "https://appflowy.io/icon.png".to_string(),
+  });
+  v_1.is_locked = Some(true);
+  let mut v_1_1 = make_test_view("1_1", v_1.id, vec![]);
+  v_1_1.layout = ViewLayout::Grid;
+  let v_1_2 = make_test_view("1_2", v_1.id, vec![]);
+  let v_1_2_1 = make_test_view("1_2_1", v_1_2.id, vec![]);
+  for view in [v_1.clone(), v_1_1.clone(), v_1_2.clone(), v_1_2_1.clone()] {
+    folder_test.insert_view(view, None, uid.as_i64());
+  }
+
+  let duplicated = folder_test
+    .duplicate_view_hierarchy(&v_1.id, None, uid.as_i64())
+    .unwrap();
+  assert_eq!(duplicated.id_map.len(), 4);
+  for old_id in [v_1.id, v_1_1.id, v_1_2.id, v_1_2_1.id] {
+    assert_ne!(duplicated.new_view_id(&old_id).unwrap(), old_id);
+  }
+
+  let root = &duplicated.views.view;
+  assert_eq!(root.id, duplicated.new_view_id(&v_1.id).unwrap());
+  assert_eq!(root.parent_view_id, Some(workspace_id));
+  assert_eq!(root.name, "page");
+  assert_eq!(root.icon, v_1.icon);
+  assert_eq!(root.is_locked, Some(true));
+  assert_eq!(duplicated.views.children.len(), 2);
+  assert_eq!(duplicated.views.children[0].view.layout, ViewLayout::Grid);
+  assert_eq!(
+    duplicated.views.children[1].children[0].view.parent_view_id,
+    Some(duplicated.new_view_id(&v_1_2.id).unwrap())
+  );
+
+  // The duplicated views are inserted next to the original views.
+  folder_test.insert_nested_views(vec![duplicated.views.clone()], uid.as_i64());
+  let workspace_views = folder_test.get_views_belong_to(&workspace_id, Some(uid.as_i64()));
+  assert!(workspace_views.iter().any(|view| view.id == v_1.id));
+  assert!(workspace_views.iter().any(|view| view.id == root.id));
+  let children = folder_test.get_views_belong_to(&root.id, Some(uid.as_i64()));
+  assert_eq!(children.len(), 2);
+}
+
+#[test]
+fn duplicate_view_hierarchy_to_other_parent_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+
+  let v_1 = make_test_view("1", workspace_id, vec![]);
+  let v_2 = make_test_view("2", workspace_id, vec![]);
+  let v_1_1 = make_test_view("1_1", v_1.id, vec![]);
+  for view in [v_1.clone(), v_2.clone(), v_1_1.clone()] {
+    folder_test.insert_view(view, None, uid.as_i64());
+  }
+
+  let duplicated = folder_test
+    .duplicate_view_hierarchy(&v_1_1.id, Some(v_2.id), uid.as_i64())
+    .unwrap();
+  assert_eq!(duplicated.views.view.parent_view_id, Some(v_2.id));
+  assert!(duplicated.views.children.is_empty());
+
+  let missing = view_id_from_any_string("missing");
+  assert!(
+    folder_test
+      .duplicate_view_hierarchy(&missing, None, uid.as_i64())
+      .is_err()
+  );
+}
@@ -1,5 +1,6 @@
 mod child_views_test;
 mod custom_section;
+mod duplicate_view_test;
 mod favorite_test;
 mod load_disk;
 // mod recent_views_test;
@@ -7,5 +8,6 @@ mod serde_test;
 mod space_info_test;
 mod trash_test;
 mod util;
+mod view_path_test;
 mod view_test;
 mod workspace_test;
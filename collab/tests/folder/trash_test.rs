@@ -95,6 +95,7 @@ async fn create_trash_callback_test() {
       },
       TrashSectionChange::TrashItemRemoved { .. } => {},
     },
+    SectionChange::Custom(_) => {},
   }))
   .await;
 }
@@ -124,6 +125,7 @@ async fn delete_trash_view_ids_callback_test() {
         );
       },
     },
+    SectionChange::Custom(_) => {},
   }))
   .await;
 }
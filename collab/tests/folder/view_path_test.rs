@@ -0,0 +1,128 @@
+use crate::util::{create_folder_with_workspace, make_test_view};
+use collab::entity::uuid_validation::view_id_from_any_string;
+use collab::folder::hierarchy_builder::ViewExtraBuilder;
+use collab::folder::{SpacePermission, UserId, ViewLayout};
+
+#[test]
+fn view_path_and_breadcrumbs_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+
+  let mut space = make_test_view("space", workspace_id, vec![]);
+  space.extra = Some(
+    ViewExtraBuilder::new()
+      .is_space(true)
+      .with_space_permission(SpacePermission::Private)
+      .build()
+      .to_string(),
+  );
+  let v_1 = make_test_view("1", space.id, vec![]);
+  let v_1_1 = make_test_view("1_1", v_1.id, vec![]);
+  let v_1_2 = make_test_view("1_2", v_1.id, vec![]);
+  for view in [space.clone(), v_1.clone(), v_1_1.clone(), v_1_2.clone()] {
+    folder_test.insert_view(view, None, uid.as_i64());
+  }
+
+  let path = folder_test
+    .get_view_path(&v_1_2.id, Some(uid.as_i64()))
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(path, vec![workspace_id, space.id, v_1.id, v_1_2.id]);
+
+  let breadcrumbs = folder_test
+    .get_view_breadcrumbs(&v_1_2.id, Some(uid.as_i64()))
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(breadcrumbs, vec![space.id, v_1.id, v_1_2.id]);
+
+  assert_eq!(folder_test.get_view_depth(&workspace_id), Some(0));
+  assert_eq!(folder_test.get_view_depth(&v_1_2.id), Some(3));
+  assert_eq!(folder_test.get_view_sibling_index(&v_1_1.id), Some(0));
+  assert_eq!(folder_test.get_view_sibling_index(&v_1_2.id), Some(1));
+
+  let (space_view, space_info) = folder_test
+    .get_view_space(&v_1_1.id, Some(uid.as_i64()))
+    .unwrap();
+  assert_eq!(space_view.id, space.id);
+  assert_eq!(space_info.space_permission, SpacePermission::Private);
+
+  let missing = view_id_from_any_string("missing");
+  assert!(folder_test.get_view_path(&missing, None).is_empty());
+  assert!(folder_test.get_view_depth(&missing).is_none());
+}
+
+#[test]
+fn view_path_without_space_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+  let v_1 = make_test_view("1", workspace_id, vec![]);
+  let v_1_1 = make_test_view("1_1", v_1.id, vec![]);
+  folder_test.insert_view(v_1.clone(), None, uid.as_i64());
+  folder_test.insert_view(v_1_1.clone(), None, uid.as_i64());
+
+  let breadcrumbs = folder_test
+    .get_view_breadcrumbs(&v_1_1.id, None)
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(breadcrumbs, vec![v_1.id, v_1_1.id]);
+  assert!(folder_test.get_view_space(&v_1_1.id, None).is_none());
+}
+
+#[test]
+fn view_path_with_circular_parent_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+  // 1 and 2 point to each other as parent.
+  let v_1 = make_test_view("1", view_id_from_any_string("2"), vec![]);
+  let v_2 = make_test_view("2", v_1.id, vec![]);
+  folder_test.insert_view(v_1.clone(), None, uid.as_i64());
+  folder_test.insert_view(v_2.clone(), None, uid.as_i64());
+
+  let path = folder_test.get_view_path(&v_2.id, None);
+  assert_eq!(path.len(), 2);
+}
+
+#[test]
+fn query_views_by_layout_and_last_edited_time_test() {
+  let uid = UserId::from(1);
+  let workspace_id = view_id_from_any_string("w1");
+  let mut folder_test = create_folder_with_workspace(uid.clone(), workspace_id);
+
+  let mut v_1 = make_test_view("1", workspace_id, vec![]);
+  v_1.last_edited_time = 10;
+  let mut v_1_1 = make_test_view("1_1", v_1.id, vec![]);
+  v_1_1.layout = ViewLayout::Grid;
+  v_1_1.last_edited_time = 30;
+  let mut v_1_2 = make_test_view("1_2", v_1.id, vec![]);
+  v_1_2.layout = ViewLayout::Board;
+  v_1_2.last_edited_time = 20;
+  for view in [v_1.clone(), v_1_1.clone(), v_1_2.clone()] {
+    folder_test.insert_view(view, None, uid.as_i64());
+  }
+
+  let database_views = folder_test.get_view_recursively_with_layouts(
+    &v_1.id,
+    &[ViewLayout::Grid, ViewLayout::Board],
+    None,
+  );
+  assert_eq!(
+    database_views
+      .iter()
+      .map(|view| view.id)
+      .collect::<Vec<_>>(),
+    vec![v_1_1.id, v_1_2.id]
+  );
+
+  let recent = folder_test
+    .get_views_sorted_by_last_edited_time(Some(2), None)
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(recent, vec![v_1_1.id, v_1_2.id]);
+}
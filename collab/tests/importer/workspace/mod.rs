@@ -6,3 +6,4 @@ mod relation_map_parser;
 mod space_view_edge_case_handler;
 mod workspace_database_remapper;
 mod workspace_remapper_test;
+mod view_duplicator_test;
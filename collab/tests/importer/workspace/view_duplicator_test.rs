@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::core::collab::{CollabOptions, default_client_id};
+use collab::core::origin::CollabOrigin;
+use collab::database::database::{
+  Database, DatabaseContext, DatabaseData, gen_row_id, get_inline_view_id,
+};
+use collab::database::database_trait::NoPersistenceDatabaseCollabService;
+use collab::database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab::database::rows::CreateRowParams;
+use collab::database::views::DatabaseLayout;
+use collab::document::blocks::Block;
+use collab::document::document::Document;
+use collab::document::document_data::default_document_data;
+use collab::entity::uuid_validation::DatabaseId;
+use collab::entity::{CollabType, EncodedCollab};
+use collab::error::CollabError;
+use collab::folder::{Folder, FolderData, UserId, View, ViewId, ViewLayout, Workspace};
+use collab::importer::workspace::view_duplicator::{ViewCollabProvider, ViewHierarchyDuplicator};
+use collab::preclude::Collab;
+use uuid::Uuid;
+
+struct TestCollabProvider {
+  documents: HashMap<ViewId, EncodedCollab>,
+  database_ids: HashMap<ViewId, DatabaseId>,
+  databases: HashMap<DatabaseId, DatabaseData>,
+  inline_view_ids: HashMap<DatabaseId, ViewId>,
+}
+
+#[async_trait]
+impl ViewCollabProvider for TestCollabProvider {
+  async fn get_document_collab(
+    &self,
+    view_id: &ViewId,
+  ) -> Result<Option<EncodedCollab>, CollabError> {
+    Ok(self.documents.get(view_id).cloned())
+  }
+
+  async fn get_database_id(&self, view_id: &ViewId) -> Result<Option<DatabaseId>, CollabError> {
+    Ok(self.database_ids.get(view_id).copied())
+  }
+
+  async fn get_database_data(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<DatabaseData>, CollabError> {
+    Ok(self.databases.get(database_id).cloned())
+  }
+
+  async fn get_database_inline_view_id(
+    &self,
+    database_id: &DatabaseId,
+  ) -> Result<Option<ViewId>, CollabError> {
+    Ok(self.inline_view_ids.get(database_id).copied())
+  }
+
+  async fn get_row_document_collab(
+    &self,
+    _row_document_id: &Uuid,
+  ) -> Result<Option<EncodedCollab>, CollabError> {
+    Ok(None)
+  }
+}
+
+fn test_view(id: ViewId, parent_id: ViewId, layout: ViewLayout) -> View {
+  View::new(id, parent_id, "".to_string(), layout, Some(1))
+}
+
+fn document_with_sub_page(
+  document_id: &ViewId,
+  sub_page_id: &ViewId,
+  view_id_key: &str,
+) -> EncodedCollab {
+  let mut data = default_document_data(&document_id.to_string());
+  let block_id = "sub_page_block".to_string();
+  data.blocks.insert(
+    block_id.clone(),
+    Block {
+      id: block_id.clone(),
+      ty: "sub_page".to_string(),
+      parent: data.page_id.clone(),
+      children: block_id.clone(),
+      external_id: None,
+      external_type: None,
+      data: HashMap::from([(view_id_key.to_string(), sub_page_id.to_string().into())]),
+    },
+  );
+  data
+    .meta
+    .children_map
+    .get_mut(&data.page_id)
+    .unwrap()
+    .push(block_id.clone());
+  data.meta.children_map.insert(block_id, vec![]);
+  Document::create(&document_id.to_string(), data, default_client_id())
+    .unwrap()
+    .encode_collab()
+    .unwrap()
+}
+
+/// Returns the data of a new database with the given view, and the id of its inline view.
+async fn database_data(database_id: DatabaseId, view_id: ViewId) -> (DatabaseData, ViewId) {
+  let params = CreateDatabaseParams {
+    database_id,
+    views: vec![CreateViewParams {
+      database_id,
+      view_id,
+      name: "grid".to_string(),
+      layout: DatabaseLayout::Grid,
+      ..Default::default()
+    }],
+    rows: vec![
+      CreateRowParams::new(gen_row_id(), database_id),
+      CreateRowParams::new(gen_row_id(), database_id),
+    ],
+    ..Default::default()
+  };
+  let collab_service = Arc::new(NoPersistenceDatabaseCollabService::new(default_client_id()));
+  let context = DatabaseContext::new(collab_service.clone(), collab_service);
+  let database = Database::create_with_view(params, context).await.unwrap();
+  let inline_view_id = Uuid::parse_str(&get_inline_view_id(&database.collab).unwrap()).unwrap();
+  let data = database.get_database_data(20, false, true).await.unwrap();
+  (data, inline_view_id)
+}
+
+fn open_collab(object_id: Uuid, encoded_collab: &EncodedCollab) -> Collab {
+  let options = CollabOptions::new(object_id, default_client_id())
+    .with_data_source(encoded_collab.clone().into());
+  Collab::new_with_options(CollabOrigin::Empty, options).unwrap()
+}
+
+fn create_folder(uid: &UserId, workspace_id: Uuid) -> Folder {
+  let workspace = Workspace::new(workspace_id, "".to_string(), uid.as_i64());
+  let options = CollabOptions::new(workspace_id, default_client_id());
+  let collab = Collab::new_with_options(CollabOrigin::Empty, options).unwrap();
+  Folder::create(collab, None, FolderData::new(uid.as_i64(), workspace))
+}
+
+#[tokio::test]
+async fn duplicate_view_hierarchy_with_collabs_test() {
+  let uid = UserId::from(1);
+  let workspace_id = Uuid::new_v4();
+  let mut folder = create_folder(&uid, workspace_id);
+
+  let page_id = Uuid::new_v4();
+  let sub_page_id = Uuid::new_v4();
+  let grid_id = Uuid::new_v4();
+  folder.insert_view(
+    test_view(page_id, workspace_id, ViewLayout::Document),
+    None,
+    uid.as_i64(),
+  );
+  folder.insert_view(
+    test_view(sub_page_id, page_id, ViewLayout::Document),
+    None,
+    uid.as_i64(),
+  );
+  folder.insert_view(
+    test_view(grid_id, page_id, ViewLayout::Grid),
+    None,
+    uid.as_i64(),
+  );
+
+  let database_id = Uuid::new_v4();
+  let (data, inline_view_id) = database_data(database_id, grid_id).await;
+  let old_row_ids = data.rows.iter().map(|row| row.id).collect::<Vec<_>>();
+  let provider = TestCollabProvider {
+    documents: HashMap::from([(
+      page_id,
+      document_with_sub_page(&page_id, &sub_page_id, "view_id"),
+    )]),
+    database_ids: HashMap::from([(grid_id, database_id)]),
+    databases: HashMap::from([(database_id, data)]),
+    inline_view_ids: HashMap::from([(database_id, inline_view_id)]),
+  };
+
+  let duplicator = ViewHierarchyDuplicator::new(provider, uid.as_i64(), default_client_id());
+  let duplicated = duplicator.duplicate(&folder, &page_id, None).await.unwrap();
+
+  let mapper = &duplicated.id_mapper;
+  let new_page_id = mapper.get_new_id_from_uuid(&page_id).unwrap();
+  let new_sub_page_id = mapper.get_new_id_from_uuid(&sub_page_id).unwrap();
+  let new_grid_id = mapper.get_new_id_from_uuid(&grid_id).unwrap();
+  let new_database_id = mapper.get_new_id_from_uuid(&database_id).unwrap();
+  assert_eq!(duplicated.views.view.id, new_page_id);
+  assert_ne!(new_database_id, database_id);
+
+  // The sub page block points to the duplicated sub page.
+  assert_eq!(duplicated.documents.len(), 1);
+  let document_info = &duplicated.documents[0];
+  assert_eq!(document_info.object_id, new_page_id);
+  assert_eq!(document_info.collab_type, CollabType::Document);
+  let collab = open_collab(new_page_id, &document_info.encoded_collab);
+  let document_data = Document::open(collab).unwrap().get_document_data().unwrap();
+  let sub_page_block = document_data.blocks.get("sub_page_block").unwrap();
+  assert_eq!(
+    sub_page_block
+      .data
+      .get("view_id")
+      .unwrap()
+      .as_str()
+      .unwrap(),
+    new_sub_page_id.to_string()
+  );
+
+  // The database and its rows get new ids.
+  assert_eq!(duplicated.databases.len(), 1);
+  let database = &duplicated.databases[0];
+  assert_eq!(database.encoded_database_collab.object_id, new_database_id);
+  assert_eq!(database.encoded_row_collabs.len(), 2);
+  for row in &database.encoded_row_collabs {
+    assert!(!old_row_ids.contains(&row.object_id));
+  }
+  for old_row_id in &old_row_ids {
+    let new_row_id = mapper.get_new_id_from_uuid(old_row_id).unwrap();
+    assert!(
+      database
+        .encoded_row_collabs
+        .iter()
+        .any(|row| row.object_id == new_row_id)
+    );
+  }
+  assert_eq!(
+    duplicated.database_view_ids.get(&new_database_id).unwrap(),
+    &vec![new_grid_id]
+  );
+
+  // The inline view of the original database maps to the inline view of the new database.
+  let collab = open_collab(
+    new_database_id,
+    &database.encoded_database_collab.encoded_collab,
+  );
+  assert_eq!(
+    mapper
+      .get_new_id_from_uuid(&inline_view_id)
+      .unwrap()
+      .to_string(),
+    get_inline_view_id(&collab).unwrap()
+  );
+}
+
+#[tokio::test]
+async fn duplicate_sub_page_with_camel_case_view_id_key_test() {
+  let uid = UserId::from(1);
+  let workspace_id = Uuid::new_v4();
+  let mut folder = create_folder(&uid, workspace_id);
+  let page_id = Uuid::new_v4();
+  let sub_page_id = Uuid::new_v4();
+  folder.insert_view(
+    test_view(page_id, workspace_id, ViewLayout::Document),
+    None,
+    uid.as_i64(),
+  );
+  folder.insert_view(
+    test_view(sub_page_id, page_id, ViewLayout::Document),
+    None,
+    uid.as_i64(),
+  );
+  let provider = TestCollabProvider {
+    documents: HashMap::from([(
+      page_id,
+      document_with_sub_page(&page_id, &sub_page_id, "viewId"),
+    )]),
+    database_ids: HashMap::new(),
+    databases: HashMap::new(),
+    inline_view_ids: HashMap::new(),
+  };
+
+  let duplicator = ViewHierarchyDuplicator::new(provider, uid.as_i64(), default_client_id());
+  let duplicated = duplicator.duplicate(&folder, &page_id, None).await.unwrap();
+  let new_page_id = duplicated.id_mapper.get_new_id_from_uuid(&page_id).unwrap();
+  let new_sub_page_id = duplicated
+    .id_mapper
+    .get_new_id_from_uuid(&sub_page_id)
+    .unwrap();
+  let collab = open_collab(new_page_id, &duplicated.documents[0].encoded_collab);
+  let document_data = Document::open(collab).unwrap().get_document_data().unwrap();
+  let sub_page_block = document_data.blocks.get("sub_page_block").unwrap();
+  assert_eq!(
+    sub_page_block.data.get("viewId").unwrap().as_str().unwrap(),
+    new_sub_page_id.to_string()
+  );
+}
+
+#[tokio::test]
+async fn duplicate_database_view_that_is_the_inline_view_test() {
+  let uid = UserId::from(1);
+  let workspace_id = Uuid::new_v4();
+  let mut folder = create_folder(&uid, workspace_id);
+  let grid_id = Uuid::new_v4();
+  folder.insert_view(
+    test_view(grid_id, workspace_id, ViewLayout::Grid),
+    None,
+    uid.as_i64(),
+  );
+  let database_id = Uuid::new_v4();
+  let (mut data, _) = database_data(database_id, grid_id).await;
+  // Databases created by older versions use the id of their first view as the inline view id,
+  // and the inline view is left out of the database data
+  data.views.clear();
+  let provider = TestCollabProvider {
+    documents: HashMap::new(),
+    database_ids: HashMap::from([(grid_id, database_id)]),
+    databases: HashMap::from([(database_id, data)]),
+    inline_view_ids: HashMap::from([(database_id, grid_id)]),
+  };
+
+  let duplicator = ViewHierarchyDuplicator::new(provider, uid.as_i64(), default_client_id());
+  let duplicated = duplicator.duplicate(&folder, &grid_id, None).await.unwrap();
+  let new_grid_id = duplicated.id_mapper.get_new_id_from_uuid(&grid_id).unwrap();
+  let new_database_id = duplicated
+    .id_mapper
+    .get_new_id_from_uuid(&database_id)
+    .unwrap();
+  assert_eq!(
+    duplicated.database_view_ids.get(&new_database_id).unwrap(),
+    &vec![new_grid_id]
+  );
+  let database = &duplicated.databases[0];
+  let collab = open_collab(
+    new_database_id,
+    &database.encoded_database_collab.encoded_collab,
+  );
+  let inline_view_id = get_inline_view_id(&collab).unwrap();
+  assert_ne!(inline_view_id, new_grid_id.to_string());
+}